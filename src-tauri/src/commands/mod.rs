@@ -14,10 +14,34 @@ pub mod security;
 // 导出 user_token 命令
 pub mod user_token;
 
+/// [NEW] 账号列表视图: 在账号原有字段之上附加 Token 过期倒计时，
+/// 前端可直接按 expires_in_seconds 着色，无需自己重算时间差
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct AccountListing {
+    #[serde(flatten)]
+    pub account: Account,
+    /// 距离 access_token 过期的秒数 (已过期为 0)
+    pub expires_in_seconds: i64,
+    /// 是否持有可用于刷新的 refresh_token
+    pub refreshable: bool,
+}
+
 /// 列出所有账号
 #[tauri::command]
-pub async fn list_accounts() -> Result<Vec<Account>, String> {
-    modules::list_accounts()
+pub async fn list_accounts() -> Result<Vec<AccountListing>, String> {
+    let now = chrono::Utc::now().timestamp();
+    Ok(modules::list_accounts()?
+        .into_iter()
+        .map(|account| {
+            let expires_in_seconds = (account.token.expiry_timestamp - now).max(0);
+            let refreshable = !account.token.refresh_token.trim().is_empty();
+            AccountListing {
+                account,
+                expires_in_seconds,
+                refreshable,
+            }
+        })
+        .collect())
 }
 
 /// 添加账号
@@ -26,12 +50,40 @@ pub async fn add_account(
     app: tauri::AppHandle,
     _email: String,
     refresh_token: String,
+    force_new: Option<bool>,
 ) -> Result<Account, String> {
     let service = modules::account_service::AccountService::new(
         crate::modules::integration::SystemManager::Desktop(app.clone()),
     );
 
-    let mut account = service.add_account(&refresh_token).await?;
+    // [NEW] force_new = true 时跳过邮箱去重，总是新建账号
+    let mut account = service
+        .add_account_with_mode(&refresh_token, force_new.unwrap_or(false))
+        .await?;
+
+    // 自动刷新配额
+    let _ = internal_refresh_account_quota(&app, &mut account).await;
+
+    // 重载账号池
+    let _ = crate::commands::proxy::reload_proxy_accounts(
+        app.state::<crate::commands::proxy::ProxyServiceState>(),
+    )
+    .await;
+
+    Ok(account)
+}
+
+/// [NEW] 从原始 Token JSON 导入账号（非 OAuth 路径）
+#[tauri::command]
+pub async fn import_account_from_token_json(
+    app: tauri::AppHandle,
+    json: String,
+) -> Result<Account, String> {
+    let service = modules::account_service::AccountService::new(
+        crate::modules::integration::SystemManager::Desktop(app.clone()),
+    );
+
+    let mut account = service.import_account_from_token_json(json).await?;
 
     // 自动刷新配额
     let _ = internal_refresh_account_quota(&app, &mut account).await;
@@ -63,6 +115,46 @@ pub async fn delete_account(
     Ok(())
 }
 
+/// [NEW] purge_account_data 结果: 各表删除的行数
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PurgeAccountDataReport {
+    pub token_usage_deleted: u64,
+    pub token_stats_hourly_deleted: u64,
+    pub proxy_logs_deleted: u64,
+}
+
+/// [NEW] 清除指定账号的历史统计与请求日志 (隐私清理)。
+/// 与删除账号解耦：删号可留统计，也可以只清数据不删号。
+/// confirm 必须显式传 true，防止前端误触发不可逆删除
+#[tauri::command]
+pub async fn purge_account_data(
+    account_email: String,
+    confirm: bool,
+) -> Result<PurgeAccountDataReport, String> {
+    if !confirm {
+        return Err("purge_account_data 需要显式确认 (confirm = true)，该操作不可逆".to_string());
+    }
+
+    crate::error::run_blocking(move || {
+        let (token_usage_deleted, token_stats_hourly_deleted) =
+            modules::token_stats::purge_account_usage(&account_email)?;
+        let proxy_logs_deleted = modules::proxy_db::purge_logs_for_account(&account_email)?;
+
+        modules::logger::log_info(&format!(
+            "🧹 [Purge] Cleared data for {}: {} usage rows, {} hourly rows, {} log rows",
+            account_email, token_usage_deleted, token_stats_hourly_deleted, proxy_logs_deleted
+        ));
+
+        Ok::<_, crate::error::GatewayError>(PurgeAccountDataReport {
+            token_usage_deleted,
+            token_stats_hourly_deleted,
+            proxy_logs_deleted,
+        })
+    })
+    .await
+    .map_err(String::from)
+}
+
 /// 批量删除账号
 #[tauri::command]
 pub async fn delete_accounts(
@@ -140,6 +232,92 @@ pub async fn switch_account(
     Ok(())
 }
 
+/// [NEW] 手动强制刷新指定账号的 Token (含轮换 refresh_token 的持久化)，
+/// 返回新的 TokenData；invalid_grant 时账号会被停用并返回错误
+#[tauri::command]
+pub async fn refresh_account_token(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_id: String,
+) -> Result<crate::models::TokenData, String> {
+    let token = modules::account::refresh_account_token(&account_id).await?;
+
+    // 让运行中的代理账号池立即用上新 Token
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+
+    Ok(token)
+}
+
+/// [NEW] 全池 dry-run 校验：并发强制刷新每个账号的 Token，返回逐账号结果，
+/// 进度通过 `account://validation-progress` 事件推送；
+/// 校验可能停用 invalid_grant 账号或轮换 Token，结束后同步代理账号池
+#[tauri::command]
+pub async fn validate_pool(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    concurrency: Option<usize>,
+) -> Result<Vec<(String, modules::account::ValidationResult)>, String> {
+    let results = modules::account::validate_pool(concurrency.unwrap_or(0)).await?;
+
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+
+    Ok(results)
+}
+
+/// [NEW] 模拟账号切换 (dry-run)：返回真实切换将执行的步骤列表，
+/// 不做任何进程控制或数据库写入，供前端预览/排查切换问题
+#[tauri::command]
+pub async fn simulate_account_switch(
+    app: tauri::AppHandle,
+    account_id: String,
+) -> Result<Vec<crate::modules::integration::SwitchPlanStep>, String> {
+    let account = modules::account::load_account(&account_id)?;
+    let manager = crate::modules::integration::SystemManager::Desktop(app);
+    manager.simulate_account_switch(&account).await
+}
+
+/// [NEW] 基准测试账号切换：执行一次真实切换并返回逐阶段耗时（毫秒）。
+/// 仅在调试日志开启时可用，避免被当成普通切换入口误用
+#[tauri::command]
+pub async fn benchmark_switch(
+    app: tauri::AppHandle,
+    account_id: String,
+) -> Result<Vec<crate::modules::integration::SwitchPhaseTiming>, String> {
+    let debug_enabled = modules::load_app_config()
+        .map(|c| c.proxy.debug_logging.enabled)
+        .unwrap_or(false);
+    if !debug_enabled {
+        return Err("基准测试仅在调试日志开启时可用（设置 -> 调试日志）".to_string());
+    }
+
+    let account = modules::account::load_account(&account_id)?;
+    let manager = crate::modules::integration::SystemManager::Desktop(app);
+    manager.benchmark_account_switch(&account).await
+}
+
+/// [NEW] 校验 storage.json 与 state.vscdb 是否与当前账号一致（只读诊断）。
+/// 用于排查"切换了账号但 IDE 仍显示旧用户"的半失败切换
+#[tauri::command]
+pub async fn verify_account_consistency(
+) -> Result<crate::modules::integration::ConsistencyReport, String> {
+    tokio::task::spawn_blocking(crate::modules::integration::verify_account_consistency)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// [NEW] 仅切换数据（写指纹 + 注入 Token），不关闭/重启 IDE
+/// 返回 IDE 是否仍在运行，供前端提示"需要重启后生效"
+#[tauri::command]
+pub async fn switch_account_data_only(
+    app: tauri::AppHandle,
+    account_id: String,
+) -> Result<bool, String> {
+    let ide_running = modules::switch_account_data_only(&account_id).await?;
+
+    // 同步托盘
+    crate::modules::tray::update_tray_menus(&app);
+
+    Ok(ide_running)
+}
+
 /// 获取当前账号
 #[tauri::command]
 pub async fn get_current_account() -> Result<Option<Account>, String> {
@@ -321,6 +499,23 @@ pub async fn restore_device_version(
     modules::restore_device_version(&account_id, &version_id)
 }
 
+/// [NEW] 列出账号的历史指纹（按记录顺序，供查看/对比）
+#[tauri::command]
+pub async fn get_device_history(
+    account_id: String,
+) -> Result<Vec<crate::models::DeviceProfile>, String> {
+    modules::get_device_history(&account_id)
+}
+
+/// [NEW] 按序号回退到历史指纹并写回 storage.json（自动备份）
+#[tauri::command]
+pub async fn revert_device_profile(
+    account_id: String,
+    index: usize,
+) -> Result<crate::models::DeviceProfile, String> {
+    modules::revert_device_profile(&account_id, index)
+}
+
 /// 删除历史指纹（baseline 不可删）
 #[tauri::command]
 pub async fn delete_device_version(account_id: String, version_id: String) -> Result<(), String> {
@@ -346,6 +541,28 @@ pub async fn load_config() -> Result<AppConfig, String> {
     modules::load_app_config()
 }
 
+/// [NEW] 查询 protected_models 当前生效的解释模式 (exclude = 排除名单 / reserve = 独占名单)
+#[tauri::command]
+pub async fn get_protected_models_mode() -> Result<crate::models::ProtectedModelsMode, String> {
+    let config = modules::load_app_config()?;
+    Ok(config.quota_protection.protected_models_mode)
+}
+
+/// [NEW] 设置 protected_models 的解释模式并持久化 (路由时每次读取配置，无需热更新)
+#[tauri::command]
+pub async fn set_protected_models_mode(
+    mode: crate::models::ProtectedModelsMode,
+) -> Result<(), String> {
+    let mut config = modules::load_app_config()?;
+    config.quota_protection.protected_models_mode = mode;
+    modules::save_app_config(&config)?;
+    tracing::info!(
+        "[QuotaProtection] protected_models 解释模式切换为 {:?}",
+        mode
+    );
+    Ok(())
+}
+
 /// 保存配置
 #[tauri::command]
 pub async fn save_config(
@@ -384,6 +601,11 @@ pub async fn save_config(
             .await;
         // [NEW] 更新 User-Agent 配置
         instance.axum_server.update_user_agent(&config.proxy).await;
+        // [NEW] 更新 SSE 事件流配置
+        instance
+            .axum_server
+            .update_event_stream(&config.proxy)
+            .await;
         // 更新熔断配置
         instance
             .token_manager
@@ -391,10 +613,125 @@ pub async fn save_config(
             .await;
         tracing::debug!("已同步热更新反代服务配置");
     }
+    drop(instance_lock);
+
+    // [NEW] 更新监控内存日志上限 (监控可独立于反代实例存在)
+    {
+        let monitor_lock = proxy_state.monitor.read().await;
+        if let Some(monitor) = monitor_lock.as_ref() {
+            monitor.set_max_logs(config.proxy.monitor_max_logs).await;
+            monitor
+                .set_neutral_codes(&config.proxy.neutral_status_codes)
+                .await;
+        }
+    }
+
+    // [NEW] 热更新上游超时配置 (连接超时对新建客户端生效)
+    crate::proxy::config::update_upstream_timeouts(
+        config.proxy.upstream_connect_timeout_ms,
+        config.proxy.upstream_request_timeout_ms,
+    );
+
+    // [NEW] 热更新模型日度 Token 上限 (spend cap)
+    crate::proxy::config::update_model_daily_token_cap(
+        config.proxy.model_daily_token_cap.clone(),
+    );
+
+    // [NEW] 热更新上游并发限制
+    crate::proxy::concurrency::configure(
+        config.proxy.max_concurrent_upstream,
+        config.proxy.on_saturation.clone(),
+    );
+
+    // [NEW] 热更新统计模型名归一化规则
+    crate::proxy::config::update_stats_model_normalization(
+        config.proxy.stats_model_normalization.clone(),
+    );
+
+    // [NEW] 热更新模型回退表
+    crate::proxy::config::update_model_fallbacks(config.proxy.model_fallbacks.clone());
+
+    // [NEW] 热更新请求体大小上限
+    crate::proxy::config::update_max_request_body_bytes(config.proxy.max_request_body_bytes);
+
+    // [NEW] 热更新粘性会话配置
+    crate::proxy::config::update_sticky_sessions(config.proxy.sticky_sessions.clone());
+
+    // [NEW] 热更新响应缓存配置
+    crate::proxy::config::update_response_cache(config.proxy.response_cache.clone());
+
+    // [NEW] 热更新自动封禁默认 TTL
+    crate::proxy::config::update_default_blacklist_ttl_secs(
+        config.proxy.security_monitor.blacklist.default_blacklist_ttl_secs,
+    );
+
+    // [NEW] 热更新转发头采信开关
+    crate::proxy::config::update_trust_forwarded_headers(config.proxy.trust_forwarded_headers);
+
+    // [NEW] 热更新单账号最小转发间隔
+    crate::proxy::config::update_per_account_min_interval_ms(
+        config.proxy.per_account_min_interval_ms,
+    );
+
+    // [NEW] 热更新 plain_log (日志 emoji 剥离)
+    crate::modules::logger::set_plain_log(config.plain_log);
+
+    Ok(())
+}
+
+/// [NEW] 导出配置 (pretty JSON，密钥/密码已脱敏，可用于 bug 报告或迁移)
+#[tauri::command]
+pub async fn export_config() -> Result<String, String> {
+    modules::export_config()
+}
+
+/// [NEW] 导入配置：校验后保存，脱敏占位符回填当前配置原值。
+/// 保存后需重启反代服务或再调一次 save_config 才会热更新运行中的实例
+#[tauri::command]
+pub async fn import_config(app: tauri::AppHandle, json: String) -> Result<(), String> {
+    modules::import_config(&json)?;
+
+    // 通知托盘配置已更新
+    let _ = app.emit("config://updated", ());
 
     Ok(())
 }
 
+/// 设置运行时 User-Agent 覆盖 (None/空字符串 = 恢复默认计算值)
+/// 持久化到配置并热更新全局及运行中的反代服务
+#[tauri::command]
+pub async fn set_user_agent_override(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    user_agent: Option<String>,
+) -> Result<String, String> {
+    let normalized = user_agent.filter(|s| !s.trim().is_empty());
+
+    // 1. 持久化到配置
+    let mut config = modules::load_app_config()?;
+    config.proxy.user_agent_override = normalized.clone();
+    if normalized.is_some() {
+        config.proxy.saved_user_agent = normalized.clone();
+    }
+    modules::save_app_config(&config)?;
+
+    // 2. 更新全局运行时覆盖 (quota/project_resolver 等直连上游的请求)
+    crate::constants::set_user_agent_override(normalized);
+
+    // 3. 热更新运行中的反代服务
+    let instance_lock = proxy_state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.axum_server.update_user_agent(&config.proxy).await;
+    }
+
+    Ok(crate::constants::effective_user_agent())
+}
+
+/// 获取当前生效的 User-Agent (覆盖值或默认计算值)
+#[tauri::command]
+pub async fn get_effective_user_agent() -> Result<String, String> {
+    Ok(crate::constants::effective_user_agent())
+}
+
 // --- OAuth 命令 ---
 
 #[tauri::command]
@@ -471,6 +808,12 @@ pub async fn toggle_account_disabled(
             }
             // 3. 保存该账号
             modules::account::save_account(&acc)?;
+            // [NEW] 审计事件：保留启停历史
+            modules::account_events::record_event(
+                &acc.id,
+                if disabled { "disabled" } else { "enabled" },
+                reason.as_deref(),
+            );
             target_account = Some(acc);
             break;
         }
@@ -487,6 +830,159 @@ pub async fn toggle_account_disabled(
     Ok(account)
 }
 
+/// [NEW] 查询账号的启停/阻止审计历史（按时间倒序，最多 limit 条，默认 50）
+#[tauri::command]
+pub async fn get_account_events(
+    account_id: String,
+    limit: Option<u32>,
+) -> Result<Vec<modules::account_events::AccountEvent>, String> {
+    tokio::task::spawn_blocking(move || {
+        modules::account_events::get_account_events(&account_id, limit.unwrap_or(50))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
+}
+
+/// 获取账号的自定义上游 Headers
+#[tauri::command]
+pub async fn get_account_custom_headers(
+    account_id: String,
+) -> Result<std::collections::HashMap<String, String>, String> {
+    let account = modules::load_account(&account_id)?;
+    Ok(account.custom_headers)
+}
+
+/// 设置账号的自定义上游 Headers (整体替换；传空表清除)
+/// Authorization 由 Token 注入管理，不允许在此设置
+#[tauri::command]
+pub async fn set_account_custom_headers(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_id: String,
+    headers: std::collections::HashMap<String, String>,
+) -> Result<(), String> {
+    if headers
+        .keys()
+        .any(|k| k.eq_ignore_ascii_case("authorization"))
+    {
+        return Err("不允许自定义 Authorization 请求头".to_string());
+    }
+
+    let mut account = modules::load_account(&account_id)?;
+    account.custom_headers = headers;
+    modules::account::save_account(&account)?;
+
+    modules::logger::log_info(&format!(
+        "账号自定义 Headers 已更新: {} ({} 项)",
+        account.email,
+        account.custom_headers.len()
+    ));
+
+    // Reload token pool so the headers registry picks up the change
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+
+    Ok(())
+}
+
+/// 设置账号分组 (None 或空串表示移出分组)
+#[tauri::command]
+pub async fn set_account_group(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_id: String,
+    group: Option<String>,
+) -> Result<(), String> {
+    let group = group.filter(|g| !g.trim().is_empty());
+
+    let mut account = modules::load_account(&account_id)?;
+    account.group = group.clone();
+    modules::account::save_account(&account)?;
+
+    modules::logger::log_info(&format!(
+        "账号分组已更新: {} -> {:?}",
+        account.email, group
+    ));
+
+    // Reload token pool so group-constrained scheduling picks up the change
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+
+    Ok(())
+}
+
+/// 重命名账号分组 (批量迁移该组下所有账号)，返回受影响的账号数
+#[tauri::command]
+pub async fn rename_account_group(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    old_name: String,
+    new_name: String,
+) -> Result<usize, String> {
+    let new_name = new_name.trim().to_string();
+    if new_name.is_empty() {
+        return Err("新分组名不能为空".to_string());
+    }
+
+    let accounts = modules::account::list_accounts()?;
+    let mut count = 0;
+    for mut acc in accounts {
+        if acc.group.as_deref() == Some(old_name.as_str()) {
+            acc.group = Some(new_name.clone());
+            modules::account::save_account(&acc)?;
+            count += 1;
+        }
+    }
+
+    modules::logger::log_info(&format!(
+        "账号分组已重命名: {} -> {} ({} 个账号)",
+        old_name, new_name, count
+    ));
+
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+
+    Ok(count)
+}
+
+/// [NEW] 获取账号调度权重
+#[tauri::command]
+pub async fn get_account_weight(account_id: String) -> Result<u32, String> {
+    let account = modules::load_account(&account_id)?;
+    Ok(account.weight)
+}
+
+/// [NEW] 设置账号调度权重 (Weighted 模式下按比例分配流量；0 = 仅手动选择)
+#[tauri::command]
+pub async fn set_account_weight(
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    account_id: String,
+    weight: u32,
+) -> Result<(), String> {
+    let mut account = modules::load_account(&account_id)?;
+    account.weight = weight;
+    modules::account::save_account(&account)?;
+
+    modules::logger::log_info(&format!(
+        "账号调度权重已更新: {} -> {}",
+        account.email, weight
+    ));
+
+    // Reload token pool so weighted scheduling picks up the change
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+
+    Ok(())
+}
+
+/// 列出所有已使用的分组名
+#[tauri::command]
+pub async fn list_account_groups() -> Result<Vec<String>, String> {
+    let accounts = modules::account::list_accounts()?;
+    let mut groups: Vec<String> = accounts
+        .into_iter()
+        .filter_map(|a| a.group)
+        .collect::<std::collections::HashSet<_>>()
+        .into_iter()
+        .collect();
+    groups.sort();
+    Ok(groups)
+}
+
 /// 预生成 OAuth 授权链接 (不打开浏览器)
 #[tauri::command]
 pub async fn prepare_oauth_url(app_handle: tauri::AppHandle) -> Result<String, String> {
@@ -502,6 +998,12 @@ pub async fn cancel_oauth_login() -> Result<(), String> {
     Ok(())
 }
 
+/// [NEW] 强制重新拉取 OIDC discovery 文档并更新端点缓存
+#[tauri::command]
+pub async fn refresh_oidc_discovery() -> Result<modules::oauth::OidcDiscovery, String> {
+    modules::oauth::refresh_oidc_discovery().await
+}
+
 /// 手动提交 OAuth Code (用于 Docker/远程环境无法自动回调时)
 #[tauri::command]
 pub async fn submit_oauth_code(code: String, state: Option<String>) -> Result<(), String> {
@@ -616,6 +1118,108 @@ pub async fn sync_account_from_db(
     Ok(Some(account))
 }
 
+/// 预览注入内容 (只读 dry-run，不写数据库)
+/// 返回 Legacy/Unified 两个 blob 的 base64、字段摘要和目标 DB 路径，用于注入前审计
+#[tauri::command]
+pub async fn preview_injection(
+    account_id: String,
+) -> Result<modules::db::InjectionPreview, String> {
+    let account = modules::load_account(&account_id)?;
+    let db_path = modules::db::get_db_path()?;
+
+    modules::logger::log_info(&format!(
+        "🔍 [Preview] Building injection preview for: {}",
+        account.email
+    ));
+
+    tokio::task::spawn_blocking(move || {
+        modules::db::preview_injection(
+            &db_path,
+            &account.token.access_token,
+            &account.token.refresh_token,
+            account.token.expiry_timestamp,
+            &account.email,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
+}
+
+/// [NEW] 试注入：对 state.vscdb 的临时副本执行完整注入 + 校验，汇报结果后丢弃副本。
+/// 让用户在不动真实会话的前提下验证注入对自己 IDE 版本是否可行
+#[tauri::command]
+pub async fn test_inject_token(account_id: String) -> Result<String, String> {
+    let account = modules::load_account(&account_id)?;
+    let db_path = modules::db::get_db_path()?;
+
+    modules::logger::log_info(&format!(
+        "🧪 [Test Inject] Running test injection on a temp copy for: {}",
+        account.email
+    ));
+
+    tokio::task::spawn_blocking(move || {
+        modules::db::test_inject_token(
+            &db_path,
+            &account.token.access_token,
+            &account.token.refresh_token,
+            account.token.expiry_timestamp,
+            &account.email,
+        )
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
+}
+
+/// [NEW] 读取当前注入到 state.vscdb 的身份 (email/expiry)，带 LRU 缓存。
+/// 供账号列表的"当前注入身份"展示反复调用，避免每次都重新解码 blob
+#[tauri::command]
+pub async fn get_injected_identity(
+    account_id: String,
+) -> Result<modules::db::InjectedIdentity, String> {
+    let db_path = modules::db::get_db_path()?;
+
+    crate::error::run_blocking(move || modules::db::get_injected_identity(&account_id, &db_path))
+        .await
+        .map_err(String::from)
+}
+
+/// [NEW] 探测已安装 IDE 的产品名与版本 (读取 product.json / package.json)。
+/// 注入不生效时可据此判断 legacy/unified key 适用性，未探测到时返回 "unknown"
+#[tauri::command]
+pub async fn detect_ide_version() -> Result<modules::process::IdeVersionInfo, String> {
+    crate::error::run_blocking(|| {
+        Ok::<_, crate::error::GatewayError>(modules::process::detect_ide_version())
+    })
+    .await
+    .map_err(String::from)
+}
+
+/// [NEW] 一键回滚到最后可用 Token：把注入前快照的 key 值写回 state.vscdb。
+/// 快照在每次注入前自动创建，含当时注入的 email 供确认
+#[tauri::command]
+pub async fn restore_last_token() -> Result<modules::db::RestoreReport, String> {
+    let db_path = modules::db::get_db_path()?;
+
+    crate::error::run_blocking(move || modules::db::restore_last_token(&db_path))
+        .await
+        .map_err(String::from)
+}
+
+/// [NEW] 调试用：只读列出 state.vscdb ItemTable 的全部 key (可选前缀过滤，
+/// 如 "antigravity")。用于排查注入未生效时目标 key 是否存在
+#[tauri::command]
+pub async fn list_state_db_keys(prefix: Option<String>) -> Result<Vec<String>, String> {
+    let db_path = modules::db::get_db_path()?;
+
+    crate::error::run_blocking(move || {
+        modules::db::list_state_db_keys(&db_path, prefix.as_deref())
+    })
+    .await
+    .map_err(String::from)
+}
+
 /// 保存文本文件 (绕过前端 Scope 限制)
 #[tauri::command]
 pub async fn save_text_file(path: String, content: String) -> Result<(), String> {
@@ -709,6 +1313,14 @@ pub async fn set_window_theme(window: tauri::Window, theme: String) -> Result<()
     window.set_theme(tauri_theme).map_err(|e| e.to_string())
 }
 
+/// [NEW] 发送一条测试系统通知，用于验证平台通知权限是否正常
+#[tauri::command]
+pub async fn test_notification(app: tauri::AppHandle) -> Result<(), String> {
+    let integration = crate::modules::integration::SystemManager::Desktop(app);
+    integration.show_notification("通知测试", "如果能看到这条通知，说明系统通知工作正常");
+    Ok(())
+}
+
 /// 获取 Antigravity 可执行文件路径
 #[tauri::command]
 pub async fn get_antigravity_path(bypass_config: Option<bool>) -> Result<String, String> {
@@ -845,6 +1457,43 @@ pub async fn toggle_proxy_status(
     Ok(())
 }
 
+/// [NEW] 批量启用/禁用所有账号的反代参与，返回实际变更的账号数
+#[tauri::command]
+pub async fn set_all_accounts_proxy_enabled(
+    app: tauri::AppHandle,
+    proxy_state: tauri::State<'_, crate::commands::proxy::ProxyServiceState>,
+    enabled: bool,
+) -> Result<usize, String> {
+    let changed =
+        tokio::task::spawn_blocking(move || modules::account::set_all_accounts_proxy_enabled(enabled))
+            .await
+            .map_err(|e| e.to_string())??;
+
+    // 如果反代服务正在运行,重新加载账号池
+    let _ = crate::commands::proxy::reload_proxy_accounts(proxy_state).await;
+
+    // 更新托盘菜单
+    crate::modules::tray::update_tray_menus(&app);
+
+    Ok(changed)
+}
+
+/// [NEW] 获取账号池健康状态汇总
+#[tauri::command]
+pub async fn get_pool_status() -> Result<modules::account::PoolStatus, String> {
+    tokio::task::spawn_blocking(modules::account::get_pool_status)
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// [NEW] 清除账号记录的最近一次失败信息
+#[tauri::command]
+pub async fn clear_account_last_error(account_id: String) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || modules::account::clear_account_last_error(&account_id))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
 /// 预热所有可用账号
 #[tauri::command]
 pub async fn warm_up_all_accounts() -> Result<String, String> {
@@ -881,11 +1530,19 @@ pub async fn save_http_api_settings(
 
 pub use crate::modules::token_stats::{AccountTokenStats, TokenStatsAggregated, TokenStatsSummary};
 
+/// [NEW] 统计记录健康状态：token_stats.db 初始化失败后写入被一次性关闭，
+/// 前端据此提示用户统计图表不再更新
+#[tauri::command]
+pub async fn get_stats_health() -> Result<crate::modules::token_stats::StatsHealth, String> {
+    Ok(crate::modules::token_stats::get_stats_health())
+}
+
 #[tauri::command]
 pub async fn get_token_stats_hourly(hours: i64) -> Result<Vec<TokenStatsAggregated>, String> {
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_hourly_stats(hours))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 /// 重置所有账号的 forbidden 状态
@@ -899,6 +1556,7 @@ pub async fn get_token_stats_daily(days: i64) -> Result<Vec<TokenStatsAggregated
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_daily_stats(days))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 #[tauri::command]
@@ -906,6 +1564,7 @@ pub async fn get_token_stats_weekly(weeks: i64) -> Result<Vec<TokenStatsAggregat
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_weekly_stats(weeks))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 #[tauri::command]
@@ -913,6 +1572,26 @@ pub async fn get_token_stats_by_account(hours: i64) -> Result<Vec<AccountTokenSt
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_account_stats(hours))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// 按分组过滤的账号统计 (只统计该分组下账号的 email)
+#[tauri::command]
+pub async fn get_token_stats_by_account_group(
+    hours: i64,
+    group: String,
+) -> Result<Vec<AccountTokenStats>, String> {
+    tokio::task::spawn_blocking(move || {
+        let emails: Vec<String> = modules::account::list_accounts()?
+            .into_iter()
+            .filter(|a| a.group.as_deref() == Some(group.as_str()))
+            .map(|a| a.email)
+            .collect();
+        crate::modules::token_stats::get_account_stats_filtered(hours, &emails)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 #[tauri::command]
@@ -920,6 +1599,35 @@ pub async fn get_token_stats_summary(hours: i64) -> Result<TokenStatsSummary, St
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_summary_stats(hours))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// [NEW] 按显式时间区间统计 (unix 秒，闭区间；用于月度对账等)
+#[tauri::command]
+pub async fn get_token_stats_by_account_range(
+    from_ts: i64,
+    to_ts: i64,
+) -> Result<Vec<AccountTokenStats>, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::token_stats::get_account_stats_range(from_ts, to_ts)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
+}
+
+/// [NEW] 按显式时间区间的汇总统计 (unix 秒，闭区间)
+#[tauri::command]
+pub async fn get_token_stats_summary_range(
+    from_ts: i64,
+    to_ts: i64,
+) -> Result<TokenStatsSummary, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::token_stats::get_summary_stats_range(from_ts, to_ts)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 #[tauri::command]
@@ -929,6 +1637,42 @@ pub async fn get_token_stats_by_model(
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_model_stats(hours))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// [NEW] 按显式时间范围 + 排序维度查询模型统计 (排序在 SQL 侧完成，
+/// 前端可直接提供可排序列而无需对大结果集重排)
+#[tauri::command]
+pub async fn get_token_stats_by_model_ex(
+    from_ts: i64,
+    to_ts: i64,
+    sort_by: crate::modules::token_stats::ModelSort,
+) -> Result<Vec<crate::modules::token_stats::ModelTokenStats>, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::token_stats::get_model_stats_ex(from_ts, to_ts, sort_by)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
+}
+
+/// [NEW] 查询各模型全时段统计 (含首次/末次使用时间)
+#[tauri::command]
+pub async fn get_model_lifetime_stats(
+) -> Result<Vec<crate::modules::token_stats::ModelLifetimeStats>, String> {
+    tokio::task::spawn_blocking(crate::modules::token_stats::get_model_lifetime_stats)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// [NEW] 查询各模型今日 Token 用量与配置的日度上限 (spend cap)
+#[tauri::command]
+pub async fn get_model_cap_usage(
+) -> Result<Vec<crate::proxy::spend_cap::ModelCapUsage>, String> {
+    tokio::task::spawn_blocking(crate::proxy::spend_cap::get_model_cap_usage)
+        .await
+        .map_err(|e| e.to_string())?
 }
 
 #[tauri::command]
@@ -938,6 +1682,7 @@ pub async fn get_token_stats_model_trend_hourly(
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_model_trend_hourly(hours))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 #[tauri::command]
@@ -949,6 +1694,7 @@ pub async fn get_token_stats_model_trend_minute(
     })
     .await
     .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 #[tauri::command]
@@ -958,6 +1704,7 @@ pub async fn get_token_stats_model_trend_daily(
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_model_trend_daily(days))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 #[tauri::command]
@@ -969,6 +1716,7 @@ pub async fn get_token_stats_account_trend_hourly(
     })
     .await
     .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 #[tauri::command]
@@ -978,11 +1726,27 @@ pub async fn get_token_stats_account_trend_daily(
     tokio::task::spawn_blocking(move || crate::modules::token_stats::get_account_trend_daily(days))
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
+/// [NEW] 从 token_usage 原始记录重算 token_stats_hourly，修复聚合漂移。
+/// 不依赖反代日志，比 rebuild_token_stats 快得多
 #[tauri::command]
-pub async fn rebuild_token_stats() -> Result<usize, String> {
-    tokio::task::spawn_blocking(move || crate::modules::token_stats::rebuild_from_logs())
+pub async fn recompute_hourly_aggregates() -> Result<usize, String> {
+    tokio::task::spawn_blocking(crate::modules::token_stats::recompute_hourly_aggregates)
         .await
         .map_err(|e| e.to_string())?
+        .map_err(String::from)
+}
+
+/// [NEW] full 省略或为 true 时保持原有全量重建行为；
+/// full = false 走增量模式，只补水位线之后的新日志
+#[tauri::command]
+pub async fn rebuild_token_stats(full: Option<bool>) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::token_stats::rebuild_from_logs(full.unwrap_or(true))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
@@ -100,6 +100,44 @@ pub async fn internal_start_proxy_service(
     // 使用自定义 Drop guard 确保无论成功失败都会重置 starting 状态
     let _starting_guard = StartingGuard(state.starting.clone());
 
+    // [NEW] 上游超时全局配置需在构建 HTTP 客户端前生效
+    crate::proxy::config::update_upstream_timeouts(
+        config.upstream_connect_timeout_ms,
+        config.upstream_request_timeout_ms,
+    );
+
+    // [NEW] 模型日度 Token 上限需在接受请求前生效
+    crate::proxy::config::update_model_daily_token_cap(config.model_daily_token_cap.clone());
+
+    // [NEW] 上游并发限制需在接受请求前生效
+    crate::proxy::concurrency::configure(config.max_concurrent_upstream, config.on_saturation.clone());
+
+    // [NEW] 统计模型名归一化规则需在记录用量前生效
+    crate::proxy::config::update_stats_model_normalization(config.stats_model_normalization.clone());
+
+    // [NEW] 模型回退表需在 handler 处理上游错误前生效
+    crate::proxy::config::update_model_fallbacks(config.model_fallbacks.clone());
+
+    // [NEW] 同步请求体大小上限
+    crate::proxy::config::update_max_request_body_bytes(config.max_request_body_bytes);
+
+    // [NEW] 同步粘性会话配置
+    crate::proxy::config::update_sticky_sessions(config.sticky_sessions.clone());
+
+    // [NEW] 同步响应缓存配置
+    crate::proxy::config::update_response_cache(config.response_cache.clone());
+
+    // [NEW] 同步自动封禁默认 TTL
+    crate::proxy::config::update_default_blacklist_ttl_secs(
+        config.security_monitor.blacklist.default_blacklist_ttl_secs,
+    );
+
+    // [NEW] 转发头采信开关需在提取 client_ip 前生效
+    crate::proxy::config::update_trust_forwarded_headers(config.trust_forwarded_headers);
+
+    // [NEW] 账号限速间隔需在发出 Token 前生效
+    crate::proxy::config::update_per_account_min_interval_ms(config.per_account_min_interval_ms);
+
     // Ensure monitor exists
     {
         let mut monitor_lock = state.monitor.write().await;
@@ -110,11 +148,16 @@ pub async fn internal_start_proxy_service(
                 } else {
                     None
                 };
-            *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000, app_handle)));
+            *monitor_lock = Some(Arc::new(ProxyMonitor::new(
+                config.monitor_max_logs,
+                app_handle,
+            )));
         }
         // Sync enabled state from config
         if let Some(monitor) = monitor_lock.as_ref() {
             monitor.set_enabled(config.enable_logging);
+            monitor.set_max_logs(config.monitor_max_logs).await;
+            monitor.set_neutral_codes(&config.neutral_status_codes).await;
         }
     }
 
@@ -237,6 +280,44 @@ pub async fn ensure_admin_server(
         return Ok(());
     }
 
+    // [NEW] 上游超时全局配置需在构建 HTTP 客户端前生效
+    crate::proxy::config::update_upstream_timeouts(
+        config.upstream_connect_timeout_ms,
+        config.upstream_request_timeout_ms,
+    );
+
+    // [NEW] 模型日度 Token 上限需在接受请求前生效
+    crate::proxy::config::update_model_daily_token_cap(config.model_daily_token_cap.clone());
+
+    // [NEW] 上游并发限制需在接受请求前生效
+    crate::proxy::concurrency::configure(config.max_concurrent_upstream, config.on_saturation.clone());
+
+    // [NEW] 统计模型名归一化规则需在记录用量前生效
+    crate::proxy::config::update_stats_model_normalization(config.stats_model_normalization.clone());
+
+    // [NEW] 模型回退表需在 handler 处理上游错误前生效
+    crate::proxy::config::update_model_fallbacks(config.model_fallbacks.clone());
+
+    // [NEW] 同步请求体大小上限
+    crate::proxy::config::update_max_request_body_bytes(config.max_request_body_bytes);
+
+    // [NEW] 同步粘性会话配置
+    crate::proxy::config::update_sticky_sessions(config.sticky_sessions.clone());
+
+    // [NEW] 同步响应缓存配置
+    crate::proxy::config::update_response_cache(config.response_cache.clone());
+
+    // [NEW] 同步自动封禁默认 TTL
+    crate::proxy::config::update_default_blacklist_ttl_secs(
+        config.security_monitor.blacklist.default_blacklist_ttl_secs,
+    );
+
+    // [NEW] 转发头采信开关需在提取 client_ip 前生效
+    crate::proxy::config::update_trust_forwarded_headers(config.trust_forwarded_headers);
+
+    // [NEW] 账号限速间隔需在发出 Token 前生效
+    crate::proxy::config::update_per_account_min_interval_ms(config.per_account_min_interval_ms);
+
     // Ensure monitor exists
     let monitor = {
         let mut monitor_lock = state.monitor.write().await;
@@ -247,11 +328,33 @@ pub async fn ensure_admin_server(
                 } else {
                     None
                 };
-            *monitor_lock = Some(Arc::new(ProxyMonitor::new(1000, app_handle)));
+            *monitor_lock = Some(Arc::new(ProxyMonitor::new(
+                config.monitor_max_logs,
+                app_handle,
+            )));
         }
         monitor_lock.as_ref().unwrap().clone()
     };
 
+    // [NEW] 绑定前预检端口：被占用时报出占用进程，而不是裸的 bind 失败
+    {
+        let check = crate::modules::process::check_port_available(
+            config.get_listen_port(),
+            Some(config.get_bind_address().to_string()),
+        );
+        if !check.available {
+            let owner = match (&check.conflict_process, check.conflict_pid) {
+                (Some(name), Some(pid)) => format!("{} (PID {})", name, pid),
+                (None, Some(pid)) => format!("PID {}", pid),
+                _ => "未知进程".to_string(),
+            };
+            return Err(format!(
+                "端口 {} 已被 {} 占用，请关闭该进程或在设置中更换端口",
+                check.port, owner
+            ));
+        }
+    }
+
     // 默认空 TokenManager 用于管理界面
     let app_data_dir = crate::modules::account::get_data_dir()?;
     let token_manager = Arc::new(TokenManager::new(app_data_dir));
@@ -260,7 +363,7 @@ pub async fn ensure_admin_server(
 
     let (axum_server, server_handle) = match crate::proxy::AxumServer::start(
         config.get_bind_address().to_string(),
-        config.port,
+        config.get_listen_port(),
         token_manager,
         config.custom_mapping.clone(),
         config.request_timeout,
@@ -274,6 +377,11 @@ pub async fn ensure_admin_server(
         integration.clone(),
         cloudflared_state,
         config.proxy_pool.clone(),
+        config.event_stream.clone(),
+        config.metrics.clone(),
+        config.health_probes.clone(),
+        config.tls_cert_path.clone(),
+        config.tls_key_path.clone(),
     )
     .await
     {
@@ -350,6 +458,12 @@ pub async fn get_proxy_status(state: State<'_, ProxyServiceState>) -> Result<Pro
     }
 }
 
+/// [NEW] 当前在途上游请求数 (并发限制观测用)
+#[tauri::command]
+pub async fn get_upstream_in_flight() -> Result<usize, String> {
+    Ok(crate::proxy::concurrency::in_flight_count())
+}
+
 /// 获取反代服务统计
 #[tauri::command]
 pub async fn get_proxy_stats(state: State<'_, ProxyServiceState>) -> Result<ProxyStats, String> {
@@ -361,6 +475,24 @@ pub async fn get_proxy_stats(state: State<'_, ProxyServiceState>) -> Result<Prox
     }
 }
 
+/// [NEW] 检查端口是否可绑定；被占用时尽力附带占用进程名/PID
+#[tauri::command]
+pub async fn check_port_available(
+    port: u16,
+    addr: Option<String>,
+) -> Result<crate::modules::process::PortCheckResult, String> {
+    tokio::task::spawn_blocking(move || crate::modules::process::check_port_available(port, addr))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// [NEW] 实时账号计数快照（今日请求数 / Token 数，内存近似值，零 DB 查询）
+#[tauri::command]
+pub async fn get_live_account_counters(
+) -> Result<Vec<crate::proxy::monitor::LiveAccountCounters>, String> {
+    Ok(crate::proxy::monitor::get_live_account_counters())
+}
+
 /// 获取反代请求日志
 #[tauri::command]
 pub async fn get_proxy_logs(
@@ -398,6 +530,16 @@ pub async fn clear_proxy_logs(state: State<'_, ProxyServiceState>) -> Result<(),
     Ok(())
 }
 
+/// [NEW] 按时间范围批量删除反代日志 (毫秒时间戳，闭区间)，返回删除条数
+#[tauri::command]
+pub async fn delete_proxy_logs_in_range(from_ts: i64, to_ts: i64) -> Result<usize, String> {
+    tokio::task::spawn_blocking(move || {
+        crate::modules::proxy_db::delete_logs_in_range(from_ts, to_ts)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
 /// 获取反代请求日志 (分页)
 /// 获取反代请求日志 (分页)
 #[tauri::command]
@@ -424,6 +566,156 @@ pub async fn get_proxy_log_detail(log_id: String) -> Result<ProxyRequestLog, Str
         .map_err(|e| e.to_string())?
 }
 
+/// [NEW] 为单条日志设置/清除人工备注
+#[tauri::command]
+pub async fn set_log_note(log_id: String, note: Option<String>) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || crate::modules::proxy_db::set_log_note(&log_id, note))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// [NEW] 标记/取消标记单条日志
+#[tauri::command]
+pub async fn set_log_flag(log_id: String, flagged: bool) -> Result<(), String> {
+    tokio::task::spawn_blocking(move || crate::modules::proxy_db::set_log_flag(&log_id, flagged))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 日志重放结果
+#[derive(Debug, serde::Serialize)]
+pub struct ReplayResult {
+    pub log_id: String,
+    pub model: String,
+    pub account_email: String,
+    pub status: u16,
+    pub duration: u64, // ms
+    pub response_body: String,
+}
+
+/// [NEW] 重放一条历史请求日志 (调试用)
+///
+/// 从 proxy_db 读取该日志保存的 request_body，按其 protocol 重建上游请求体，
+/// 走当前账号池重新发送，返回新的 status/body/duration。
+/// 不写入新的监控日志，避免重放请求污染流量统计。
+#[tauri::command]
+pub async fn replay_proxy_log(
+    log_id: String,
+    state: State<'_, ProxyServiceState>,
+) -> Result<ReplayResult, String> {
+    // 1. 加载日志详情 (含 request_body)
+    let log_id_clone = log_id.clone();
+    let log =
+        tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_log_detail(&log_id_clone))
+            .await
+            .map_err(|e| e.to_string())??;
+
+    let request_body = log
+        .request_body
+        .as_deref()
+        .filter(|b| !b.trim().is_empty())
+        .ok_or_else(|| "该日志没有保存请求体，无法重放 (请开启请求监控后重试)".to_string())?;
+
+    // 2. 需要运行中的反代实例 (账号池 + UpstreamClient)
+    let instance_lock = state.instance.read().await;
+    let instance = instance_lock
+        .as_ref()
+        .ok_or_else(|| "Proxy service is not running".to_string())?;
+    let token_manager = instance.token_manager.clone();
+    let upstream = instance.axum_server.upstream.clone();
+    drop(instance_lock);
+
+    // 优先用路由后的模型名，保持与原请求一致
+    let model = log
+        .mapped_model
+        .clone()
+        .or_else(|| log.model.clone())
+        .ok_or_else(|| "该日志没有模型信息，无法重放".to_string())?;
+    let protocol = log.protocol.clone().unwrap_or_else(|| "gemini".to_string());
+
+    // 3. 从账号池获取 Token (重放不做会话粘性)
+    let quota_group = if model.to_lowercase().contains("claude") {
+        "claude"
+    } else {
+        "gemini"
+    };
+    let (access_token, project_id, email, account_id, _wait_ms) = token_manager
+        .get_token(quota_group, false, None, &model, None)
+        .await?;
+
+    // 4. 按原协议重建上游 v1internal 请求体
+    let body: serde_json::Value = match protocol.as_str() {
+        "anthropic" | "claude" => {
+            let claude_req: crate::proxy::mappers::claude::models::ClaudeRequest =
+                serde_json::from_str(request_body)
+                    .map_err(|e| format!("Failed to parse stored Claude request body: {}", e))?;
+            crate::proxy::mappers::claude::transform_claude_request_in(
+                &claude_req,
+                &project_id,
+                false,
+            )?
+        }
+        "openai" => {
+            let openai_req: crate::proxy::mappers::openai::OpenAIRequest =
+                serde_json::from_str(request_body)
+                    .map_err(|e| format!("Failed to parse stored OpenAI request body: {}", e))?;
+            let (gemini_body, _session_id, _message_count) =
+                crate::proxy::mappers::openai::transform_openai_request(
+                    &openai_req,
+                    &project_id,
+                    &model,
+                );
+            gemini_body
+        }
+        _ => {
+            // gemini / warmup 等: 请求体本身已是 Gemini 格式，直接包装
+            let parsed: serde_json::Value = serde_json::from_str(request_body)
+                .map_err(|e| format!("Failed to parse stored request body: {}", e))?;
+            crate::proxy::mappers::gemini::wrapper::wrap_request(&parsed, &project_id, &model, None)
+        }
+    };
+
+    tracing::info!(
+        "[Replay] Replaying log {} via {} (model: {}, protocol: {})",
+        log_id,
+        email,
+        model,
+        protocol
+    );
+
+    // 5. 非流式重放，便于完整捕获响应体
+    let start_time = std::time::Instant::now();
+    let response = upstream
+        .call_v1_internal(
+            "generateContent",
+            &access_token,
+            body,
+            None,
+            Some(account_id.as_str()),
+        )
+        .await?;
+
+    let status = response.status().as_u16();
+    let response_body = response.text().await.map_err(|e| e.to_string())?;
+    let duration = start_time.elapsed().as_millis() as u64;
+
+    tracing::info!(
+        "[Replay] Log {} replayed: HTTP {} ({}ms)",
+        log_id,
+        status,
+        duration
+    );
+
+    Ok(ReplayResult {
+        log_id,
+        model,
+        account_email: email,
+        status,
+        duration,
+        response_body,
+    })
+}
+
 /// 获取日志总数
 #[tauri::command]
 pub async fn get_proxy_logs_count() -> Result<u64, String> {
@@ -477,9 +769,18 @@ pub async fn export_proxy_logs_json(file_path: String, json_data: String) -> Res
 pub async fn get_proxy_logs_count_filtered(
     filter: String,
     errors_only: bool,
+    account_email: Option<String>,
+    status_min: Option<u16>,
+    status_max: Option<u16>,
 ) -> Result<u64, String> {
     tokio::task::spawn_blocking(move || {
-        crate::modules::proxy_db::get_logs_count_filtered(&filter, errors_only)
+        crate::modules::proxy_db::get_logs_count_filtered(
+            &filter,
+            errors_only,
+            account_email,
+            status_min,
+            status_max,
+        )
     })
     .await
     .map_err(|e| e.to_string())?
@@ -490,16 +791,39 @@ pub async fn get_proxy_logs_count_filtered(
 pub async fn get_proxy_logs_filtered(
     filter: String,
     errors_only: bool,
+    account_email: Option<String>,
+    status_min: Option<u16>,
+    status_max: Option<u16>,
     limit: usize,
     offset: usize,
 ) -> Result<Vec<crate::proxy::monitor::ProxyRequestLog>, String> {
     tokio::task::spawn_blocking(move || {
-        crate::modules::proxy_db::get_logs_filtered(&filter, errors_only, limit, offset)
+        crate::modules::proxy_db::get_logs_filtered(
+            &filter,
+            errors_only,
+            account_email,
+            status_min,
+            status_max,
+            limit,
+            offset,
+        )
     })
     .await
     .map_err(|e| e.to_string())?
 }
 
+/// [NEW] 游标分页获取日志 (keyset): 深翻页性能不随历史行数退化。
+/// cursor = None 从最新一条开始；用返回的 next_cursor 继续向更早滚动
+#[tauri::command]
+pub async fn get_proxy_logs_before(
+    cursor: Option<crate::modules::proxy_db::LogCursor>,
+    limit: usize,
+) -> Result<crate::modules::proxy_db::LogPage, String> {
+    crate::error::run_blocking(move || crate::modules::proxy_db::get_logs_before(cursor, limit))
+        .await
+        .map_err(String::from)
+}
+
 /// 生成 API Key
 #[tauri::command]
 pub fn generate_api_key() -> String {
@@ -552,6 +876,21 @@ pub async fn update_model_mapping(
     Ok(())
 }
 
+/// [NEW] 用当前持久化的映射配置试算一个模型名的路由结果 (不发送真实流量)
+/// 返回解析后的模型名及命中的规则，供 UI 验证别名/通配符配置
+#[tauri::command]
+pub async fn resolve_model_alias(
+    requested: String,
+) -> Result<crate::proxy::common::model_mapping::ModelRouteResolution, String> {
+    let app_config = crate::modules::config::load_app_config()?;
+    Ok(
+        crate::proxy::common::model_mapping::resolve_model_route_explained(
+            &requested,
+            &app_config.proxy.custom_mapping,
+        ),
+    )
+}
+
 fn join_base_url(base: &str, path: &str) -> String {
     let base = base.trim_end_matches('/');
     let path = if path.starts_with('/') {
@@ -773,6 +1112,34 @@ pub async fn get_preferred_account(
     }
 }
 
+/// [NEW] 设置调度分组约束 (None 表示不限制；约束后只在该分组的账号中轮询)
+#[tauri::command]
+pub async fn set_proxy_group_filter(
+    state: State<'_, ProxyServiceState>,
+    group: Option<String>,
+) -> Result<(), String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        instance.token_manager.set_group_filter(group).await;
+        Ok(())
+    } else {
+        Err("服务未运行".to_string())
+    }
+}
+
+/// [NEW] 获取当前调度分组约束
+#[tauri::command]
+pub async fn get_proxy_group_filter(
+    state: State<'_, ProxyServiceState>,
+) -> Result<Option<String>, String> {
+    let instance_lock = state.instance.read().await;
+    if let Some(instance) = instance_lock.as_ref() {
+        Ok(instance.token_manager.get_group_filter().await)
+    } else {
+        Ok(None)
+    }
+}
+
 /// 清除指定账号的限流记录
 #[tauri::command]
 pub async fn clear_proxy_rate_limit(
@@ -801,6 +1168,34 @@ pub async fn clear_all_proxy_rate_limits(
     }
 }
 
+/// [NEW] 列出当前有效的粘性会话绑定
+#[tauri::command]
+pub async fn get_sticky_mappings(
+) -> Result<Vec<crate::proxy::middleware::sticky_session::StickyMappingView>, String> {
+    Ok(crate::proxy::middleware::sticky_session::list_mappings())
+}
+
+/// [NEW] 清除粘性会话绑定：key = None 时全部清除，返回清除条数
+#[tauri::command]
+pub async fn clear_sticky_mappings(key: Option<String>) -> Result<usize, String> {
+    Ok(crate::proxy::middleware::sticky_session::clear_mappings(
+        key.as_deref(),
+    ))
+}
+
+/// [NEW] 获取响应缓存统计 (命中/未命中/有效条数)
+#[tauri::command]
+pub async fn get_response_cache_stats(
+) -> Result<crate::proxy::middleware::response_cache::ResponseCacheStats, String> {
+    Ok(crate::proxy::middleware::response_cache::cache_stats())
+}
+
+/// [NEW] 清空响应缓存，返回清除条数
+#[tauri::command]
+pub async fn clear_response_cache() -> Result<usize, String> {
+    Ok(crate::proxy::middleware::response_cache::clear_cache())
+}
+
 /// 强制清理端口
 #[tauri::command]
 pub async fn force_cleanup_ports() -> Result<bool, String> {
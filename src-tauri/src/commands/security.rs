@@ -44,7 +44,7 @@ pub struct IpStatsResponse {
 /// 获取 IP 访问日志列表
 #[tauri::command]
 pub async fn get_ip_access_logs(query: IpAccessLogQuery) -> Result<IpAccessLogResponse, String> {
-    tokio::task::spawn_blocking(move || {
+    crate::error::run_blocking(move || -> Result<IpAccessLogResponse, String> {
         let offset = (query.page.max(1) - 1) * query.page_size;
 
         let logs = security_db::get_ip_access_logs(
@@ -60,13 +60,41 @@ pub async fn get_ip_access_logs(query: IpAccessLogQuery) -> Result<IpAccessLogRe
         Ok(IpAccessLogResponse { logs, total })
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(String::from)
+}
+
+/// [NEW] 导出时间范围内的 IP 访问日志到文件 (CSV/JSON，安全审计用)
+/// 可选沿用列表页的过滤条件，返回导出的条数
+#[tauri::command]
+pub async fn export_ip_access_logs(
+    file_path: String,
+    from_ts: i64,
+    to_ts: i64,
+    format: String,
+    search: Option<String>,
+    blocked_only: Option<bool>,
+) -> Result<usize, String> {
+    crate::error::run_blocking(move || -> Result<usize, String> {
+        let (content, count) = security_db::export_ip_access_logs(
+            from_ts,
+            to_ts,
+            &format,
+            search.as_deref(),
+            blocked_only.unwrap_or(false),
+        )?;
+
+        std::fs::write(&file_path, content).map_err(|e| format!("Failed to write file: {}", e))?;
+
+        Ok(count)
+    })
+    .await
+    .map_err(String::from)
 }
 
 /// 获取 IP 统计信息
 #[tauri::command]
 pub async fn get_ip_stats() -> Result<IpStatsResponse, String> {
-    tokio::task::spawn_blocking(|| {
+    crate::error::run_blocking(|| -> Result<IpStatsResponse, String> {
         let stats = security_db::get_ip_stats()?;
         let top_ips = security_db::get_top_ips(10, 24)?; // Top 10 IPs in last 24 hours
 
@@ -78,15 +106,23 @@ pub async fn get_ip_stats() -> Result<IpStatsResponse, String> {
         })
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 /// 清空 IP 访问日志
 #[tauri::command]
 pub async fn clear_ip_access_logs() -> Result<(), String> {
-    tokio::task::spawn_blocking(|| security_db::clear_ip_access_logs())
+    crate::error::run_blocking(|| security_db::clear_ip_access_logs())
+        .await
+        .map_err(String::from)
+}
+
+/// [NEW] 从反代请求日志重建 IP 访问日志 (升级后回填 Security Monitor)
+#[tauri::command]
+pub async fn rebuild_ip_access_logs() -> Result<usize, String> {
+    crate::error::run_blocking(|| security_db::rebuild_ip_logs_from_proxy_logs())
         .await
-        .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 // ==================== IP 黑名单命令 ====================
@@ -94,9 +130,9 @@ pub async fn clear_ip_access_logs() -> Result<(), String> {
 /// 获取 IP 黑名单列表
 #[tauri::command]
 pub async fn get_ip_blacklist() -> Result<Vec<security_db::IpBlacklistEntry>, String> {
-    tokio::task::spawn_blocking(|| security_db::get_blacklist())
+    crate::error::run_blocking(|| security_db::get_blacklist())
         .await
-        .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 /// 添加 IP 到黑名单
@@ -110,7 +146,7 @@ pub async fn add_ip_to_blacklist(request: AddBlacklistRequest) -> Result<(), Str
         );
     }
 
-    tokio::task::spawn_blocking(move || {
+    crate::error::run_blocking(move || {
         security_db::add_to_blacklist(
             &request.ip_pattern,
             request.reason.as_deref(),
@@ -119,49 +155,39 @@ pub async fn add_ip_to_blacklist(request: AddBlacklistRequest) -> Result<(), Str
         )
     })
     .await
-    .map_err(|e| e.to_string())??;
+    .map_err(String::from)?;
     Ok(())
 }
 
-/// 从黑名单移除 IP
+/// 从黑名单移除 IP (按模式精确匹配)
 #[tauri::command]
 pub async fn remove_ip_from_blacklist(ip_pattern: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
-        // 先获取黑名单列表，找到对应的id
-        let entries = security_db::get_blacklist()?;
-        let entry = entries.iter().find(|e| e.ip_pattern == ip_pattern);
-
-        if let Some(entry) = entry {
-            security_db::remove_from_blacklist(&entry.id)
-        } else {
-            Err(format!("IP pattern {} not found in blacklist", ip_pattern))
-        }
-    })
-    .await
-    .map_err(|e| e.to_string())?
+    crate::error::run_blocking(move || security_db::remove_from_blacklist_by_pattern(&ip_pattern))
+        .await
+        .map_err(String::from)
 }
 
 /// 清空黑名单
 #[tauri::command]
 pub async fn clear_ip_blacklist() -> Result<(), String> {
-    tokio::task::spawn_blocking(|| {
-        // 获取所有黑名单条目并逐个删除
+    crate::error::run_blocking(|| -> Result<(), String> {
+        // 获取所有黑名单条目并按 id 逐个删除
         let entries = security_db::get_blacklist()?;
         for entry in entries {
-            security_db::remove_from_blacklist(&entry.ip_pattern)?;
+            security_db::remove_from_blacklist(&entry.id)?;
         }
         Ok(())
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 /// 检查 IP 是否在黑名单中
 #[tauri::command]
 pub async fn check_ip_in_blacklist(ip: String) -> Result<bool, String> {
-    tokio::task::spawn_blocking(move || security_db::is_ip_in_blacklist(&ip))
+    crate::error::run_blocking(move || security_db::is_ip_in_blacklist(&ip))
         .await
-        .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 // ==================== IP 白名单命令 ====================
@@ -169,9 +195,9 @@ pub async fn check_ip_in_blacklist(ip: String) -> Result<bool, String> {
 /// 获取 IP 白名单列表
 #[tauri::command]
 pub async fn get_ip_whitelist() -> Result<Vec<security_db::IpWhitelistEntry>, String> {
-    tokio::task::spawn_blocking(|| security_db::get_whitelist())
+    crate::error::run_blocking(|| security_db::get_whitelist())
         .await
-        .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 /// 添加 IP 到白名单
@@ -185,18 +211,18 @@ pub async fn add_ip_to_whitelist(request: AddWhitelistRequest) -> Result<(), Str
         );
     }
 
-    tokio::task::spawn_blocking(move || {
+    crate::error::run_blocking(move || {
         security_db::add_to_whitelist(&request.ip_pattern, request.description.as_deref())
     })
     .await
-    .map_err(|e| e.to_string())??;
+    .map_err(String::from)?;
     Ok(())
 }
 
 /// 从白名单移除 IP
 #[tauri::command]
 pub async fn remove_ip_from_whitelist(ip_pattern: String) -> Result<(), String> {
-    tokio::task::spawn_blocking(move || {
+    crate::error::run_blocking(move || {
         // 先获取白名单列表，找到对应的id
         let entries = security_db::get_whitelist()?;
         let entry = entries.iter().find(|e| e.ip_pattern == ip_pattern);
@@ -208,30 +234,30 @@ pub async fn remove_ip_from_whitelist(ip_pattern: String) -> Result<(), String>
         }
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 /// 清空白名单
 #[tauri::command]
 pub async fn clear_ip_whitelist() -> Result<(), String> {
-    tokio::task::spawn_blocking(|| {
-        // 获取所有白名单条目并逐个删除
+    crate::error::run_blocking(|| -> Result<(), String> {
+        // 获取所有白名单条目并按 id 逐个删除 (同黑名单，remove 接受 id 而非模式)
         let entries = security_db::get_whitelist()?;
         for entry in entries {
-            security_db::remove_from_whitelist(&entry.ip_pattern)?;
+            security_db::remove_from_whitelist(&entry.id)?;
         }
         Ok(())
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 /// 检查 IP 是否在白名单中
 #[tauri::command]
 pub async fn check_ip_in_whitelist(ip: String) -> Result<bool, String> {
-    tokio::task::spawn_blocking(move || security_db::is_ip_in_whitelist(&ip))
+    crate::error::run_blocking(move || security_db::is_ip_in_whitelist(&ip))
         .await
-        .map_err(|e| e.to_string())?
+        .map_err(String::from)
 }
 
 // ==================== 安全配置命令 ====================
@@ -247,14 +273,16 @@ pub async fn get_security_config(
         return Ok(instance.config.security_monitor.clone());
     }
 
-    tokio::task::spawn_blocking(|| {
-        // 2. 如果服务未运行，从磁盘加载
-        let app_config = crate::modules::config::load_app_config()
-            .map_err(|e| format!("Failed to load config: {}", e))?;
-        Ok(app_config.proxy.security_monitor)
-    })
+    crate::error::run_blocking(
+        || -> Result<crate::proxy::config::SecurityMonitorConfig, String> {
+            // 2. 如果服务未运行，从磁盘加载
+            let app_config = crate::modules::config::load_app_config()
+                .map_err(|e| format!("Failed to load config: {}", e))?;
+            Ok(app_config.proxy.security_monitor)
+        },
+    )
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 /// 更新安全监控配置
@@ -264,7 +292,7 @@ pub async fn update_security_config(
     app_state: State<'_, crate::commands::proxy::ProxyServiceState>,
 ) -> Result<(), String> {
     let config_clone = config.clone();
-    tokio::task::spawn_blocking(move || {
+    crate::error::run_blocking(move || {
         // 1. 同步保存到配置文件
         let mut app_config = crate::modules::config::load_app_config()
             .map_err(|e| format!("Failed to load config: {}", e))?;
@@ -273,7 +301,7 @@ pub async fn update_security_config(
             .map_err(|e| format!("Failed to save config: {}", e))
     })
     .await
-    .map_err(|e| e.to_string())??;
+    .map_err(String::from)?;
 
     // 2. 更新内存中的配置 (如果服务正在运行)
     {
@@ -300,11 +328,11 @@ pub async fn get_ip_token_stats(
     limit: Option<usize>,
     hours: Option<i64>,
 ) -> Result<Vec<crate::modules::proxy_db::IpTokenStats>, String> {
-    tokio::task::spawn_blocking(move || {
+    crate::error::run_blocking(move || {
         crate::modules::proxy_db::get_token_usage_by_ip(limit.unwrap_or(100), hours.unwrap_or(720))
     })
     .await
-    .map_err(|e| e.to_string())?
+    .map_err(String::from)
 }
 
 // ==================== 辅助函数 ====================
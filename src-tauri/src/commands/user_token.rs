@@ -13,6 +13,9 @@ pub struct CreateTokenRequest {
     pub max_ips: i32,
     pub curfew_start: Option<String>,
     pub curfew_end: Option<String>,
+    /// [NEW] 固定路由到指定账号
+    #[serde(default)]
+    pub pinned_account_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -23,6 +26,9 @@ pub struct UpdateTokenRequest {
     pub max_ips: Option<i32>,
     pub curfew_start: Option<Option<String>>,
     pub curfew_end: Option<Option<String>>,
+    /// [NEW] Some(None) = 取消固定, Some(Some(id)) = 固定到账号
+    #[serde(default)]
+    pub pinned_account_id: Option<Option<String>>,
 }
 
 // 命令实现
@@ -43,6 +49,7 @@ pub async fn create_user_token(request: CreateTokenRequest) -> Result<UserToken,
         request.max_ips,
         request.curfew_start,
         request.curfew_end,
+        request.pinned_account_id,
     )
 }
 
@@ -57,6 +64,7 @@ pub async fn update_user_token(id: String, request: UpdateTokenRequest) -> Resul
         request.max_ips,
         request.curfew_start,
         request.curfew_end,
+        request.pinned_account_id,
     )
 }
 
@@ -175,6 +183,12 @@ pub async fn login_topoo_user(app_handle: tauri::AppHandle) -> Result<LoginRespo
         proxy_id: None,
         proxy_bound_at: None,
         custom_label: None,
+        custom_headers: std::collections::HashMap::new(),
+        group: None,
+        weight: 1,
+        order_index: 0,
+        last_error: None,
+        last_error_at: None,
     };
 
     modules::logger::log_info(&format!("Topoo 用户登录成功: {}", account.email));
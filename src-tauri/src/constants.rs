@@ -1,4 +1,4 @@
-use std::sync::LazyLock;
+use std::sync::{LazyLock, RwLock};
 use regex::Regex;
 
 /// URL to fetch the latest Antigravity version
@@ -98,6 +98,32 @@ pub static USER_AGENT: LazyLock<String> = LazyLock::new(|| {
     )
 });
 
+/// Runtime User-Agent override (from app config or the `set_user_agent_override` command).
+/// Some upstreams fingerprint on the exact UA string, so users may need to pin a
+/// specific Antigravity build string instead of the computed default.
+static USER_AGENT_OVERRIDE: RwLock<Option<String>> = RwLock::new(None);
+
+/// Set or clear the runtime User-Agent override.
+/// Empty/whitespace-only strings are treated as "no override".
+pub fn set_user_agent_override(ua: Option<String>) {
+    let normalized = ua.filter(|s| !s.trim().is_empty());
+    if let Ok(mut lock) = USER_AGENT_OVERRIDE.write() {
+        tracing::info!("User-Agent override updated: {:?}", normalized);
+        *lock = normalized;
+    }
+}
+
+/// Effective User-Agent for upstream requests: override if set, otherwise the
+/// computed [`USER_AGENT`] constant.
+pub fn effective_user_agent() -> String {
+    if let Ok(lock) = USER_AGENT_OVERRIDE.read() {
+        if let Some(ua) = lock.as_ref() {
+            return ua.clone();
+        }
+    }
+    USER_AGENT.clone()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
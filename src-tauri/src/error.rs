@@ -40,3 +40,114 @@ impl Serialize for AppError {
 
 // Implement alias for Result to simplify usage
 pub type AppResult<T> = Result<T, AppError>;
+
+/// [NEW] 统一的结构化错误类型：让调用方 (及前端) 能按错误种类分支处理。
+/// Display 保持为裸消息 (不加前缀)，因此 `From<GatewayError> for String`
+/// 产生与迁移前完全一致的文案；kind 仅在序列化 (`{ kind, message }`) 中体现。
+/// 迁移期间旧的 `Result<_, String>` 签名通过 `?` + From 继续工作。
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum GatewayError {
+    /// 文件系统读写失败
+    #[error("{0}")]
+    Io(String),
+    /// SQLite / 账号文件等持久化层错误
+    #[error("{0}")]
+    Db(String),
+    /// JSON / protobuf / 时间戳等解析失败
+    #[error("{0}")]
+    Parse(String),
+    /// Token 失效、鉴权被拒
+    #[error("{0}")]
+    Auth(String),
+    /// 目标资源不存在 (账号/文件/key)
+    #[error("{0}")]
+    NotFound(String),
+    /// 数据库被占用 (如 IDE 持有 state.vscdb 锁)
+    #[error("{0}")]
+    Locked(String),
+    /// 上游 HTTP 请求失败
+    #[error("{0}")]
+    Upstream(String),
+    /// 配置缺失或非法
+    #[error("{0}")]
+    Config(String),
+    /// 其他未分类错误
+    #[error("{0}")]
+    Other(String),
+}
+
+impl GatewayError {
+    /// 稳定的 kind 标识，供前端做类型化/本地化展示
+    pub fn kind(&self) -> &'static str {
+        match self {
+            GatewayError::Io(_) => "io",
+            GatewayError::Db(_) => "db",
+            GatewayError::Parse(_) => "parse",
+            GatewayError::Auth(_) => "auth",
+            GatewayError::NotFound(_) => "not_found",
+            GatewayError::Locked(_) => "locked",
+            GatewayError::Upstream(_) => "upstream",
+            GatewayError::Config(_) => "config",
+            GatewayError::Other(_) => "other",
+        }
+    }
+}
+
+// 序列化为 { kind, message }，前端可以按 kind 分支
+impl Serialize for GatewayError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        use serde::ser::SerializeStruct;
+        let mut s = serializer.serialize_struct("GatewayError", 2)?;
+        s.serialize_field("kind", self.kind())?;
+        s.serialize_field("message", &self.to_string())?;
+        s.end()
+    }
+}
+
+// 兼容层：允许仍然返回 Result<_, String> 的调用方直接用 `?` 传播
+impl From<GatewayError> for String {
+    fn from(e: GatewayError) -> Self {
+        e.to_string()
+    }
+}
+
+// 兼容层 (反向)：已迁移的函数内部调用尚未迁移的 String 错误辅助函数时，
+// `?` 会把裸消息收进 Other；这些调用点随迁移推进逐步获得精确 kind
+impl From<String> for GatewayError {
+    fn from(msg: String) -> Self {
+        GatewayError::Other(msg)
+    }
+}
+
+pub type GatewayResult<T> = Result<T, GatewayError>;
+
+/// [NEW] spawn_blocking 的 panic 安全封装：阻塞闭包 panic 时提取 panic 载荷、
+/// 记录日志并返回带具体原因的错误，而不是不透明的 "task ... panicked"。
+/// 闭包自身的错误经 From 归入 GatewayError (String 错误落到 Other)
+pub async fn run_blocking<T, E, F>(task: F) -> GatewayResult<T>
+where
+    F: FnOnce() -> Result<T, E> + Send + 'static,
+    T: Send + 'static,
+    E: Into<GatewayError> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(task).await {
+        Ok(result) => result.map_err(Into::into),
+        Err(join_err) if join_err.is_panic() => {
+            let payload = join_err.into_panic();
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| (*s).to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "non-string panic payload".to_string());
+            tracing::error!("[Blocking] Task panicked: {}", message);
+            Err(GatewayError::Other(format!(
+                "blocking task panicked: {}",
+                message
+            )))
+        }
+        Err(join_err) => Err(GatewayError::Other(join_err.to_string())),
+    }
+}
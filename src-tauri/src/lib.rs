@@ -62,6 +62,13 @@ pub fn run() {
     // Initialize token stats database
     if let Err(e) = modules::token_stats::init_db() {
         error!("Failed to initialize token stats database: {}", e);
+        // [NEW] 一次性关闭统计写入，避免之后每个请求重复报错；转发不受影响
+        modules::token_stats::disable_recording(&e.to_string());
+    }
+
+    // [NEW] Initialize account audit events database
+    if let Err(e) = modules::account_events::init_db() {
+        error!("Failed to initialize account events database: {}", e);
     }
 
     // Initialize security database
@@ -85,6 +92,9 @@ pub fn run() {
         let rt = tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime");
         rt.block_on(async {
             // Initialize states manually
+            modules::integration::set_global_manager(
+                crate::modules::integration::SystemManager::Headless,
+            );
             let proxy_state = commands::proxy::ProxyServiceState::new();
             let cf_state = Arc::new(commands::cloudflared::CloudflaredState::new());
 
@@ -94,6 +104,14 @@ pub fn run() {
                     // Force LAN access in headless/docker mode so it binds to 0.0.0.0
                     config.proxy.allow_lan_access = true;
 
+                    // Apply persisted User-Agent override before any upstream request
+                    constants::set_user_agent_override(config.proxy.user_agent_override.clone());
+
+                    // [NEW] Apply persisted plain_log setting (env TOPOO_PLAIN_LOG still wins)
+                    if config.plain_log {
+                        modules::logger::set_plain_log(true);
+                    }
+
                     // [NEW] 支持通过环境变量注入 API Key
                     // 优先级：ABV_API_KEY > API_KEY > 配置文件
                     let env_key = std::env::var("ABV_API_KEY")
@@ -145,6 +163,10 @@ pub fn run() {
                         }
                     }
 
+                    // [NEW] TOPOO_* 环境变量覆盖 (端口/上游代理/管理密码等)，
+                    // 优先级高于配置文件，便于 Docker 免配置文件部署
+                    modules::config::apply_env_overrides(&mut config);
+
                     info!("--------------------------------------------------");
                     info!("🚀 Headless mode proxy service starting...");
                     info!("📍 Port: {}", config.proxy.port);
@@ -193,6 +215,7 @@ pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .plugin(tauri_plugin_notification::init())
         .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_autostart::init(
             tauri_plugin_autostart::MacosLauncher::LaunchAgent,
@@ -218,11 +241,24 @@ pub fn run() {
             modules::tray::create_tray(app.handle())?;
             info!("Tray created");
 
+            // Register global SystemManager for deep modules (notifications/events)
+            modules::integration::set_global_manager(
+                crate::modules::integration::SystemManager::Desktop(app.handle().clone()),
+            );
+
             // 立即启动管理服务器 (8045)，以便 Web 端能访问
             let handle = app.handle().clone();
             tauri::async_runtime::spawn(async move {
                 // Load config
                 if let Ok(config) = modules::config::load_app_config() {
+                    // Apply persisted User-Agent override before any upstream request
+                    constants::set_user_agent_override(config.proxy.user_agent_override.clone());
+
+                    // [NEW] Apply persisted plain_log setting (env TOPOO_PLAIN_LOG still wins)
+                    if config.plain_log {
+                        modules::logger::set_plain_log(true);
+                    }
+
                     let state = handle.state::<commands::proxy::ProxyServiceState>();
                     let cf_state = handle.state::<commands::cloudflared::CloudflaredState>();
                     let integration =
@@ -294,10 +330,18 @@ pub fn run() {
             // Account management commands
             commands::list_accounts,
             commands::add_account,
+            commands::import_account_from_token_json,
             commands::delete_account,
             commands::delete_accounts,
+            commands::purge_account_data,
             commands::reorder_accounts,
             commands::switch_account,
+            commands::refresh_account_token,
+            commands::validate_pool,
+            commands::simulate_account_switch,
+            commands::benchmark_switch,
+            commands::verify_account_consistency,
+            commands::switch_account_data_only,
             // Device fingerprint
             commands::get_device_profiles,
             commands::bind_device_profile,
@@ -308,8 +352,16 @@ pub fn run() {
             commands::list_device_versions,
             commands::restore_device_version,
             commands::delete_device_version,
+            commands::get_device_history,
+            commands::revert_device_profile,
             commands::open_device_folder,
             commands::get_current_account,
+            commands::preview_injection,
+            commands::test_inject_token,
+            commands::get_injected_identity,
+            commands::list_state_db_keys,
+            commands::restore_last_token,
+            commands::detect_ide_version,
             // Quota commands
             commands::fetch_account_quota,
             commands::refresh_all_quotas,
@@ -317,11 +369,18 @@ pub fn run() {
             // Config commands
             commands::load_config,
             commands::save_config,
+            commands::get_protected_models_mode,
+            commands::set_protected_models_mode,
+            commands::export_config,
+            commands::import_config,
+            commands::set_user_agent_override,
+            commands::get_effective_user_agent,
             // Additional commands
             commands::prepare_oauth_url,
             commands::start_oauth_login,
             commands::complete_oauth_login,
             commands::cancel_oauth_login,
+            commands::refresh_oidc_discovery,
             commands::submit_oauth_code,
             commands::import_v1_accounts,
             commands::import_from_db,
@@ -336,6 +395,7 @@ pub fn run() {
             commands::get_data_dir_path,
             commands::show_main_window,
             commands::set_window_theme,
+            commands::test_notification,
             commands::get_antigravity_path,
             commands::get_antigravity_args,
             commands::check_for_updates,
@@ -344,33 +404,59 @@ pub fn run() {
             commands::should_check_updates,
             commands::update_last_check_time,
             commands::toggle_proxy_status,
+            commands::set_all_accounts_proxy_enabled,
+            commands::get_pool_status,
+            commands::clear_account_last_error,
             commands::toggle_account_disabled,
+            commands::get_account_events,
+            commands::get_account_custom_headers,
+            commands::set_account_custom_headers,
+            commands::set_account_group,
+            commands::rename_account_group,
+            commands::list_account_groups,
+            commands::get_account_weight,
+            commands::set_account_weight,
             // Proxy service commands
             commands::proxy::start_proxy_service,
             commands::proxy::stop_proxy_service,
             commands::proxy::get_proxy_status,
             commands::proxy::get_proxy_stats,
+            commands::proxy::get_live_account_counters,
+            commands::proxy::check_port_available,
+            commands::proxy::get_upstream_in_flight,
             commands::proxy::get_proxy_logs,
             commands::proxy::get_proxy_logs_paginated,
             commands::proxy::get_proxy_log_detail,
+            commands::proxy::set_log_note,
+            commands::proxy::set_log_flag,
+            commands::proxy::replay_proxy_log,
             commands::proxy::get_proxy_logs_count,
             commands::proxy::export_proxy_logs,
             commands::proxy::export_proxy_logs_json,
             commands::proxy::get_proxy_logs_count_filtered,
             commands::proxy::get_proxy_logs_filtered,
+            commands::proxy::get_proxy_logs_before,
             commands::proxy::set_proxy_monitor_enabled,
             commands::proxy::clear_proxy_logs,
+            commands::proxy::delete_proxy_logs_in_range,
             commands::proxy::generate_api_key,
             commands::proxy::reload_proxy_accounts,
             commands::proxy::update_model_mapping,
+            commands::proxy::resolve_model_alias,
             commands::proxy::fetch_zai_models,
             commands::proxy::get_proxy_scheduling_config,
             commands::proxy::update_proxy_scheduling_config,
             commands::proxy::clear_proxy_session_bindings,
             commands::proxy::set_preferred_account,
             commands::proxy::get_preferred_account,
+            commands::proxy::set_proxy_group_filter,
+            commands::proxy::get_proxy_group_filter,
             commands::proxy::clear_proxy_rate_limit,
             commands::proxy::clear_all_proxy_rate_limits,
+            commands::proxy::get_sticky_mappings,
+            commands::proxy::clear_sticky_mappings,
+            commands::proxy::get_response_cache_stats,
+            commands::proxy::clear_response_cache,
             commands::proxy::force_cleanup_ports,
             // Autostart commands
             commands::autostart::toggle_auto_launch,
@@ -382,26 +468,37 @@ pub fn run() {
             commands::get_http_api_settings,
             commands::save_http_api_settings,
             // Token 统计命令
+            commands::get_stats_health,
             commands::get_token_stats_hourly,
             commands::get_token_stats_daily,
             commands::get_token_stats_weekly,
             commands::get_token_stats_by_account,
+            commands::get_token_stats_by_account_group,
             commands::get_token_stats_summary,
+            commands::get_token_stats_by_account_range,
+            commands::get_token_stats_summary_range,
             commands::get_token_stats_by_model,
+            commands::get_token_stats_by_model_ex,
+            commands::get_model_lifetime_stats,
+            commands::get_model_cap_usage,
             commands::get_token_stats_model_trend_minute,
             commands::get_token_stats_model_trend_hourly,
             commands::get_token_stats_model_trend_daily,
             commands::get_token_stats_account_trend_hourly,
             commands::get_token_stats_account_trend_daily,
+            commands::recompute_hourly_aggregates,
+            commands::rebuild_token_stats,
             proxy::cli_sync::get_cli_sync_status,
             proxy::cli_sync::execute_cli_sync,
             proxy::cli_sync::execute_cli_restore,
             proxy::cli_sync::get_cli_config_content,
             // Security/IP monitoring commands
             commands::security::get_ip_access_logs,
+            commands::security::export_ip_access_logs,
             commands::security::get_ip_stats,
             commands::security::get_ip_token_stats,
             commands::security::clear_ip_access_logs,
+            commands::security::rebuild_ip_access_logs,
             commands::security::get_ip_blacklist,
             commands::security::add_ip_to_blacklist,
             commands::security::remove_ip_from_blacklist,
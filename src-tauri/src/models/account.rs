@@ -1,5 +1,5 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use super::{token::TokenData, quota::QuotaData};
 
 /// 账号数据结构
@@ -57,6 +57,24 @@ pub struct Account {
     /// 用户自定义标签
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub custom_label: Option<String>,
+    /// 自定义上游请求头 (如组织 ID)，转发时附加；不允许覆盖 Authorization
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub custom_headers: HashMap<String, String>,
+    /// [NEW] 账号分组 (按项目切分统计与调度)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// [NEW] 调度权重 (Weighted 模式下按比例分配流量；0 = 仅手动选择，不参与自动调度)
+    #[serde(default = "default_account_weight")]
+    pub weight: u32,
+    /// [NEW] 显式调度顺序 (Priority 模式按此升序依次尝试；默认 0，相同值按池内顺序)
+    #[serde(default)]
+    pub order_index: i32,
+    /// [NEW] 该账号最近一次代理请求失败的错误信息 (非 2xx 时更新)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error: Option<String>,
+    /// [NEW] 最近一次失败的 Unix 时间戳
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_error_at: Option<i64>,
 }
 
 impl Account {
@@ -85,6 +103,12 @@ impl Account {
             proxy_id: None,
             proxy_bound_at: None,
             custom_label: None,
+            custom_headers: HashMap::new(),
+            group: None,
+            weight: default_account_weight(),
+            order_index: 0,
+            last_error: None,
+            last_error_at: None,
         }
     }
 
@@ -97,6 +121,10 @@ impl Account {
     }
 }
 
+fn default_account_weight() -> u32 {
+    1
+}
+
 /// 账号索引数据（accounts.json）
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AccountIndex {
@@ -27,12 +27,110 @@ pub struct AppConfig {
     pub circuit_breaker: CircuitBreakerConfig, // [NEW] Circuit breaker configuration
     #[serde(default = "default_target_app_name")]
     pub target_app_name: String, // [NEW] Target application name (e.g. Antigravity)
+    #[serde(default = "default_flatpak_app_id")]
+    pub flatpak_app_id: String, // [NEW] Flatpak app id for Linux detection (varies by packaging)
+    #[serde(default)]
+    pub oauth_open_mode: OauthOpenMode, // [NEW] How to surface the OAuth auth URL (browser/clipboard)
+    #[serde(default)]
+    pub hot_inject_without_restart: bool, // [NEW] Inject token into live IDE DB without close/restart
+    #[serde(default)]
+    pub injected_db_keys: Vec<InjectedKeySpec>, // [NEW] Data-driven injection set; empty = built-in default
+    #[serde(default)]
+    pub oauth_issuer: Option<String>, // [NEW] OIDC issuer for endpoint discovery; None = Google default
+    #[serde(default)]
+    pub oauth_scopes: Vec<String>, // [NEW] OAuth scope override; empty = built-in default (incl. openid)
+    #[serde(default)]
+    pub macos_close_strategy: MacosCloseStrategy, // [NEW] macOS fallback when no main process identified
+    #[serde(default = "default_require_ide_closed_for_injection")]
+    pub require_ide_closed_for_injection: bool, // [NEW] Refuse DB injection while the IDE still runs
+    #[serde(default)]
+    pub plain_log: bool, // [NEW] Strip emoji/decorations from log lines (ASCII-only consoles/CI)
+    #[serde(default = "default_switch_cooldown_ms")]
+    pub switch_cooldown_ms: u64, // [NEW] Minimum interval between account switches (anti-thrash)
+    #[serde(default = "default_refresh_concurrency")]
+    pub refresh_concurrency: usize, // [NEW] Max parallel account refreshes in the batch scan
+    #[serde(default = "default_refresh_account_timeout_secs")]
+    pub refresh_account_timeout_secs: u64, // [NEW] Per-account timeout for the batch refresh scan
+}
+
+/// [NEW] 数据驱动的数据库注入 key 描述：builder 决定写入值如何生成，
+/// IDE 新增必需 key 时只需改配置而无需发版
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InjectedKeySpec {
+    /// ItemTable 中的 key
+    pub key: String,
+    pub builder: InjectedKeyBuilder,
+    /// builder = literal 时写入的字面值
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+/// [NEW] 注入值构建方式
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InjectedKeyBuilder {
+    /// 读取现有值并合并新 Token (legacy protobuf blob)，key 必须已存在
+    LegacyMerge,
+    /// 生成统一 Token 消息 (unified protobuf blob)
+    Unified,
+    /// 写入固定字面值 (value 字段)
+    Literal,
+}
+
+/// [NEW] OAuth 授权链接打开方式
+/// - Browser: 调用系统默认浏览器打开 (原有行为，打开失败则中断流程)
+/// - ClipboardOnly: 不启动浏览器，仅发 `oauth-copy-url` 事件由前端写入剪贴板
+/// - Both: 先复制再尝试打开浏览器，打开失败不中断 (链接已在剪贴板)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OauthOpenMode {
+    #[default]
+    Browser,
+    ClipboardOnly,
+    Both,
+}
+
+/// [NEW] macOS 下 close_antigravity 未识别出主进程时的兜底关闭策略
+/// - MainOnly: 不广播 SIGTERM，直接等待后走 SIGKILL 升级 (最保守，可能留残留)
+/// - LowestPidFirst: 先 SIGTERM 最小 PID (通常是父进程，由它协调子进程退出)，
+///   等待后再升级，尽量避免 helper 先于主进程退出触发崩溃弹窗 (默认)
+/// - AllAtOnce: 一次性 SIGTERM 全部进程 (原有行为，可能弹窗)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacosCloseStrategy {
+    MainOnly,
+    #[default]
+    LowestPidFirst,
+    AllAtOnce,
+}
+
+fn default_require_ide_closed_for_injection() -> bool {
+    true
+}
+
+/// [NEW] 切换冷却默认 2s：足够吸收双击/连点，不影响正常手动切换节奏
+pub(crate) fn default_switch_cooldown_ms() -> u64 {
+    2000
+}
+
+/// [NEW] 批量刷新默认并发 5：足够跑满 40+ 账号的扫描，又不会触发上游限流
+fn default_refresh_concurrency() -> usize {
+    5
+}
+
+/// [NEW] 单账号刷新超时默认 30s (含一次重试的网络往返)
+fn default_refresh_account_timeout_secs() -> u64 {
+    30
 }
 
 fn default_target_app_name() -> String {
     "Topoo Gateway".to_string()
 }
 
+fn default_flatpak_app_id() -> String {
+    "com.google.Antigravity".to_string()
+}
+
 /// Scheduled warmup configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ScheduledWarmupConfig {
@@ -68,6 +166,18 @@ impl Default for ScheduledWarmupConfig {
     }
 }
 
+/// [NEW] 账号 protected_models 名单的解释模式，决定路由时如何使用该名单
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtectedModelsMode {
+    /// 排除名单 (默认，即现有行为)：名单内的模型不由该账号服务
+    #[default]
+    Exclude,
+    /// 独占名单：模型一旦被任一账号列入，仅列入它的账号可服务该模型;
+    /// 未被任何账号列入的模型不受影响
+    Reserve,
+}
+
 /// Quota protection configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuotaProtectionConfig {
@@ -80,6 +190,10 @@ pub struct QuotaProtectionConfig {
     /// List of monitored models (e.g. gemini-3-flash, gemini-3-pro-high, claude-sonnet-4-5)
     #[serde(default = "default_monitored_models")]
     pub monitored_models: Vec<String>,
+
+    /// [NEW] protected_models 的解释模式 (exclude = 排除名单 / reserve = 独占名单)
+    #[serde(default)]
+    pub protected_models_mode: ProtectedModelsMode,
 }
 
 fn default_monitored_models() -> Vec<String> {
@@ -97,6 +211,7 @@ impl QuotaProtectionConfig {
             enabled: false,
             threshold_percentage: 10, // Default 10% reserve
             monitored_models: default_monitored_models(),
+            protected_models_mode: ProtectedModelsMode::default(),
         }
     }
 }
@@ -188,6 +303,18 @@ impl AppConfig {
             pinned_quota_models: PinnedQuotaModelsConfig::default(),
             circuit_breaker: CircuitBreakerConfig::default(),
             target_app_name: default_target_app_name(),
+            flatpak_app_id: default_flatpak_app_id(),
+            oauth_open_mode: OauthOpenMode::default(),
+            hot_inject_without_restart: false,
+            injected_db_keys: Vec::new(),
+            oauth_issuer: None,
+            oauth_scopes: Vec::new(),
+            macos_close_strategy: MacosCloseStrategy::default(),
+            require_ide_closed_for_injection: default_require_ide_closed_for_injection(),
+            plain_log: false,
+            switch_cooldown_ms: default_switch_cooldown_ms(),
+            refresh_concurrency: default_refresh_concurrency(),
+            refresh_account_timeout_secs: default_refresh_account_timeout_secs(),
         }
     }
 }
@@ -23,8 +23,14 @@ const ACCOUNTS_DIR: &str = "accounts";
 // ... existing functions get_data_dir, get_accounts_dir, load_account_index, save_account_index ...
 /// Get data directory path
 pub fn get_data_dir() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("failed_to_get_home_dir")?;
-    let data_dir = home.join(DATA_DIR);
+    // [NEW] Docker/Headless: TOPOO_DATA_DIR 覆盖默认的用户目录数据路径
+    let data_dir = match std::env::var("TOPOO_DATA_DIR") {
+        Ok(dir) if !dir.trim().is_empty() => PathBuf::from(dir.trim()),
+        _ => {
+            let home = dirs::home_dir().ok_or("failed_to_get_home_dir")?;
+            home.join(DATA_DIR)
+        }
+    };
 
     // Ensure directory exists
     if !data_dir.exists() {
@@ -280,18 +286,34 @@ pub fn upsert_account(
     email: String,
     name: Option<String>,
     token: TokenData,
+) -> Result<Account, String> {
+    upsert_account_with_mode(email, name, token, false)
+}
+
+/// [NEW] 按邮箱去重保存: 池中已有同邮箱账号 (忽略大小写) 时原地更新其
+/// token 并复用原 id，避免重复登录后账号池堆积过期副本；
+/// force_new = true 跳过去重，总是新建独立账号
+pub fn upsert_account_with_mode(
+    email: String,
+    name: Option<String>,
+    token: TokenData,
+    force_new: bool,
 ) -> Result<Account, String> {
     let _lock = ACCOUNT_INDEX_LOCK
         .lock()
         .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
     let mut index = load_account_index()?;
 
-    // Find account ID if exists
-    let existing_account_id = index
-        .accounts
-        .iter()
-        .find(|s| s.email == email)
-        .map(|s| s.id.clone());
+    // Find account ID if exists ([NEW] 忽略大小写，避免 Gmail 大小写差异造成重复)
+    let existing_account_id = if force_new {
+        None
+    } else {
+        index
+            .accounts
+            .iter()
+            .find(|s| s.email.eq_ignore_ascii_case(&email))
+            .map(|s| s.id.clone())
+    };
 
     if let Some(account_id) = existing_account_id {
         // Update existing account
@@ -310,6 +332,11 @@ pub fn upsert_account(
                     account.disabled = false;
                     account.disabled_reason = None;
                     account.disabled_at = None;
+                    crate::modules::account_events::record_event(
+                        &account_id,
+                        "enabled",
+                        Some("token_upserted"),
+                    );
                 }
                 account.update_last_used();
                 save_account(&account)?;
@@ -320,6 +347,10 @@ pub fn upsert_account(
                     save_account_index(&index)?;
                 }
 
+                crate::modules::logger::log_info(&format!(
+                    "[Account] Dedup hit: updated existing account {} in place (id: {})",
+                    account.email, account_id
+                ));
                 return Ok(account);
             }
             Err(e) => {
@@ -349,6 +380,11 @@ pub fn upsert_account(
 
     // Release lock, let add_account handle it
     drop(_lock);
+    crate::modules::logger::log_info(&format!(
+        "[Account] Creating new account for {}{}",
+        email,
+        if force_new { " (force_new)" } else { "" }
+    ));
     add_account(email, name, token)
 }
 
@@ -481,6 +517,25 @@ pub fn reorder_accounts(account_ids: &[String]) -> Result<(), String> {
 
     index.accounts = new_accounts;
 
+    // [NEW] 同步调度优先级：order_index 跟随列表顺序，Priority 模式按此依次尝试
+    for (idx, summary) in index.accounts.iter().enumerate() {
+        let order_index = idx as i32;
+        match load_account(&summary.id) {
+            Ok(mut account) => {
+                if account.order_index != order_index {
+                    account.order_index = order_index;
+                    save_account(&account)?;
+                }
+            }
+            Err(e) => {
+                crate::modules::logger::log_warn(&format!(
+                    "Skipping order_index update for {}: {}",
+                    summary.id, e
+                ));
+            }
+        }
+    }
+
     crate::modules::logger::log_info(&format!(
         "Account order updated, {} accounts total",
         index.accounts.len()
@@ -538,8 +593,10 @@ pub async fn switch_account(
         .await
         .map_err(|e| format!("Token refresh failed: {}", e))?;
 
-    // If Token updated, save back to account file
-    if fresh_token.access_token != account.token.access_token {
+    // If Token updated (access or rotated refresh token), save back to account file
+    if fresh_token.access_token != account.token.access_token
+        || fresh_token.refresh_token != account.token.refresh_token
+    {
         account.token = fresh_token.clone();
         save_account(&account)?;
     }
@@ -599,6 +656,79 @@ pub async fn switch_account(
     Ok(())
 }
 
+/// [NEW] Data-only account switch: write profile + inject token without touching the IDE process.
+/// Returns whether the IDE is currently running so the UI can warn that a restart is needed
+/// before the injected token takes effect.
+pub async fn switch_account_data_only(account_id: &str) -> Result<bool, String> {
+    use crate::modules::{db, device, oauth, process};
+
+    let mut account = load_account(account_id)?;
+    crate::modules::logger::log_info(&format!(
+        "[Switch/DataOnly] Switching to account: {} (ID: {})",
+        account.email, account.id
+    ));
+
+    // 1. Ensure Token is valid (auto-refresh), same as the full switch
+    let fresh_token = oauth::ensure_fresh_token(&account.token, None)
+        .await
+        .map_err(|e| format!("Token refresh failed: {}", e))?;
+    if fresh_token.access_token != account.token.access_token
+        || fresh_token.refresh_token != account.token.refresh_token
+    {
+        account.token = fresh_token;
+        save_account(&account)?;
+    }
+
+    // 2. Same isolation guarantee: generate a fingerprint if none is bound
+    if account.device_profile.is_none() {
+        let new_profile = modules::device::generate_profile();
+        apply_profile_to_account(
+            &mut account,
+            new_profile,
+            Some("auto_generated".to_string()),
+            true,
+        )?;
+    }
+
+    // 3. Persist state first (consistent with switch_account)
+    {
+        let _lock = ACCOUNT_INDEX_LOCK
+            .lock()
+            .map_err(|e| format!("failed_to_acquire_lock: {}", e))?;
+        let mut index = load_account_index()?;
+        index.current_account_id = Some(account_id.to_string());
+        save_account_index(&index)?;
+    }
+    account.update_last_used();
+    save_account(&account)?;
+
+    // 4. Data layer only: write device profile + inject token, skip process close/start
+    let storage_path = device::get_storage_path()?;
+    if let Some(ref profile) = account.device_profile {
+        device::write_profile(&storage_path, profile)?;
+    }
+
+    let db_path = db::get_db_path()?;
+    if db_path.exists() {
+        // [FIX] 备份失败时中止：与完整切换一致，无备份不注入
+        db::backup_db(&db_path)?;
+    }
+    db::inject_token(
+        &db_path,
+        &account.token.access_token,
+        &account.token.refresh_token,
+        account.token.expiry_timestamp,
+        &account.email,
+    )?;
+
+    let ide_running = process::is_antigravity_running();
+    crate::modules::logger::log_info(&format!(
+        "✅ [Switch/DataOnly] Completed: {} (IDE running: {})",
+        account.email, ide_running
+    ));
+    Ok(ide_running)
+}
+
 /// Get device profile info: current storage.json + account bound profile
 #[derive(Debug, Serialize)]
 pub struct DeviceProfiles {
@@ -704,6 +834,40 @@ pub fn restore_device_version(account_id: &str, version_id: &str) -> Result<Devi
     Ok(target_profile)
 }
 
+/// [NEW] List historical device profiles in recorded order (for viewing/diffing)
+pub fn get_device_history(account_id: &str) -> Result<Vec<DeviceProfile>, String> {
+    let account = load_account(account_id)?;
+    Ok(account
+        .device_history
+        .iter()
+        .map(|v| v.profile.clone())
+        .collect())
+}
+
+/// [NEW] Revert to a historical device profile by index and write it back to storage.json
+/// (with backup), for returning to a previous working identity after a rotation
+pub fn revert_device_profile(account_id: &str, index: usize) -> Result<DeviceProfile, String> {
+    use crate::modules::device;
+
+    let mut account = load_account(account_id)?;
+    let version = account
+        .device_history
+        .get(index)
+        .cloned()
+        .ok_or("Historical device profile not found")?;
+
+    let storage_path = device::get_storage_path()?;
+    let _ = device::backup_storage(&storage_path)?;
+    device::write_profile(&storage_path, &version.profile)?;
+
+    account.device_profile = Some(version.profile.clone());
+    for h in account.device_history.iter_mut() {
+        h.is_current = h.id == version.id;
+    }
+    save_account(&account)?;
+    Ok(version.profile)
+}
+
 /// Delete specific historical device profile (baseline cannot be deleted)
 pub fn delete_device_version(account_id: &str, version_id: &str) -> Result<(), String> {
     if version_id == "baseline" {
@@ -850,6 +1014,11 @@ pub fn update_account_quota(account_id: &str, quota: QuotaData) -> Result<(), St
                     account.proxy_disabled = false;
                     account.proxy_disabled_reason = None;
                     account.proxy_disabled_at = None;
+                    crate::modules::account_events::record_event(
+                        &account.id,
+                        "proxy_enabled",
+                        Some("quota_protection_migrated"),
+                    );
                 }
             }
         }
@@ -881,6 +1050,17 @@ pub fn toggle_proxy_status(
 
     save_account(&account)?;
 
+    // [NEW] 审计事件：保留启停历史而不仅是最新状态
+    crate::modules::account_events::record_event(
+        account_id,
+        if enable {
+            "proxy_enabled"
+        } else {
+            "proxy_disabled"
+        },
+        reason,
+    );
+
     // Also update index summary
     let mut index = load_account_index()?;
     if let Some(summary) = index.accounts.iter_mut().find(|a| a.id == account_id) {
@@ -891,6 +1071,140 @@ pub fn toggle_proxy_status(
     Ok(())
 }
 
+/// [NEW] Clear the recorded last proxy error for an account
+pub fn clear_account_last_error(account_id: &str) -> Result<(), String> {
+    let mut account = load_account(account_id)?;
+
+    account.last_error = None;
+    account.last_error_at = None;
+
+    save_account(&account)
+}
+
+/// [NEW] Bulk enable/disable proxy participation for all accounts.
+/// Returns the number of accounts whose state actually changed.
+pub fn set_all_accounts_proxy_enabled(enabled: bool) -> Result<usize, String> {
+    let accounts = list_accounts()?;
+    let mut changed = 0;
+
+    for account in &accounts {
+        // Skip accounts already in the desired state
+        if account.proxy_disabled == !enabled {
+            continue;
+        }
+        toggle_proxy_status(
+            &account.id,
+            enabled,
+            if enabled { None } else { Some("bulk_disable") },
+        )?;
+        changed += 1;
+    }
+
+    crate::modules::logger::log_info(&format!(
+        "[Pool] Bulk {} proxy for {} account(s)",
+        if enabled { "enabled" } else { "disabled" },
+        changed
+    ));
+
+    Ok(changed)
+}
+
+/// [NEW] 账号池健康状态汇总
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolStatus {
+    pub total: usize,
+    /// 可参与代理调度的账号数 (未禁用)
+    pub enabled: usize,
+    /// 代理被禁用的账号数
+    pub proxy_disabled: usize,
+    /// 禁用原因分桶 (无原因归入 "unknown")
+    pub proxy_disabled_reasons: std::collections::HashMap<String, usize>,
+    /// 403 验证阻止中的账号数
+    pub validation_blocked: usize,
+    /// 验证阻止明细 (剩余冷却秒数)
+    pub validation_blocked_accounts: Vec<ValidationBlockedStatus>,
+    /// access_token 已过期的账号数
+    pub token_expired: usize,
+    /// [NEW] 各账号最近一次失败明细 (按时间倒序)
+    pub last_errors: Vec<AccountLastError>,
+}
+
+/// [NEW] 验证阻止账号明细
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationBlockedStatus {
+    pub email: String,
+    pub remaining_seconds: i64,
+}
+
+/// [NEW] 账号最近失败明细
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountLastError {
+    pub email: String,
+    pub error: String,
+    pub at: i64,
+}
+
+/// [NEW] Aggregate a one-glance health view over the whole account pool
+pub fn get_pool_status() -> Result<PoolStatus, String> {
+    let accounts = list_accounts()?;
+    let now = chrono::Utc::now().timestamp();
+
+    let mut status = PoolStatus {
+        total: accounts.len(),
+        enabled: 0,
+        proxy_disabled: 0,
+        proxy_disabled_reasons: std::collections::HashMap::new(),
+        validation_blocked: 0,
+        validation_blocked_accounts: Vec::new(),
+        token_expired: 0,
+        last_errors: Vec::new(),
+    };
+
+    for account in &accounts {
+        if account.proxy_disabled {
+            status.proxy_disabled += 1;
+            let reason = account
+                .proxy_disabled_reason
+                .clone()
+                .unwrap_or_else(|| "unknown".to_string());
+            *status.proxy_disabled_reasons.entry(reason).or_insert(0) += 1;
+        } else if !account.disabled {
+            status.enabled += 1;
+        }
+
+        if account.validation_blocked {
+            status.validation_blocked += 1;
+            let remaining_seconds = account
+                .validation_blocked_until
+                .map(|until| (until - now).max(0))
+                .unwrap_or(0);
+            status
+                .validation_blocked_accounts
+                .push(ValidationBlockedStatus {
+                    email: account.email.clone(),
+                    remaining_seconds,
+                });
+        }
+
+        if account.token.expiry_timestamp <= now {
+            status.token_expired += 1;
+        }
+
+        if let Some(error) = &account.last_error {
+            status.last_errors.push(AccountLastError {
+                email: account.email.clone(),
+                error: error.clone(),
+                at: account.last_error_at.unwrap_or(0),
+            });
+        }
+    }
+
+    // 最近失败的排在最前面
+    status.last_errors.sort_by(|a, b| b.at.cmp(&a.at));
+
+    Ok(status)
+}
+
 /// Export all accounts' refresh_tokens
 #[allow(dead_code)]
 pub fn export_accounts() -> Result<Vec<(String, String)>, String> {
@@ -904,6 +1218,59 @@ pub fn export_accounts() -> Result<Vec<(String, String)>, String> {
     Ok(exports)
 }
 
+/// [NEW] 手动强制刷新单个账号的 Token (不做过期时间判断，立即刷新)。
+/// 持久化刷新结果 (含 Google 轮换返回的新 refresh_token)；
+/// invalid_grant 时按统一流程停用账号并记录事件
+pub async fn refresh_account_token(account_id: &str) -> Result<crate::models::TokenData, String> {
+    use crate::modules::oauth;
+
+    let mut account = load_account(account_id)?;
+
+    let token_res =
+        match oauth::refresh_access_token(&account.token.refresh_token, Some(&account.id)).await {
+            Ok(t) => t,
+            Err(e) => {
+                if e.contains("invalid_grant") {
+                    modules::logger::log_error(&format!(
+                        "Disabling account {} due to invalid_grant during manual refresh",
+                        account.email
+                    ));
+                    let now = chrono::Utc::now().timestamp();
+                    account.disabled = true;
+                    account.disabled_at = Some(now);
+                    account.disabled_reason = Some(format!("invalid_grant: {}", e));
+                    account.proxy_disabled = true;
+                    account.proxy_disabled_at = Some(now);
+                    account.proxy_disabled_reason = Some(format!("invalid_grant: {}", e));
+                    let _ = save_account(&account);
+                    crate::modules::account_events::record_event(
+                        &account.id,
+                        "disabled",
+                        Some("invalid_grant"),
+                    );
+                    modules::integration::notify_account_invalidated(&account.email, &e);
+                }
+                return Err(e);
+            }
+        };
+
+    // 合并轮换的 refresh_token (未返回新值时保留旧值) 并持久化
+    let new_token = oauth::apply_refresh_response(&account.token, &token_res);
+    account.token = new_token.clone();
+    upsert_account(
+        account.email.clone(),
+        account.name.clone(),
+        new_token.clone(),
+    )?;
+
+    modules::logger::log_info(&format!(
+        "🔄 Manually refreshed token for {} (expires at {})",
+        account.email, new_token.expiry_timestamp
+    ));
+
+    Ok(new_token)
+}
+
 /// Quota query with retry (moved from commands to modules for reuse)
 pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppResult<QuotaData> {
     use crate::error::AppError;
@@ -919,10 +1286,20 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
                     "Disabling account {} due to invalid_grant during token refresh (quota check)",
                     account.email
                 ));
+                let now = chrono::Utc::now().timestamp();
                 account.disabled = true;
-                account.disabled_at = Some(chrono::Utc::now().timestamp());
+                account.disabled_at = Some(now);
                 account.disabled_reason = Some(format!("invalid_grant: {}", e));
+                account.proxy_disabled = true;
+                account.proxy_disabled_at = Some(now);
+                account.proxy_disabled_reason = Some(format!("invalid_grant: {}", e));
                 let _ = save_account(account);
+                crate::modules::account_events::record_event(
+                    &account.id,
+                    "disabled",
+                    Some("invalid_grant"),
+                );
+                modules::integration::notify_account_invalidated(&account.email, &e);
             }
             return Err(AppError::OAuth(e));
         }
@@ -1025,23 +1402,27 @@ pub async fn fetch_quota_with_retry(account: &mut Account) -> crate::error::AppR
                                 "Disabling account {} due to invalid_grant during forced refresh (quota check)",
                                 account.email
                             ));
+                            let now = chrono::Utc::now().timestamp();
                             account.disabled = true;
-                            account.disabled_at = Some(chrono::Utc::now().timestamp());
+                            account.disabled_at = Some(now);
                             account.disabled_reason = Some(format!("invalid_grant: {}", e));
+                            account.proxy_disabled = true;
+                            account.proxy_disabled_at = Some(now);
+                            account.proxy_disabled_reason = Some(format!("invalid_grant: {}", e));
                             let _ = save_account(account);
+                            crate::modules::account_events::record_event(
+                                &account.id,
+                                "disabled",
+                                Some("invalid_grant"),
+                            );
+                            modules::integration::notify_account_invalidated(&account.email, &e);
                         }
                         return Err(AppError::OAuth(e));
                     }
                 };
 
-                let new_token = TokenData::new(
-                    token_res.access_token.clone(),
-                    account.token.refresh_token.clone(),
-                    token_res.expires_in,
-                    account.token.email.clone(),
-                    account.token.project_id.clone(), // Keep original project_id
-                    None,                             // Add None as session_id
-                );
+                // Merge rotated refresh_token if Google returned one (keep the old otherwise)
+                let new_token = oauth::apply_refresh_response(&account.token, &token_res);
 
                 // Re-fetch display name
                 let name = if account.name.is_none()
@@ -1107,22 +1488,31 @@ pub struct RefreshStats {
 }
 
 /// Core logic to batch refresh all account quotas (decoupled from Tauri status)
+///
+/// [NEW] 并发数与单账号超时可配置 (refresh_concurrency / refresh_account_timeout_secs)，
+/// 通过 buffer_unordered 限并发；慢账号超时跳过，不再拖垮整轮扫描
 pub async fn refresh_all_quotas_logic() -> Result<RefreshStats, String> {
-    use futures::future::join_all;
-    use std::sync::Arc;
-    use tokio::sync::Semaphore;
-
-    const MAX_CONCURRENT: usize = 5;
+    use futures::StreamExt;
+
+    let config = crate::modules::config::load_app_config().ok();
+    let max_concurrent = config
+        .as_ref()
+        .map(|c| c.refresh_concurrency)
+        .filter(|&n| n > 0)
+        .unwrap_or(5);
+    let per_account_timeout_secs = config
+        .as_ref()
+        .map(|c| c.refresh_account_timeout_secs)
+        .filter(|&n| n > 0)
+        .unwrap_or(30);
     let start = std::time::Instant::now();
 
     crate::modules::logger::log_info(&format!(
-        "Starting batch refresh of all account quotas (Concurrent mode, max: {})",
-        MAX_CONCURRENT
+        "Starting batch refresh of all account quotas (Concurrent mode, max: {}, per-account timeout: {}s)",
+        max_concurrent, per_account_timeout_secs
     ));
     let accounts = list_accounts()?;
 
-    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT));
-
     let tasks: Vec<_> = accounts
         .into_iter()
         .filter(|account| {
@@ -1147,12 +1537,23 @@ pub async fn refresh_all_quotas_logic() -> Result<RefreshStats, String> {
         .map(|mut account| {
             let email = account.email.clone();
             let account_id = account.id.clone();
-            let permit = semaphore.clone();
             async move {
-                let _guard = permit.acquire().await.unwrap();
                 crate::modules::logger::log_info(&format!("  - Processing {}", email));
-                match fetch_quota_with_retry(&mut account).await {
-                    Ok(quota) => {
+                let result = tokio::time::timeout(
+                    std::time::Duration::from_secs(per_account_timeout_secs),
+                    fetch_quota_with_retry(&mut account),
+                )
+                .await;
+                match result {
+                    Err(_) => {
+                        let msg = format!(
+                            "Account {}: Refresh timed out after {}s",
+                            email, per_account_timeout_secs
+                        );
+                        crate::modules::logger::log_error(&msg);
+                        Err(msg)
+                    }
+                    Ok(Ok(quota)) => {
                         if let Err(e) = update_account_quota(&account_id, quota) {
                             let msg = format!("Account {}: Save quota failed - {}", email, e);
                             crate::modules::logger::log_error(&msg);
@@ -1162,7 +1563,7 @@ pub async fn refresh_all_quotas_logic() -> Result<RefreshStats, String> {
                             Ok(())
                         }
                     }
-                    Err(e) => {
+                    Ok(Err(e)) => {
                         let msg = format!("Account {}: Fetch quota failed - {}", email, e);
                         crate::modules::logger::log_error(&msg);
                         Err(msg)
@@ -1173,7 +1574,11 @@ pub async fn refresh_all_quotas_logic() -> Result<RefreshStats, String> {
         .collect();
 
     let total = tasks.len();
-    let results = join_all(tasks).await;
+    // [NEW] 有界并发：最多 max_concurrent 个账号同时在刷新
+    let results: Vec<Result<(), String>> = futures::stream::iter(tasks)
+        .buffer_unordered(max_concurrent)
+        .collect()
+        .await;
 
     let mut success = 0;
     let mut failed = 0;
@@ -1204,3 +1609,120 @@ pub async fn refresh_all_quotas_logic() -> Result<RefreshStats, String> {
         details,
     })
 }
+
+/// [NEW] 单账号校验结果 (全池 dry-run 校验用)
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "snake_case", tag = "status")]
+pub enum ValidationResult {
+    /// Token 刷新成功，账号可用
+    Ok,
+    /// 校验前就已停用，未发起网络请求
+    Disabled { reason: Option<String> },
+    /// refresh_token 已失效 (invalid_grant)，账号已按统一流程停用
+    Invalid { error: String },
+    /// 网络/超时等暂时性失败，账号状态未改动
+    Failed { error: String },
+}
+
+/// [NEW] 全池 dry-run 校验：并发对每个账号做一次强制 Token 刷新，
+/// 返回逐账号结果。invalid_grant 的账号由 refresh_account_token 按统一
+/// 流程停用；每完成一个账号发 `account://validation-progress` 进度事件。
+/// concurrency = 0 时退回配置的 refresh_concurrency (默认 5)
+pub async fn validate_pool(concurrency: usize) -> Result<Vec<(String, ValidationResult)>, String> {
+    use futures::StreamExt;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    let config = crate::modules::config::load_app_config().ok();
+    let max_concurrent = if concurrency > 0 {
+        concurrency
+    } else {
+        config
+            .as_ref()
+            .map(|c| c.refresh_concurrency)
+            .filter(|&n| n > 0)
+            .unwrap_or(5)
+    };
+    let per_account_timeout_secs = config
+        .as_ref()
+        .map(|c| c.refresh_account_timeout_secs)
+        .filter(|&n| n > 0)
+        .unwrap_or(30);
+
+    let accounts = list_accounts()?;
+    let total = accounts.len();
+    let done = Arc::new(AtomicUsize::new(0));
+
+    crate::modules::logger::log_info(&format!(
+        "Starting pool validation for {} account(s) (max concurrent: {}, per-account timeout: {}s)",
+        total, max_concurrent, per_account_timeout_secs
+    ));
+
+    let tasks: Vec<_> = accounts
+        .into_iter()
+        .map(|account| {
+            let done = done.clone();
+            async move {
+                let result = if account.disabled {
+                    // 已停用账号不发请求，结果里仍然占一行，便于前端展示全貌
+                    ValidationResult::Disabled {
+                        reason: account.disabled_reason.clone(),
+                    }
+                } else {
+                    match tokio::time::timeout(
+                        std::time::Duration::from_secs(per_account_timeout_secs),
+                        refresh_account_token(&account.id),
+                    )
+                    .await
+                    {
+                        Err(_) => ValidationResult::Failed {
+                            error: format!("Timed out after {}s", per_account_timeout_secs),
+                        },
+                        Ok(Ok(_)) => ValidationResult::Ok,
+                        Ok(Err(e)) if e.contains("invalid_grant") => {
+                            ValidationResult::Invalid { error: e }
+                        }
+                        Ok(Err(e)) => ValidationResult::Failed { error: e },
+                    }
+                };
+
+                let status = match &result {
+                    ValidationResult::Ok => "ok",
+                    ValidationResult::Disabled { .. } => "disabled",
+                    ValidationResult::Invalid { .. } => "invalid",
+                    ValidationResult::Failed { .. } => "failed",
+                };
+                let finished = done.fetch_add(1, Ordering::SeqCst) + 1;
+                crate::modules::logger::log_info(&format!(
+                    "  - [{}/{}] {} -> {}",
+                    finished, total, account.email, status
+                ));
+                modules::integration::emit_pool_validation_progress(
+                    finished,
+                    total,
+                    &account.id,
+                    &account.email,
+                    status,
+                );
+
+                (account.id, result)
+            }
+        })
+        .collect();
+
+    let results: Vec<(String, ValidationResult)> = futures::stream::iter(tasks)
+        .buffer_unordered(max_concurrent)
+        .collect()
+        .await;
+
+    let ok = results
+        .iter()
+        .filter(|(_, r)| matches!(r, ValidationResult::Ok))
+        .count();
+    crate::modules::logger::log_info(&format!(
+        "Pool validation completed: {}/{} account(s) usable",
+        ok, total
+    ));
+
+    Ok(results)
+}
@@ -0,0 +1,116 @@
+use rusqlite::{params, Connection};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::error::{GatewayError, GatewayResult};
+
+/// [NEW] 账号状态变更审计事件：只记最新状态的 disabled_at/disabled_reason
+/// 回答不了"上周二这个账号为什么停了"，这里保留完整历史
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountEvent {
+    pub account_id: String,
+    /// 事件类型：disabled / enabled / proxy_disabled / proxy_enabled /
+    /// validation_blocked / validation_unblocked
+    pub event: String,
+    /// 触发原因（如 invalid_grant、quota_protection、bulk_disable、手动操作为 None）
+    pub reason: Option<String>,
+    /// Unix 秒
+    pub timestamp: i64,
+}
+
+fn get_db_path() -> GatewayResult<PathBuf> {
+    let data_dir = crate::modules::account::get_data_dir()?;
+    Ok(data_dir.join("account_events.db"))
+}
+
+fn connect_db() -> GatewayResult<Connection> {
+    let db_path = get_db_path()?;
+    let conn = Connection::open(db_path).map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    conn.pragma_update(None, "journal_mode", "WAL")
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+    conn.pragma_update(None, "busy_timeout", 5000)
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    Ok(conn)
+}
+
+/// Initialize the account events database
+pub fn init_db() -> GatewayResult<()> {
+    let conn = connect_db()?;
+
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS account_events (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            account_id TEXT NOT NULL,
+            event TEXT NOT NULL,
+            reason TEXT,
+            timestamp INTEGER NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    conn.execute(
+        "CREATE INDEX IF NOT EXISTS idx_account_events_account
+         ON account_events (account_id, timestamp DESC)",
+        [],
+    )
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    Ok(())
+}
+
+/// 写入一条审计事件。调用方通常 fire-and-forget（审计失败不应阻断状态变更本身），
+/// 失败时这里统一记一条 warn
+pub fn record_event(account_id: &str, event: &str, reason: Option<&str>) {
+    let result: GatewayResult<()> = (|| {
+        let conn = connect_db()?;
+        conn.execute(
+            "INSERT INTO account_events (account_id, event, reason, timestamp)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![account_id, event, reason, chrono::Utc::now().timestamp()],
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+        Ok(())
+    })();
+
+    if let Err(e) = result {
+        crate::modules::logger::log_warn(&format!(
+            "[AccountEvents] Failed to record {} for {}: {}",
+            event, account_id, e
+        ));
+    }
+}
+
+/// 查询某账号的事件历史（按时间倒序，最多 limit 条）
+pub fn get_account_events(account_id: &str, limit: u32) -> GatewayResult<Vec<AccountEvent>> {
+    let conn = connect_db()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT account_id, event, reason, timestamp
+             FROM account_events
+             WHERE account_id = ?1
+             ORDER BY timestamp DESC, id DESC
+             LIMIT ?2",
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let rows = stmt
+        .query_map(params![account_id, limit], |row| {
+            Ok(AccountEvent {
+                account_id: row.get(0)?,
+                event: row.get(1)?,
+                reason: row.get(2)?,
+                timestamp: row.get(3)?,
+            })
+        })
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
+    }
+    Ok(result)
+}
@@ -13,6 +13,16 @@ impl AccountService {
 
     /// 添加账号逻辑
     pub async fn add_account(&self, refresh_token: &str) -> Result<Account, String> {
+        self.add_account_with_mode(refresh_token, false).await
+    }
+
+    /// [NEW] 添加账号 (可跳过邮箱去重): 默认重复登录同邮箱时原地更新已有账号;
+    /// force_new = true 时总是新建独立账号
+    pub async fn add_account_with_mode(
+        &self,
+        refresh_token: &str,
+        force_new: bool,
+    ) -> Result<Account, String> {
         // [FIX #1583] 生成临时 UUID 作为账号上下文，避免传递 None 导致代理选择异常
         let temp_account_id = uuid::Uuid::new_v4().to_string();
         
@@ -27,19 +37,26 @@ impl AccountService {
             .await
             .ok();
 
-        // 4. 构造 TokenData
+        // 4. 构造 TokenData (刷新时 Google 可能轮换 refresh_token，优先保存新值)
         let token = TokenData::new(
             token_res.access_token.clone(),
-            refresh_token.to_string(),
+            token_res
+                .refresh_token
+                .clone()
+                .unwrap_or_else(|| refresh_token.to_string()),
             token_res.expires_in,
             Some(user_info.email.clone()),
             project_id,
             None,
         );
 
-        // 5. 持久化
-        let mut account =
-            modules::upsert_account(user_info.email.clone(), user_info.get_display_name(), token)?;
+        // 5. 持久化 ([NEW] 按邮箱去重，除非显式 force_new)
+        let mut account = modules::account::upsert_account_with_mode(
+            user_info.email.clone(),
+            user_info.get_display_name(),
+            token,
+            force_new,
+        )?;
 
         // 6. [NEW] 自动获取配额信息（用于刷新时间排序）
         let email_for_log = account.email.clone();
@@ -78,6 +95,122 @@ impl AccountService {
         Ok(account)
     }
 
+    /// [NEW] 从原始 Token JSON 导入账号（非 OAuth 路径）
+    ///
+    /// 接受的 schema:
+    /// `{ "access_token": String, "refresh_token": String, "expiry_timestamp"?: i64, "expires_in"?: i64, "email"?: String }`
+    ///
+    /// 解析后通过上游验证 Token 有效性（必要时先刷新），再持久化到账号池。
+    pub async fn import_account_from_token_json(&self, json: String) -> Result<Account, String> {
+        let value: serde_json::Value =
+            serde_json::from_str(&json).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+        let obj = value
+            .as_object()
+            .ok_or_else(|| "Invalid token JSON: expected a JSON object".to_string())?;
+
+        let require_string = |field: &str| -> Result<String, String> {
+            match obj.get(field) {
+                Some(serde_json::Value::String(s)) if !s.trim().is_empty() => {
+                    Ok(s.trim().to_string())
+                }
+                Some(serde_json::Value::String(_)) => {
+                    Err(format!("Invalid token JSON: field `{}` is empty", field))
+                }
+                Some(_) => Err(format!(
+                    "Invalid token JSON: field `{}` must be a string",
+                    field
+                )),
+                None => Err(format!("Invalid token JSON: missing field `{}`", field)),
+            }
+        };
+
+        let access_token = require_string("access_token")?;
+        let refresh_token = require_string("refresh_token")?;
+
+        let optional_i64 = |field: &str| -> Result<Option<i64>, String> {
+            match obj.get(field) {
+                Some(v) if v.is_null() => Ok(None),
+                Some(v) => v.as_i64().map(Some).ok_or_else(|| {
+                    format!("Invalid token JSON: field `{}` must be an integer", field)
+                }),
+                None => Ok(None),
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        let expiry_timestamp = match optional_i64("expiry_timestamp")? {
+            Some(ts) => ts,
+            None => now + optional_i64("expires_in")?.unwrap_or(0),
+        };
+
+        let declared_email = match obj.get("email") {
+            Some(serde_json::Value::String(s)) if !s.trim().is_empty() => {
+                Some(s.trim().to_string())
+            }
+            Some(v) if !v.is_null() => {
+                return Err("Invalid token JSON: field `email` must be a string".to_string())
+            }
+            _ => None,
+        };
+
+        // [FIX #1583] 生成临时 UUID 作为账号上下文
+        let temp_account_id = uuid::Uuid::new_v4().to_string();
+
+        // 验证 Token：过期则先刷新，否则直接用 access_token 获取用户信息
+        // (刷新时 Google 可能轮换 refresh_token，优先保存新值)
+        let (access_token, expires_in, refresh_token) = if expiry_timestamp <= now {
+            let token_res =
+                modules::oauth::refresh_access_token(&refresh_token, Some(&temp_account_id))
+                    .await
+                    .map_err(|e| format!("Token validation failed (refresh): {}", e))?;
+            let effective_refresh = token_res.refresh_token.unwrap_or(refresh_token);
+            (token_res.access_token, token_res.expires_in, effective_refresh)
+        } else {
+            (access_token, expiry_timestamp - now, refresh_token)
+        };
+
+        let user_info = modules::oauth::get_user_info(&access_token, Some(&temp_account_id))
+            .await
+            .map_err(|e| format!("Token validation failed (user info): {}", e))?;
+
+        if let Some(declared) = &declared_email {
+            if !declared.eq_ignore_ascii_case(&user_info.email) {
+                modules::logger::log_warn(&format!(
+                    "[Service] Imported token email mismatch: declared {}, actual {}",
+                    declared, user_info.email
+                ));
+            }
+        }
+
+        let project_id = crate::proxy::project_resolver::fetch_project_id(&access_token)
+            .await
+            .ok();
+
+        let token = TokenData::new(
+            access_token,
+            refresh_token,
+            expires_in,
+            Some(user_info.email.clone()),
+            project_id,
+            None,
+        );
+
+        let account = modules::upsert_account(
+            user_info.email.clone(),
+            user_info.get_display_name(),
+            token,
+        )?;
+
+        self.integration.update_tray();
+
+        modules::logger::log_info(&format!(
+            "[Service] Imported account from token JSON: {}",
+            account.email
+        ));
+        Ok(account)
+    }
+
     /// 删除账号逻辑
     pub fn delete_account(&self, account_id: &str) -> Result<(), String> {
         modules::delete_account(account_id)?;
@@ -110,6 +243,8 @@ impl AccountService {
         modules::oauth_server::prepare_oauth_url(handle).await
     }
 
+    /// [NEW] Headless 模式下无法打开浏览器：此调用会直接返回含授权链接的错误，
+    /// 调用方应改用 prepare_oauth_url + complete_oauth_login（用户手动打开链接授权）
     pub async fn start_oauth_login(&self) -> Result<Account, String> {
         let handle = match &self.integration {
             modules::integration::SystemManager::Desktop(h) => Some(h.clone()),
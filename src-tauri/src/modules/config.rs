@@ -78,6 +78,139 @@ pub fn load_app_config() -> Result<AppConfig, String> {
     Ok(config)
 }
 
+/// [NEW] Headless/Docker 环境变量覆盖 (env 优先于文件配置)。支持的变量：
+/// - TOPOO_PORT           反代监听端口 (u16，0 或非法值忽略)
+/// - TOPOO_UPSTREAM_PROXY 上游代理地址 (http://, https://, socks5://)，设置后自动启用
+/// - TOPOO_ADMIN_TOKEN    Web UI 管理密码
+/// - TOPOO_DATA_DIR       数据目录 (在 account::get_data_dir 中生效)
+/// - TOPOO_LOG_LEVEL      日志级别 (RUST_LOG 语法，在 logger::init_logger 中生效)
+pub fn apply_env_overrides(config: &mut AppConfig) {
+    let vars: std::collections::HashMap<String, String> = std::env::vars().collect();
+    apply_env_overrides_from(config, &vars);
+}
+
+/// 环境变量合并逻辑，从给定 map 读取便于测试。
+/// 变量缺省、为空或解析失败时保持文件配置不变
+fn apply_env_overrides_from(
+    config: &mut AppConfig,
+    vars: &std::collections::HashMap<String, String>,
+) {
+    if let Some(port) = vars
+        .get("TOPOO_PORT")
+        .and_then(|v| v.trim().parse::<u16>().ok())
+        .filter(|p| *p > 0)
+    {
+        crate::modules::logger::log_info(&format!("[Env] TOPOO_PORT = {}", port));
+        config.proxy.port = port;
+    }
+
+    if let Some(url) = vars
+        .get("TOPOO_UPSTREAM_PROXY")
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    {
+        crate::modules::logger::log_info(&format!("[Env] TOPOO_UPSTREAM_PROXY = {}", url));
+        config.proxy.upstream_proxy.enabled = true;
+        config.proxy.upstream_proxy.url = url.to_string();
+    }
+
+    if let Some(token) = vars
+        .get("TOPOO_ADMIN_TOKEN")
+        .map(|v| v.trim())
+        .filter(|v| !v.is_empty())
+    {
+        crate::modules::logger::log_info("[Env] Using admin token from TOPOO_ADMIN_TOKEN");
+        config.proxy.admin_password = Some(token.to_string());
+    }
+}
+
+/// [NEW] 导出时统一脱敏的字段名 (任意层级命中即替换) 与占位符
+const SECRET_KEYS: &[&str] = &["api_key", "admin_password", "password", "token"];
+const REDACTED_PLACEHOLDER: &str = "***REDACTED***";
+
+/// 递归脱敏：命中密钥字段且为非空字符串时替换为占位符
+fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, v) in map.iter_mut() {
+                if SECRET_KEYS.contains(&key.as_str()) {
+                    if let serde_json::Value::String(s) = v {
+                        if !s.is_empty() {
+                            *v = serde_json::Value::String(REDACTED_PLACEHOLDER.to_string());
+                        }
+                    }
+                } else {
+                    redact_secrets(v);
+                }
+            }
+        }
+        serde_json::Value::Array(arr) => {
+            for v in arr.iter_mut() {
+                redact_secrets(v);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 递归回填：导入的 JSON 中仍为占位符的字段恢复当前配置的原值，
+/// 使 export -> import 往返不丢失密钥
+fn restore_redacted(value: &mut serde_json::Value, current: &serde_json::Value) {
+    match (value, current) {
+        (serde_json::Value::Object(map), serde_json::Value::Object(cur)) => {
+            for (key, v) in map.iter_mut() {
+                match cur.get(key) {
+                    Some(cur_v) => {
+                        if v.as_str() == Some(REDACTED_PLACEHOLDER) {
+                            *v = cur_v.clone();
+                        } else {
+                            restore_redacted(v, cur_v);
+                        }
+                    }
+                    None => {
+                        // 当前配置没有对应值，无法回填，清空避免把占位符当密钥保存
+                        if v.as_str() == Some(REDACTED_PLACEHOLDER) {
+                            *v = serde_json::Value::String(String::new());
+                        }
+                    }
+                }
+            }
+        }
+        (serde_json::Value::Array(arr), serde_json::Value::Array(cur)) => {
+            for (v, cur_v) in arr.iter_mut().zip(cur.iter()) {
+                restore_redacted(v, cur_v);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// [NEW] 导出当前生效配置为 pretty JSON (密钥/密码统一脱敏)，
+/// 可安全贴进 bug 报告或迁移到其他机器
+pub fn export_config() -> Result<String, String> {
+    let config = load_app_config()?;
+    let mut v = serde_json::to_value(&config)
+        .map_err(|e| format!("failed_to_serialize_config: {}", e))?;
+    redact_secrets(&mut v);
+    serde_json::to_string_pretty(&v)
+        .map_err(|e| format!("failed_to_serialize_config: {}", e))
+}
+
+/// [NEW] 导入配置：校验 JSON 能解析为完整 AppConfig 后落盘；
+/// 脱敏占位符字段回填当前配置原值
+pub fn import_config(json: &str) -> Result<(), String> {
+    let mut v: serde_json::Value = serde_json::from_str(json)
+        .map_err(|e| format!("failed_to_parse_config_file: {}", e))?;
+
+    let current = serde_json::to_value(load_app_config()?)
+        .map_err(|e| format!("failed_to_serialize_config: {}", e))?;
+    restore_redacted(&mut v, &current);
+
+    let config: AppConfig = serde_json::from_value(v)
+        .map_err(|e| format!("invalid_config: {}", e))?;
+    save_app_config(&config)
+}
+
 /// Save application configuration
 pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
     let data_dir = get_data_dir()?;
@@ -89,3 +222,86 @@ pub fn save_app_config(config: &AppConfig) -> Result<(), String> {
     fs::write(&config_path, content)
         .map_err(|e| format!("failed_to_save_config: {}", e))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn vars(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_env_overrides_take_precedence_over_file_config() {
+        let mut config = AppConfig::new();
+        config.proxy.port = 8045;
+        config.proxy.admin_password = Some("from-file".to_string());
+
+        apply_env_overrides_from(
+            &mut config,
+            &vars(&[("TOPOO_PORT", "9000"), ("TOPOO_ADMIN_TOKEN", "from-env")]),
+        );
+
+        assert_eq!(config.proxy.port, 9000);
+        assert_eq!(config.proxy.admin_password.as_deref(), Some("from-env"));
+    }
+
+    #[test]
+    fn test_missing_env_vars_keep_file_config() {
+        let mut config = AppConfig::new();
+        config.proxy.port = 8045;
+        config.proxy.admin_password = Some("from-file".to_string());
+
+        apply_env_overrides_from(&mut config, &vars(&[]));
+
+        assert_eq!(config.proxy.port, 8045);
+        assert_eq!(config.proxy.admin_password.as_deref(), Some("from-file"));
+    }
+
+    #[test]
+    fn test_invalid_or_empty_values_are_ignored() {
+        let mut config = AppConfig::new();
+        config.proxy.port = 8045;
+
+        apply_env_overrides_from(
+            &mut config,
+            &vars(&[
+                ("TOPOO_PORT", "not-a-port"),
+                ("TOPOO_ADMIN_TOKEN", "   "),
+                ("TOPOO_UPSTREAM_PROXY", ""),
+            ]),
+        );
+
+        assert_eq!(config.proxy.port, 8045);
+        assert_eq!(config.proxy.admin_password, None);
+        assert!(!config.proxy.upstream_proxy.enabled);
+    }
+
+    #[test]
+    fn test_port_zero_is_rejected() {
+        let mut config = AppConfig::new();
+        config.proxy.port = 8045;
+
+        apply_env_overrides_from(&mut config, &vars(&[("TOPOO_PORT", "0")]));
+
+        assert_eq!(config.proxy.port, 8045);
+    }
+
+    #[test]
+    fn test_upstream_proxy_env_enables_and_sets_url() {
+        let mut config = AppConfig::new();
+        assert!(!config.proxy.upstream_proxy.enabled);
+
+        apply_env_overrides_from(
+            &mut config,
+            &vars(&[("TOPOO_UPSTREAM_PROXY", " socks5://10.0.0.1:1080 ")]),
+        );
+
+        assert!(config.proxy.upstream_proxy.enabled);
+        assert_eq!(config.proxy.upstream_proxy.url, "socks5://10.0.0.1:1080");
+    }
+}
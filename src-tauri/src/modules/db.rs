@@ -1,6 +1,9 @@
+use crate::error::{GatewayError, GatewayResult};
+use crate::models::config::{InjectedKeyBuilder, InjectedKeySpec};
 use crate::utils::protobuf;
 use base64::{engine::general_purpose, Engine as _};
 use rusqlite::Connection;
+use serde::Serialize;
 use std::path::PathBuf;
 
 fn get_antigravity_path() -> Option<PathBuf> {
@@ -16,7 +19,7 @@ fn get_antigravity_path() -> Option<PathBuf> {
 }
 
 /// Get Antigravity database path (cross-platform)
-pub fn get_db_path() -> Result<PathBuf, String> {
+pub fn get_db_path() -> GatewayResult<PathBuf> {
     // Prefer path specified by --user-data-dir argument
     if let Some(user_data_dir) = crate::modules::process::get_user_data_dir_from_process() {
         let custom_db_path = user_data_dir
@@ -50,7 +53,8 @@ pub fn get_db_path() -> Result<PathBuf, String> {
         .map(|c| c.target_app_name)
         .unwrap_or_else(|_| "Topoo Gateway".to_string());
 
-    let home = dirs::home_dir().ok_or("Failed to get home directory")?;
+    let home = dirs::home_dir()
+        .ok_or_else(|| GatewayError::NotFound("Failed to get home directory".to_string()))?;
 
     // Candidate paths to check (Priority: Configured Name -> Hardcoded Legacy Name)
     let candidates = vec![
@@ -74,6 +78,20 @@ pub fn get_db_path() -> Result<PathBuf, String> {
         )),
         #[cfg(target_os = "linux")]
         home.join(".config/Antigravity/User/globalStorage/state.vscdb"),
+        // [NEW] Flatpak 沙箱内配置目录 (~/.var/app/<id>/config/...)
+        #[cfg(target_os = "linux")]
+        home.join(format!(
+            ".var/app/{}/config/{}/User/globalStorage/state.vscdb",
+            crate::modules::process::get_flatpak_app_id(),
+            target_app
+        )),
+        // [NEW] Snap 沙箱内配置目录
+        #[cfg(target_os = "linux")]
+        home.join(format!(
+            "snap/{}/current/.config/{}/User/globalStorage/state.vscdb",
+            target_app.to_lowercase(),
+            target_app
+        )),
     ];
 
     for path in candidates {
@@ -95,7 +113,7 @@ pub fn get_db_path() -> Result<PathBuf, String> {
     #[cfg(target_os = "windows")]
     {
         let appdata = std::env::var("APPDATA")
-            .map_err(|_| "Failed to get APPDATA environment variable".to_string())?;
+            .map_err(|_| GatewayError::Config("Failed to get APPDATA environment variable".to_string()))?;
         Ok(
             PathBuf::from(appdata)
                 .join(format!("{}\\User\\globalStorage\\state.vscdb", target_app)),
@@ -111,50 +129,22 @@ pub fn get_db_path() -> Result<PathBuf, String> {
     }
 }
 
-/// Inject Token and Email into database
-pub fn inject_token(
-    db_path: &std::path::PathBuf,
+/// Build the merged Legacy blob (jetskiStateSync.agentManagerInitState) from the
+/// current base64 value stored in the DB. Shared by `inject_token` and
+/// `preview_injection` so the preview can never drift from the real write path.
+fn build_legacy_merged_blob(
+    current_data: &str,
     access_token: &str,
     refresh_token: &str,
     expiry: i64,
     email: &str,
-) -> Result<String, String> {
-    crate::modules::logger::log_info(&format!(
-        "🔧 [DB Inject] Starting injection for email: {}",
-        email
-    ));
-
-    // 1. Open database
-    let conn = Connection::open(db_path).map_err(|e| format!("Failed to open database: {}", e))?;
-
-    // [OPTIMIZATION] Set busy timeout to avoid immediate failure when DB is locked by IDE
-    let _ = conn.execute("PRAGMA busy_timeout = 5000", []);
-
-    // 2. Read current data from Legacy key
-    let current_data: String = conn
-        .query_row(
-            "SELECT value FROM ItemTable WHERE key = ?",
-            ["jetskiStateSync.agentManagerInitState"],
-            |row| row.get(0),
-        )
-        .map_err(|e| format!("Failed to read data: {}", e))?;
-
-    crate::modules::logger::log_info(&format!(
-        "📖 [DB Inject] Read current data, length: {} bytes",
-        current_data.len()
-    ));
-
-    // 3. Base64 decode
+) -> GatewayResult<Vec<u8>> {
+    // Base64 decode
     let blob = general_purpose::STANDARD
-        .decode(&current_data)
-        .map_err(|e| format!("Base64 decoding failed: {}", e))?;
+        .decode(current_data)
+        .map_err(|e| GatewayError::Parse(format!("Base64 decoding failed: {}", e)))?;
 
-    crate::modules::logger::log_info(&format!(
-        "🔓 [DB Inject] Decoded blob, length: {} bytes",
-        blob.len()
-    ));
-
-    // 4. Remove old Identity and Token fields
+    // Remove old Identity and Token fields
     // Field 1: UserID
     // Field 2: Email
     // Field 6: OAuthTokenInfo
@@ -162,99 +152,742 @@ pub fn inject_token(
     clean_data = protobuf::remove_field(&clean_data, 2)?;
     clean_data = protobuf::remove_field(&clean_data, 6)?;
 
-    crate::modules::logger::log_info(&format!(
-        "🧹 [DB Inject] Cleaned old fields, new length: {} bytes",
-        clean_data.len()
-    ));
-
-    // 5. Create new fields
+    // Create new fields
     let new_email_field = protobuf::create_email_field(email);
     let new_oauth_field = protobuf::create_oauth_field(access_token, refresh_token, expiry);
 
-    crate::modules::logger::log_info(&format!(
-        "✨ [DB Inject] Created new fields - email: {} bytes, oauth: {} bytes",
-        new_email_field.len(),
-        new_oauth_field.len()
-    ));
-
-    // 6. Merge data
+    // Merge data
     // We intentionally do NOT re-inject Field 1 (UserID) to force the client
     // to re-authenticate the session with the new token.
-    let final_data = [clean_data, new_email_field, new_oauth_field].concat();
-    let final_b64 = general_purpose::STANDARD.encode(&final_data);
+    Ok([clean_data, new_email_field, new_oauth_field].concat())
+}
 
-    crate::modules::logger::log_info(&format!(
-        "🔀 [DB Inject] Merged data, final length: {} bytes (base64: {} bytes)",
-        final_data.len(),
-        final_b64.len()
-    ));
+/// Dry-run preview of what `inject_token` would write for an account.
+#[derive(Debug, Clone, Serialize)]
+pub struct InjectionPreview {
+    /// Target state.vscdb path that the injection would write to
+    pub db_path: String,
+    /// Base64 of the merged Legacy blob (jetskiStateSync.agentManagerInitState)
+    pub legacy_base64: String,
+    /// Top-level field summary of the merged Legacy blob
+    pub legacy_fields: Vec<protobuf::FieldSummary>,
+    /// Base64 of the Unified token blob (antigravityUnifiedStateSync.oauthToken)
+    pub unified_base64: String,
+    /// Top-level field summary of the Unified token blob
+    pub unified_fields: Vec<protobuf::FieldSummary>,
+}
 
-    // 7. Write to database
-    let rows_affected = conn
-        .execute(
-            "UPDATE ItemTable SET value = ? WHERE key = ?",
-            [&final_b64, "jetskiStateSync.agentManagerInitState"],
+/// Build the exact blobs `inject_token` would write, without touching the database.
+/// Opens the DB read-only to fetch the current Legacy value, then reuses the real
+/// construction code so the preview matches a subsequent injection byte-for-byte.
+pub fn preview_injection(
+    db_path: &std::path::PathBuf,
+    access_token: &str,
+    refresh_token: &str,
+    expiry: i64,
+    email: &str,
+) -> GatewayResult<InjectionPreview> {
+    // Read-only open: the preview must never mutate state.vscdb
+    let conn = Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| GatewayError::Db(format!("Failed to open database (read-only): {}", e)))?;
+
+    let current_data: String = conn
+        .query_row(
+            "SELECT value FROM ItemTable WHERE key = ?",
+            ["jetskiStateSync.agentManagerInitState"],
+            |row| row.get(0),
         )
-        .map_err(|e| format!("Failed to write data: {}", e))?;
+        .map_err(|e| GatewayError::Db(format!("Failed to read data: {}", e)))?;
+
+    // 1. Legacy merged blob (same code path as inject_token)
+    let legacy_blob =
+        build_legacy_merged_blob(&current_data, access_token, refresh_token, expiry, email)?;
+    let legacy_fields = protobuf::describe_fields(&legacy_blob)?;
+
+    // 2. Unified blob (same builder as inject_token)
+    let unified_blob = protobuf::create_unified_token_message(access_token, refresh_token, expiry);
+    let unified_fields = protobuf::describe_fields(&unified_blob)?;
+
+    Ok(InjectionPreview {
+        db_path: db_path.to_string_lossy().to_string(),
+        legacy_base64: general_purpose::STANDARD.encode(&legacy_blob),
+        legacy_fields,
+        unified_base64: general_purpose::STANDARD.encode(&unified_blob),
+        unified_fields,
+    })
+}
 
-    crate::modules::logger::log_info(&format!(
-        "💾 [DB Inject] Database UPDATE executed, rows affected: {}",
-        rows_affected
-    ));
+/// [NEW] Identity fields currently injected into state.vscdb (decoded from the Legacy blob)
+#[derive(Debug, Clone, Serialize)]
+pub struct InjectedIdentity {
+    /// Email field (Field 2) of the Legacy blob, if present and valid UTF-8
+    pub email: Option<String>,
+    /// Token expiry (unix seconds) from OAuthTokenInfo.expiry (Field 6 -> Field 4)
+    pub expiry: Option<i64>,
+}
 
-    if rows_affected == 0 {
-        return Err("Database UPDATE affected 0 rows! Key might not exist.".to_string());
-    }
+/// [NEW] Read back the identity currently stored in the Legacy blob, without mutating the DB.
+/// Inverse of `build_legacy_merged_blob`: decodes Field 2 (email) and Field 6 -> Field 4
+/// (OAuthTokenInfo.expiry). Used by the consistency check to compare the injected email
+/// against what the app believes is the current account.
+pub fn read_injected_identity(db_path: &std::path::PathBuf) -> GatewayResult<InjectedIdentity> {
+    let conn = Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| GatewayError::Db(format!("Failed to open database (read-only): {}", e)))?;
 
-    // [NEW] Verify the write by reading back
-    let verify_data: String = conn
+    let current_data: String = conn
         .query_row(
             "SELECT value FROM ItemTable WHERE key = ?",
             ["jetskiStateSync.agentManagerInitState"],
             |row| row.get(0),
         )
-        .map_err(|e| format!("Failed to verify write: {}", e))?;
+        .map_err(|e| GatewayError::Db(format!("Failed to read data: {}", e)))?;
+
+    let data = general_purpose::STANDARD
+        .decode(&current_data)
+        .map_err(|e| GatewayError::Parse(format!("Base64 decode failed: {}", e)))?;
+
+    // Field 2: email (string)
+    let email = protobuf::find_field(&data, 2)?
+        .and_then(|bytes| String::from_utf8(bytes).ok())
+        .filter(|s| !s.is_empty());
+
+    // Field 6: OAuthTokenInfo -> Field 4: Timestamp { Field 1: seconds (varint) }
+    let expiry = match protobuf::find_field(&data, 6)? {
+        Some(oauth_msg) => match protobuf::find_field(&oauth_msg, 4)? {
+            Some(timestamp_msg) => {
+                // Timestamp message: tag varint (field 1, wire_type 0) followed by the seconds varint
+                let (tag, offset) = protobuf::read_varint(&timestamp_msg, 0)?;
+                if tag >> 3 == 1 && tag & 7 == 0 {
+                    let (seconds, _) = protobuf::read_varint(&timestamp_msg, offset)?;
+                    Some(seconds as i64)
+                } else {
+                    None
+                }
+            }
+            None => None,
+        },
+        None => None,
+    };
+
+    Ok(InjectedIdentity { email, expiry })
+}
+
+/// [NEW] 只读列出 state.vscdb ItemTable 的全部 key (可选前缀过滤)。
+/// 注入未生效时用于排查统一/legacy key 在当前 IDE 版本下是否存在，
+/// 无需借助外部 DB 工具
+pub fn list_state_db_keys(
+    db_path: &std::path::PathBuf,
+    prefix: Option<&str>,
+) -> GatewayResult<Vec<String>> {
+    let conn = Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| GatewayError::Db(format!("Failed to open database (read-only): {}", e)))?;
+
+    let mut stmt = conn
+        .prepare("SELECT key FROM ItemTable ORDER BY key")
+        .map_err(|e| GatewayError::Db(format!("Failed to prepare query: {}", e)))?;
+
+    let keys = stmt
+        .query_map([], |row| row.get::<_, String>(0))
+        .map_err(|e| GatewayError::Db(format!("Failed to read keys: {}", e)))?
+        .filter_map(|r| r.ok())
+        .filter(|key| match prefix {
+            Some(p) if !p.is_empty() => key.starts_with(p),
+            _ => true,
+        })
+        .collect();
+
+    Ok(keys)
+}
+
+// ============================================================================
+// [NEW] 解码结果 LRU 缓存: 账号详情/列表反复查询"当前注入身份"时避免
+// 重复的 base64 + protobuf 解码；任何注入写入后整体失效 (blob 已变)
+// ============================================================================
+
+/// 默认缓存容量 (账号数通常远小于此值)
+const DEFAULT_IDENTITY_CACHE_CAPACITY: usize = 16;
+
+struct IdentityCache {
+    capacity: usize,
+    /// LRU 顺序: 最久未使用在前，最近使用在后
+    entries: Vec<(String, InjectedIdentity)>,
+}
+
+static IDENTITY_CACHE: std::sync::Mutex<IdentityCache> = std::sync::Mutex::new(IdentityCache {
+    capacity: DEFAULT_IDENTITY_CACHE_CAPACITY,
+    entries: Vec::new(),
+});
+
+/// [NEW] 清空身份缓存。注入会改写 state.vscdb 的 Legacy blob，
+/// 所有账号的缓存条目都随之失效，因此整体清空
+pub fn invalidate_identity_cache() {
+    if let Ok(mut cache) = IDENTITY_CACHE.lock() {
+        cache.entries.clear();
+    }
+}
+
+/// [NEW] 带缓存读取指定账号视角下的当前注入身份。
+/// 命中缓存直接返回解码结果；未命中时解码 Legacy blob 并按 LRU 淘汰写入
+pub fn get_injected_identity(
+    account_id: &str,
+    db_path: &std::path::PathBuf,
+) -> GatewayResult<InjectedIdentity> {
+    if let Ok(mut cache) = IDENTITY_CACHE.lock() {
+        if let Some(pos) = cache.entries.iter().position(|(id, _)| id == account_id) {
+            // 命中: 移到队尾标记为最近使用
+            let entry = cache.entries.remove(pos);
+            let identity = entry.1.clone();
+            cache.entries.push(entry);
+            return Ok(identity);
+        }
+    }
+
+    let identity = read_injected_identity(db_path)?;
+
+    if let Ok(mut cache) = IDENTITY_CACHE.lock() {
+        while cache.entries.len() >= cache.capacity {
+            cache.entries.remove(0);
+        }
+        cache.entries.push((account_id.to_string(), identity.clone()));
+    }
+
+    Ok(identity)
+}
+
+/// [NEW] 注入前备份 state.vscdb 到 .vscdb.backup。
+/// Windows 上 IDE 退出的瞬间可能仍短暂持有文件锁导致 copy 失败，
+/// 因此带短暂退避重试；仍失败时返回错误 — 调用方必须中止注入，
+/// 绝不能在没有恢复点的情况下改写数据库
+pub fn backup_db(db_path: &std::path::PathBuf) -> GatewayResult<PathBuf> {
+    const MAX_ATTEMPTS: u32 = 4;
+    const BACKOFF_MS: u64 = 250;
+
+    let backup_path = db_path.with_extension("vscdb.backup");
+    let mut last_err = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        match std::fs::copy(db_path, &backup_path) {
+            Ok(_) => return Ok(backup_path),
+            Err(e) => {
+                last_err = e.to_string();
+                if attempt < MAX_ATTEMPTS {
+                    let wait_ms = BACKOFF_MS * attempt as u64;
+                    crate::modules::logger::log_warn(&format!(
+                        "⏳ [DB Backup] Copy failed (attempt {}/{}), retrying in {}ms: {}",
+                        attempt, MAX_ATTEMPTS, wait_ms, last_err
+                    ));
+                    std::thread::sleep(std::time::Duration::from_millis(wait_ms));
+                }
+            }
+        }
+    }
+
+    Err(GatewayError::Io(format!(
+        "Failed to back up database after {} attempts: {}. Aborting before injection — refusing to modify state.vscdb without a recovery point.",
+        MAX_ATTEMPTS, last_err
+    )))
+}
+
+/// Inject Token and Email into database
+/// [FIX] Retries with backoff when state.vscdb is still locked by the exiting IDE:
+/// busy_timeout alone is not enough when the process holds the lock past 5s.
+pub fn inject_token(
+    db_path: &std::path::PathBuf,
+    access_token: &str,
+    refresh_token: &str,
+    expiry: i64,
+    email: &str,
+) -> GatewayResult<String> {
+    const MAX_ATTEMPTS: u32 = 5;
+    const BACKOFF_MS: u64 = 500;
+
+    // If the IDE is still running, wait briefly for it to release the lock
+    for _ in 0..6 {
+        if !crate::modules::process::is_antigravity_running() {
+            break;
+        }
+        crate::modules::logger::log_warn(
+            "⏳ [DB Inject] IDE process still running, waiting for it to release the DB lock...",
+        );
+        std::thread::sleep(std::time::Duration::from_millis(500));
+    }
 
-    if verify_data != final_b64 {
-        crate::modules::logger::log_error(
-            "❌ [DB Inject] VERIFICATION FAILED! Data was not written correctly!",
+    // [NEW] 安全模式: 等待超时后 IDE 仍持有 state.vscdb 时拒绝写入，
+    // 避免对被锁数据库强行注入造成损坏 (busy_timeout 并不能完全规避)
+    if crate::modules::process::is_antigravity_running() {
+        let require_closed = crate::modules::config::load_app_config()
+            .map(|c| c.require_ide_closed_for_injection)
+            .unwrap_or(true);
+        if require_closed {
+            return Err(GatewayError::Locked(
+                "IDE is still running and holds state.vscdb, injection refused (require_ide_closed_for_injection = true). Close the IDE first, or disable the safeguard in settings to force the write.".to_string(),
+            ));
+        }
+        crate::modules::logger::log_warn(
+            "⚠️ [DB Inject] IDE still running but safeguard disabled, attempting write anyway",
         );
-        return Err("Database write verification failed!".to_string());
     }
 
-    crate::modules::logger::log_info(
-        "✅ [DB Inject] Verification passed! Data written successfully.",
-    );
+    // [NEW] 注入前快照各注入 key 的当前值，供 restore_last_token 一键回滚；
+    // 快照失败只告警不阻断 (文件级 .backup 已提供兜底恢复点)
+    if let Err(e) = snapshot_injected_keys(db_path) {
+        crate::modules::logger::log_warn(&format!(
+            "⚠️ [DB Snapshot] Pre-injection key snapshot failed: {}",
+            e
+        ));
+    }
+
+    let mut last_err = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        match inject_token_once(db_path, access_token, refresh_token, expiry, email) {
+            Ok(msg) => {
+                // [NEW] 注入改写了 Legacy blob，缓存的解码结果全部失效
+                invalidate_identity_cache();
+                return Ok(msg);
+            }
+            Err(e) if is_locked_error(&e) => {
+                let wait_ms = BACKOFF_MS * attempt as u64;
+                crate::modules::logger::log_warn(&format!(
+                    "⏳ [DB Inject] Database locked (attempt {}/{}), retrying in {}ms...",
+                    attempt, MAX_ATTEMPTS, wait_ms
+                ));
+                last_err = e.to_string();
+                std::thread::sleep(std::time::Duration::from_millis(wait_ms));
+            }
+            Err(e) => return Err(e),
+        }
+    }
 
-    // [NEW] 8. Inject into Unified Storage Key (antigravityUnifiedStateSync.oauthToken)
-    // This is required for newer versions of the IDE (Topoo Gateway)
-    let unified_token_blob =
-        protobuf::create_unified_token_message(access_token, refresh_token, expiry);
-    let unified_token_b64 = general_purpose::STANDARD.encode(&unified_token_blob);
+    Err(GatewayError::Locked(format!(
+        "Database is still locked after {} attempts: {}. The IDE process must be fully closed before injecting — please check for leftover processes and try again.",
+        MAX_ATTEMPTS, last_err
+    )))
+}
 
-    let unified_key = "antigravityUnifiedStateSync.oauthToken";
+/// [NEW] Live injection for hot reload: does NOT wait for the IDE to exit.
+/// Newer IDE builds re-read the unified token key without a restart, so the write
+/// happens while the IDE keeps the DB open; lock errors still get a short retry.
+pub fn inject_token_live(
+    db_path: &std::path::PathBuf,
+    access_token: &str,
+    refresh_token: &str,
+    expiry: i64,
+    email: &str,
+) -> GatewayResult<String> {
+    const MAX_ATTEMPTS: u32 = 3;
+    const BACKOFF_MS: u64 = 300;
+
+    // [NEW] 热注入同样改写注入 key，先快照以便 restore_last_token 回滚
+    if let Err(e) = snapshot_injected_keys(db_path) {
+        crate::modules::logger::log_warn(&format!(
+            "⚠️ [DB Snapshot] Pre-injection key snapshot failed: {}",
+            e
+        ));
+    }
+
+    let mut last_err = String::new();
+    for attempt in 1..=MAX_ATTEMPTS {
+        match inject_token_once(db_path, access_token, refresh_token, expiry, email) {
+            Ok(msg) => {
+                // [NEW] 注入改写了 Legacy blob，缓存的解码结果全部失效
+                invalidate_identity_cache();
+                return Ok(msg);
+            }
+            Err(e) if is_locked_error(&e) => {
+                let wait_ms = BACKOFF_MS * attempt as u64;
+                crate::modules::logger::log_warn(&format!(
+                    "⏳ [DB Inject] Live injection hit a lock (attempt {}/{}), retrying in {}ms...",
+                    attempt, MAX_ATTEMPTS, wait_ms
+                ));
+                last_err = e.to_string();
+                std::thread::sleep(std::time::Duration::from_millis(wait_ms));
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(GatewayError::Locked(format!(
+        "Live injection failed, database still locked after {} attempts: {}",
+        MAX_ATTEMPTS, last_err
+    )))
+}
+
+/// [NEW] 试注入：把 state.vscdb 复制到临时文件，对副本执行完整注入 + 回读校验，
+/// 汇报注入前后的字节数，结束后删除副本 — 真实数据库全程不被触碰。
+/// 用于用户在切换账号前验证自己 IDE 版本的注入兼容性
+pub fn test_inject_token(
+    db_path: &std::path::PathBuf,
+    access_token: &str,
+    refresh_token: &str,
+    expiry: i64,
+    email: &str,
+) -> GatewayResult<String> {
+    if !db_path.exists() {
+        return Err(GatewayError::NotFound(format!("Database not found: {:?}", db_path)));
+    }
+
+    let temp_path = std::env::temp_dir().join(format!(
+        "state_test_inject_{}.vscdb",
+        chrono::Utc::now().timestamp_millis()
+    ));
+    std::fs::copy(db_path, &temp_path)
+        .map_err(|e| GatewayError::Io(format!("Failed to copy database to temp file: {}", e)))?;
+    let original_size = std::fs::metadata(&temp_path).map(|m| m.len()).unwrap_or(0);
+
+    // 对副本执行与真实注入完全相同的写入 + 校验逻辑；副本不会被 IDE 锁定，无需等待退出
+    let result = inject_token_once(&temp_path, access_token, refresh_token, expiry, email);
+    let injected_size = std::fs::metadata(&temp_path).map(|m| m.len()).unwrap_or(0);
+
+    // 无论成败都丢弃副本
+    let _ = std::fs::remove_file(&temp_path);
+
+    match result {
+        Ok(msg) => Ok(format!(
+            "Test injection succeeded on a temporary copy ({} -> {} bytes). {}",
+            original_size, injected_size, msg
+        )),
+        Err(e) => Err(GatewayError::Other(format!(
+            "Test injection failed on a temporary copy ({} bytes): {}",
+            original_size, e
+        ))),
+    }
+}
+
+// ============================================================================
+// [NEW] "最后可用 Token" key 级快照: 每次注入前把注入集 key 的当前值存入
+// 数据目录 last_token_snapshot.json，注入损坏会话后可一键回滚。
+// 相比 .backup 文件副本更精细，且 IDE 升级重构 DB 文件后依然可用
+// ============================================================================
+
+/// 单个 key 的快照值 (value = None 表示注入前该 key 不存在，回滚时删除)
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct TokenSnapshotEntry {
+    pub key: String,
+    pub value: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, serde::Deserialize)]
+pub struct TokenSnapshot {
+    /// 快照创建时间 (unix 秒)
+    pub created_at: i64,
+    /// 快照时 Legacy blob 中注入的 email，用于确认回滚目标身份
+    pub injected_email: Option<String>,
+    pub entries: Vec<TokenSnapshotEntry>,
+}
+
+/// [NEW] 回滚结果 (供前端展示恢复到了哪个账号)
+#[derive(Debug, Clone, Serialize)]
+pub struct RestoreReport {
+    pub injected_email: Option<String>,
+    pub snapshot_created_at: i64,
+    pub keys_restored: usize,
+    pub keys_removed: usize,
+}
+
+fn token_snapshot_path() -> GatewayResult<PathBuf> {
+    Ok(crate::modules::account::get_data_dir()?.join("last_token_snapshot.json"))
+}
+
+/// [NEW] 注入前快照注入集各 key 的当前值。只读打开数据库，不产生任何写入
+pub fn snapshot_injected_keys(db_path: &std::path::PathBuf) -> GatewayResult<()> {
+    use rusqlite::OptionalExtension;
+
+    let conn = Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| GatewayError::Db(format!("Failed to open database (read-only): {}", e)))?;
+
+    let mut entries = Vec::new();
+    for spec in injected_key_specs() {
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM ItemTable WHERE key = ?",
+                [spec.key.as_str()],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(|e| GatewayError::Db(format!("Failed to read key {}: {}", spec.key, e)))?;
+        entries.push(TokenSnapshotEntry {
+            key: spec.key,
+            value,
+        });
+    }
+
+    // metadata: 快照值对应的注入身份 (即"最后可用"的账号)
+    let injected_email = read_injected_identity(db_path).ok().and_then(|i| i.email);
+
+    let snapshot = TokenSnapshot {
+        created_at: chrono::Utc::now().timestamp(),
+        injected_email,
+        entries,
+    };
+
+    let path = token_snapshot_path()?;
+    let json = serde_json::to_string_pretty(&snapshot)
+        .map_err(|e| GatewayError::Parse(format!("Failed to serialize token snapshot: {}", e)))?;
+    std::fs::write(&path, json)
+        .map_err(|e| GatewayError::Io(format!("Failed to write token snapshot: {}", e)))?;
 
     crate::modules::logger::log_info(&format!(
-        "✨ [DB Inject] Injecting into Unified Key: {}",
-        unified_key
+        "💾 [DB Snapshot] Saved pre-injection key snapshot ({} keys, email: {})",
+        snapshot.entries.len(),
+        snapshot.injected_email.as_deref().unwrap_or("<none>")
     ));
+    Ok(())
+}
 
-    conn.execute(
-        "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
-        [unified_key, &unified_token_b64],
-    )
-    .map_err(|e| format!("Failed to write Unified Token: {}", e))?;
+/// [NEW] 一键回滚：把快照中的 key 值写回 ItemTable (快照时不存在的 key 删除)。
+/// 与注入一样会使身份缓存整体失效
+pub fn restore_last_token(db_path: &std::path::PathBuf) -> GatewayResult<RestoreReport> {
+    let path = token_snapshot_path()?;
+    if !path.exists() {
+        return Err(GatewayError::NotFound(
+            "No token snapshot found — a snapshot is created automatically before each injection"
+                .to_string(),
+        ));
+    }
+
+    let raw = std::fs::read_to_string(&path)
+        .map_err(|e| GatewayError::Io(format!("Failed to read token snapshot: {}", e)))?;
+    let snapshot: TokenSnapshot = serde_json::from_str(&raw)
+        .map_err(|e| GatewayError::Parse(format!("Failed to parse token snapshot: {}", e)))?;
+
+    let conn = Connection::open(db_path)
+        .map_err(|e| GatewayError::Db(format!("Failed to open database: {}", e)))?;
+    let _ = conn.execute("PRAGMA busy_timeout = 5000", []);
+
+    let mut keys_restored = 0;
+    let mut keys_removed = 0;
+    for entry in &snapshot.entries {
+        match &entry.value {
+            Some(value) => {
+                conn.execute(
+                    "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
+                    [entry.key.as_str(), value.as_str()],
+                )
+                .map_err(|e| GatewayError::Db(format!("Failed to restore key {}: {}", entry.key, e)))?;
+                keys_restored += 1;
+            }
+            None => {
+                conn.execute(
+                    "DELETE FROM ItemTable WHERE key = ?",
+                    [entry.key.as_str()],
+                )
+                .map_err(|e| GatewayError::Db(format!("Failed to remove key {}: {}", entry.key, e)))?;
+                keys_removed += 1;
+            }
+        }
+    }
+
+    // 回滚同样改写了注入 key，缓存的解码结果全部失效
+    invalidate_identity_cache();
+
+    crate::modules::logger::log_info(&format!(
+        "🔙 [DB Snapshot] Restored last token snapshot ({} restored, {} removed, email: {})",
+        keys_restored,
+        keys_removed,
+        snapshot.injected_email.as_deref().unwrap_or("<none>")
+    ));
 
-    // 9. Inject Onboarding flag
-    let onboarding_key = "antigravityOnboarding";
+    Ok(RestoreReport {
+        injected_email: snapshot.injected_email,
+        snapshot_created_at: snapshot.created_at,
+        keys_restored,
+        keys_removed,
+    })
+}
+
+/// [NEW] Sentinel key supported IDE builds watch to re-read the injected token.
+/// 写入当前毫秒时间戳；支持热重载的 IDE 版本读取新 Token 后会删除该 key
+const HOT_RELOAD_SENTINEL_KEY: &str = "antigravityUnifiedStateSync.tokenReloadRequestedAt";
+
+/// [NEW] Touch the hot-reload sentinel so a watching IDE re-reads the unified token key
+pub fn touch_hot_reload_sentinel(db_path: &std::path::PathBuf) -> GatewayResult<()> {
+    let conn =
+        Connection::open(db_path).map_err(|e| GatewayError::Db(format!("Failed to open database: {}", e)))?;
+    let _ = conn.execute("PRAGMA busy_timeout = 5000", []);
+    let now_ms = chrono::Utc::now().timestamp_millis().to_string();
     conn.execute(
         "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
-        [onboarding_key, "true"],
+        [HOT_RELOAD_SENTINEL_KEY, now_ms.as_str()],
     )
-    .map_err(|e| format!("Failed to write Onboarding flag: {}", e))?;
+    .map_err(|e| GatewayError::Db(format!("Failed to write hot-reload sentinel: {}", e)))?;
+    Ok(())
+}
+
+/// [NEW] Whether the IDE consumed (deleted) the hot-reload sentinel
+pub fn hot_reload_confirmed(db_path: &std::path::PathBuf) -> GatewayResult<bool> {
+    let conn = Connection::open_with_flags(db_path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .map_err(|e| GatewayError::Db(format!("Failed to open database (read-only): {}", e)))?;
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM ItemTable WHERE key = ?",
+            [HOT_RELOAD_SENTINEL_KEY],
+            |row| row.get(0),
+        )
+        .map_err(|e| GatewayError::Db(format!("Failed to check hot-reload sentinel: {}", e)))?;
+    Ok(count == 0)
+}
+
+/// SQLite lock errors surface as "database is locked" / "database table is locked"
+fn is_locked_error(error: &GatewayError) -> bool {
+    let msg = error.to_string().to_lowercase();
+    msg.contains("database is locked") || msg.contains("database table is locked")
+}
+
+/// [NEW] 内置默认注入集 (与历史行为一致：legacy 合并 + unified Token + onboarding 标记)
+fn default_injected_keys() -> Vec<InjectedKeySpec> {
+    vec![
+        InjectedKeySpec {
+            key: "jetskiStateSync.agentManagerInitState".to_string(),
+            builder: InjectedKeyBuilder::LegacyMerge,
+            value: None,
+        },
+        InjectedKeySpec {
+            key: "antigravityUnifiedStateSync.oauthToken".to_string(),
+            builder: InjectedKeyBuilder::Unified,
+            value: None,
+        },
+        InjectedKeySpec {
+            key: "antigravityOnboarding".to_string(),
+            builder: InjectedKeyBuilder::Literal,
+            value: Some("true".to_string()),
+        },
+    ]
+}
+
+/// [NEW] 实际使用的注入集：配置了 injected_db_keys 时覆盖内置默认
+fn injected_key_specs() -> Vec<InjectedKeySpec> {
+    match crate::modules::config::load_app_config() {
+        Ok(config) if !config.injected_db_keys.is_empty() => config.injected_db_keys,
+        _ => default_injected_keys(),
+    }
+}
+
+/// Single injection attempt (open + read + merge + write + verify)
+/// [NEW] 注入的 key 集合由 injected_key_specs() 驱动，便于无需发版即可新增 key
+fn inject_token_once(
+    db_path: &std::path::PathBuf,
+    access_token: &str,
+    refresh_token: &str,
+    expiry: i64,
+    email: &str,
+) -> GatewayResult<String> {
+    crate::modules::logger::log_info(&format!(
+        "🔧 [DB Inject] Starting injection for email: {}",
+        email
+    ));
+
+    // 1. Open database
+    let conn =
+        Connection::open(db_path).map_err(|e| GatewayError::Db(format!("Failed to open database: {}", e)))?;
+
+    // [OPTIMIZATION] Set busy timeout to avoid immediate failure when DB is locked by IDE
+    let _ = conn.execute("PRAGMA busy_timeout = 5000", []);
+
+    let specs = injected_key_specs();
+    for spec in &specs {
+        match spec.builder {
+            InjectedKeyBuilder::LegacyMerge => {
+                // 2. Read current data from the legacy key (must already exist)
+                let current_data: String = conn
+                    .query_row(
+                        "SELECT value FROM ItemTable WHERE key = ?",
+                        [spec.key.as_str()],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| GatewayError::Db(format!("Failed to read data: {}", e)))?;
+
+                crate::modules::logger::log_info(&format!(
+                    "📖 [DB Inject] Read current data, length: {} bytes",
+                    current_data.len()
+                ));
+
+                // 3-6. Decode, strip old Identity/Token fields and merge in the new ones
+                let final_data = build_legacy_merged_blob(
+                    &current_data,
+                    access_token,
+                    refresh_token,
+                    expiry,
+                    email,
+                )?;
+                let final_b64 = general_purpose::STANDARD.encode(&final_data);
+
+                crate::modules::logger::log_info(&format!(
+                    "🔀 [DB Inject] Merged data, final length: {} bytes (base64: {} bytes)",
+                    final_data.len(),
+                    final_b64.len()
+                ));
+
+                // 7. Write to database
+                let rows_affected = conn
+                    .execute(
+                        "UPDATE ItemTable SET value = ? WHERE key = ?",
+                        [final_b64.as_str(), spec.key.as_str()],
+                    )
+                    .map_err(|e| GatewayError::Db(format!("Failed to write data: {}", e)))?;
+
+                crate::modules::logger::log_info(&format!(
+                    "💾 [DB Inject] Database UPDATE executed, rows affected: {}",
+                    rows_affected
+                ));
+
+                if rows_affected == 0 {
+                    return Err(GatewayError::NotFound(
+                        "Database UPDATE affected 0 rows! Key might not exist.".to_string(),
+                    ));
+                }
+
+                // [NEW] Verify the write by reading back
+                let verify_data: String = conn
+                    .query_row(
+                        "SELECT value FROM ItemTable WHERE key = ?",
+                        [spec.key.as_str()],
+                        |row| row.get(0),
+                    )
+                    .map_err(|e| GatewayError::Db(format!("Failed to verify write: {}", e)))?;
+
+                if verify_data != final_b64 {
+                    crate::modules::logger::log_error(
+                        "❌ [DB Inject] VERIFICATION FAILED! Data was not written correctly!",
+                    );
+                    return Err(GatewayError::Db("Database write verification failed!".to_string()));
+                }
+
+                crate::modules::logger::log_info(
+                    "✅ [DB Inject] Verification passed! Data written successfully.",
+                );
+            }
+            InjectedKeyBuilder::Unified => {
+                // 8. Inject the unified token blob (required by newer IDE versions)
+                let unified_token_blob =
+                    protobuf::create_unified_token_message(access_token, refresh_token, expiry);
+                let unified_token_b64 = general_purpose::STANDARD.encode(&unified_token_blob);
+
+                crate::modules::logger::log_info(&format!(
+                    "✨ [DB Inject] Injecting into Unified Key: {}",
+                    spec.key
+                ));
+
+                conn.execute(
+                    "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
+                    [spec.key.as_str(), unified_token_b64.as_str()],
+                )
+                .map_err(|e| GatewayError::Db(format!("Failed to write Unified Token: {}", e)))?;
+            }
+            InjectedKeyBuilder::Literal => {
+                // 9. Fixed literal values (e.g. onboarding flag)
+                let value = spec.value.clone().unwrap_or_default();
+                conn.execute(
+                    "INSERT OR REPLACE INTO ItemTable (key, value) VALUES (?, ?)",
+                    [spec.key.as_str(), value.as_str()],
+                )
+                .map_err(|e| GatewayError::Db(format!("Failed to write literal key {}: {}", spec.key, e)))?;
+            }
+        }
+    }
 
     Ok(format!(
-        "Token injection successful (Legacy + Unified)!\nDatabase: {:?}\nEmail: {}",
-        db_path, email
+        "Token injection successful ({} key(s))!\nDatabase: {:?}\nEmail: {}",
+        specs.len(),
+        db_path,
+        email
     ))
 }
@@ -1,3 +1,4 @@
+use crate::error::{GatewayError, GatewayResult};
 use crate::models::DeviceProfile;
 use crate::modules::{logger, process};
 use chrono::Local;
@@ -11,17 +12,19 @@ use uuid::Uuid;
 const DATA_DIR: &str = ".antigravity_tools";
 const GLOBAL_BASELINE: &str = "device_original.json";
 
-fn get_data_dir() -> Result<PathBuf, String> {
-    let home = dirs::home_dir().ok_or("failed_to_get_home_dir")?;
+fn get_data_dir() -> GatewayResult<PathBuf> {
+    let home = dirs::home_dir()
+        .ok_or_else(|| GatewayError::NotFound("failed_to_get_home_dir".to_string()))?;
     let data_dir = home.join(DATA_DIR);
     if !data_dir.exists() {
-        fs::create_dir_all(&data_dir).map_err(|e| format!("failed_to_create_data_dir: {}", e))?;
+        fs::create_dir_all(&data_dir)
+            .map_err(|e| GatewayError::Io(format!("failed_to_create_data_dir: {}", e)))?;
     }
     Ok(data_dir)
 }
 
 /// Find storage.json path (prefer custom/portable paths)
-pub fn get_storage_path() -> Result<PathBuf, String> {
+pub fn get_storage_path() -> GatewayResult<PathBuf> {
     // 1) --user-data-dir flag
     if let Some(user_data_dir) = process::get_user_data_dir_from_process() {
         let path = user_data_dir
@@ -55,7 +58,8 @@ pub fn get_storage_path() -> Result<PathBuf, String> {
 
     #[cfg(target_os = "macos")]
     {
-        let home = dirs::home_dir().ok_or("failed_to_get_home_dir")?;
+        let home = dirs::home_dir()
+        .ok_or_else(|| GatewayError::NotFound("failed_to_get_home_dir".to_string()))?;
         let path = home.join(format!(
             "Library/Application Support/{}/User/globalStorage/storage.json",
             target_app
@@ -68,7 +72,8 @@ pub fn get_storage_path() -> Result<PathBuf, String> {
     #[cfg(target_os = "windows")]
     {
         let appdata =
-            std::env::var("APPDATA").map_err(|_| "failed_to_get_appdata_env".to_string())?;
+            std::env::var("APPDATA")
+            .map_err(|_| GatewayError::Config("failed_to_get_appdata_env".to_string()))?;
         let path = PathBuf::from(appdata)
             .join(format!("{}\\User\\globalStorage\\storage.json", target_app));
         if path.exists() {
@@ -78,57 +83,108 @@ pub fn get_storage_path() -> Result<PathBuf, String> {
 
     #[cfg(target_os = "linux")]
     {
-        let home = dirs::home_dir().ok_or("failed_to_get_home_dir")?;
-        let path = home.join(format!(
-            ".config/{}/User/globalStorage/storage.json",
-            target_app
-        ));
-        if path.exists() {
-            return Ok(path);
+        let home = dirs::home_dir()
+        .ok_or_else(|| GatewayError::NotFound("failed_to_get_home_dir".to_string()))?;
+        let candidates = vec![
+            home.join(format!(
+                ".config/{}/User/globalStorage/storage.json",
+                target_app
+            )),
+            // [NEW] Flatpak 沙箱内配置目录 (~/.var/app/<id>/config/...)
+            home.join(format!(
+                ".var/app/{}/config/{}/User/globalStorage/storage.json",
+                process::get_flatpak_app_id(),
+                target_app
+            )),
+            // [NEW] Snap 沙箱内配置目录
+            home.join(format!(
+                "snap/{}/current/.config/{}/User/globalStorage/storage.json",
+                target_app.to_lowercase(),
+                target_app
+            )),
+        ];
+        for path in candidates {
+            if path.exists() {
+                return Ok(path);
+            }
         }
     }
 
-    Err("storage_json_not_found".to_string())
+    Err(GatewayError::NotFound("storage_json_not_found".to_string()))
 }
 
 /// Get directory of storage.json
-pub fn get_storage_dir() -> Result<PathBuf, String> {
+pub fn get_storage_dir() -> GatewayResult<PathBuf> {
     let path = get_storage_path()?;
     path.parent()
         .map(|p| p.to_path_buf())
-        .ok_or_else(|| "failed_to_get_storage_parent_dir".to_string())
+        .ok_or_else(|| GatewayError::NotFound("failed_to_get_storage_parent_dir".to_string()))
 }
 
 /// Get state.vscdb path (same directory as storage.json)
-pub fn get_state_db_path() -> Result<PathBuf, String> {
+pub fn get_state_db_path() -> GatewayResult<PathBuf> {
     let dir = get_storage_dir()?;
     Ok(dir.join("state.vscdb"))
 }
 
 /// Backup storage.json, returns backup file path
 #[allow(dead_code)]
-pub fn backup_storage(storage_path: &Path) -> Result<PathBuf, String> {
+pub fn backup_storage(storage_path: &Path) -> GatewayResult<PathBuf> {
     if !storage_path.exists() {
-        return Err(format!("storage_json_missing: {:?}", storage_path));
+        return Err(GatewayError::NotFound(format!(
+            "storage_json_missing: {:?}",
+            storage_path
+        )));
     }
     let dir = storage_path
         .parent()
-        .ok_or_else(|| "failed_to_get_storage_parent_dir".to_string())?;
+        .ok_or_else(|| GatewayError::NotFound("failed_to_get_storage_parent_dir".to_string()))?;
     let backup_path = dir.join(format!(
         "storage.json.backup_{}",
         Local::now().format("%Y%m%d_%H%M%S")
     ));
-    fs::copy(storage_path, &backup_path).map_err(|e| format!("backup_failed: {}", e))?;
+    fs::copy(storage_path, &backup_path).map_err(|e| GatewayError::Io(format!("backup_failed: {}", e)))?;
     Ok(backup_path)
 }
 
-/// Read current device profile from storage.json
-#[allow(dead_code)]
-pub fn read_profile(storage_path: &Path) -> Result<DeviceProfile, String> {
+/// [NEW] Read and parse storage.json with a one-shot backup recovery.
+/// A truncated/empty file otherwise surfaces as a confusing parse error; on
+/// parse failure this restores the most recent storage.json.backup_* (via
+/// list_backups) and retries once.
+fn read_storage_json_with_recovery(storage_path: &Path) -> GatewayResult<Value> {
     let content = fs::read_to_string(storage_path)
-        .map_err(|e| format!("read_failed ({:?}): {}", storage_path, e))?;
+        .map_err(|e| GatewayError::Io(format!("read_failed ({:?}): {}", storage_path, e)))?;
+
+    let parse_err = match serde_json::from_str::<Value>(&content) {
+        Ok(json) => return Ok(json),
+        Err(e) => e,
+    };
+
+    logger::log_warn(&format!(
+        "storage_json_parse_failed ({:?}): {}, attempting backup recovery",
+        storage_path, parse_err
+    ));
+
+    let backups = list_backups(storage_path)?;
+    let latest = match backups.first() {
+        Some(p) => p.clone(),
+        None => return Err(GatewayError::Parse("storage_json_corrupt_no_backup".to_string())),
+    };
+
+    fs::copy(&latest, storage_path).map_err(|e| GatewayError::Io(format!("restore_failed: {}", e)))?;
+    let content = fs::read_to_string(storage_path)
+        .map_err(|e| GatewayError::Io(format!("read_failed ({:?}): {}", storage_path, e)))?;
     let json: Value = serde_json::from_str(&content)
-        .map_err(|e| format!("parse_failed ({:?}): {}", storage_path, e))?;
+        .map_err(|e| GatewayError::Parse(format!("parse_failed_after_restore ({:?}): {}", latest, e)))?;
+
+    logger::log_info(&format!("storage_json_recovered_from_backup: {:?}", latest));
+    Ok(json)
+}
+
+/// Read current device profile from storage.json
+#[allow(dead_code)]
+pub fn read_profile(storage_path: &Path) -> GatewayResult<DeviceProfile> {
+    let json = read_storage_json_with_recovery(storage_path)?;
 
     // Supports nested telemetry or flat telemetry.xxx
     let get_field = |key: &str| -> Option<String> {
@@ -147,29 +203,36 @@ pub fn read_profile(storage_path: &Path) -> Result<DeviceProfile, String> {
     };
 
     Ok(DeviceProfile {
-        machine_id: get_field("machineId").ok_or("missing_machine_id")?,
-        mac_machine_id: get_field("macMachineId").ok_or("missing_mac_machine_id")?,
-        dev_device_id: get_field("devDeviceId").ok_or("missing_dev_device_id")?,
-        sqm_id: get_field("sqmId").ok_or("missing_sqm_id")?,
+        machine_id: get_field("machineId")
+            .ok_or_else(|| GatewayError::Parse("missing_machine_id".to_string()))?,
+        mac_machine_id: get_field("macMachineId")
+            .ok_or_else(|| GatewayError::Parse("missing_mac_machine_id".to_string()))?,
+        dev_device_id: get_field("devDeviceId")
+            .ok_or_else(|| GatewayError::Parse("missing_dev_device_id".to_string()))?,
+        sqm_id: get_field("sqmId").ok_or_else(|| GatewayError::Parse("missing_sqm_id".to_string()))?,
     })
 }
 
 /// Write device profile to storage.json
-pub fn write_profile(storage_path: &Path, profile: &DeviceProfile) -> Result<(), String> {
+pub fn write_profile(storage_path: &Path, profile: &DeviceProfile) -> GatewayResult<()> {
     if !storage_path.exists() {
-        return Err(format!("storage_json_missing: {:?}", storage_path));
+        return Err(GatewayError::NotFound(format!(
+            "storage_json_missing: {:?}",
+            storage_path
+        )));
     }
 
-    let content = fs::read_to_string(storage_path).map_err(|e| format!("read_failed: {}", e))?;
-    let mut json: Value =
-        serde_json::from_str(&content).map_err(|e| format!("parse_failed: {}", e))?;
+    let content = fs::read_to_string(storage_path)
+        .map_err(|e| GatewayError::Io(format!("read_failed: {}", e)))?;
+    let mut json: Value = serde_json::from_str(&content)
+        .map_err(|e| GatewayError::Parse(format!("parse_failed: {}", e)))?;
 
     // Ensure telemetry is an object
     if !json.get("telemetry").map_or(false, |v| v.is_object()) {
         if json.as_object_mut().is_some() {
             json["telemetry"] = serde_json::json!({});
         } else {
-            return Err("json_top_level_not_object".to_string());
+            return Err(GatewayError::Parse("json_top_level_not_object".to_string()));
         }
     }
 
@@ -188,7 +251,7 @@ pub fn write_profile(storage_path: &Path, profile: &DeviceProfile) -> Result<(),
         );
         telemetry.insert("sqmId".to_string(), Value::String(profile.sqm_id.clone()));
     } else {
-        return Err("telemetry_not_object".to_string());
+        return Err(GatewayError::Parse("telemetry_not_object".to_string()));
     }
 
     // Write flat keys as well, compatible with old formats
@@ -219,10 +282,10 @@ pub fn write_profile(storage_path: &Path, profile: &DeviceProfile) -> Result<(),
         );
     }
 
-    let updated =
-        serde_json::to_string_pretty(&json).map_err(|e| format!("serialize_failed: {}", e))?;
+    let updated = serde_json::to_string_pretty(&json)
+        .map_err(|e| GatewayError::Parse(format!("serialize_failed: {}", e)))?;
     fs::write(storage_path, updated)
-        .map_err(|e| format!("write_failed ({:?}): {}", storage_path, e))?;
+        .map_err(|e| GatewayError::Io(format!("write_failed ({:?}): {}", storage_path, e)))?;
     logger::log_info(&format!("device_profile_written to {:?}", storage_path));
 
     // Sync ItemTable.storage.serviceMachineId in state.vscdb
@@ -232,10 +295,8 @@ pub fn write_profile(storage_path: &Path, profile: &DeviceProfile) -> Result<(),
 
 /// Only sync serviceMachineId, don't change other fields
 #[allow(dead_code)]
-pub fn sync_service_machine_id(storage_path: &Path, service_id: &str) -> Result<(), String> {
-    let content = fs::read_to_string(storage_path).map_err(|e| format!("read_failed: {}", e))?;
-    let mut json: Value =
-        serde_json::from_str(&content).map_err(|e| format!("parse_failed: {}", e))?;
+pub fn sync_service_machine_id(storage_path: &Path, service_id: &str) -> GatewayResult<()> {
+    let mut json = read_storage_json_with_recovery(storage_path)?;
 
     if let Some(map) = json.as_object_mut() {
         map.insert(
@@ -244,9 +305,9 @@ pub fn sync_service_machine_id(storage_path: &Path, service_id: &str) -> Result<
         );
     }
 
-    let updated =
-        serde_json::to_string_pretty(&json).map_err(|e| format!("serialize_failed: {}", e))?;
-    fs::write(storage_path, updated).map_err(|e| format!("write_failed: {}", e))?;
+    let updated = serde_json::to_string_pretty(&json)
+        .map_err(|e| GatewayError::Parse(format!("serialize_failed: {}", e)))?;
+    fs::write(storage_path, updated).map_err(|e| GatewayError::Io(format!("write_failed: {}", e)))?;
     logger::log_info("service_machine_id_synced");
 
     let _ = sync_state_service_machine_id_value(service_id);
@@ -255,13 +316,11 @@ pub fn sync_service_machine_id(storage_path: &Path, service_id: &str) -> Result<
 
 /// Read serviceMachineId from storage.json (fallback to devDeviceId), sync back if missing and sync state.vscdb
 #[allow(dead_code)]
-pub fn sync_service_machine_id_from_storage(storage_path: &Path) -> Result<(), String> {
+pub fn sync_service_machine_id_from_storage(storage_path: &Path) -> GatewayResult<()> {
     if !storage_path.exists() {
-        return Err("storage_json_missing".to_string());
+        return Err(GatewayError::NotFound("storage_json_missing".to_string()));
     }
-    let content = fs::read_to_string(storage_path).map_err(|e| format!("read_failed: {}", e))?;
-    let mut json: Value =
-        serde_json::from_str(&content).map_err(|e| format!("parse_failed: {}", e))?;
+    let mut json = read_storage_json_with_recovery(storage_path)?;
 
     let service_id = json
         .get("storage.serviceMachineId")
@@ -278,7 +337,7 @@ pub fn sync_service_machine_id_from_storage(storage_path: &Path) -> Result<(), S
                 .and_then(|v| v.as_str())
                 .map(|s| s.to_string())
         })
-        .ok_or("missing_ids_in_storage")?;
+        .ok_or_else(|| GatewayError::Parse("missing_ids_in_storage".to_string()))?;
 
     let mut dirty = false;
     if json
@@ -296,33 +355,34 @@ pub fn sync_service_machine_id_from_storage(storage_path: &Path) -> Result<(), S
     }
 
     if dirty {
-        let updated =
-            serde_json::to_string_pretty(&json).map_err(|e| format!("serialize_failed: {}", e))?;
-        fs::write(storage_path, updated).map_err(|e| format!("write_failed: {}", e))?;
+        let updated = serde_json::to_string_pretty(&json)
+            .map_err(|e| GatewayError::Parse(format!("serialize_failed: {}", e)))?;
+        fs::write(storage_path, updated)
+            .map_err(|e| GatewayError::Io(format!("write_failed: {}", e)))?;
         logger::log_info("service_machine_id_added");
     }
 
     sync_state_service_machine_id_value(&service_id)
 }
 
-fn sync_state_service_machine_id_value(service_id: &str) -> Result<(), String> {
+fn sync_state_service_machine_id_value(service_id: &str) -> GatewayResult<()> {
     let db_path = get_state_db_path()?;
     if !db_path.exists() {
         logger::log_warn(&format!("state_db_missing: {:?}", db_path));
         return Ok(());
     }
 
-    let conn = Connection::open(&db_path).map_err(|e| format!("db_open_failed: {}", e))?;
+    let conn = Connection::open(&db_path).map_err(|e| GatewayError::Db(format!("db_open_failed: {}", e)))?;
     conn.execute(
         "CREATE TABLE IF NOT EXISTS ItemTable (key TEXT PRIMARY KEY, value TEXT);",
         [],
     )
-    .map_err(|e| format!("failed_to_create_item_table: {}", e))?;
+    .map_err(|e| GatewayError::Db(format!("failed_to_create_item_table: {}", e)))?;
     conn.execute(
         "INSERT OR REPLACE INTO ItemTable (key, value) VALUES ('storage.serviceMachineId', ?1);",
         [service_id],
     )
-    .map_err(|e| format!("failed_to_write_to_db: {}", e))?;
+    .map_err(|e| GatewayError::Db(format!("failed_to_write_to_db: {}", e)))?;
     logger::log_info("service_machine_id_synced_to_db");
     Ok(())
 }
@@ -342,23 +402,23 @@ pub fn load_global_original() -> Option<DeviceProfile> {
     None
 }
 
-pub fn save_global_original(profile: &DeviceProfile) -> Result<(), String> {
+pub fn save_global_original(profile: &DeviceProfile) -> GatewayResult<()> {
     let dir = get_data_dir()?;
     let path = dir.join(GLOBAL_BASELINE);
     if path.exists() {
         return Ok(()); // already exists, don't overwrite
     }
-    let content =
-        serde_json::to_string_pretty(profile).map_err(|e| format!("serialize_failed: {}", e))?;
-    fs::write(&path, content).map_err(|e| format!("write_failed: {}", e))
+    let content = serde_json::to_string_pretty(profile)
+        .map_err(|e| GatewayError::Parse(format!("serialize_failed: {}", e)))?;
+    fs::write(&path, content).map_err(|e| GatewayError::Io(format!("write_failed: {}", e)))
 }
 
 /// List storage.json backups in current directory (descending by time)
 #[allow(dead_code)]
-pub fn list_backups(storage_path: &Path) -> Result<Vec<PathBuf>, String> {
+pub fn list_backups(storage_path: &Path) -> GatewayResult<Vec<PathBuf>> {
     let dir = storage_path
         .parent()
-        .ok_or_else(|| "failed_to_get_storage_parent_dir".to_string())?;
+        .ok_or_else(|| GatewayError::NotFound("failed_to_get_storage_parent_dir".to_string()))?;
     let mut backups = Vec::new();
     if let Ok(entries) = fs::read_dir(dir) {
         for entry in entries.flatten() {
@@ -381,10 +441,10 @@ pub fn list_backups(storage_path: &Path) -> Result<Vec<PathBuf>, String> {
 
 /// Restore backup to storage.json. If use_oldest=true, use oldest backup, else use latest.
 #[allow(dead_code)]
-pub fn restore_backup(storage_path: &Path, use_oldest: bool) -> Result<PathBuf, String> {
+pub fn restore_backup(storage_path: &Path, use_oldest: bool) -> GatewayResult<PathBuf> {
     let backups = list_backups(storage_path)?;
     if backups.is_empty() {
-        return Err("no_backups_found".to_string());
+        return Err(GatewayError::NotFound("no_backups_found".to_string()));
     }
     let target = if use_oldest {
         backups.last().unwrap().clone()
@@ -393,7 +453,7 @@ pub fn restore_backup(storage_path: &Path, use_oldest: bool) -> Result<PathBuf,
     };
     // backup current first
     let _ = backup_storage(storage_path)?;
-    fs::copy(&target, storage_path).map_err(|e| format!("restore_failed: {}", e))?;
+    fs::copy(&target, storage_path).map_err(|e| GatewayError::Io(format!("restore_failed: {}", e)))?;
     logger::log_info(&format!("storage_json_restored: {:?}", target));
     Ok(target)
 }
@@ -432,3 +492,122 @@ fn new_standard_machine_id() -> String {
     }
     id
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// write_profile 只更新四个已知 telemetry 键 (及平铺变体/serviceMachineId)，
+    /// 其余 telemetry 子键与根级键必须在写入往返后原样保留
+    #[test]
+    fn test_write_profile_preserves_unknown_keys() {
+        let temp_dir = std::env::temp_dir();
+        let storage_path = temp_dir.join(format!("test_storage_{}.json", Uuid::new_v4()));
+
+        let original = serde_json::json!({
+            "telemetry": {
+                "machineId": "old-machine",
+                "macMachineId": "old-mac",
+                "devDeviceId": "old-dev",
+                "sqmId": "{OLD}",
+                "firstSessionDate": "2024-01-01T00:00:00.000Z",
+                "currentSessionDate": "2024-06-01T00:00:00.000Z",
+                "installTime": 1700000000
+            },
+            "telemetry.machineId": "old-machine",
+            "backupWorkspaces": { "folders": ["/tmp/project"] },
+            "windowControlHeight": 35
+        });
+        fs::write(
+            &storage_path,
+            serde_json::to_string_pretty(&original).unwrap(),
+        )
+        .unwrap();
+
+        let profile = DeviceProfile {
+            machine_id: "new-machine".to_string(),
+            mac_machine_id: "new-mac".to_string(),
+            dev_device_id: "new-dev".to_string(),
+            sqm_id: "{NEW}".to_string(),
+        };
+        write_profile(&storage_path, &profile).expect("write_profile failed");
+
+        let content = fs::read_to_string(&storage_path).unwrap();
+        let json: Value = serde_json::from_str(&content).unwrap();
+        let telemetry = json.get("telemetry").and_then(|v| v.as_object()).unwrap();
+
+        // 四个已知键被更新
+        assert_eq!(telemetry.get("machineId").unwrap(), "new-machine");
+        assert_eq!(telemetry.get("macMachineId").unwrap(), "new-mac");
+        assert_eq!(telemetry.get("devDeviceId").unwrap(), "new-dev");
+        assert_eq!(telemetry.get("sqmId").unwrap(), "{NEW}");
+
+        // 其余 telemetry 子键原样保留
+        assert_eq!(
+            telemetry.get("firstSessionDate").unwrap(),
+            "2024-01-01T00:00:00.000Z"
+        );
+        assert_eq!(
+            telemetry.get("currentSessionDate").unwrap(),
+            "2024-06-01T00:00:00.000Z"
+        );
+        assert_eq!(telemetry.get("installTime").unwrap(), 1700000000);
+
+        // 根级无关键原样保留
+        assert_eq!(
+            json.get("backupWorkspaces").unwrap(),
+            &serde_json::json!({ "folders": ["/tmp/project"] })
+        );
+        assert_eq!(json.get("windowControlHeight").unwrap(), 35);
+
+        // 平铺变体与 serviceMachineId 同步
+        assert_eq!(json.get("telemetry.machineId").unwrap(), "new-machine");
+        assert_eq!(json.get("storage.serviceMachineId").unwrap(), "new-dev");
+
+        let _ = fs::remove_file(&storage_path);
+    }
+
+    /// storage.json 损坏时应从最近的备份恢复并重试一次；
+    /// 无备份时返回明确的 storage_json_corrupt_no_backup 错误
+    #[test]
+    fn test_read_profile_recovers_from_backup_on_corrupt_json() {
+        // 独立子目录，避免 list_backups 扫到其他测试的备份
+        let dir = std::env::temp_dir().join(format!("test_device_{}", Uuid::new_v4()));
+        fs::create_dir_all(&dir).unwrap();
+        let storage_path = dir.join("storage.json");
+
+        // 无备份: 明确报错
+        fs::write(&storage_path, "{\"telemetry\": {\"machineId\"").unwrap();
+        let err = read_profile(&storage_path).unwrap_err();
+        assert_eq!(err.to_string(), "storage_json_corrupt_no_backup");
+
+        // 有备份: 自动恢复
+        let valid = serde_json::json!({
+            "telemetry": {
+                "machineId": "backup-machine",
+                "macMachineId": "backup-mac",
+                "devDeviceId": "backup-dev",
+                "sqmId": "{BACKUP}"
+            }
+        });
+        fs::write(
+            dir.join("storage.json.backup_20240101_000000"),
+            serde_json::to_string_pretty(&valid).unwrap(),
+        )
+        .unwrap();
+
+        let profile = read_profile(&storage_path).expect("should recover from backup");
+        assert_eq!(profile.machine_id, "backup-machine");
+        assert_eq!(profile.dev_device_id, "backup-dev");
+
+        // storage.json 本身已被恢复
+        let restored: Value =
+            serde_json::from_str(&fs::read_to_string(&storage_path).unwrap()).unwrap();
+        assert_eq!(
+            restored["telemetry"]["machineId"],
+            Value::String("backup-machine".to_string())
+        );
+
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -212,6 +212,14 @@ struct LogsRequest {
     filter: String,
     #[serde(default)]
     errors_only: bool,
+    /// [NEW] 按账号邮箱精确过滤
+    #[serde(default)]
+    account_email: Option<String>,
+    /// [NEW] 状态码范围过滤 (含边界)
+    #[serde(default)]
+    status_min: Option<u16>,
+    #[serde(default)]
+    status_max: Option<u16>,
 }
 
 // ============================================================================
@@ -426,11 +434,25 @@ async fn get_logs(
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let limit = if params.limit == 0 { 50 } else { params.limit };
 
-    let total = proxy_db::get_logs_count_filtered(&params.filter, params.errors_only)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
-
-    let logs = proxy_db::get_logs_filtered(&params.filter, params.errors_only, limit, params.offset)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+    let total = proxy_db::get_logs_count_filtered(
+        &params.filter,
+        params.errors_only,
+        params.account_email.clone(),
+        params.status_min,
+        params.status_max,
+    )
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
+
+    let logs = proxy_db::get_logs_filtered(
+        &params.filter,
+        params.errors_only,
+        params.account_email.clone(),
+        params.status_min,
+        params.status_max,
+        limit,
+        params.offset,
+    )
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, Json(ErrorResponse { error: e })))?;
 
     Ok(Json(LogsResponse {
         total,
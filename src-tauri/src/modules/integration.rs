@@ -1,6 +1,115 @@
 use crate::models::Account;
 use crate::modules::{db, device, process};
-use std::fs;
+use std::sync::OnceLock;
+
+/// 全局 SystemManager（启动时设置一次），供无法拿到 AppHandle 的深层模块
+/// （如 TokenManager 刷新路径）发送通知/事件使用
+static GLOBAL_MANAGER: OnceLock<SystemManager> = OnceLock::new();
+
+/// 设置全局 SystemManager（仅首次调用生效）
+pub fn set_global_manager(manager: SystemManager) {
+    let _ = GLOBAL_MANAGER.set(manager);
+}
+
+/// 获取全局 SystemManager，未设置时回退到 Headless
+pub fn global_manager() -> SystemManager {
+    GLOBAL_MANAGER.get().cloned().unwrap_or(SystemManager::Headless)
+}
+
+/// 账号 refresh_token 失效（invalid_grant/撤销）时的统一通知：
+/// 系统通知 + 前端事件 `account://invalidated`，避免账号池静默缩水
+pub fn notify_account_invalidated(email: &str, reason: &str) {
+    let manager = global_manager();
+    manager.show_notification(
+        "账号已失效",
+        &format!("{} 的 refresh_token 已失效，已停用反代", email),
+    );
+    if let SystemManager::Desktop(handle) = &manager {
+        use tauri::Emitter;
+        let _ = handle.emit(
+            "account://invalidated",
+            serde_json::json!({ "email": email, "reason": reason }),
+        );
+    }
+}
+
+/// [NEW] 全池校验进度事件 `account://validation-progress`，供前端进度条；
+/// Headless 模式下静默跳过
+pub fn emit_pool_validation_progress(
+    done: usize,
+    total: usize,
+    account_id: &str,
+    email: &str,
+    status: &str,
+) {
+    if let SystemManager::Desktop(handle) = &global_manager() {
+        use tauri::Emitter;
+        let _ = handle.emit(
+            "account://validation-progress",
+            serde_json::json!({
+                "done": done,
+                "total": total,
+                "account_id": account_id,
+                "email": email,
+                "status": status,
+            }),
+        );
+    }
+}
+
+// ============================================================================
+// [NEW] 切换互斥 + 冷却：双击"切换"会触发并发的 close/inject/start
+// 流程并损坏状态，这里用全局闸门拒绝重入，并在两次切换间强制最小间隔
+// ============================================================================
+
+struct SwitchGate {
+    in_progress: bool,
+    /// 上一次切换（无论成败）结束的时刻，用于冷却判定
+    last_finished_at: Option<std::time::Instant>,
+}
+
+static SWITCH_GATE: std::sync::Mutex<SwitchGate> = std::sync::Mutex::new(SwitchGate {
+    in_progress: false,
+    last_finished_at: None,
+});
+
+/// RAII 守卫：Drop 时释放闸门并记录结束时刻，切换出错提前返回也不会卡死
+pub struct SwitchGuard;
+
+impl Drop for SwitchGuard {
+    fn drop(&mut self) {
+        let mut gate = SWITCH_GATE.lock().unwrap_or_else(|p| p.into_inner());
+        gate.in_progress = false;
+        gate.last_finished_at = Some(std::time::Instant::now());
+    }
+}
+
+/// 尝试进入切换流程：已有切换在进行或处于冷却期时返回 Err("switch_in_progress...")
+fn begin_switch_with_cooldown(cooldown_ms: u64) -> Result<SwitchGuard, String> {
+    let mut gate = SWITCH_GATE.lock().unwrap_or_else(|p| p.into_inner());
+    if gate.in_progress {
+        return Err("switch_in_progress".to_string());
+    }
+    if let Some(finished_at) = gate.last_finished_at {
+        let elapsed_ms = finished_at.elapsed().as_millis() as u64;
+        if elapsed_ms < cooldown_ms {
+            return Err(format!(
+                "switch_in_progress: cooldown {}ms remaining",
+                cooldown_ms - elapsed_ms
+            ));
+        }
+    }
+    gate.in_progress = true;
+    Ok(SwitchGuard)
+}
+
+/// 按配置的 switch_cooldown_ms 进入切换流程（配置读取失败时用默认值）
+pub fn begin_switch() -> Result<SwitchGuard, String> {
+    let cooldown_ms = crate::modules::config::load_app_config()
+        .map(|c| c.switch_cooldown_ms)
+        .unwrap_or(crate::models::config::default_switch_cooldown_ms());
+    begin_switch_with_cooldown(cooldown_ms)
+}
 
 pub trait SystemIntegration: Send + Sync {
     /// 当切换账号时执行的系统层操作（如杀进程、写入文件、注入数据库）
@@ -20,6 +129,9 @@ pub struct DesktopIntegration {
 
 impl SystemIntegration for DesktopIntegration {
     async fn on_account_switch(&self, account: &crate::models::Account) -> Result<(), String> {
+        // [NEW] 全局切换闸门：并发/连点切换直接拒绝，出错时守卫 Drop 自动释放
+        let _switch_guard = begin_switch()?;
+
         crate::modules::logger::log_info(&format!(
             "[Antigravity] Executing system switch for: {}",
             account.email
@@ -32,28 +144,51 @@ impl SystemIntegration for DesktopIntegration {
             storage_path
         ));
 
-        // 2. 关闭外部进程
+        // 2. [NEW] 热注入模式：支持的 IDE 版本无需重启即可重读统一 Token key。
+        // 注入到运行中的数据库并触发重载哨兵；超时未确认则回退到关闭/重启流程
+        let hot_inject = crate::modules::config::load_app_config()
+            .map(|c| c.hot_inject_without_restart)
+            .unwrap_or(false);
+        if hot_inject && process::is_antigravity_running() {
+            match try_hot_inject(account).await {
+                Ok(()) => {
+                    crate::modules::logger::log_info(
+                        "[Antigravity] Hot reload confirmed, skipping restart.",
+                    );
+                    let _ = crate::modules::tray::update_tray_menus(&self.app_handle);
+                    return Ok(());
+                }
+                Err(e) => {
+                    crate::modules::logger::log_warn(&format!(
+                        "[Antigravity] Hot inject failed ({}), falling back to restart.",
+                        e
+                    ));
+                }
+            }
+        }
+
+        // 3. 关闭外部进程
         if process::is_antigravity_running() {
             crate::modules::logger::log_info("[Antigravity] App is running, closing...");
             process::close_antigravity(20)?;
             crate::modules::logger::log_info("[Antigravity] App closed.");
         }
 
-        // 3. 写入设备 Profile
+        // 4. 写入设备 Profile
         if let Some(ref profile) = account.device_profile {
             crate::modules::logger::log_info("[Antigravity] Writing device profile...");
             device::write_profile(&storage_path, profile)?;
             crate::modules::logger::log_info("[Antigravity] Device profile written.");
         }
 
-        // 4. 数据库处理与 Token 注入
+        // 5. 数据库处理与 Token 注入
         crate::modules::logger::log_info("[Antigravity] Preparing database injection...");
         let db_path = db::get_db_path()?;
         crate::modules::logger::log_info(&format!("[Antigravity] DB path: {:?}", db_path));
 
         if db_path.exists() {
-            let backup_path = db_path.with_extension("vscdb.backup");
-            let _ = fs::copy(&db_path, &backup_path);
+            // [FIX] 备份失败时中止切换：绝不能在没有恢复点的情况下注入
+            db::backup_db(&db_path)?;
             crate::modules::logger::log_info("[Antigravity] Database backed up.");
         } else {
             crate::modules::logger::log_warn("[Antigravity] Database file not found!");
@@ -71,12 +206,12 @@ impl SystemIntegration for DesktopIntegration {
         )?;
         crate::modules::logger::log_info("[Antigravity] Tokens injected successfully.");
 
-        // 5. 重启外部进程
+        // 6. 重启外部进程
         crate::modules::logger::log_info("[Antigravity] Starting app...");
         process::start_antigravity()?;
         crate::modules::logger::log_info("[Antigravity] App started.");
 
-        // 6. 更新托盘
+        // 7. 更新托盘
         crate::modules::logger::log_info("[Antigravity] Updating tray...");
         let _ = crate::modules::tray::update_tray_menus(&self.app_handle);
 
@@ -88,11 +223,66 @@ impl SystemIntegration for DesktopIntegration {
     }
 
     fn show_notification(&self, title: &str, body: &str) {
-        // 使用 tauri-plugin-dialog 或原生通知（此处简化）
+        // [NEW] 通过 tauri-plugin-notification 发送真实系统通知，日志保留便于排查
+        use tauri_plugin_notification::NotificationExt;
         crate::modules::logger::log_info(&format!("[Notification] {}: {}", title, body));
+        if let Err(e) = self
+            .app_handle
+            .notification()
+            .builder()
+            .title(title)
+            .body(body)
+            .show()
+        {
+            crate::modules::logger::log_error(&format!("系统通知发送失败: {}", e));
+        }
     }
 }
 
+/// [NEW] 哨兵确认超时：超过该时长未见 IDE 消费哨兵就回退重启流程
+const HOT_RELOAD_CONFIRM_TIMEOUT_MS: u64 = 5_000;
+const HOT_RELOAD_POLL_INTERVAL_MS: u64 = 500;
+
+/// [NEW] 向运行中的 IDE 数据库注入 Token 并等待热重载确认。
+/// 流程：备份 -> 注入 (不等进程退出) -> 写重载哨兵 -> 轮询等待 IDE 删除哨兵。
+/// 任一步失败或超时即返回 Err，调用方回退到关闭/重启流程
+async fn try_hot_inject(account: &Account) -> Result<(), String> {
+    let db_path = db::get_db_path()?;
+    crate::modules::logger::log_info(&format!(
+        "[Antigravity] Hot injecting token for {} into live DB: {:?}",
+        account.email, db_path
+    ));
+
+    if db_path.exists() {
+        // [FIX] 备份失败时返回 Err，调用方回退到关闭/重启流程 (该流程会再次尝试备份)
+        db::backup_db(&db_path)?;
+    }
+
+    db::inject_token_live(
+        &db_path,
+        &account.token.access_token,
+        &account.token.refresh_token,
+        account.token.expiry_timestamp,
+        &account.email,
+    )?;
+
+    db::touch_hot_reload_sentinel(&db_path)?;
+
+    let deadline = std::time::Instant::now()
+        + std::time::Duration::from_millis(HOT_RELOAD_CONFIRM_TIMEOUT_MS);
+    while std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(HOT_RELOAD_POLL_INTERVAL_MS)).await;
+        if db::hot_reload_confirmed(&db_path)? {
+            return Ok(());
+        }
+    }
+
+    Err(format!(
+        "IDE did not confirm hot reload within {}ms",
+        HOT_RELOAD_CONFIRM_TIMEOUT_MS
+    ))
+}
+
 /// Headless/Docker 实现：仅执行数据层操作，忽略 UI 和进程控制
 pub struct HeadlessIntegration;
 
@@ -115,6 +305,28 @@ impl SystemIntegration for HeadlessIntegration {
         crate::modules::logger::log_info(&format!("[Log Notification] {}: {}", title, body));
     }
 }
+/// [NEW] 模拟账号切换时的单个步骤（dry-run 结果，供前端展示）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SwitchPlanStep {
+    /// 步骤标识：close_process / write_profile / backup_db / inject_token / start_process
+    pub step: String,
+    /// 人类可读说明（包含路径等上下文）
+    pub detail: String,
+    /// 真实切换时该步骤是否会执行
+    pub would_execute: bool,
+}
+
+/// [NEW] 账号切换基准测试中单个阶段的耗时（供前端/日志定位慢在哪一步）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct SwitchPhaseTiming {
+    /// 阶段标识：close_process / write_profile / backup_db / inject_token / start_process
+    pub phase: String,
+    /// 该阶段耗时（毫秒）
+    pub elapsed_ms: u64,
+    /// 该阶段是否实际执行（如应用未运行则关闭阶段被跳过）
+    pub executed: bool,
+}
+
 /// 系统集成管理器：替代 Arc<dyn SystemIntegration> 以解决 async trait 的 dyn 兼容性问题
 #[derive(Clone)]
 pub enum SystemManager {
@@ -123,6 +335,217 @@ pub enum SystemManager {
 }
 
 impl SystemManager {
+    /// [NEW] Dry-run 账号切换：按真实 on_account_switch 的顺序逐步检查环境并记录
+    /// 将会发生什么（关闭进程 / 写指纹 / 备份注入数据库 / 重启进程），
+    /// 只读取环境信息，不做任何进程控制或数据库写入
+    pub async fn simulate_account_switch(
+        &self,
+        account: &Account,
+    ) -> Result<Vec<SwitchPlanStep>, String> {
+        let mut plan = Vec::new();
+
+        if matches!(self, SystemManager::Headless) {
+            crate::modules::logger::log_info(&format!(
+                "[Simulate] Headless mode: switch to {} is memory-only",
+                account.email
+            ));
+            plan.push(SwitchPlanStep {
+                step: "headless_noop".to_string(),
+                detail: "Headless 模式仅更新内存状态，不执行系统层操作".to_string(),
+                would_execute: false,
+            });
+            return Ok(plan);
+        }
+
+        crate::modules::logger::log_info(&format!(
+            "[Simulate] Dry-run system switch for: {}",
+            account.email
+        ));
+
+        // 1. 关闭外部进程（仅在运行时）
+        let running = process::is_antigravity_running();
+        crate::modules::logger::log_info(&format!("[Simulate] App running: {}", running));
+        plan.push(SwitchPlanStep {
+            step: "close_process".to_string(),
+            detail: if running {
+                "目标应用正在运行，切换时会先关闭".to_string()
+            } else {
+                "目标应用未运行，无需关闭".to_string()
+            },
+            would_execute: running,
+        });
+
+        // 2. 写入设备 Profile（真实切换中无指纹会先自动生成）
+        let storage_path = device::get_storage_path();
+        match &storage_path {
+            Ok(path) => {
+                crate::modules::logger::log_info(&format!(
+                    "[Simulate] Storage path: {:?}",
+                    path
+                ));
+                plan.push(SwitchPlanStep {
+                    step: "write_profile".to_string(),
+                    detail: if account.device_profile.is_some() {
+                        format!("写入已绑定的设备指纹到 {:?}", path)
+                    } else {
+                        format!("账号未绑定指纹，切换时会自动生成并写入 {:?}", path)
+                    },
+                    would_execute: true,
+                });
+            }
+            Err(e) => {
+                plan.push(SwitchPlanStep {
+                    step: "write_profile".to_string(),
+                    detail: format!("无法定位 storage.json，真实切换会在此失败: {}", e),
+                    would_execute: false,
+                });
+            }
+        }
+
+        // 3. 数据库备份与 Token 注入
+        match db::get_db_path() {
+            Ok(db_path) => {
+                crate::modules::logger::log_info(&format!("[Simulate] DB path: {:?}", db_path));
+                let db_exists = db_path.exists();
+                plan.push(SwitchPlanStep {
+                    step: "backup_db".to_string(),
+                    detail: if db_exists {
+                        format!("备份数据库到 {:?}", db_path.with_extension("vscdb.backup"))
+                    } else {
+                        "数据库文件不存在，跳过备份".to_string()
+                    },
+                    would_execute: db_exists,
+                });
+                plan.push(SwitchPlanStep {
+                    step: "inject_token".to_string(),
+                    detail: format!("注入 {} 的 Token 到 {:?}", account.email, db_path),
+                    would_execute: true,
+                });
+            }
+            Err(e) => {
+                plan.push(SwitchPlanStep {
+                    step: "inject_token".to_string(),
+                    detail: format!("无法定位数据库，真实切换会在此失败: {}", e),
+                    would_execute: false,
+                });
+            }
+        }
+
+        // 4. 重启外部进程
+        plan.push(SwitchPlanStep {
+            step: "start_process".to_string(),
+            detail: "切换完成后重新启动目标应用".to_string(),
+            would_execute: true,
+        });
+
+        crate::modules::logger::log_info(&format!(
+            "[Simulate] Dry-run complete: {} step(s) planned",
+            plan.len()
+        ));
+        Ok(plan)
+    }
+
+    /// [NEW] 基准测试账号切换：执行一次真实切换，但对每个阶段单独计时，
+    /// 用于定位"切换 30s+"究竟慢在进程关闭轮询、数据库锁等待还是应用启动。
+    /// 阶段顺序与 DesktopIntegration::on_account_switch 一致（不走热注入路径）
+    pub async fn benchmark_account_switch(
+        &self,
+        account: &Account,
+    ) -> Result<Vec<SwitchPhaseTiming>, String> {
+        if matches!(self, SystemManager::Headless) {
+            return Err("Headless 模式切换仅更新内存状态，无需基准测试".to_string());
+        }
+
+        // [NEW] 基准测试也是真实切换，同样受全局切换闸门约束
+        let _switch_guard = begin_switch()?;
+
+        crate::modules::logger::log_info(&format!(
+            "[Benchmark] Timed system switch for: {}",
+            account.email
+        ));
+
+        let mut timings = Vec::new();
+        let storage_path = device::get_storage_path()?;
+
+        // 1. 关闭外部进程
+        let running = process::is_antigravity_running();
+        let start = std::time::Instant::now();
+        if running {
+            process::close_antigravity(20)?;
+        }
+        timings.push(SwitchPhaseTiming {
+            phase: "close_process".to_string(),
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            executed: running,
+        });
+
+        // 2. 写入设备 Profile
+        let has_profile = account.device_profile.is_some();
+        let start = std::time::Instant::now();
+        if let Some(ref profile) = account.device_profile {
+            device::write_profile(&storage_path, profile)?;
+        }
+        timings.push(SwitchPhaseTiming {
+            phase: "write_profile".to_string(),
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            executed: has_profile,
+        });
+
+        // 3. 数据库备份
+        let db_path = db::get_db_path()?;
+        let db_exists = db_path.exists();
+        let start = std::time::Instant::now();
+        if db_exists {
+            // [FIX] 与真实切换一致：备份失败即中止，不在无备份时注入
+            db::backup_db(&db_path)?;
+        }
+        timings.push(SwitchPhaseTiming {
+            phase: "backup_db".to_string(),
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            executed: db_exists,
+        });
+
+        // 4. Token 注入
+        let start = std::time::Instant::now();
+        db::inject_token(
+            &db_path,
+            &account.token.access_token,
+            &account.token.refresh_token,
+            account.token.expiry_timestamp,
+            &account.email,
+        )?;
+        timings.push(SwitchPhaseTiming {
+            phase: "inject_token".to_string(),
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            executed: true,
+        });
+
+        // 5. 重启外部进程
+        let start = std::time::Instant::now();
+        process::start_antigravity()?;
+        timings.push(SwitchPhaseTiming {
+            phase: "start_process".to_string(),
+            elapsed_ms: start.elapsed().as_millis() as u64,
+            executed: true,
+        });
+
+        // 6. 更新托盘（不计入阶段耗时）
+        if let SystemManager::Desktop(handle) = self {
+            let _ = crate::modules::tray::update_tray_menus(handle);
+        }
+
+        crate::modules::logger::log_info(&format!(
+            "[Benchmark] Switch phases: {}",
+            timings
+                .iter()
+                .map(|t| format!("{}={}ms", t.phase, t.elapsed_ms))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+
+        Ok(timings)
+    }
+
     pub async fn on_account_switch(&self, account: &Account) -> Result<(), String> {
         match self {
             SystemManager::Desktop(handle) => {
@@ -209,3 +632,152 @@ impl SystemIntegration for SystemManager {
         }
     }
 }
+
+/// [NEW] storage.json 与 state.vscdb 的一致性诊断结果
+/// （用于排查"切换了账号但 IDE 仍显示旧用户"的半失败切换）
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ConsistencyReport {
+    /// 应用认为的当前账号邮箱（账号索引 current_account_id）
+    pub expected_email: Option<String>,
+    /// state.vscdb Legacy blob 中实际注入的邮箱
+    pub injected_email: Option<String>,
+    /// 两个邮箱是否一致（任一侧缺失时为 None）
+    pub emails_match: Option<bool>,
+    /// 注入 Token 的过期时间（unix 秒）
+    pub token_expiry: Option<i64>,
+    /// 注入 Token 是否已过期（无过期时间时为 None）
+    pub token_expired: Option<bool>,
+    /// storage.json 中的 telemetry.machineId
+    pub storage_machine_id: Option<String>,
+    /// 当前账号绑定的设备指纹 machineId
+    pub account_machine_id: Option<String>,
+    /// 指纹是否一致（账号未绑定指纹或 storage.json 不可读时为 None）
+    pub machine_ids_match: Option<bool>,
+    /// 人类可读的问题列表（为空表示未发现不一致）
+    pub issues: Vec<String>,
+}
+
+/// [NEW] 校验 storage.json 与 state.vscdb 是否与当前账号一致。
+/// 只读检查：对比账号索引中的当前账号邮箱与数据库 Legacy blob 里注入的邮箱、
+/// Token 是否过期、以及设备指纹 machineId 是否与绑定值一致
+pub fn verify_account_consistency() -> Result<ConsistencyReport, String> {
+    let current = crate::modules::account::get_current_account()?;
+    let expected_email = current.as_ref().map(|a| a.email.clone());
+
+    // 1. 数据库侧：解码 Legacy blob 中的邮箱与过期时间
+    let (injected_email, token_expiry) = match db::get_db_path() {
+        Ok(db_path) => match db::read_injected_identity(&db_path) {
+            Ok(identity) => (identity.email, identity.expiry),
+            Err(e) => {
+                crate::modules::logger::log_warn(&format!(
+                    "[Consistency] Failed to decode injected identity: {}",
+                    e
+                ));
+                (None, None)
+            }
+        },
+        Err(e) => {
+            crate::modules::logger::log_warn(&format!(
+                "[Consistency] Failed to locate state.vscdb: {}",
+                e
+            ));
+            (None, None)
+        }
+    };
+
+    // 2. storage.json 侧：当前写入的设备指纹
+    let storage_machine_id = device::get_storage_path()
+        .and_then(|p| device::read_profile(&p))
+        .map(|profile| profile.machine_id)
+        .ok();
+    let account_machine_id = current
+        .as_ref()
+        .and_then(|a| a.device_profile.as_ref())
+        .map(|p| p.machine_id.clone());
+
+    // 3. 对比并汇总问题
+    let emails_match = match (&expected_email, &injected_email) {
+        (Some(expected), Some(injected)) => Some(expected == injected),
+        _ => None,
+    };
+    let token_expired = token_expiry.map(|expiry| expiry <= chrono::Utc::now().timestamp());
+    let machine_ids_match = match (&storage_machine_id, &account_machine_id) {
+        (Some(storage), Some(bound)) => Some(storage == bound),
+        _ => None,
+    };
+
+    let mut issues = Vec::new();
+    if expected_email.is_none() {
+        issues.push("账号索引中没有当前账号".to_string());
+    }
+    if injected_email.is_none() {
+        issues.push("无法从 state.vscdb 解码出已注入的邮箱".to_string());
+    }
+    if emails_match == Some(false) {
+        issues.push(format!(
+            "邮箱不一致：应用当前账号为 {}，但数据库中注入的是 {}（切换可能半途失败）",
+            expected_email.as_deref().unwrap_or("?"),
+            injected_email.as_deref().unwrap_or("?")
+        ));
+    }
+    if token_expired == Some(true) {
+        issues.push("数据库中注入的 Token 已过期".to_string());
+    }
+    if machine_ids_match == Some(false) {
+        issues.push("storage.json 的 machineId 与当前账号绑定的设备指纹不一致".to_string());
+    }
+
+    Ok(ConsistencyReport {
+        expected_email,
+        injected_email,
+        emails_match,
+        token_expiry,
+        token_expired,
+        storage_machine_id,
+        account_machine_id,
+        machine_ids_match,
+        issues,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 测试共享全局 SWITCH_GATE，串行执行避免相互干扰
+    static TEST_GUARD: std::sync::Mutex<()> = std::sync::Mutex::new(());
+
+    /// 两个"并发"切换：第一个持有闸门期间，第二个必须被 switch_in_progress 拒绝
+    #[test]
+    fn test_concurrent_switch_rejected() {
+        let _t = TEST_GUARD.lock().unwrap_or_else(|p| p.into_inner());
+
+        let first = begin_switch_with_cooldown(0).expect("first switch should acquire the gate");
+
+        let second = begin_switch_with_cooldown(0);
+        assert_eq!(second.err().as_deref(), Some("switch_in_progress"));
+
+        // 第一个切换结束（含出错提前返回的场景）后闸门必须释放
+        drop(first);
+        let third = begin_switch_with_cooldown(0).expect("gate must release after drop");
+        drop(third);
+    }
+
+    /// 冷却期内的切换被拒绝，冷却结束后放行
+    #[test]
+    fn test_switch_cooldown_enforced() {
+        let _t = TEST_GUARD.lock().unwrap_or_else(|p| p.into_inner());
+
+        drop(begin_switch_with_cooldown(0).expect("acquire"));
+
+        // 刚结束：长冷却应拒绝
+        let during_cooldown = begin_switch_with_cooldown(60_000);
+        assert!(during_cooldown
+            .err()
+            .map(|e| e.starts_with("switch_in_progress"))
+            .unwrap_or(false));
+
+        // 冷却为 0：立即放行
+        drop(begin_switch_with_cooldown(0).expect("zero cooldown must not block"));
+    }
+}
@@ -2,8 +2,95 @@ use tracing::{info, warn, error};
 use tracing_subscriber::{fmt, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 use crate::modules::account::get_data_dir;
 
+/// [NEW] plain_log 开关：为 true 时集中剥离日志行中的 emoji/装饰符号，
+/// 调用点无需改动 (部分 Windows 控制台及 ASCII 日志管道无法渲染 🔧📖🔓 等)
+static PLAIN_LOG: AtomicBool = AtomicBool::new(false);
+
+/// [NEW] 设置 plain_log 模式 (配置加载/保存时调用，运行时热生效)
+pub fn set_plain_log(enabled: bool) {
+    let prev = PLAIN_LOG.swap(enabled, Ordering::Relaxed);
+    if prev != enabled {
+        if enabled {
+            info!("Plain log mode enabled (emoji/decorations stripped)");
+        } else {
+            info!("Plain log mode disabled");
+        }
+    }
+}
+
+fn plain_log_enabled() -> bool {
+    PLAIN_LOG.load(Ordering::Relaxed)
+}
+
+/// [NEW] 判断字符是否属于装饰性符号 (emoji / dingbats / 箭头 / 变体选择符等)；
+/// 中文等正文字符不在范围内，不受影响
+fn is_decorative(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1F000..=0x1FAFF   // emoji 主区块 (🔧📖🔓 🚀 等)
+            | 0x2600..=0x27BF // 杂项符号与 dingbats (⚠ ✅ ❌ 等)
+            | 0x2B00..=0x2BFF // 杂项符号与箭头 (⭐ 等)
+            | 0x2300..=0x23FF // 杂项技术符号 (⏳ ⌛ 等)
+            | 0x2190..=0x21FF // 箭头
+            | 0xFE00..=0xFE0F // 变体选择符
+            | 0x200D          // 零宽连接符
+    )
+}
+
+/// [NEW] 剥离装饰符号，保留正文；符号后紧跟的分隔空格一并吸收，避免残留双空格
+pub fn strip_decorations(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut just_removed = false;
+    for c in line.chars() {
+        if is_decorative(c) {
+            just_removed = true;
+            continue;
+        }
+        if just_removed && c == ' ' && (out.is_empty() || out.ends_with(' ')) {
+            just_removed = false;
+            continue;
+        }
+        just_removed = false;
+        out.push(c);
+    }
+    out
+}
+
+/// [NEW] 包装底层 writer：plain_log 开启时在写出前剥离装饰符号。
+/// 开关在每次写入时读取，配置热更新后无需重建日志系统
+struct PlainWriter<W>(W);
+
+impl<W: std::io::Write> std::io::Write for PlainWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if !plain_log_enabled() {
+            return self.0.write(buf);
+        }
+        let stripped = strip_decorations(&String::from_utf8_lossy(buf));
+        self.0.write_all(stripped.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct PlainMakeWriter<M>(M);
+
+impl<'a, M> fmt::MakeWriter<'a> for PlainMakeWriter<M>
+where
+    M: fmt::MakeWriter<'a>,
+{
+    type Writer = PlainWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        PlainWriter(self.0.make_writer())
+    }
+}
+
 // Custom local timezone time formatter
 struct LocalTimer;
 
@@ -29,7 +116,15 @@ pub fn get_log_dir() -> Result<PathBuf, String> {
 pub fn init_logger() {
     // Capture log macro logs
     let _ = tracing_log::LogTracer::init();
-    
+
+    // [NEW] CI/Headless 可用 TOPOO_PLAIN_LOG=1 在配置加载前开启 (桌面端随配置热更新)
+    if matches!(
+        std::env::var("TOPOO_PLAIN_LOG").ok().as_deref().map(str::trim),
+        Some("1") | Some("true")
+    ) {
+        PLAIN_LOG.store(true, Ordering::Relaxed);
+    }
+
     let log_dir = match get_log_dir() {
         Ok(dir) => dir,
         Err(e) => {
@@ -45,22 +140,31 @@ pub fn init_logger() {
     
     // 2. Console output layer (using local timezone)
     let console_layer = fmt::Layer::new()
+        .with_writer(PlainMakeWriter(std::io::stdout))
         .with_target(false)
         .with_thread_ids(false)
         .with_level(true)
         .with_timer(LocalTimer);
-        
+
     // 3. File output layer (disable ANSI formatting, use local timezone)
     let file_layer = fmt::Layer::new()
-        .with_writer(non_blocking)
+        .with_writer(PlainMakeWriter(non_blocking))
         .with_ansi(false)
         .with_target(true)
         .with_level(true)
         .with_timer(LocalTimer);
 
     // 4. Set filtering layer (default to INFO level to reduce log size)
-    let filter_layer = EnvFilter::try_from_default_env()
-        .unwrap_or_else(|_| EnvFilter::new("info"));
+    // [NEW] RUST_LOG 未设置时允许 TOPOO_LOG_LEVEL 指定级别 (Docker/Headless)
+    let filter_layer = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => match std::env::var("TOPOO_LOG_LEVEL") {
+            Ok(level) if !level.trim().is_empty() => {
+                EnvFilter::try_new(level.trim()).unwrap_or_else(|_| EnvFilter::new("info"))
+            }
+            _ => EnvFilter::new("info"),
+        },
+    };
 
     // 6. Log bridge layer
     let bridge_layer = crate::modules::log_bridge::TauriLogBridgeLayer::new();
@@ -222,3 +326,28 @@ pub fn log_warn(message: &str) {
 pub fn log_error(message: &str) {
     error!("{}", message);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::strip_decorations;
+
+    #[test]
+    fn test_strip_decorations_removes_emoji_keeps_text() {
+        assert_eq!(
+            strip_decorations("🔧 [DB] Injecting token data..."),
+            "[DB] Injecting token data..."
+        );
+        assert_eq!(
+            strip_decorations("⏳ [DB Backup] Copy failed (attempt 1/4)"),
+            "[DB Backup] Copy failed (attempt 1/4)"
+        );
+        // 行中部的符号：吸收其后的分隔空格，不留双空格
+        assert_eq!(strip_decorations("done ✅ ok"), "done ok");
+    }
+
+    #[test]
+    fn test_strip_decorations_keeps_cjk_and_ascii() {
+        let line = "数据库注入完成: account@example.com (id: 3)";
+        assert_eq!(strip_decorations(line), line);
+    }
+}
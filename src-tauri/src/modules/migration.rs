@@ -162,20 +162,27 @@ pub async fn import_from_v1() -> Result<Vec<Account>, String> {
                             email_placeholder
                         ));
 
-                        let (email, access_token, expires_in) =
+                        // 刷新时 Google 可能轮换 refresh_token，优先保存新值
+                        let (email, access_token, expires_in, refresh_token) =
                             match oauth::refresh_access_token(&refresh_token, None).await {
                                 Ok(token_resp) => {
+                                    let effective_refresh = token_resp
+                                        .refresh_token
+                                        .clone()
+                                        .unwrap_or(refresh_token);
                                     match oauth::get_user_info(&token_resp.access_token, None).await
                                     {
                                         Ok(user_info) => (
                                             user_info.email,
                                             token_resp.access_token,
                                             token_resp.expires_in,
+                                            effective_refresh,
                                         ),
                                         Err(_) => (
                                             email_placeholder.clone(),
                                             token_resp.access_token,
                                             token_resp.expires_in,
+                                            effective_refresh,
                                         ),
                                     }
                                 }
@@ -188,6 +195,7 @@ pub async fn import_from_v1() -> Result<Vec<Account>, String> {
                                         email_placeholder.clone(),
                                         "imported_access_token".to_string(),
                                         0,
+                                        refresh_token,
                                     )
                                 }
                             };
@@ -253,9 +261,10 @@ pub async fn import_from_custom_db_path(path_str: String) -> Result<Account, Str
 
     crate::modules::logger::log_info(&format!("Successfully retrieved account info: {}", email));
 
+    // 刷新时 Google 可能轮换 refresh_token，优先保存新值
     let token_data = TokenData::new(
-        token_resp.access_token,
-        refresh_token,
+        token_resp.access_token.clone(),
+        token_resp.refresh_token.clone().unwrap_or(refresh_token),
         token_resp.expires_in,
         Some(email.clone()),
         None, // project_id will be fetched on demand
@@ -1,4 +1,5 @@
 pub mod account;
+pub mod account_events;
 pub mod quota;
 pub mod config;
 pub mod logger;
@@ -8,6 +8,91 @@ const USERINFO_URL: &str = "https://www.googleapis.com/oauth2/v2/userinfo";
 
 const AUTH_URL: &str = "https://accounts.google.com/o/oauth2/v2/auth";
 
+/// [NEW] 默认 OIDC issuer (Google)；可通过 AppConfig.oauth_issuer 覆盖
+const DEFAULT_ISSUER: &str = "https://accounts.google.com";
+
+/// [NEW] OpenID Discovery 文档中我们关心的端点
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OidcDiscovery {
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub userinfo_endpoint: String,
+}
+
+/// [NEW] 已缓存的 discovery 端点；None = 未获取过，使用硬编码默认值
+static OIDC_DISCOVERY: std::sync::OnceLock<std::sync::RwLock<Option<OidcDiscovery>>> =
+    std::sync::OnceLock::new();
+
+fn discovery_cache() -> &'static std::sync::RwLock<Option<OidcDiscovery>> {
+    OIDC_DISCOVERY.get_or_init(|| std::sync::RwLock::new(None))
+}
+
+fn cached_discovery() -> Option<OidcDiscovery> {
+    discovery_cache().read().ok().and_then(|d| d.clone())
+}
+
+/// [NEW] 授权端点：优先 discovery 缓存，失败/未获取时回退硬编码
+fn auth_endpoint() -> String {
+    cached_discovery()
+        .map(|d| d.authorization_endpoint)
+        .unwrap_or_else(|| AUTH_URL.to_string())
+}
+
+fn token_endpoint() -> String {
+    cached_discovery()
+        .map(|d| d.token_endpoint)
+        .unwrap_or_else(|| TOKEN_URL.to_string())
+}
+
+fn userinfo_endpoint() -> String {
+    cached_discovery()
+        .map(|d| d.userinfo_endpoint)
+        .unwrap_or_else(|| USERINFO_URL.to_string())
+}
+
+/// [NEW] 强制重新拉取 `.well-known/openid-configuration` 并更新端点缓存。
+/// issuer 取 AppConfig.oauth_issuer，未配置时用 Google 默认值；
+/// 失败时保留原有缓存（继续用硬编码/旧端点），不影响登录流程
+pub async fn refresh_oidc_discovery() -> Result<OidcDiscovery, String> {
+    let issuer = crate::modules::config::load_app_config()
+        .ok()
+        .and_then(|c| c.oauth_issuer)
+        .unwrap_or_else(|| DEFAULT_ISSUER.to_string());
+    let url = format!(
+        "{}/.well-known/openid-configuration",
+        issuer.trim_end_matches('/')
+    );
+
+    let client = crate::utils::http::get_client();
+    let response = client
+        .get(&url)
+        .send()
+        .await
+        .map_err(|e| format!("OIDC discovery request failed: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "OIDC discovery failed with status {}",
+            response.status()
+        ));
+    }
+
+    let discovery = response
+        .json::<OidcDiscovery>()
+        .await
+        .map_err(|e| format!("OIDC discovery parsing failed: {}", e))?;
+
+    if let Ok(mut cache) = discovery_cache().write() {
+        *cache = Some(discovery.clone());
+    }
+    crate::modules::logger::log_info(&format!(
+        "OIDC discovery refreshed from {}: token_endpoint={}",
+        url, discovery.token_endpoint
+    ));
+
+    Ok(discovery)
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TokenResponse {
     pub access_token: String,
@@ -49,18 +134,41 @@ impl UserInfo {
     }
 }
 
-/// Generate OAuth authorization URL
-pub fn get_auth_url(redirect_uri: &str, state: &str) -> String {
-    let scopes = vec![
+/// [NEW] 内置默认授权范围（包含 openid/email/profile，保证登录流程能取到 ID Token）
+fn default_oauth_scopes() -> Vec<String> {
+    [
         "https://www.googleapis.com/auth/cloud-platform",
         "https://www.googleapis.com/auth/userinfo.email",
         "https://www.googleapis.com/auth/userinfo.profile",
-        "https://www.googleapis.com/auth/userinfo.profile",
         "https://www.googleapis.com/auth/cclog",
         "https://www.googleapis.com/auth/experimentsandconfigs",
         "openid",
     ]
-    .join(" ");
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+/// [NEW] 实际使用的 scope 列表：配置了 oauth_scopes 时覆盖内置默认
+fn oauth_scopes() -> Vec<String> {
+    match crate::modules::config::load_app_config() {
+        Ok(config) if !config.oauth_scopes.is_empty() => config.oauth_scopes,
+        _ => default_oauth_scopes(),
+    }
+}
+
+/// [NEW] 用给定 scope 列表构建授权 URL，并在打开浏览器之前校验 scope：
+/// 缺少 openid 时 Google 不会返回 ID Token，登录只会在授权完成后才失败，
+/// 提前在这里拦截并给出明确错误
+pub fn build_auth_url(redirect_uri: &str, state: &str, scopes: &[String]) -> Result<String, String> {
+    if !scopes.iter().any(|s| s == "openid") {
+        return Err(
+            "授权范围缺少 openid，将无法获取 ID Token；请在 oauth_scopes 配置中加入 openid"
+                .to_string(),
+        );
+    }
+
+    let scopes = scopes.join(" ");
 
     let params = vec![
         ("client_id", CLIENT_ID),
@@ -73,8 +181,15 @@ pub fn get_auth_url(redirect_uri: &str, state: &str) -> String {
         ("state", state),
     ];
 
-    let url = url::Url::parse_with_params(AUTH_URL, &params).expect("Invalid Auth URL");
-    url.to_string()
+    let url = url::Url::parse_with_params(&auth_endpoint(), &params)
+        .map_err(|e| format!("Invalid Auth URL: {}", e))?;
+    Ok(url.to_string())
+}
+
+/// Generate OAuth authorization URL
+/// [NEW] scope 列表可通过 oauth_scopes 配置覆盖；缺少 openid 时在此直接报错
+pub fn get_auth_url(redirect_uri: &str, state: &str) -> Result<String, String> {
+    build_auth_url(redirect_uri, state, &oauth_scopes())
 }
 
 /// Exchange authorization code for token
@@ -95,7 +210,7 @@ pub async fn exchange_code(code: &str, redirect_uri: &str) -> Result<TokenRespon
     ];
 
     let response = client
-        .post(TOKEN_URL)
+        .post(token_endpoint())
         .form(&params)
         .send()
         .await
@@ -168,7 +283,7 @@ pub async fn refresh_access_token(
     }
 
     let response = client
-        .post(TOKEN_URL)
+        .post(token_endpoint())
         .form(&params)
         .send()
         .await
@@ -212,7 +327,7 @@ pub async fn get_user_info(
     };
 
     let response = client
-        .get(USERINFO_URL)
+        .get(userinfo_endpoint())
         .bearer_auth(access_token)
         .send()
         .await
@@ -249,15 +364,27 @@ pub async fn ensure_fresh_token(
     ));
     let response = refresh_access_token(&current_token.refresh_token, account_id).await?;
 
-    // Construct new TokenData
-    Ok(crate::models::TokenData::new(
-        response.access_token,
-        current_token.refresh_token.clone(), // refresh_token may not be returned on refresh
+    Ok(apply_refresh_response(current_token, &response))
+}
+
+/// [NEW] Merge a refresh response into the current TokenData.
+/// Google may rotate the refresh_token on refresh; if a new one is returned it MUST
+/// be persisted, otherwise the old (still valid) one is kept.
+pub fn apply_refresh_response(
+    current_token: &crate::models::TokenData,
+    response: &TokenResponse,
+) -> crate::models::TokenData {
+    crate::models::TokenData::new(
+        response.access_token.clone(),
+        response
+            .refresh_token
+            .clone()
+            .unwrap_or_else(|| current_token.refresh_token.clone()),
         response.expires_in,
         current_token.email.clone(),
         current_token.project_id.clone(), // Keep original project_id
         None,                             // session_id will be generated in token_manager
-    ))
+    )
 }
 
 #[cfg(test)]
@@ -268,10 +395,69 @@ mod tests {
     fn test_get_auth_url_contains_state() {
         let redirect_uri = "http://localhost:8080/callback";
         let state = "test-state-123456";
-        let url = get_auth_url(redirect_uri, state);
+        let url = build_auth_url(redirect_uri, state, &default_oauth_scopes()).unwrap();
 
         assert!(url.contains("state=test-state-123456"));
         assert!(url.contains("redirect_uri=http%3A%2F%2Flocalhost%3A8080%2Fcallback"));
         assert!(url.contains("response_type=code"));
+        assert!(url.contains("openid"));
+    }
+
+    #[test]
+    fn test_build_auth_url_rejects_scopes_without_openid() {
+        // 缺少 openid 时必须在打开浏览器前报错，而不是授权完成后才发现拿不到 ID Token
+        let scopes = vec![
+            "https://www.googleapis.com/auth/userinfo.email".to_string(),
+            "https://www.googleapis.com/auth/userinfo.profile".to_string(),
+        ];
+        let err = build_auth_url("http://localhost:8080/callback", "state", &scopes).unwrap_err();
+        assert!(err.contains("openid"));
+    }
+
+    fn make_account() -> crate::models::Account {
+        let token = crate::models::TokenData::new(
+            "old-access".to_string(),
+            "old-refresh".to_string(),
+            3600,
+            Some("user@example.com".to_string()),
+            Some("project-1".to_string()),
+            None,
+        );
+        crate::models::Account::new("acc-1".to_string(), "user@example.com".to_string(), token)
+    }
+
+    #[test]
+    fn test_apply_refresh_response_rotates_refresh_token() {
+        let mut account = make_account();
+        let response = TokenResponse {
+            access_token: "new-access".to_string(),
+            expires_in: 3599,
+            token_type: "Bearer".to_string(),
+            refresh_token: Some("new-refresh".to_string()),
+            id_token: None,
+        };
+
+        account.token = apply_refresh_response(&account.token, &response);
+
+        assert_eq!(account.token.access_token, "new-access");
+        assert_eq!(account.token.refresh_token, "new-refresh");
+        assert_eq!(account.token.project_id.as_deref(), Some("project-1"));
+    }
+
+    #[test]
+    fn test_apply_refresh_response_keeps_old_refresh_token_when_omitted() {
+        let mut account = make_account();
+        let response = TokenResponse {
+            access_token: "new-access".to_string(),
+            expires_in: 3599,
+            token_type: "Bearer".to_string(),
+            refresh_token: None,
+            id_token: None,
+        };
+
+        account.token = apply_refresh_response(&account.token, &response);
+
+        assert_eq!(account.token.access_token, "new-access");
+        assert_eq!(account.token.refresh_token, "old-refresh");
     }
 }
@@ -6,6 +6,13 @@ use tokio::net::TcpListener;
 use tokio::sync::mpsc;
 use tokio::sync::watch;
 
+/// [NEW] 回调连接的请求行读取限时：正常的 provider 回调远在此之内完成，
+/// 超时的连接（端口扫描、浏览器预连接）回 408 后丢弃
+const CALLBACK_READ_TIMEOUT_SECS: u64 = 5;
+
+/// [NEW] 读取超时时的响应
+const CALLBACK_408_RESPONSE: &[u8] = b"HTTP/1.1 408 Request Timeout\r\nConnection: close\r\n\r\n";
+
 struct OAuthFlowState {
     auth_url: String,
     #[allow(dead_code)]
@@ -14,6 +21,19 @@ struct OAuthFlowState {
     cancel_tx: watch::Sender<bool>,
     code_tx: mpsc::Sender<Result<String, String>>,
     code_rx: Option<mpsc::Receiver<Result<String, String>>>,
+    /// [NEW] 本流程派生的回调监听任务句柄 (IPv4/IPv6)，
+    /// 状态被替换或取消时逐一 abort，释放监听端口
+    listener_tasks: Vec<tokio::task::JoinHandle<()>>,
+}
+
+/// [NEW] 拆除一个被替换/取消的流程: 发取消信号后 abort 监听任务兜底。
+/// 仅靠 watch 信号时，卡在读写中的任务不会及时退出；abort 会直接
+/// drop 掉 TcpListener，确保快速反复点击 "Start OAuth" 不会堆积占端口的僵尸监听
+fn teardown_flow(flow: OAuthFlowState) {
+    let _ = flow.cancel_tx.send(true);
+    for task in flow.listener_tasks {
+        task.abort();
+    }
 }
 
 static OAUTH_FLOW_STATE: OnceLock<Mutex<Option<OAuthFlowState>>> = OnceLock::new();
@@ -22,6 +42,18 @@ fn get_oauth_flow_state() -> &'static Mutex<Option<OAuthFlowState>> {
     OAUTH_FLOW_STATE.get_or_init(|| Mutex::new(None))
 }
 
+/// [NEW] Lock the flow state, recovering from poisoning. A listener task that panics
+/// while holding the lock would otherwise wedge every future OAuth flow until restart;
+/// the guarded value is just an `Option` that stays coherent, so recovery is safe.
+fn lock_oauth_flow_state() -> std::sync::MutexGuard<'static, Option<OAuthFlowState>> {
+    get_oauth_flow_state().lock().unwrap_or_else(|poisoned| {
+        crate::modules::logger::log_warn(
+            "OAuth flow state mutex was poisoned by a panicked task; recovering",
+        );
+        poisoned.into_inner()
+    })
+}
+
 fn oauth_success_html() -> &'static str {
     "HTTP/1.1 200 OK\r\nContent-Type: text/html; charset=utf-8\r\n\r\n\
     <html>\
@@ -47,17 +79,18 @@ async fn ensure_oauth_flow_prepared(
     app_handle: Option<tauri::AppHandle>,
 ) -> Result<String, String> {
     // Return URL if flow already exists and is still "fresh" (receiver hasn't been taken)
-    if let Ok(mut state) = get_oauth_flow_state().lock() {
+    {
+        let mut state = lock_oauth_flow_state();
         if let Some(s) = state.as_mut() {
             if s.code_rx.is_some() {
                 return Ok(s.auth_url.clone());
-            } else {
-                // Flow is already "in progress" (rx taken), but user requested a NEW one.
-                // Force cancel the old one to allow a new attempt.
-                let _ = s.cancel_tx.send(true);
-                *state = None;
             }
         }
+        // Flow is already "in progress" (rx taken), but user requested a NEW one.
+        // Force cancel the old one (and abort its listeners) to allow a new attempt.
+        if let Some(old) = state.take() {
+            teardown_flow(old);
+        }
     }
 
     // Create loopback listeners.
@@ -121,8 +154,25 @@ async fn ensure_oauth_flow_prepared(
         format!("http://[::1]:{}/oauth-callback", port)
     };
 
+    // [NEW] 仅单栈监听时提醒用户：浏览器若把 localhost 解析到另一栈，回调永远到不了，
+    // 表现为 5 分钟静默超时。redirect_uri 已用显式 IP 强制正确栈，但用户手动复制链接时
+    // 可能仍改回 localhost，因此向前端发警告事件并写日志。
+    if !(has_ipv4 && has_ipv6) {
+        let bound_stack = if has_ipv4 { "IPv4 (127.0.0.1)" } else { "IPv6 ([::1])" };
+        let warning = format!(
+            "OAuth callback listener only bound on {}; use the explicit-IP link as-is — \
+             replacing it with `localhost` may never reach the callback and the flow will time out",
+            bound_stack
+        );
+        crate::modules::logger::log_warn(&warning);
+        if let Some(h) = &app_handle {
+            use tauri::Emitter;
+            let _ = h.emit("oauth-single-stack-warning", &warning);
+        }
+    }
+
     let state_str = uuid::Uuid::new_v4().to_string();
-    let auth_url = oauth::get_auth_url(&redirect_uri, &state_str);
+    let auth_url = oauth::get_auth_url(&redirect_uri, &state_str)?;
 
     // Cancellation signal (supports multiple consumers)
     let (cancel_tx, cancel_rx) = watch::channel(false);
@@ -132,20 +182,47 @@ async fn ensure_oauth_flow_prepared(
     // Start listeners immediately: even if the user authorizes before clicking "Start OAuth",
     // the browser can still hit our callback and finish the flow.
     let app_handle_for_tasks = app_handle.clone();
+    // [NEW] 收集监听任务句柄，随流程状态保存以便替换/取消时 abort
+    let mut listener_tasks: Vec<tokio::task::JoinHandle<()>> = Vec::new();
 
     if let Some(l4) = ipv4_listener {
         let tx = code_tx.clone();
         let mut rx = cancel_rx.clone();
         let app_handle = app_handle_for_tasks.clone();
-        tokio::spawn(async move {
-            if let Ok((mut stream, _)) = tokio::select! {
-                res = l4.accept() => res.map_err(|e| format!("failed_to_accept_connection: {}", e)),
-                _ = rx.changed() => Err("OAuth cancelled".to_string()),
-            } {
+        listener_tasks.push(tokio::spawn(async move {
+            // [NEW] 循环 accept：超时/空连接回 408 后继续等待真正的回调，
+            // 避免唯一一次 accept 机会被端口扫描或浏览器预连接耗尽
+            loop {
+                let mut stream = match tokio::select! {
+                    res = l4.accept() => res.map_err(|e| format!("failed_to_accept_connection: {}", e)),
+                    _ = rx.changed() => Err("OAuth cancelled".to_string()),
+                } {
+                    Ok((stream, _)) => stream,
+                    Err(_) => return,
+                };
                 // Reuse the existing parsing/response code by constructing a temporary listener task
                 // that sends into the shared mpsc channel.
+                // [NEW] 限时读取：只连不发数据的连接不能无限占住回调任务
                 let mut buffer = [0u8; 4096];
-                let bytes_read = stream.read(&mut buffer).await.unwrap_or(0);
+                let bytes_read = match tokio::time::timeout(
+                    std::time::Duration::from_secs(CALLBACK_READ_TIMEOUT_SECS),
+                    stream.read(&mut buffer),
+                )
+                .await
+                {
+                    Ok(res) => res.unwrap_or(0),
+                    Err(_) => {
+                        crate::modules::logger::log_warn(
+                            "OAuth callback connection sent no data within timeout, replying 408",
+                        );
+                        let _ = stream.write_all(CALLBACK_408_RESPONSE).await;
+                        continue;
+                    }
+                };
+                if bytes_read == 0 {
+                    // 未送达任何数据就断开 (典型的端口扫描)，继续等待真正的回调
+                    continue;
+                }
                 let request = String::from_utf8_lossy(&buffer[..bytes_read]);
 
                 // [FIX #931/850/778] More robust parsing and detailed logging
@@ -191,12 +268,9 @@ async fn ensure_oauth_flow_prepared(
 
                 // Verify state
                 let state_valid = {
-                    if let Ok(lock) = get_oauth_flow_state().lock() {
-                        if let Some(s) = lock.as_ref() {
-                            received_state.as_ref() == Some(&s.state)
-                        } else {
-                            false
-                        }
+                    let lock = lock_oauth_flow_state();
+                    if let Some(s) = lock.as_ref() {
+                        received_state.as_ref() == Some(&s.state)
                     } else {
                         false
                     }
@@ -229,21 +303,47 @@ async fn ensure_oauth_flow_prepared(
                     let _ = h.emit("oauth-callback-received", ());
                 }
                 let _ = tx.send(result).await;
+                break;
             }
-        });
+        }));
     }
 
     if let Some(l6) = ipv6_listener {
         let tx = code_tx.clone();
         let mut rx = cancel_rx;
         let app_handle = app_handle_for_tasks;
-        tokio::spawn(async move {
-            if let Ok((mut stream, _)) = tokio::select! {
-                res = l6.accept() => res.map_err(|e| format!("failed_to_accept_connection: {}", e)),
-                _ = rx.changed() => Err("OAuth cancelled".to_string()),
-            } {
+        listener_tasks.push(tokio::spawn(async move {
+            // [NEW] 循环 accept：超时/空连接回 408 后继续等待真正的回调，
+            // 避免唯一一次 accept 机会被端口扫描或浏览器预连接耗尽
+            loop {
+                let mut stream = match tokio::select! {
+                    res = l6.accept() => res.map_err(|e| format!("failed_to_accept_connection: {}", e)),
+                    _ = rx.changed() => Err("OAuth cancelled".to_string()),
+                } {
+                    Ok((stream, _)) => stream,
+                    Err(_) => return,
+                };
+                // [NEW] 限时读取：只连不发数据的连接不能无限占住回调任务
                 let mut buffer = [0u8; 4096];
-                let bytes_read = stream.read(&mut buffer).await.unwrap_or(0);
+                let bytes_read = match tokio::time::timeout(
+                    std::time::Duration::from_secs(CALLBACK_READ_TIMEOUT_SECS),
+                    stream.read(&mut buffer),
+                )
+                .await
+                {
+                    Ok(res) => res.unwrap_or(0),
+                    Err(_) => {
+                        crate::modules::logger::log_warn(
+                            "OAuth callback connection sent no data within timeout, replying 408",
+                        );
+                        let _ = stream.write_all(CALLBACK_408_RESPONSE).await;
+                        continue;
+                    }
+                };
+                if bytes_read == 0 {
+                    // 未送达任何数据就断开 (典型的端口扫描)，继续等待真正的回调
+                    continue;
+                }
                 let request = String::from_utf8_lossy(&buffer[..bytes_read]);
 
                 let query_params = request
@@ -285,12 +385,9 @@ async fn ensure_oauth_flow_prepared(
 
                 // Verify state
                 let state_valid = {
-                    if let Ok(lock) = get_oauth_flow_state().lock() {
-                        if let Some(s) = lock.as_ref() {
-                            received_state.as_ref() == Some(&s.state)
-                        } else {
-                            false
-                        }
+                    let lock = lock_oauth_flow_state();
+                    if let Some(s) = lock.as_ref() {
+                        received_state.as_ref() == Some(&s.state)
                     } else {
                         false
                     }
@@ -323,12 +420,14 @@ async fn ensure_oauth_flow_prepared(
                     let _ = h.emit("oauth-callback-received", ());
                 }
                 let _ = tx.send(result).await;
+                break;
             }
-        });
+        }));
     }
 
     // Save state
-    if let Ok(mut state) = get_oauth_flow_state().lock() {
+    {
+        let mut state = lock_oauth_flow_state();
         *state = Some(OAuthFlowState {
             auth_url: auth_url.clone(),
             redirect_uri,
@@ -336,6 +435,7 @@ async fn ensure_oauth_flow_prepared(
             cancel_tx,
             code_tx,
             code_rx: Some(code_rx),
+            listener_tasks,
         });
     }
 
@@ -355,34 +455,79 @@ pub async fn prepare_oauth_url(app_handle: Option<tauri::AppHandle>) -> Result<S
 
 /// Cancel current OAuth flow
 pub fn cancel_oauth_flow() {
-    if let Ok(mut state) = get_oauth_flow_state().lock() {
-        if let Some(s) = state.take() {
-            let _ = s.cancel_tx.send(true);
-            crate::modules::logger::log_info("Sent OAuth cancellation signal");
-        }
+    let mut state = lock_oauth_flow_state();
+    if let Some(s) = state.take() {
+        teardown_flow(s);
+        crate::modules::logger::log_info("Sent OAuth cancellation signal");
     }
 }
 
 /// Start OAuth flow and wait for callback, then exchange token
+///
+/// [NEW] Requires an AppHandle to open the browser. Headless callers (no AppHandle)
+/// get the auth URL back immediately instead of hanging until the 5-minute timeout:
+/// use `prepare_oauth_url` to obtain the link, have the user open it manually,
+/// then call `complete_oauth_flow` to wait for the callback.
 pub async fn start_oauth_flow(
     app_handle: Option<tauri::AppHandle>,
 ) -> Result<oauth::TokenResponse, String> {
     // Ensure URL + listener are ready (this way if the user authorizes first, it won't get stuck)
     let auth_url = ensure_oauth_flow_prepared(app_handle.clone()).await?;
 
-    if let Some(h) = app_handle {
-        // Open default browser
-        use tauri_plugin_opener::OpenerExt;
-        h.opener()
-            .open_url(&auth_url, None::<String>)
-            .map_err(|e| format!("failed_to_open_browser: {}", e))?;
+    let Some(h) = app_handle else {
+        // Listener stays armed so the prepared URL remains valid for complete_oauth_flow
+        crate::modules::logger::log_warn(
+            "start_oauth_flow called without AppHandle; returning auth URL for manual authorization",
+        );
+        return Err(format!(
+            "headless_oauth_manual_authorization_required: open this URL in a browser, then call complete_oauth_flow: {}",
+            auth_url
+        ));
+    };
+
+    // [NEW] 根据 oauth_open_mode 决定如何呈现授权链接：
+    // ClipboardOnly/Both 先发 `oauth-copy-url` 事件让前端写入剪贴板
+    // (后端无剪贴板插件，由 webview 执行实际复制)
+    let open_mode = crate::modules::config::load_app_config()
+        .map(|c| c.oauth_open_mode)
+        .unwrap_or_default();
+
+    if matches!(
+        open_mode,
+        crate::models::config::OauthOpenMode::ClipboardOnly
+            | crate::models::config::OauthOpenMode::Both
+    ) {
+        use tauri::Emitter;
+        let _ = h.emit("oauth-copy-url", &auth_url);
+    }
+
+    match open_mode {
+        crate::models::config::OauthOpenMode::Browser => {
+            use tauri_plugin_opener::OpenerExt;
+            h.opener()
+                .open_url(&auth_url, None::<String>)
+                .map_err(|e| format!("failed_to_open_browser: {}", e))?;
+        }
+        crate::models::config::OauthOpenMode::Both => {
+            // 打开失败不中断：链接已复制，用户可手动粘贴到浏览器
+            use tauri_plugin_opener::OpenerExt;
+            if let Err(e) = h.opener().open_url(&auth_url, None::<String>) {
+                crate::modules::logger::log_warn(&format!(
+                    "failed_to_open_browser (continuing, URL copied): {}",
+                    e
+                ));
+            }
+        }
+        crate::models::config::OauthOpenMode::ClipboardOnly => {
+            crate::modules::logger::log_info(
+                "oauth_open_mode=clipboard_only: skipping browser launch",
+            );
+        }
     }
 
     // Take code_rx to wait for it
     let (mut code_rx, redirect_uri) = {
-        let mut lock = get_oauth_flow_state()
-            .lock()
-            .map_err(|_| "OAuth state lock corrupted".to_string())?;
+        let mut lock = lock_oauth_flow_state();
         let Some(state) = lock.as_mut() else {
             return Err("OAuth state does not exist".to_string());
         };
@@ -404,7 +549,8 @@ pub async fn start_oauth_flow(
         };
 
     // Clean up flow state (release cancel_tx, etc.)
-    if let Ok(mut lock) = get_oauth_flow_state().lock() {
+    {
+        let mut lock = lock_oauth_flow_state();
         *lock = None;
     }
 
@@ -422,9 +568,7 @@ pub async fn complete_oauth_flow(
 
     // Take receiver to wait for code
     let (mut code_rx, redirect_uri) = {
-        let mut lock = get_oauth_flow_state()
-            .lock()
-            .map_err(|_| "OAuth state lock corrupted".to_string())?;
+        let mut lock = lock_oauth_flow_state();
         let Some(state) = lock.as_mut() else {
             return Err("OAuth state does not exist".to_string());
         };
@@ -443,7 +587,8 @@ pub async fn complete_oauth_flow(
             Err(_) => return Err("OAuth flow timed out after 5 minutes".to_string()),
         };
 
-    if let Ok(mut lock) = get_oauth_flow_state().lock() {
+    {
+        let mut lock = lock_oauth_flow_state();
         *lock = None;
     }
 
@@ -458,7 +603,7 @@ pub async fn submit_oauth_code(
     state_input: Option<String>,
 ) -> Result<(), String> {
     let tx = {
-        let lock = get_oauth_flow_state().lock().map_err(|e| e.to_string())?;
+        let lock = lock_oauth_flow_state();
         if let Some(state) = lock.as_ref() {
             // Verify state if provided
             if let Some(provided_state) = state_input {
@@ -501,23 +646,24 @@ pub fn prepare_oauth_flow_manually(
     redirect_uri: String,
     state_str: String,
 ) -> Result<(String, mpsc::Receiver<Result<String, String>>), String> {
-    let auth_url = oauth::get_auth_url(&redirect_uri, &state_str);
+    let auth_url = oauth::get_auth_url(&redirect_uri, &state_str)?;
 
     // Check if we can reuse existing state
-    if let Ok(mut lock) = get_oauth_flow_state().lock() {
-        if let Some(s) = lock.as_mut() {
+    {
+        let mut lock = lock_oauth_flow_state();
+        if let Some(s) = lock.take() {
             // If we already have a code_rx, we can't easily "steal" it again because it's already returned.
             // But if this is a NEW request (different state), we should overwrite.
-            // For now, let's just clear and restart to be safe.
-            let _ = s.cancel_tx.send(true);
-            *lock = None;
+            // For now, let's just clear and restart to be safe (aborting any listeners).
+            teardown_flow(s);
         }
     }
 
     let (cancel_tx, _cancel_rx) = watch::channel(false);
     let (code_tx, code_rx) = mpsc::channel(1);
 
-    if let Ok(mut state) = get_oauth_flow_state().lock() {
+    {
+        let mut state = lock_oauth_flow_state();
         *state = Some(OAuthFlowState {
             auth_url: auth_url.clone(),
             redirect_uri: redirect_uri.clone(),
@@ -525,8 +671,78 @@ pub fn prepare_oauth_flow_manually(
             cancel_tx,
             code_tx,
             code_rx: None, // We return it directly
+            listener_tasks: Vec::new(),
         });
     }
 
     Ok((auth_url, code_rx))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 串行化访问全局 OAUTH_FLOW_STATE 的测试，避免互相覆盖状态
+    static TEST_GUARD: Mutex<()> = Mutex::new(());
+
+    #[test]
+    fn test_lock_recovers_from_poisoned_mutex() {
+        let _guard = TEST_GUARD.lock().unwrap_or_else(|p| p.into_inner());
+        // Poison the mutex by panicking while holding the lock
+        let _ = std::thread::spawn(|| {
+            let _guard = get_oauth_flow_state().lock().unwrap();
+            panic!("simulated listener panic while holding OAuth flow lock");
+        })
+        .join();
+        assert!(get_oauth_flow_state().is_poisoned());
+
+        // The next flow must still be able to read and reset the state
+        {
+            let mut state = lock_oauth_flow_state();
+            assert!(state.is_none());
+            *state = None;
+        }
+
+        // And plain lock sites keep working afterwards too
+        let state = lock_oauth_flow_state();
+        assert!(state.is_none());
+    }
+
+    /// [NEW] 快速反复 "Start OAuth" 不得堆积占端口的僵尸监听:
+    /// 每次重建流程都应 abort 上一个流程的监听任务并释放其端口
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_repeated_prepare_does_not_accumulate_bound_ports() {
+        let _guard = TEST_GUARD.lock().unwrap_or_else(|p| p.into_inner());
+
+        let mut ports = Vec::new();
+        for _ in 0..5 {
+            ensure_oauth_flow_prepared(None)
+                .await
+                .expect("prepare OAuth flow failed");
+            // 模拟 "流程进行中" (receiver 已被取走)，下一次 prepare 会强制重建
+            let port = {
+                let mut state = lock_oauth_flow_state();
+                let s = state.as_mut().expect("flow state must exist after prepare");
+                s.code_rx.take();
+                Url::parse(&s.redirect_uri)
+                    .ok()
+                    .and_then(|u| u.port())
+                    .expect("redirect_uri must carry the callback port")
+            };
+            ports.push(port);
+        }
+
+        cancel_oauth_flow();
+        // abort 异步生效，给被终止的监听任务让出调度时间
+        tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+        // 所有历史端口都必须可重新绑定 — 仍被占用说明留下了僵尸监听
+        for port in ports {
+            assert!(
+                TcpListener::bind(format!("127.0.0.1:{}", port)).await.is_ok(),
+                "port {} is still bound by a leaked OAuth listener",
+                port
+            );
+        }
+    }
+}
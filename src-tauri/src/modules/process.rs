@@ -197,6 +197,38 @@ fn get_self_family_pids(system: &sysinfo::System) -> std::collections::HashSet<u
     family_pids
 }
 
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+/// [NEW] Whether any ancestor of `pid` (walking up the parent chain, max depth 16)
+/// is in `candidates`. Used to recognize helpers by ancestry: a process whose parent
+/// is the identified main process is a helper even if it was renamed and carries no
+/// `--type=` argument
+fn has_ancestor_in(
+    system: &sysinfo::System,
+    pid: u32,
+    candidates: &std::collections::HashSet<u32>,
+) -> bool {
+    let mut next = pid;
+    for _ in 0..16 {
+        let process = match system.process(sysinfo::Pid::from_u32(next)) {
+            Some(p) => p,
+            None => return false,
+        };
+        let parent_id = match process.parent() {
+            Some(p) => p.as_u32(),
+            None => return false,
+        };
+        if candidates.contains(&parent_id) {
+            return true;
+        }
+        // Avoid cycles (e.g. pid 0 parenting itself)
+        if parent_id == next {
+            return false;
+        }
+        next = parent_id;
+    }
+    false
+}
+
 /// Get PIDs of all Antigravity processes (including main and helper processes)
 fn get_antigravity_pids() -> Vec<u32> {
     let target_app = crate::modules::config::load_app_config()
@@ -368,11 +400,23 @@ pub fn close_antigravity(#[allow(unused_variables)] timeout_secs: u64) -> Result
         // Windows: Precise kill by PID to support multiple versions or custom filenames
         let pids = get_antigravity_pids();
         if !pids.is_empty() {
+            // [NEW] Parent-child identification: a candidate descending from another
+            // candidate is a helper (robust against renamed helpers). Kill main
+            // processes first so they don't respawn children mid-cleanup
+            let mut system = System::new();
+            system.refresh_processes(sysinfo::ProcessesToUpdate::All);
+            let pid_set: std::collections::HashSet<u32> = pids.iter().copied().collect();
+            let (main_pids, helper_pids): (Vec<u32>, Vec<u32>) = pids
+                .iter()
+                .partition(|pid| !has_ancestor_in(&system, **pid, &pid_set));
+
             crate::modules::logger::log_info(&format!(
-                "Precisely closing {} identified processes on Windows...",
-                pids.len()
+                "Precisely closing {} identified processes on Windows ({} main, {} helper)...",
+                pids.len(),
+                main_pids.len(),
+                helper_pids.len()
             ));
-            for pid in pids {
+            for pid in main_pids.iter().chain(helper_pids.iter()) {
                 let _ = Command::new("taskkill")
                     .args(["/F", "/PID", &pid.to_string()])
                     .creation_flags(0x08000000) // CREATE_NO_WINDOW
@@ -396,6 +440,10 @@ pub fn close_antigravity(#[allow(unused_variables)] timeout_secs: u64) -> Result
 
             let mut main_pid = None;
 
+            // [NEW] Parent-child identification: any candidate descending from another
+            // candidate is a helper, regardless of its name or arguments
+            let pid_set: std::collections::HashSet<u32> = pids.iter().copied().collect();
+
             // Load manual configuration path as highest priority reference
             let manual_path = crate::modules::config::load_app_config()
                 .ok()
@@ -419,6 +467,10 @@ pub fn close_antigravity(#[allow(unused_variables)] timeout_secs: u64) -> Result
                         pid_u32, name, args_str
                     ));
 
+                    // [NEW] Robust against renamed helpers: a child of another
+                    // identified process cannot be the main process
+                    let is_helper_by_parent = has_ancestor_in(&system, *pid_u32, &pid_set);
+
                     // 1. Priority to manual path matching
                     if let (Some(ref m_path), Some(p_exe)) = (&manual_path, process.exe()) {
                         if let Ok(p_path) = p_exe.canonicalize() {
@@ -440,7 +492,10 @@ pub fn close_antigravity(#[allow(unused_variables)] timeout_secs: u64) -> Result
                                         || name.to_lowercase().contains("sandbox")
                                         || name.to_lowercase().contains("language_server");
 
-                                    if !is_helper_by_args && !is_helper_by_name {
+                                    if !is_helper_by_args
+                                        && !is_helper_by_name
+                                        && !is_helper_by_parent
+                                    {
                                         main_pid = Some(*pid_u32);
                                         crate::modules::logger::log_info(&format!(
                                             "   => Identified as main process (manual path match)"
@@ -464,7 +519,7 @@ pub fn close_antigravity(#[allow(unused_variables)] timeout_secs: u64) -> Result
 
                     let is_helper_by_args = args_str.contains("--type=");
 
-                    if !is_helper_by_name && !is_helper_by_args {
+                    if !is_helper_by_name && !is_helper_by_args && !is_helper_by_parent {
                         if main_pid.is_none() {
                             main_pid = Some(*pid_u32);
                             crate::modules::logger::log_info(&format!(
@@ -473,7 +528,7 @@ pub fn close_antigravity(#[allow(unused_variables)] timeout_secs: u64) -> Result
                         }
                     } else {
                         crate::modules::logger::log_info(&format!(
-                            "   => Identified as helper process (Helper/Args)"
+                            "   => Identified as helper process (Helper/Args/Parent)"
                         ));
                     }
                 }
@@ -499,13 +554,37 @@ pub fn close_antigravity(#[allow(unused_variables)] timeout_secs: u64) -> Result
                     }
                 }
             } else {
-                crate::modules::logger::log_warn(
-                    "No clear main process identified, attempting SIGTERM for all processes (may cause popups)",
-                );
-                for pid in &pids {
-                    let _ = Command::new("kill")
-                        .args(["-15", &pid.to_string()])
-                        .output();
+                // [NEW] 未识别出主进程时按配置的兜底策略处理，尽量避免崩溃弹窗
+                let strategy = crate::modules::config::load_app_config()
+                    .map(|c| c.macos_close_strategy)
+                    .unwrap_or_default();
+                match strategy {
+                    crate::models::config::MacosCloseStrategy::MainOnly => {
+                        crate::modules::logger::log_warn(
+                            "No clear main process identified (strategy: main_only), skipping SIGTERM broadcast and waiting for escalation",
+                        );
+                    }
+                    crate::models::config::MacosCloseStrategy::LowestPidFirst => {
+                        if let Some(lowest) = pids.iter().min() {
+                            crate::modules::logger::log_warn(&format!(
+                                "No clear main process identified (strategy: lowest_pid_first), sending SIGTERM to lowest PID {} and waiting before escalation",
+                                lowest
+                            ));
+                            let _ = Command::new("kill")
+                                .args(["-15", &lowest.to_string()])
+                                .output();
+                        }
+                    }
+                    crate::models::config::MacosCloseStrategy::AllAtOnce => {
+                        crate::modules::logger::log_warn(
+                            "No clear main process identified, attempting SIGTERM for all processes (may cause popups)",
+                        );
+                        for pid in &pids {
+                            let _ = Command::new("kill")
+                                .args(["-15", &pid.to_string()])
+                                .output();
+                        }
+                    }
                 }
             }
 
@@ -730,7 +809,24 @@ pub fn start_antigravity() -> Result<(), String> {
     Ok(())
 }
 
+/// [NEW] 从参数列表提取 --user-data-dir 的值 (支持空格分隔与等号两种写法)
+pub(crate) fn find_user_data_dir_flag(args: &[String]) -> Option<String> {
+    for i in 0..args.len() {
+        if args[i] == "--user-data-dir" && i + 1 < args.len() {
+            return Some(args[i + 1].clone());
+        } else if let Some(value) = args[i].strip_prefix("--user-data-dir=") {
+            return Some(value.to_string());
+        }
+    }
+    None
+}
+
 /// Get arguments from the running process
+///
+/// [FIX] macOS 上通过 `open -a App --args ...` 启动时，主进程的 cmd()
+/// 不一定带 `--user-data-dir`，但 renderer/helper 进程 (带 `--type=`)
+/// 会继承该标志。这里在主进程参数缺失时用 helper 进程兜底，
+/// 并剥离 `open` 约定中的 `--args` 分隔符
 pub fn get_args_from_running_process() -> Option<Vec<String>> {
     let mut system = System::new_all();
     system.refresh_all();
@@ -739,6 +835,9 @@ pub fn get_args_from_running_process() -> Option<Vec<String>> {
         .unwrap_or_else(|_| "Topoo Gateway".to_string());
     let target_app_lower = target_app.to_lowercase();
 
+    let mut main_args: Option<Vec<String>> = None;
+    let mut helper_user_data_dir: Option<String> = None;
+
     for (_, process) in system.processes() {
         let name = process.name().to_string_lossy().to_lowercase();
         if name.contains(&target_app_lower) && !name.contains("tools") {
@@ -750,35 +849,67 @@ pub fn get_args_from_running_process() -> Option<Vec<String>> {
             // Simple heuristic to avoid helpers
             let cmd_str = cmd.join(" ");
             if !cmd_str.contains("--type=") {
-                return Some(cmd);
+                if main_args.is_none() {
+                    main_args = Some(cmd);
+                }
+            } else if helper_user_data_dir.is_none() {
+                helper_user_data_dir = find_user_data_dir_flag(&cmd);
             }
         }
     }
-    None
+
+    let mut args = match main_args {
+        Some(a) => a,
+        None => {
+            // 主进程不可见但 helper 进程携带了继承的 --user-data-dir
+            let dir = helper_user_data_dir?;
+            crate::modules::logger::log_info(&format!(
+                "Main process args unavailable, using inherited --user-data-dir from helper: {}",
+                dir
+            ));
+            return Some(vec![format!("--user-data-dir={}", dir)]);
+        }
+    };
+
+    // 剥离 macOS `open -a App --args ...` 的分隔符，后续解析按普通参数处理
+    args.retain(|a| a != "--args");
+
+    if find_user_data_dir_flag(&args).is_none() {
+        if let Some(dir) = helper_user_data_dir {
+            crate::modules::logger::log_info(&format!(
+                "Main process missing --user-data-dir, inherited from helper: {}",
+                dir
+            ));
+            args.push(format!("--user-data-dir={}", dir));
+        }
+    }
+
+    crate::modules::logger::log_info(&format!("Detected running process args: {:?}", args));
+    Some(args)
 }
 
 /// Get --user-data-dir argument value (if exists)
+///
+/// [FIX] 先从运行中的进程检测 (含 macOS helper 兜底)，
+/// 检测不到时再回退到配置的 antigravity_args
 pub fn get_user_data_dir_from_process() -> Option<std::path::PathBuf> {
-    let config = crate::modules::config::load_app_config().ok();
-    let args_from_config = config.and_then(|c| c.antigravity_args);
-
-    let args = if let Some(a) = args_from_config {
-        a
-    } else {
-        // Fallback to running process info
-        get_args_from_running_process().unwrap_or_default()
-    };
-
-    for i in 0..args.len() {
-        if args[i] == "--user-data-dir" && i + 1 < args.len() {
-            return Some(std::path::PathBuf::from(&args[i + 1]));
-        } else if args[i].starts_with("--user-data-dir=") {
-            let parts: Vec<&str> = args[i].splitn(2, '=').collect();
-            if parts.len() == 2 {
-                return Some(std::path::PathBuf::from(parts[1]));
-            }
+    if let Some(args) = get_args_from_running_process() {
+        if let Some(dir) = find_user_data_dir_flag(&args) {
+            return Some(std::path::PathBuf::from(dir));
         }
     }
+
+    let args_from_config = crate::modules::config::load_app_config()
+        .ok()
+        .and_then(|c| c.antigravity_args)
+        .unwrap_or_default();
+    if let Some(dir) = find_user_data_dir_flag(&args_from_config) {
+        crate::modules::logger::log_info(&format!(
+            "--user-data-dir resolved from config antigravity_args: {}",
+            dir
+        ));
+        return Some(std::path::PathBuf::from(dir));
+    }
     None
 }
 
@@ -798,6 +929,13 @@ pub fn get_antigravity_executable_path() -> Option<std::path::PathBuf> {
     check_standard_locations()
 }
 
+/// [NEW] Flatpak app id for Linux detection (varies by packaging, configurable)
+pub fn get_flatpak_app_id() -> String {
+    crate::modules::config::load_app_config()
+        .map(|c| c.flatpak_app_id)
+        .unwrap_or_else(|_| "com.google.Antigravity".to_string())
+}
+
 /// Check standard installation locations
 fn check_standard_locations() -> Option<std::path::PathBuf> {
     let target_app = crate::modules::config::load_app_config()
@@ -841,12 +979,24 @@ fn check_standard_locations() -> Option<std::path::PathBuf> {
     #[cfg(target_os = "linux")]
     {
         let target_app_lower = target_app.to_lowercase();
-        let paths = vec![
-            format!("/usr/bin/{}", target_app_lower),
-            format!("/opt/{}/{}", target_app, target_app_lower),
+        let flatpak_id = get_flatpak_app_id();
+        let mut paths = vec![
+            std::path::PathBuf::from(format!("/usr/bin/{}", target_app_lower)),
+            std::path::PathBuf::from(format!("/opt/{}/{}", target_app, target_app_lower)),
+            // [NEW] Flatpak 安装 (系统级导出入口)
+            std::path::PathBuf::from(format!("/var/lib/flatpak/exports/bin/{}", flatpak_id)),
+            // [NEW] Snap 安装
+            std::path::PathBuf::from(format!("/snap/bin/{}", target_app_lower)),
+            std::path::PathBuf::from(format!(
+                "/snap/{}/current/usr/share/{}/{}",
+                target_app_lower, target_app_lower, target_app_lower
+            )),
         ];
-        for p in paths {
-            let path = std::path::PathBuf::from(p);
+        // [NEW] Flatpak 用户级安装
+        if let Some(home) = dirs::home_dir() {
+            paths.push(home.join(format!(".local/share/flatpak/exports/bin/{}", flatpak_id)));
+        }
+        for path in paths {
             if path.exists() {
                 return Some(path);
             }
@@ -855,3 +1005,186 @@ fn check_standard_locations() -> Option<std::path::PathBuf> {
 
     None
 }
+
+// ==================================================================================
+// [NEW] IDE 版本探测：注入行为随 IDE 版本变化 (legacy vs unified key)，
+// 读取安装目录的 product.json / package.json 获取版本与产品名，
+// 供注入器选择 key 集及用户在 bug 报告中标明确切版本
+// ==================================================================================
+
+/// IDE 版本信息 (探测失败的字段保持 "unknown")
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct IdeVersionInfo {
+    pub product_name: String,
+    pub version: String,
+    /// 读取到版本信息的元数据文件路径，便于排查
+    pub source: Option<String>,
+}
+
+/// 从可执行路径推导元数据 (product.json / package.json) 所在目录的候选位置
+fn ide_metadata_candidates(exe_path: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut candidates = Vec::new();
+
+    #[cfg(target_os = "macos")]
+    {
+        // 路径可能是 .app bundle 本身，也可能是 bundle 内的可执行文件
+        for ancestor in exe_path.ancestors() {
+            if ancestor.extension().and_then(|e| e.to_str()) == Some("app") {
+                candidates.push(ancestor.join("Contents").join("Resources").join("app"));
+                break;
+            }
+        }
+    }
+
+    // Windows / Linux (Electron 布局): <install>/resources/app
+    if let Some(dir) = exe_path.parent() {
+        candidates.push(dir.join("resources").join("app"));
+        candidates.push(dir.to_path_buf());
+    }
+
+    candidates
+}
+
+/// [NEW] 探测已安装 IDE 的产品名与版本；任何一步失败都优雅回退到 "unknown"
+pub fn detect_ide_version() -> IdeVersionInfo {
+    let mut info = IdeVersionInfo {
+        product_name: "unknown".to_string(),
+        version: "unknown".to_string(),
+        source: None,
+    };
+
+    let Some(exe_path) = get_antigravity_executable_path() else {
+        return info;
+    };
+
+    for dir in ide_metadata_candidates(&exe_path) {
+        // product.json: 产品名 (nameLong/nameShort)，部分发行版也带 version
+        let product_path = dir.join("product.json");
+        if let Ok(raw) = std::fs::read_to_string(&product_path) {
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&raw) {
+                if let Some(name) = json
+                    .get("nameLong")
+                    .or_else(|| json.get("nameShort"))
+                    .or_else(|| json.get("applicationName"))
+                    .and_then(|v| v.as_str())
+                {
+                    info.product_name = name.to_string();
+                    info.source = Some(product_path.display().to_string());
+                }
+                if let Some(version) = json.get("version").and_then(|v| v.as_str()) {
+                    info.version = version.to_string();
+                }
+            }
+        }
+
+        // package.json: 版本号权威来源，产品名兜底
+        let package_path = dir.join("package.json");
+        if let Ok(raw) = std::fs::read_to_string(&package_path) {
+            if let Ok(json) = serde_json::from_str::<serde_json::Value>(&raw) {
+                if info.version == "unknown" {
+                    if let Some(version) = json.get("version").and_then(|v| v.as_str()) {
+                        info.version = version.to_string();
+                        info.source = Some(package_path.display().to_string());
+                    }
+                }
+                if info.product_name == "unknown" {
+                    if let Some(name) = json.get("name").and_then(|v| v.as_str()) {
+                        info.product_name = name.to_string();
+                    }
+                }
+            }
+        }
+
+        if info.version != "unknown" && info.product_name != "unknown" {
+            break;
+        }
+    }
+
+    info
+}
+
+// ==================================================================================
+// [NEW] 端口占用检测：反代启动前预检，把"启动失败"变成可读的冲突提示
+// ==================================================================================
+
+/// 端口可用性检查结果
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct PortCheckResult {
+    pub port: u16,
+    pub addr: String,
+    pub available: bool,
+    /// 占用端口的进程 PID（仅在可探测时提供）
+    pub conflict_pid: Option<u32>,
+    /// 占用端口的进程名（仅在可探测时提供）
+    pub conflict_process: Option<String>,
+}
+
+/// [NEW] 检查端口是否可绑定；被占用时尽力找出占用进程（lsof/netstat + sysinfo）
+pub fn check_port_available(port: u16, addr: Option<String>) -> PortCheckResult {
+    let addr = addr.unwrap_or_else(|| "0.0.0.0".to_string());
+    let available = std::net::TcpListener::bind(format!("{}:{}", addr, port)).is_ok();
+
+    let (conflict_pid, conflict_process) = if available {
+        (None, None)
+    } else {
+        match find_pid_listening_on_port(port) {
+            Some(pid) => (Some(pid), process_name_by_pid(pid)),
+            None => (None, None),
+        }
+    };
+
+    PortCheckResult {
+        port,
+        addr,
+        available,
+        conflict_pid,
+        conflict_process,
+    }
+}
+
+/// 尽力探测监听指定端口的进程 PID（平台工具不可用时返回 None）
+fn find_pid_listening_on_port(port: u16) -> Option<u32> {
+    #[cfg(not(target_os = "windows"))]
+    {
+        // lsof -t 只输出 PID，一行一个
+        let output = Command::new("lsof")
+            .args(["-t", "-i", &format!(":{}", port), "-sTCP:LISTEN"])
+            .output()
+            .ok()?;
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .and_then(|line| line.trim().parse().ok())
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        const CREATE_NO_WINDOW: u32 = 0x08000000;
+        let output = Command::new("netstat")
+            .args(["-ano", "-p", "TCP"])
+            .creation_flags(CREATE_NO_WINDOW)
+            .output()
+            .ok()?;
+        let needle = format!(":{}", port);
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .filter(|line| line.contains("LISTENING"))
+            .find(|line| {
+                line.split_whitespace()
+                    .nth(1)
+                    .map(|local| local.ends_with(&needle))
+                    .unwrap_or(false)
+            })
+            .and_then(|line| line.split_whitespace().last())
+            .and_then(|pid| pid.parse().ok())
+    }
+}
+
+/// 用 sysinfo 把 PID 映射为进程名
+fn process_name_by_pid(pid: u32) -> Option<String> {
+    let mut system = System::new();
+    system.refresh_processes(sysinfo::ProcessesToUpdate::All);
+    system
+        .process(sysinfo::Pid::from_u32(pid))
+        .map(|p| p.name().to_string_lossy().to_string())
+}
@@ -26,10 +26,48 @@ fn connect_db() -> Result<Connection, String> {
     Ok(conn)
 }
 
+/// [NEW] Ordered schema migrations, tracked via PRAGMA user_version.
+/// Entry at index i is applied when upgrading to schema version i+1.
+/// Append-only: never edit or reorder shipped entries.
+const MIGRATIONS: &[&str] = &[
+    // v1: [NEW] flag logs whose token counts were estimated from streamed text
+    "ALTER TABLE request_logs ADD COLUMN tokens_estimated INTEGER;",
+    // v2: [NEW] manual annotations (support/triage); written only via
+    // set_log_note/set_log_flag, never by the monitor's own upserts
+    "ALTER TABLE request_logs ADD COLUMN note TEXT;
+     ALTER TABLE request_logs ADD COLUMN flagged INTEGER;",
+    // v3: [NEW] mark responses served from the in-memory response cache
+    "ALTER TABLE request_logs ADD COLUMN cached INTEGER;",
+];
+
+/// [NEW] Apply any pending schema migrations (idempotent, version-gated)
+fn run_migrations(conn: &Connection) -> Result<(), String> {
+    let current: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| e.to_string())?;
+
+    for (i, sql) in MIGRATIONS.iter().enumerate() {
+        let version = (i + 1) as i64;
+        if version <= current {
+            continue;
+        }
+        conn.execute_batch(sql)
+            .map_err(|e| format!("proxy_db migration v{} failed: {}", version, e))?;
+        conn.pragma_update(None, "user_version", version)
+            .map_err(|e| e.to_string())?;
+        tracing::info!("proxy_logs.db migrated to schema v{}", version);
+    }
+    Ok(())
+}
+
 pub fn init_db() -> Result<(), String> {
     // connect_db will initialize WAL mode and other pragmas
     let conn = connect_db()?;
+    init_schema(&conn)
+}
 
+/// Create tables/indexes and apply migrations on an existing connection
+fn init_schema(conn: &Connection) -> Result<(), String> {
     conn.execute(
         "CREATE TABLE IF NOT EXISTS request_logs (
             id TEXT PRIMARY KEY,
@@ -75,15 +113,54 @@ pub fn init_db() -> Result<(), String> {
     )
     .map_err(|e| e.to_string())?;
 
+    // [NEW] Apply versioned migrations; the legacy ALTER attempts above stay for
+    // installs that predate schema versioning
+    run_migrations(conn)?;
+
     Ok(())
 }
 
-pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
+/// [FIX] Upsert on `id` so a repeated id (retries, stream reconnects) updates the
+/// existing row instead of inflating stats with a duplicate.
+/// Returns `true` when the id was newly inserted, so callers can record
+/// token stats only once per unique request.
+pub fn save_log(log: &ProxyRequestLog) -> Result<bool, String> {
     let conn = connect_db()?;
+    save_log_with_conn(&conn, log)
+}
+
+// 注意: note/flagged 不在列清单中，monitor 的 upsert 永远不会覆盖人工标注
+fn save_log_with_conn(conn: &Connection, log: &ProxyRequestLog) -> Result<bool, String> {
+    let existed: bool = conn
+        .query_row(
+            "SELECT EXISTS(SELECT 1 FROM request_logs WHERE id = ?1)",
+            params![log.id],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
 
     conn.execute(
-        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17)",
+        "INSERT INTO request_logs (id, timestamp, method, url, status, duration, model, error, request_body, response_body, input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, tokens_estimated, cached)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19)
+         ON CONFLICT(id) DO UPDATE SET
+            timestamp = excluded.timestamp,
+            method = excluded.method,
+            url = excluded.url,
+            status = excluded.status,
+            duration = excluded.duration,
+            model = excluded.model,
+            error = excluded.error,
+            request_body = excluded.request_body,
+            response_body = excluded.response_body,
+            input_tokens = excluded.input_tokens,
+            output_tokens = excluded.output_tokens,
+            account_email = excluded.account_email,
+            mapped_model = excluded.mapped_model,
+            protocol = excluded.protocol,
+            client_ip = excluded.client_ip,
+            username = excluded.username,
+            tokens_estimated = excluded.tokens_estimated,
+            cached = excluded.cached",
         params![
             log.id,
             log.timestamp,
@@ -102,10 +179,12 @@ pub fn save_log(log: &ProxyRequestLog) -> Result<(), String> {
             log.protocol,
             log.client_ip,
             log.username,
+            log.tokens_estimated,
+            log.cached,
         ],
     ).map_err(|e| e.to_string())?;
 
-    Ok(())
+    Ok(!existed)
 }
 
 /// Get logs with pagination, optionally including body
@@ -125,7 +204,7 @@ pub fn get_logs_paginated(
     let sql = format!(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 {}, {},
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, tokens_estimated, note, flagged, cached
          FROM request_logs 
          ORDER BY timestamp DESC 
          LIMIT ?1 OFFSET ?2",
@@ -151,6 +230,12 @@ pub fn get_logs_paginated(
                 response_body: row.get(9).unwrap_or(None),
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
+                cached_input_tokens: None,
+                reasoning_tokens: None,
+                tokens_estimated: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                note: row.get(18).unwrap_or(None),
+                flagged: row.get::<_, Option<bool>>(19).unwrap_or(None).unwrap_or(false),
+                cached: row.get::<_, Option<bool>>(20).unwrap_or(None).unwrap_or(false),
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: row.get(16).unwrap_or(None),
@@ -175,6 +260,106 @@ pub fn get_logs(limit: usize) -> Result<Vec<ProxyRequestLog>, String> {
     get_logs_summary(limit, 0)
 }
 
+/// [NEW] 日志游标 (keyset): 指向上一页最后一条记录的 (timestamp, id)
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct LogCursor {
+    pub timestamp: i64,
+    pub id: String,
+}
+
+/// [NEW] 游标分页结果: next_cursor = None 表示已到历史末尾
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct LogPage {
+    pub logs: Vec<ProxyRequestLog>,
+    pub next_cursor: Option<LogCursor>,
+}
+
+/// [NEW] 基于 (timestamp, id) keyset 的游标分页。
+/// 与 OFFSET 分页不同，深翻页不需要扫过前面的所有行，
+/// 日志达到百万级后滚动加载仍保持常数级查询开销。
+/// cursor = None 表示从最新一条开始
+pub fn get_logs_before(cursor: Option<LogCursor>, limit: usize) -> Result<LogPage, String> {
+    let conn = connect_db()?;
+
+    let (where_sql, params_vec): (&str, Vec<Box<dyn rusqlite::types::ToSql>>) = match &cursor {
+        Some(c) => (
+            "WHERE (timestamp, id) < (?1, ?2)",
+            vec![
+                Box::new(c.timestamp) as Box<dyn rusqlite::types::ToSql>,
+                Box::new(c.id.clone()),
+            ],
+        ),
+        None => ("", Vec::new()),
+    };
+
+    let limit_idx = params_vec.len() + 1;
+    let mut params_vec = params_vec;
+    params_vec.push(Box::new(limit as i64));
+
+    // id 作为 timestamp 相同时的决胜键，保证游标严格单调、翻页不重不漏
+    let sql = format!(
+        "SELECT id, timestamp, method, url, status, duration, model, error,
+                NULL as request_body, NULL as response_body,
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, tokens_estimated, note, flagged, cached
+         FROM request_logs
+         {}
+         ORDER BY timestamp DESC, id DESC
+         LIMIT ?{}",
+        where_sql, limit_idx
+    );
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let logs_iter = stmt
+        .query_map(
+            rusqlite::params_from_iter(params_vec.iter().map(|p| p.as_ref())),
+            |row| {
+                Ok(ProxyRequestLog {
+                    id: row.get(0)?,
+                    timestamp: row.get(1)?,
+                    method: row.get(2)?,
+                    url: row.get(3)?,
+                    status: row.get(4)?,
+                    duration: row.get(5)?,
+                    model: row.get(6)?,
+                    mapped_model: row.get(13).unwrap_or(None),
+                    account_email: row.get(12).unwrap_or(None),
+                    error: row.get(7)?,
+                    request_body: None,
+                    response_body: None,
+                    input_tokens: row.get(10).unwrap_or(None),
+                    output_tokens: row.get(11).unwrap_or(None),
+                    cached_input_tokens: None,
+                    reasoning_tokens: None,
+                    tokens_estimated: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                    note: row.get(18).unwrap_or(None),
+                    flagged: row.get::<_, Option<bool>>(19).unwrap_or(None).unwrap_or(false),
+                    cached: row.get::<_, Option<bool>>(20).unwrap_or(None).unwrap_or(false),
+                    protocol: row.get(14).unwrap_or(None),
+                    client_ip: row.get(15).unwrap_or(None),
+                    username: row.get(16).unwrap_or(None),
+                })
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let mut logs = Vec::new();
+    for log in logs_iter {
+        logs.push(log.map_err(|e| e.to_string())?);
+    }
+
+    // 返回满页时才给出下一页游标；不满页说明已到末尾
+    let next_cursor = if logs.len() == limit && limit > 0 {
+        logs.last().map(|l| LogCursor {
+            timestamp: l.timestamp,
+            id: l.id.clone(),
+        })
+    } else {
+        None
+    };
+
+    Ok(LogPage { logs, next_cursor })
+}
+
 pub fn get_stats() -> Result<crate::proxy::monitor::ProxyStats, String> {
     let conn = connect_db()?;
 
@@ -191,10 +376,37 @@ pub fn get_stats() -> Result<crate::proxy::monitor::ProxyStats, String> {
         )
         .map_err(|e| e.to_string())?;
 
+    // [NEW] 配置为中性的状态码 (如 429/499) 单独统计，不计入 error
+    let neutral: Vec<u16> = crate::modules::config::load_app_config()
+        .map(|c| c.proxy.neutral_status_codes)
+        .unwrap_or_default();
+
+    let warning_count: u64 = if neutral.is_empty() {
+        0
+    } else {
+        // 纯数字拼接，无注入风险
+        let codes = neutral
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        conn.query_row(
+            &format!(
+                "SELECT COUNT(*) FROM request_logs
+                 WHERE (status < 200 OR status >= 400) AND status IN ({})",
+                codes
+            ),
+            [],
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?
+    };
+
     Ok(crate::proxy::monitor::ProxyStats {
         total_requests,
         success_count,
-        error_count,
+        error_count: error_count.saturating_sub(warning_count),
+        warning_count,
     })
 }
 
@@ -206,7 +418,7 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
         .prepare(
             "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username, tokens_estimated, note, flagged, cached
          FROM request_logs 
          WHERE id = ?1",
         )
@@ -228,6 +440,12 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
             response_body: row.get(9).unwrap_or(None),
             input_tokens: row.get(10).unwrap_or(None),
             output_tokens: row.get(11).unwrap_or(None),
+            cached_input_tokens: None,
+            reasoning_tokens: None,
+            tokens_estimated: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+            note: row.get(18).unwrap_or(None),
+            flagged: row.get::<_, Option<bool>>(19).unwrap_or(None).unwrap_or(false),
+            cached: row.get::<_, Option<bool>>(20).unwrap_or(None).unwrap_or(false),
             protocol: row.get(14).unwrap_or(None),
             client_ip: row.get(15).unwrap_or(None),
             username: row.get(16).unwrap_or(None),
@@ -236,6 +454,36 @@ pub fn get_log_detail(log_id: &str) -> Result<ProxyRequestLog, String> {
     .map_err(|e| e.to_string())
 }
 
+/// [NEW] Set or clear a manual note on a log entry
+pub fn set_log_note(log_id: &str, note: Option<String>) -> Result<(), String> {
+    let conn = connect_db()?;
+    let updated = conn
+        .execute(
+            "UPDATE request_logs SET note = ?1 WHERE id = ?2",
+            params![note, log_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Log not found: {}", log_id));
+    }
+    Ok(())
+}
+
+/// [NEW] Mark or unmark a log entry as flagged
+pub fn set_log_flag(log_id: &str, flagged: bool) -> Result<(), String> {
+    let conn = connect_db()?;
+    let updated = conn
+        .execute(
+            "UPDATE request_logs SET flagged = ?1 WHERE id = ?2",
+            params![flagged, log_id],
+        )
+        .map_err(|e| e.to_string())?;
+    if updated == 0 {
+        return Err(format!("Log not found: {}", log_id));
+    }
+    Ok(())
+}
+
 /// Cleanup old logs (keep last N days)
 pub fn cleanup_old_logs(days: i64) -> Result<usize, String> {
     let conn = connect_db()?;
@@ -255,6 +503,48 @@ pub fn cleanup_old_logs(days: i64) -> Result<usize, String> {
     Ok(deleted)
 }
 
+/// [NEW] 删除指定账号的全部请求日志，返回删除条数。
+/// 供 purge_account_data 清理隐私数据使用，与账号删除本身解耦
+pub fn purge_logs_for_account(account_email: &str) -> Result<u64, String> {
+    let conn = connect_db()?;
+
+    let deleted = conn
+        .execute(
+            "DELETE FROM request_logs WHERE account_email = ?1",
+            [account_email],
+        )
+        .map_err(|e| e.to_string())?;
+
+    // 回收磁盘空间 (与 cleanup_old_logs 保持一致)
+    conn.execute("VACUUM", []).map_err(|e| e.to_string())?;
+
+    Ok(deleted as u64)
+}
+
+/// [NEW] 按时间范围批量删除日志 (timestamp 毫秒，闭区间)，返回删除条数
+///
+/// 注意: token_stats.db 中已累计的 token 统计不会随之扣减，
+/// 删除后如需口径一致请重建统计 (如 rebuild_ip_access_logs 的做法)。
+pub fn delete_logs_in_range(from_ts: i64, to_ts: i64) -> Result<usize, String> {
+    let conn = connect_db()?;
+    delete_logs_in_range_with_conn(&conn, from_ts, to_ts)
+}
+
+fn delete_logs_in_range_with_conn(
+    conn: &Connection,
+    from_ts: i64,
+    to_ts: i64,
+) -> Result<usize, String> {
+    let deleted = conn
+        .execute(
+            "DELETE FROM request_logs WHERE timestamp BETWEEN ?1 AND ?2",
+            [from_ts, to_ts],
+        )
+        .map_err(|e| e.to_string())?;
+
+    Ok(deleted)
+}
+
 /// Limit maximum log count (keep newest N records)
 #[allow(dead_code)]
 pub fn limit_max_logs(max_count: usize) -> Result<usize, String> {
@@ -295,28 +585,59 @@ pub fn get_logs_count() -> Result<u64, String> {
 /// Get count of logs matching search filter
 /// filter: search text to match in url, method, model, or status
 /// errors_only: if true, only count logs with status < 200 or >= 400
-pub fn get_logs_count_filtered(filter: &str, errors_only: bool) -> Result<u64, String> {
+/// account_email / status_min / status_max: [FIX] same optional filters as
+/// get_logs_filtered, so pagination totals match the returned rows
+pub fn get_logs_count_filtered(
+    filter: &str,
+    errors_only: bool,
+    account_email: Option<String>,
+    status_min: Option<u16>,
+    status_max: Option<u16>,
+) -> Result<u64, String> {
     let conn = connect_db()?;
 
-    let filter_pattern = format!("%{}%", filter);
+    // Build the WHERE clause with the exact same logic as get_logs_filtered
+    let mut clauses: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
 
-    let sql = if errors_only {
-        "SELECT COUNT(*) FROM request_logs WHERE (status < 200 OR status >= 400)"
-    } else if filter.is_empty() {
-        "SELECT COUNT(*) FROM request_logs"
+    if errors_only {
+        clauses.push("(status < 200 OR status >= 400)".to_string());
+    }
+    if !filter.is_empty() {
+        params_vec.push(Box::new(format!("%{}%", filter)));
+        let i = params_vec.len();
+        clauses.push(format!(
+            "(url LIKE ?{i} OR method LIKE ?{i} OR model LIKE ?{i} OR CAST(status AS TEXT) LIKE ?{i} OR account_email LIKE ?{i} OR client_ip LIKE ?{i} OR username LIKE ?{i})"
+        ));
+    }
+    if let Some(email) = account_email {
+        params_vec.push(Box::new(email));
+        clauses.push(format!("account_email = ?{}", params_vec.len()));
+    }
+    if let Some(min) = status_min {
+        params_vec.push(Box::new(min));
+        clauses.push(format!("status >= ?{}", params_vec.len()));
+    }
+    if let Some(max) = status_max {
+        params_vec.push(Box::new(max));
+        clauses.push(format!("status <= ?{}", params_vec.len()));
+    }
+
+    let where_sql = if clauses.is_empty() {
+        String::new()
     } else {
-        "SELECT COUNT(*) FROM request_logs WHERE
-            (url LIKE ?1 OR method LIKE ?1 OR model LIKE ?1 OR CAST(status AS TEXT) LIKE ?1 OR account_email LIKE ?1 OR username LIKE ?1)"
+        format!("WHERE {}", clauses.join(" AND "))
     };
 
-    let count: u64 = if filter.is_empty() && !errors_only {
-        conn.query_row(sql, [], |row| row.get(0))
-    } else if errors_only {
-        conn.query_row(sql, [], |row| row.get(0))
-    } else {
-        conn.query_row(sql, [&filter_pattern], |row| row.get(0))
-    }
-    .map_err(|e| e.to_string())?;
+    let sql = format!("SELECT COUNT(*) FROM request_logs {}", where_sql);
+
+    let count: u64 = conn
+        .query_row(
+            &sql,
+            rusqlite::params_from_iter(params_vec.iter().map(|p| p.as_ref())),
+            |row| row.get(0),
+        )
+        .map_err(|e| e.to_string())?;
 
     Ok(count)
 }
@@ -324,97 +645,73 @@ pub fn get_logs_count_filtered(filter: &str, errors_only: bool) -> Result<u64, S
 /// Get logs with search filter and pagination
 /// filter: search text to match in url, method, model, or status
 /// errors_only: if true, only return logs with status < 200 or >= 400
+/// account_email: [NEW] only return logs for this exact account
+/// status_min/status_max: [NEW] inclusive status-code range (e.g. 500..=599)
 pub fn get_logs_filtered(
     filter: &str,
     errors_only: bool,
+    account_email: Option<String>,
+    status_min: Option<u16>,
+    status_max: Option<u16>,
     limit: usize,
     offset: usize,
 ) -> Result<Vec<ProxyRequestLog>, String> {
     let conn = connect_db()?;
 
-    let filter_pattern = format!("%{}%", filter);
+    // [NEW] Build the WHERE clause dynamically from the optional filters
+    let mut clauses: Vec<String> = Vec::new();
+    let mut params_vec: Vec<Box<dyn rusqlite::types::ToSql>> = Vec::new();
 
-    let sql = if errors_only {
-        "SELECT id, timestamp, method, url, status, duration, model, error, 
-                NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
-         FROM request_logs 
-         WHERE (status < 200 OR status >= 400)
-         ORDER BY timestamp DESC 
-         LIMIT ?1 OFFSET ?2"
-    } else if filter.is_empty() {
-        "SELECT id, timestamp, method, url, status, duration, model, error, 
-                NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
-         FROM request_logs 
-         ORDER BY timestamp DESC 
-         LIMIT ?1 OFFSET ?2"
+    if errors_only {
+        clauses.push("(status < 200 OR status >= 400)".to_string());
+    }
+    if !filter.is_empty() {
+        params_vec.push(Box::new(format!("%{}%", filter)));
+        let i = params_vec.len();
+        clauses.push(format!(
+            "(url LIKE ?{i} OR method LIKE ?{i} OR model LIKE ?{i} OR CAST(status AS TEXT) LIKE ?{i} OR account_email LIKE ?{i} OR client_ip LIKE ?{i} OR username LIKE ?{i})"
+        ));
+    }
+    if let Some(email) = account_email {
+        params_vec.push(Box::new(email));
+        clauses.push(format!("account_email = ?{}", params_vec.len()));
+    }
+    if let Some(min) = status_min {
+        params_vec.push(Box::new(min));
+        clauses.push(format!("status >= ?{}", params_vec.len()));
+    }
+    if let Some(max) = status_max {
+        params_vec.push(Box::new(max));
+        clauses.push(format!("status <= ?{}", params_vec.len()));
+    }
+
+    let where_sql = if clauses.is_empty() {
+        String::new()
     } else {
+        format!("WHERE {}", clauses.join(" AND "))
+    };
+
+    params_vec.push(Box::new(limit as i64));
+    let limit_idx = params_vec.len();
+    params_vec.push(Box::new(offset as i64));
+    let offset_idx = params_vec.len();
+
+    let sql = format!(
         "SELECT id, timestamp, method, url, status, duration, model, error, 
                 NULL as request_body, NULL as response_body,
-                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username
+                input_tokens, output_tokens, account_email, mapped_model, protocol, client_ip, username, tokens_estimated, note, flagged, cached
          FROM request_logs 
-         WHERE (url LIKE ?3 OR method LIKE ?3 OR model LIKE ?3 OR CAST(status AS TEXT) LIKE ?3 OR account_email LIKE ?3 OR client_ip LIKE ?3 OR username LIKE ?3)
+         {} 
          ORDER BY timestamp DESC 
-         LIMIT ?1 OFFSET ?2"
-    };
+         LIMIT ?{} OFFSET ?{}",
+        where_sql, limit_idx, offset_idx
+    );
 
-    let logs: Vec<ProxyRequestLog> = if filter.is_empty() && !errors_only {
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-        let logs_iter = stmt
-            .query_map([limit, offset], |row| {
-                Ok(ProxyRequestLog {
-                    id: row.get(0)?,
-                    timestamp: row.get(1)?,
-                    method: row.get(2)?,
-                    url: row.get(3)?,
-                    status: row.get(4)?,
-                    duration: row.get(5)?,
-                    model: row.get(6)?,
-                    mapped_model: row.get(13).unwrap_or(None),
-                    account_email: row.get(12).unwrap_or(None),
-                    error: row.get(7)?,
-                    request_body: None,
-                    response_body: None,
-                    input_tokens: row.get(10).unwrap_or(None),
-                    output_tokens: row.get(11).unwrap_or(None),
-                    protocol: row.get(14).unwrap_or(None),
-                    client_ip: row.get(15).unwrap_or(None),
-                    username: None,
-                })
-            })
-            .map_err(|e| e.to_string())?;
-        logs_iter.filter_map(|r| r.ok()).collect()
-    } else if errors_only {
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-        let logs_iter = stmt
-            .query_map([limit, offset], |row| {
-                Ok(ProxyRequestLog {
-                    id: row.get(0)?,
-                    timestamp: row.get(1)?,
-                    method: row.get(2)?,
-                    url: row.get(3)?,
-                    status: row.get(4)?,
-                    duration: row.get(5)?,
-                    model: row.get(6)?,
-                    mapped_model: row.get(13).unwrap_or(None),
-                    account_email: row.get(12).unwrap_or(None),
-                    error: row.get(7)?,
-                    request_body: None,
-                    response_body: None,
-                    input_tokens: row.get(10).unwrap_or(None),
-                    output_tokens: row.get(11).unwrap_or(None),
-                    protocol: row.get(14).unwrap_or(None),
-                    client_ip: row.get(15).unwrap_or(None),
-                    username: None,
-                })
-            })
-            .map_err(|e| e.to_string())?;
-        logs_iter.filter_map(|r| r.ok()).collect()
-    } else {
-        let mut stmt = conn.prepare(sql).map_err(|e| e.to_string())?;
-        let logs_iter = stmt
-            .query_map(rusqlite::params![limit, offset, filter_pattern], |row| {
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+    let logs_iter = stmt
+        .query_map(
+            rusqlite::params_from_iter(params_vec.iter().map(|p| p.as_ref())),
+            |row| {
                 Ok(ProxyRequestLog {
                     id: row.get(0)?,
                     timestamp: row.get(1)?,
@@ -430,14 +727,21 @@ pub fn get_logs_filtered(
                     response_body: None,
                     input_tokens: row.get(10).unwrap_or(None),
                     output_tokens: row.get(11).unwrap_or(None),
+                    cached_input_tokens: None,
+                    reasoning_tokens: None,
+                    tokens_estimated: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                    note: row.get(18).unwrap_or(None),
+                    flagged: row.get::<_, Option<bool>>(19).unwrap_or(None).unwrap_or(false),
+                    cached: row.get::<_, Option<bool>>(20).unwrap_or(None).unwrap_or(false),
                     protocol: row.get(14).unwrap_or(None),
                     client_ip: row.get(15).unwrap_or(None),
-                    username: None,
+                    username: row.get(16).unwrap_or(None),
                 })
-            })
-            .map_err(|e| e.to_string())?;
-        logs_iter.filter_map(|r| r.ok()).collect()
-    };
+            },
+        )
+        .map_err(|e| e.to_string())?;
+
+    let logs: Vec<ProxyRequestLog> = logs_iter.filter_map(|r| r.ok()).collect();
 
     Ok(logs)
 }
@@ -450,7 +754,7 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
         .prepare(
             "SELECT id, timestamp, method, url, status, duration, model, error, 
                 request_body, response_body, input_tokens, output_tokens, 
-                account_email, mapped_model, protocol, client_ip, username
+                account_email, mapped_model, protocol, client_ip, username, tokens_estimated, note, flagged, cached
          FROM request_logs 
          ORDER BY timestamp DESC",
         )
@@ -473,6 +777,66 @@ pub fn get_all_logs_for_export() -> Result<Vec<ProxyRequestLog>, String> {
                 response_body: row.get(9).unwrap_or(None),
                 input_tokens: row.get(10).unwrap_or(None),
                 output_tokens: row.get(11).unwrap_or(None),
+                cached_input_tokens: None,
+                reasoning_tokens: None,
+                tokens_estimated: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                note: row.get(18).unwrap_or(None),
+                flagged: row.get::<_, Option<bool>>(19).unwrap_or(None).unwrap_or(false),
+                cached: row.get::<_, Option<bool>>(20).unwrap_or(None).unwrap_or(false),
+                protocol: row.get(14).unwrap_or(None),
+                client_ip: row.get(15).unwrap_or(None),
+                username: None,
+            })
+        })
+        .map_err(|e| e.to_string())?;
+
+    let mut logs = Vec::new();
+    for log in logs_iter {
+        logs.push(log.map_err(|e| e.to_string())?);
+    }
+    Ok(logs)
+}
+
+/// [NEW] Get logs newer than the given timestamp watermark (for incremental stats rebuild)
+/// id 是 UUID 文本无法比较大小，所以用毫秒时间戳做水位线；按时间升序返回，
+/// 调用方用最后一条的 timestamp 更新水位线
+pub fn get_logs_for_export_after(min_timestamp_ms: i64) -> Result<Vec<ProxyRequestLog>, String> {
+    let conn = connect_db()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, timestamp, method, url, status, duration, model, error,
+                request_body, response_body, input_tokens, output_tokens,
+                account_email, mapped_model, protocol, client_ip, username, tokens_estimated, note, flagged, cached
+         FROM request_logs
+         WHERE timestamp > ?1
+         ORDER BY timestamp ASC",
+        )
+        .map_err(|e| e.to_string())?;
+
+    let logs_iter = stmt
+        .query_map([min_timestamp_ms], |row| {
+            Ok(ProxyRequestLog {
+                id: row.get(0)?,
+                timestamp: row.get(1)?,
+                method: row.get(2)?,
+                url: row.get(3)?,
+                status: row.get(4)?,
+                duration: row.get(5)?,
+                model: row.get(6)?,
+                mapped_model: row.get(13).unwrap_or(None),
+                account_email: row.get(12).unwrap_or(None),
+                error: row.get(7)?,
+                request_body: row.get(8).unwrap_or(None),
+                response_body: row.get(9).unwrap_or(None),
+                input_tokens: row.get(10).unwrap_or(None),
+                output_tokens: row.get(11).unwrap_or(None),
+                cached_input_tokens: None,
+                reasoning_tokens: None,
+                tokens_estimated: row.get::<_, Option<bool>>(17).unwrap_or(None).unwrap_or(false),
+                note: row.get(18).unwrap_or(None),
+                flagged: row.get::<_, Option<bool>>(19).unwrap_or(None).unwrap_or(false),
+                cached: row.get::<_, Option<bool>>(20).unwrap_or(None).unwrap_or(false),
                 protocol: row.get(14).unwrap_or(None),
                 client_ip: row.get(15).unwrap_or(None),
                 username: None,
@@ -541,3 +905,117 @@ pub fn get_token_usage_by_ip(limit: usize, hours: i64) -> Result<Vec<IpTokenStat
 
     Ok(stats)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_log(id: &str) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: id.to_string(),
+            timestamp: 1_700_000_000_000,
+            method: "POST".to_string(),
+            url: "/v1/messages".to_string(),
+            status: 200,
+            duration: 1234,
+            model: Some("claude-sonnet".to_string()),
+            mapped_model: None,
+            account_email: Some("test@example.com".to_string()),
+            client_ip: Some("127.0.0.1".to_string()),
+            error: None,
+            request_body: None,
+            response_body: None,
+            input_tokens: Some(100),
+            output_tokens: Some(50),
+            cached_input_tokens: None,
+            reasoning_tokens: None,
+            tokens_estimated: false,
+            note: None,
+            flagged: false,
+            cached: false,
+            protocol: Some("anthropic".to_string()),
+            username: None,
+        }
+    }
+
+    #[test]
+    fn test_save_log_upserts_on_duplicate_id() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+
+        let log = sample_log("dup-id");
+        // First save inserts a new row, so token stats should be counted
+        assert!(save_log_with_conn(&conn, &log).unwrap());
+        // Second save with the same id updates in place and must not be
+        // counted again (single-counted tokens per unique id)
+        assert!(!save_log_with_conn(&conn, &log).unwrap());
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM request_logs", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+
+        let (input, output): (i64, i64) = conn
+            .query_row(
+                "SELECT SUM(input_tokens), SUM(output_tokens) FROM request_logs",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(input, 100);
+        assert_eq!(output, 50);
+    }
+
+    #[test]
+    fn test_save_log_updates_fields_on_conflict() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+
+        let mut log = sample_log("retry-id");
+        save_log_with_conn(&conn, &log).unwrap();
+
+        log.status = 500;
+        log.error = Some("upstream error".to_string());
+        save_log_with_conn(&conn, &log).unwrap();
+
+        let (status, error): (u16, Option<String>) = conn
+            .query_row(
+                "SELECT status, error FROM request_logs WHERE id = 'retry-id'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(status, 500);
+        assert_eq!(error.as_deref(), Some("upstream error"));
+    }
+
+    #[test]
+    fn test_delete_logs_in_range_only_removes_window() {
+        let conn = Connection::open_in_memory().unwrap();
+        init_schema(&conn).unwrap();
+
+        // 三天的日志，每天一条 (毫秒时间戳)
+        let day_ms: i64 = 24 * 3600 * 1000;
+        let base: i64 = 1_700_000_000_000;
+        for (i, id) in ["day1", "day2", "day3"].iter().enumerate() {
+            let mut log = sample_log(id);
+            log.timestamp = base + i as i64 * day_ms;
+            save_log_with_conn(&conn, &log).unwrap();
+        }
+
+        // 只删除中间一天
+        let deleted =
+            delete_logs_in_range_with_conn(&conn, base + day_ms, base + 2 * day_ms - 1).unwrap();
+        assert_eq!(deleted, 1);
+
+        let mut stmt = conn
+            .prepare("SELECT id FROM request_logs ORDER BY timestamp")
+            .unwrap();
+        let remaining: Vec<String> = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .map(|r| r.unwrap())
+            .collect();
+        assert_eq!(remaining, vec!["day1", "day3"]);
+    }
+}
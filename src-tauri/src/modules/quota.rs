@@ -81,7 +81,7 @@ async fn fetch_project_id(access_token: &str, email: &str, account_id: Option<&s
         .post(format!("{}/v1internal:loadCodeAssist", CLOUD_CODE_BASE_URL))
         .header(reqwest::header::AUTHORIZATION, format!("Bearer {}", access_token))
         .header(reqwest::header::CONTENT_TYPE, "application/json")
-        .header(reqwest::header::USER_AGENT, crate::constants::USER_AGENT.as_str())
+        .header(reqwest::header::USER_AGENT, crate::constants::effective_user_agent())
         .json(&meta)
         .send()
         .await;
@@ -154,7 +154,7 @@ pub async fn fetch_quota_with_cache(
         match client
             .post(url)
             .bearer_auth(access_token)
-            .header(reqwest::header::USER_AGENT, crate::constants::USER_AGENT.as_str())
+            .header(reqwest::header::USER_AGENT, crate::constants::effective_user_agent())
             .json(&json!(payload))
             .send()
             .await
@@ -255,8 +255,10 @@ pub async fn get_valid_token_for_warmup(account: &crate::models::account::Accoun
     // Check and auto-refresh token
     let new_token = crate::modules::oauth::ensure_fresh_token(&account.token, Some(&account.id)).await?;
     
-    // If token changed (meant refreshed), save it
-    if new_token.access_token != account.token.access_token {
+    // If token changed (access or rotated refresh token), save it
+    if new_token.access_token != account.token.access_token
+        || new_token.refresh_token != account.token.refresh_token
+    {
         account.token = new_token;
         if let Err(e) = crate::modules::account::save_account(&account) {
             crate::modules::logger::log_warn(&format!("[Warmup] Failed to save refreshed token: {}", e));
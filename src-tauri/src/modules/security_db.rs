@@ -167,6 +167,28 @@ pub fn init_db() -> Result<(), String> {
     // Migration: Add username column to ip_access_logs
     let _ = conn.execute("ALTER TABLE ip_access_logs ADD COLUMN username TEXT", []);
 
+    // [NEW] Migration: 旧库的黑白名单可能缺少 UNIQUE 约束，先去重再补建唯一索引
+    // (保留每个 ip_pattern 最早的一行，使按模式删除具有确定性)
+    let _ = conn.execute(
+        "DELETE FROM ip_blacklist WHERE rowid NOT IN (SELECT MIN(rowid) FROM ip_blacklist GROUP BY ip_pattern)",
+        [],
+    );
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_blacklist_pattern_unique ON ip_blacklist (ip_pattern)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
+    let _ = conn.execute(
+        "DELETE FROM ip_whitelist WHERE rowid NOT IN (SELECT MIN(rowid) FROM ip_whitelist GROUP BY ip_pattern)",
+        [],
+    );
+    conn.execute(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_whitelist_pattern_unique ON ip_whitelist (ip_pattern)",
+        [],
+    )
+    .map_err(|e| e.to_string())?;
+
     Ok(())
 }
 
@@ -277,6 +299,114 @@ pub fn get_ip_access_logs(
     Ok(logs)
 }
 
+/// [NEW] 查询时间范围内的 IP 访问日志 (闭区间，供导出使用)
+/// 可选沿用 get_ip_access_logs 的过滤条件 (ip_filter / blocked_only)
+pub fn get_ip_access_logs_in_range(
+    from_ts: i64,
+    to_ts: i64,
+    ip_filter: Option<&str>,
+    blocked_only: bool,
+) -> Result<Vec<IpAccessLog>, String> {
+    let conn = connect_db()?;
+
+    let mut sql = String::from(
+        "SELECT id, client_ip, timestamp, method, path, user_agent, status, duration, api_key_hash, blocked, block_reason, username
+         FROM ip_access_logs
+         WHERE timestamp BETWEEN ?1 AND ?2",
+    );
+    if blocked_only {
+        sql.push_str(" AND blocked = 1");
+    }
+    if ip_filter.is_some() {
+        sql.push_str(" AND client_ip LIKE ?3");
+    }
+    sql.push_str(" ORDER BY timestamp ASC");
+
+    let mut stmt = conn.prepare(&sql).map_err(|e| e.to_string())?;
+
+    let map_row = |row: &rusqlite::Row| -> rusqlite::Result<IpAccessLog> {
+        Ok(IpAccessLog {
+            id: row.get(0)?,
+            client_ip: row.get(1)?,
+            timestamp: row.get(2)?,
+            method: row.get(3)?,
+            path: row.get(4)?,
+            user_agent: row.get(5)?,
+            status: row.get(6)?,
+            duration: row.get(7)?,
+            api_key_hash: row.get(8)?,
+            blocked: row.get::<_, i32>(9)? != 0,
+            block_reason: row.get(10)?,
+            username: row.get(11).unwrap_or(None),
+        })
+    };
+
+    let logs_iter = if let Some(ip) = ip_filter {
+        stmt.query_map(params![from_ts, to_ts, format!("%{}%", ip)], map_row)
+            .map_err(|e| e.to_string())?
+    } else {
+        stmt.query_map(params![from_ts, to_ts], map_row)
+            .map_err(|e| e.to_string())?
+    };
+
+    let mut logs = Vec::new();
+    for log in logs_iter {
+        logs.push(log.map_err(|e| e.to_string())?);
+    }
+    Ok(logs)
+}
+
+/// CSV 字段转义：包含分隔符/引号/换行时加引号，内部引号翻倍
+fn csv_escape(field: &str) -> String {
+    if field.contains(',') || field.contains('"') || field.contains('\n') || field.contains('\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// [NEW] 导出时间范围内的 IP 访问日志 (安全审计/SOC 共享)
+/// format: "csv" 或 "json"；返回 (序列化内容, 条数)
+pub fn export_ip_access_logs(
+    from_ts: i64,
+    to_ts: i64,
+    format: &str,
+    ip_filter: Option<&str>,
+    blocked_only: bool,
+) -> Result<(String, usize), String> {
+    let logs = get_ip_access_logs_in_range(from_ts, to_ts, ip_filter, blocked_only)?;
+    let count = logs.len();
+
+    let content = match format.to_ascii_lowercase().as_str() {
+        "json" => serde_json::to_string_pretty(&logs)
+            .map_err(|e| format!("Failed to serialize logs: {}", e))?,
+        "csv" => {
+            let mut out = String::from(
+                "client_ip,timestamp,method,path,status,duration,blocked,block_reason,username,user_agent\n",
+            );
+            for log in &logs {
+                out.push_str(&format!(
+                    "{},{},{},{},{},{},{},{},{},{}\n",
+                    csv_escape(&log.client_ip),
+                    log.timestamp,
+                    csv_escape(log.method.as_deref().unwrap_or("")),
+                    csv_escape(log.path.as_deref().unwrap_or("")),
+                    log.status.map(|s| s.to_string()).unwrap_or_default(),
+                    log.duration.map(|d| d.to_string()).unwrap_or_default(),
+                    log.blocked,
+                    csv_escape(log.block_reason.as_deref().unwrap_or("")),
+                    csv_escape(log.username.as_deref().unwrap_or("")),
+                    csv_escape(log.user_agent.as_deref().unwrap_or("")),
+                ));
+            }
+            out
+        }
+        other => return Err(format!("Unsupported export format: {}", other)),
+    };
+
+    Ok((content, count))
+}
+
 /// 获取 IP 统计概览
 pub fn get_ip_stats() -> Result<IpStats, String> {
     let conn = connect_db()?;
@@ -381,7 +511,7 @@ pub fn cleanup_old_ip_logs(days: i64) -> Result<usize, String> {
 // 黑名单操作
 // ============================================================================
 
-/// 添加 IP 到黑名单
+/// 添加 IP 到黑名单 (按 ip_pattern upsert: 已存在时更新 reason/expires_at 而非插入重复行)
 pub fn add_to_blacklist(
     ip_pattern: &str,
     reason: Option<&str>,
@@ -393,25 +523,52 @@ pub fn add_to_blacklist(
     let id = uuid::Uuid::new_v4().to_string();
     let now = chrono::Utc::now().timestamp();
 
+    // [NEW] 自动封禁条目默认带 TTL，避免永久封禁无限累积；
+    // 手动条目保持原语义：未显式给出 expires_at 即永久
+    let expires_at = match (expires_at, created_by) {
+        (None, "auto") => {
+            let ttl = crate::proxy::config::get_default_blacklist_ttl_secs();
+            if ttl > 0 {
+                Some(now + ttl as i64)
+            } else {
+                None
+            }
+        }
+        (explicit, _) => explicit,
+    };
+
     conn.execute(
         "INSERT INTO ip_blacklist (id, ip_pattern, reason, created_at, expires_at, created_by, hit_count)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0)",
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, 0)
+         ON CONFLICT(ip_pattern) DO UPDATE SET
+             reason = excluded.reason,
+             expires_at = excluded.expires_at,
+             created_by = excluded.created_by",
         params![id, ip_pattern, reason, now, expires_at, created_by],
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(IpBlacklistEntry {
-        id,
-        ip_pattern: ip_pattern.to_string(),
-        reason: reason.map(|s| s.to_string()),
-        created_at: now,
-        expires_at,
-        created_by: created_by.to_string(),
-        hit_count: 0,
-    })
+    // upsert 命中已有行时 id/created_at/hit_count 保持原值，查回真实数据
+    conn.query_row(
+        "SELECT id, ip_pattern, reason, created_at, expires_at, created_by, hit_count
+         FROM ip_blacklist WHERE ip_pattern = ?1",
+        [ip_pattern],
+        |row| {
+            Ok(IpBlacklistEntry {
+                id: row.get(0)?,
+                ip_pattern: row.get(1)?,
+                reason: row.get(2)?,
+                created_at: row.get(3)?,
+                expires_at: row.get(4)?,
+                created_by: row.get(5)?,
+                hit_count: row.get(6)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
 }
 
-/// 从黑名单移除
+/// 从黑名单移除 (按条目 id，不接受 ip_pattern)
 pub fn remove_from_blacklist(id: &str) -> Result<(), String> {
     let conn = connect_db()?;
 
@@ -421,6 +578,21 @@ pub fn remove_from_blacklist(id: &str) -> Result<(), String> {
     Ok(())
 }
 
+/// 从黑名单移除 (按 ip_pattern 精确匹配)
+pub fn remove_from_blacklist_by_pattern(ip_pattern: &str) -> Result<(), String> {
+    let conn = connect_db()?;
+
+    let affected = conn
+        .execute("DELETE FROM ip_blacklist WHERE ip_pattern = ?1", [ip_pattern])
+        .map_err(|e| e.to_string())?;
+
+    if affected == 0 {
+        return Err(format!("IP pattern {} not found in blacklist", ip_pattern));
+    }
+
+    Ok(())
+}
+
 /// 获取黑名单列表
 pub fn get_blacklist() -> Result<Vec<IpBlacklistEntry>, String> {
     let conn = connect_db()?;
@@ -557,7 +729,7 @@ fn cidr_match(ip: &str, cidr: &str) -> bool {
 // 白名单操作
 // ============================================================================
 
-/// 添加 IP 到白名单
+/// 添加 IP 到白名单 (按 ip_pattern upsert: 已存在时更新 description 而非插入重复行)
 pub fn add_to_whitelist(ip_pattern: &str, description: Option<&str>) -> Result<IpWhitelistEntry, String> {
     let conn = connect_db()?;
 
@@ -566,17 +738,28 @@ pub fn add_to_whitelist(ip_pattern: &str, description: Option<&str>) -> Result<I
 
     conn.execute(
         "INSERT INTO ip_whitelist (id, ip_pattern, description, created_at)
-         VALUES (?1, ?2, ?3, ?4)",
+         VALUES (?1, ?2, ?3, ?4)
+         ON CONFLICT(ip_pattern) DO UPDATE SET
+             description = excluded.description",
         params![id, ip_pattern, description, now],
     )
     .map_err(|e| e.to_string())?;
 
-    Ok(IpWhitelistEntry {
-        id,
-        ip_pattern: ip_pattern.to_string(),
-        description: description.map(|s| s.to_string()),
-        created_at: now,
-    })
+    // 同黑名单: upsert 命中已有行时 id/created_at 保持原值
+    conn.query_row(
+        "SELECT id, ip_pattern, description, created_at
+         FROM ip_whitelist WHERE ip_pattern = ?1",
+        [ip_pattern],
+        |row| {
+            Ok(IpWhitelistEntry {
+                id: row.get(0)?,
+                ip_pattern: row.get(1)?,
+                description: row.get(2)?,
+                created_at: row.get(3)?,
+            })
+        },
+    )
+    .map_err(|e| e.to_string())
 }
 
 /// 从白名单移除
@@ -657,6 +840,49 @@ pub fn clear_ip_access_logs() -> Result<(), String> {
     Ok(())
 }
 
+/// [NEW] 从反代请求日志重建 IP 访问日志
+/// 清空 ip_access_logs 后按 proxy_db 日志回放（映射逻辑与 monitor.rs 同步落库一致）
+/// 用于 Security Monitor 表后加入时的数据回填
+pub fn rebuild_ip_logs_from_proxy_logs() -> Result<usize, String> {
+    // 1. Clear existing IP access logs
+    clear_ip_access_logs()?;
+
+    // 2. Get all logs from proxy_db
+    let logs = crate::modules::proxy_db::get_all_logs_for_export()?;
+    let mut count = 0;
+
+    // 3. Replay logs (only entries with a client IP appear in the Security Monitor)
+    for log in logs {
+        if let Some(ip) = &log.client_ip {
+            let security_log = IpAccessLog {
+                id: uuid::Uuid::new_v4().to_string(),
+                client_ip: ip.clone(),
+                timestamp: log.timestamp / 1000, // ms to s
+                method: Some(log.method.clone()),
+                path: Some(log.url.clone()),
+                user_agent: None,
+                status: Some(log.status as i32),
+                duration: Some(log.duration as i64),
+                api_key_hash: None,
+                blocked: false, // 来自 monitor 的日志均已通过 IP 过滤
+                block_reason: None,
+                username: log.username.clone(),
+            };
+
+            if let Err(e) = save_ip_access_log(&security_log) {
+                crate::modules::logger::log_warn(&format!(
+                    "Failed to re-record IP log for {}: {}",
+                    log.id, e
+                ));
+            } else {
+                count += 1;
+            }
+        }
+    }
+
+    Ok(count)
+}
+
 /// 获取 IP 访问日志总数
 pub fn get_ip_access_logs_count(ip_filter: Option<&str>, blocked_only: bool) -> Result<u64, String> {
     let conn = connect_db()?;
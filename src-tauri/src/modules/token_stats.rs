@@ -1,4 +1,6 @@
-use rusqlite::{params, Connection};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::error::{GatewayError, GatewayResult};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
@@ -10,6 +12,12 @@ pub struct TokenStatsAggregated {
     pub total_output_tokens: u64,
     pub total_tokens: u64,
     pub request_count: u64,
+    /// [NEW] Cached input tokens (None when no row in range reported a breakdown)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cached_input_tokens: Option<u64>,
+    /// [NEW] Reasoning tokens (None when no row in range reported a breakdown)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_reasoning_tokens: Option<u64>,
 }
 
 /// Per-account token statistics
@@ -30,6 +38,12 @@ pub struct TokenStatsSummary {
     pub total_tokens: u64,
     pub total_requests: u64,
     pub unique_accounts: u64,
+    /// [NEW] Cached input tokens breakdown (optional, see TokenStatsAggregated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cached_input_tokens: Option<u64>,
+    /// [NEW] Reasoning tokens breakdown (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_reasoning_tokens: Option<u64>,
 }
 
 /// Per-model token statistics
@@ -40,6 +54,12 @@ pub struct ModelTokenStats {
     pub total_output_tokens: u64,
     pub total_tokens: u64,
     pub request_count: u64,
+    /// [NEW] Cached input tokens breakdown (optional, see TokenStatsAggregated)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_cached_input_tokens: Option<u64>,
+    /// [NEW] Reasoning tokens breakdown (optional)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub total_reasoning_tokens: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -55,28 +75,96 @@ pub struct AccountTrendPoint {
     pub account_data: std::collections::HashMap<String, u64>,
 }
 
-pub(crate) fn get_db_path() -> Result<PathBuf, String> {
+pub(crate) fn get_db_path() -> GatewayResult<PathBuf> {
     let data_dir = crate::modules::account::get_data_dir()?;
     Ok(data_dir.join("token_stats.db"))
 }
 
-fn connect_db() -> Result<Connection, String> {
+fn connect_db() -> GatewayResult<Connection> {
     let db_path = get_db_path()?;
-    let conn = Connection::open(db_path).map_err(|e| e.to_string())?;
+    let conn = Connection::open(db_path).map_err(|e| GatewayError::Db(e.to_string()))?;
 
     // Enable WAL mode for better concurrency
     conn.pragma_update(None, "journal_mode", "WAL")
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
     conn.pragma_update(None, "busy_timeout", 5000)
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
     conn.pragma_update(None, "synchronous", "NORMAL")
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     Ok(conn)
 }
 
+/// [NEW] Ordered schema migrations, tracked via PRAGMA user_version.
+/// Entry at index i is applied when upgrading to schema version i+1.
+/// Append-only: never edit or reorder shipped entries.
+const MIGRATIONS: &[&str] = &[
+    // v1: cache/reasoning token breakdown (nullable; rows recorded before the upgrade stay NULL)
+    "ALTER TABLE token_usage ADD COLUMN cached_input_tokens INTEGER;
+     ALTER TABLE token_usage ADD COLUMN reasoning_tokens INTEGER;
+     ALTER TABLE token_stats_hourly ADD COLUMN total_cached_input_tokens INTEGER;
+     ALTER TABLE token_stats_hourly ADD COLUMN total_reasoning_tokens INTEGER;",
+    // v2: normalized model name for stats grouping (raw name stays in `model`)
+    "ALTER TABLE token_usage ADD COLUMN normalized_model TEXT;",
+];
+
+/// [NEW] Apply any pending schema migrations (idempotent, version-gated)
+fn run_migrations(conn: &Connection) -> GatewayResult<()> {
+    let current: i64 = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    for (i, sql) in MIGRATIONS.iter().enumerate() {
+        let version = (i + 1) as i64;
+        if version <= current {
+            continue;
+        }
+        conn.execute_batch(sql).map_err(|e| {
+            GatewayError::Db(format!("token_stats migration v{} failed: {}", version, e))
+        })?;
+        conn.pragma_update(None, "user_version", version)
+            .map_err(|e| GatewayError::Db(e.to_string()))?;
+        tracing::info!("token_stats.db migrated to schema v{}", version);
+    }
+    Ok(())
+}
+
+/// [NEW] 统计写入是否可用。init_db 失败 (权限/磁盘满) 时一次性关闭写入，
+/// 避免之后每个请求重复报错；读取接口不受影响，代理转发照常进行
+static RECORDING_ACTIVE: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(true);
+static RECORDING_DISABLED_REASON: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+
+pub fn is_recording_active() -> bool {
+    RECORDING_ACTIVE.load(std::sync::atomic::Ordering::Relaxed)
+}
+
+/// [NEW] 一次性关闭统计写入并记录原因 (仅告警一次，重复调用无副作用)
+pub fn disable_recording(reason: &str) {
+    if RECORDING_ACTIVE.swap(false, std::sync::atomic::Ordering::Relaxed) {
+        let _ = RECORDING_DISABLED_REASON.set(reason.to_string());
+        crate::modules::logger::log_warn(&format!(
+            "[TokenStats] 统计库不可用，本次运行不再记录用量 (代理转发不受影响): {}",
+            reason
+        ));
+    }
+}
+
+/// [NEW] 统计记录健康状态 (供前端提示统计数据是否在持续更新)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatsHealth {
+    pub recording_active: bool,
+    pub disabled_reason: Option<String>,
+}
+
+pub fn get_stats_health() -> StatsHealth {
+    StatsHealth {
+        recording_active: is_recording_active(),
+        disabled_reason: RECORDING_DISABLED_REASON.get().cloned(),
+    }
+}
+
 /// Initialize the token stats database
-pub fn init_db() -> Result<(), String> {
+pub fn init_db() -> GatewayResult<()> {
     let conn = connect_db()?;
 
     // Create main usage table
@@ -92,20 +180,20 @@ pub fn init_db() -> Result<(), String> {
         )",
         [],
     )
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     // Create indexes for efficient queries
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_token_timestamp ON token_usage (timestamp DESC)",
         [],
     )
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     conn.execute(
         "CREATE INDEX IF NOT EXISTS idx_token_account ON token_usage (account_email)",
         [],
     )
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     // Create hourly aggregation table for fast queries
     conn.execute(
@@ -120,79 +208,255 @@ pub fn init_db() -> Result<(), String> {
         )",
         [],
     )
-    .map_err(|e| e.to_string())?;
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    // [NEW] Small key/value metadata table (e.g. incremental rebuild watermark)
+    conn.execute(
+        "CREATE TABLE IF NOT EXISTS stats_meta (
+            key TEXT PRIMARY KEY,
+            value TEXT NOT NULL
+        )",
+        [],
+    )
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    // [NEW] Apply versioned migrations on top of the baseline schema
+    run_migrations(&conn)?;
 
     Ok(())
 }
 
-/// Record token usage from a request
-pub fn record_usage(
+/// [NEW] Watermark key: max proxy log timestamp (ms) already replayed into stats
+const META_LAST_IMPORTED_LOG_TS: &str = "last_imported_log_ts";
+
+fn get_meta_i64(conn: &Connection, key: &str) -> GatewayResult<Option<i64>> {
+    let value: Option<String> = conn
+        .query_row(
+            "SELECT value FROM stats_meta WHERE key = ?1",
+            [key],
+            |row| row.get(0),
+        )
+        .optional()
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+    Ok(value.and_then(|v| v.parse().ok()))
+}
+
+fn set_meta_i64(conn: &Connection, key: &str, value: i64) -> GatewayResult<()> {
+    conn.execute(
+        "INSERT INTO stats_meta (key, value) VALUES (?1, ?2)
+         ON CONFLICT(key) DO UPDATE SET value = ?2",
+        params![key, value.to_string()],
+    )
+    .map_err(|e| GatewayError::Db(e.to_string()))?;
+    Ok(())
+}
+
+/// [NEW] 统计用模型名归一化：把带日期/厂商前缀的变体折叠成基础名
+///
+/// 优先级：用户规则 (精确 > 通配符，来自 `proxy.stats_model_normalization`) > 内置规则。
+/// 内置规则：去掉 `provider/` 前缀 (`openai/gpt-4o` -> `gpt-4o`)，
+/// 去掉尾部日期后缀 (`gpt-4o-2024-08-06` / `gpt-4o-20240806` -> `gpt-4o`)。
+/// 原始模型名始终原样保留在 `token_usage.model` 列。
+pub fn normalize_model_name(raw: &str) -> String {
+    let rules = crate::proxy::config::get_stats_model_normalization();
+
+    // 1. 用户精确规则
+    if let Some(target) = rules.get(raw) {
+        return target.clone();
+    }
+
+    // 2. 用户通配符规则 (与模型路由相同的匹配语义)
+    for (pattern, target) in rules.iter() {
+        if pattern.contains('*')
+            && crate::proxy::common::model_mapping::wildcard_match(pattern, raw)
+        {
+            return target.clone();
+        }
+    }
+
+    // 3. 内置：去掉厂商前缀 (只取最后一个 '/' 之后的部分)
+    let base = raw.rsplit('/').next().unwrap_or(raw);
+
+    // 4. 内置：去掉尾部日期后缀 -YYYY-MM-DD 或 -YYYYMMDD
+    strip_date_suffix(base).to_string()
+}
+
+/// [NEW] 去掉模型名尾部的日期后缀 (-2024-08-06 / -20240806)，不匹配则原样返回
+fn strip_date_suffix(name: &str) -> &str {
+    // 非 ASCII 名称不做切分 (避免字节索引落在字符中间)
+    if !name.is_ascii() {
+        return name;
+    }
+    let is_digits = |s: &str| !s.is_empty() && s.chars().all(|c| c.is_ascii_digit());
+
+    // -YYYYMMDD
+    if name.len() > 9 {
+        let (head, tail) = name.split_at(name.len() - 9);
+        if let Some(digits) = tail.strip_prefix('-') {
+            if digits.len() == 8 && is_digits(digits) {
+                return head;
+            }
+        }
+    }
+
+    // -YYYY-MM-DD
+    if name.len() > 11 {
+        let (head, tail) = name.split_at(name.len() - 11);
+        if let Some(rest) = tail.strip_prefix('-') {
+            let parts: Vec<&str> = rest.split('-').collect();
+            if parts.len() == 3
+                && parts[0].len() == 4
+                && parts[1].len() == 2
+                && parts[2].len() == 2
+                && parts.iter().all(|p| is_digits(p))
+            {
+                return head;
+            }
+        }
+    }
+
+    name
+}
+
+/// [NEW] Record token usage including the cache/reasoning breakdown newer models report
+pub fn record_usage_detailed(
     account_email: &str,
     model: &str,
     input_tokens: u32,
     output_tokens: u32,
-) -> Result<(), String> {
-    record_usage_with_time(
+    cached_input_tokens: Option<u32>,
+    reasoning_tokens: Option<u32>,
+) -> GatewayResult<()> {
+    record_usage_detailed_with_time(
         account_email,
         model,
         input_tokens,
         output_tokens,
+        cached_input_tokens,
+        reasoning_tokens,
         chrono::Utc::now().timestamp(),
     )
 }
 
-/// Record token usage with specific timestamp (for backfill/migration)
-pub fn record_usage_with_time(
+/// [NEW] Core recording path: raw row + hourly aggregate, with optional breakdown columns
+#[allow(clippy::too_many_arguments)]
+pub fn record_usage_detailed_with_time(
     account_email: &str,
     model: &str,
     input_tokens: u32,
     output_tokens: u32,
+    cached_input_tokens: Option<u32>,
+    reasoning_tokens: Option<u32>,
     timestamp: i64,
-) -> Result<(), String> {
+) -> GatewayResult<()> {
+    // [NEW] 统计库初始化失败时静默跳过，避免每个请求都报错
+    if !is_recording_active() {
+        return Ok(());
+    }
+
     let conn = connect_db()?;
     let total_tokens = input_tokens + output_tokens;
+    // [NEW] 归一化名仅用于分组统计，原始模型名仍存 model 列
+    let normalized_model = normalize_model_name(model);
 
     // Insert into raw usage table
     conn.execute(
-        "INSERT INTO token_usage (timestamp, account_email, model, input_tokens, output_tokens, total_tokens)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-        params![timestamp, account_email, model, input_tokens, output_tokens, total_tokens],
-    ).map_err(|e| e.to_string())?;
+        "INSERT INTO token_usage (timestamp, account_email, model, input_tokens, output_tokens, total_tokens, cached_input_tokens, reasoning_tokens, normalized_model)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+        params![timestamp, account_email, model, input_tokens, output_tokens, total_tokens, cached_input_tokens, reasoning_tokens, normalized_model],
+    ).map_err(|e| GatewayError::Db(e.to_string()))?;
 
     // Use the provided timestamp for bucket calculation
-    let dt =
-        chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp, 0).ok_or("Invalid timestamp")?;
+    let dt = chrono::DateTime::<chrono::Utc>::from_timestamp(timestamp, 0)
+        .ok_or_else(|| GatewayError::Parse("Invalid timestamp".to_string()))?;
     let hour_bucket = dt.format("%Y-%m-%d %H:00").to_string();
 
     conn.execute(
-        "INSERT INTO token_stats_hourly (hour_bucket, account_email, total_input_tokens, total_output_tokens, total_tokens, request_count)
-         VALUES (?1, ?2, ?3, ?4, ?5, 1)
+        "INSERT INTO token_stats_hourly (hour_bucket, account_email, total_input_tokens, total_output_tokens, total_tokens, request_count, total_cached_input_tokens, total_reasoning_tokens)
+         VALUES (?1, ?2, ?3, ?4, ?5, 1, ?6, ?7)
          ON CONFLICT(hour_bucket, account_email) DO UPDATE SET
             total_input_tokens = total_input_tokens + ?3,
             total_output_tokens = total_output_tokens + ?4,
             total_tokens = total_tokens + ?5,
-            request_count = request_count + 1",
-        params![hour_bucket, account_email, input_tokens, output_tokens, total_tokens],
-    ).map_err(|e| e.to_string())?;
+            request_count = request_count + 1,
+            total_cached_input_tokens = COALESCE(total_cached_input_tokens, 0) + COALESCE(?6, 0),
+            total_reasoning_tokens = COALESCE(total_reasoning_tokens, 0) + COALESCE(?7, 0)",
+        params![hour_bucket, account_email, input_tokens, output_tokens, total_tokens, cached_input_tokens, reasoning_tokens],
+    ).map_err(|e| GatewayError::Db(e.to_string()))?;
 
     Ok(())
 }
 
+/// [NEW] 从 token_usage 原始记录重建 token_stats_hourly 聚合表。
+/// 与 rebuild_from_logs 不同：不依赖反代日志，单条 GROUP BY 即可修复
+/// 聚合漂移 (如手工插入原始记录或聚合写入半途失败)。返回重建的桶数
+pub fn recompute_hourly_aggregates() -> GatewayResult<usize> {
+    let mut conn = connect_db()?;
+
+    // 事务内先清空再重建，避免期间的查询读到空表
+    let tx = conn
+        .transaction()
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    tx.execute("DELETE FROM token_stats_hourly", [])
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let rebuilt = tx
+        .execute(
+            "INSERT INTO token_stats_hourly (hour_bucket, account_email, total_input_tokens, total_output_tokens, total_tokens, request_count, total_cached_input_tokens, total_reasoning_tokens)
+             SELECT strftime('%Y-%m-%d %H:00', timestamp, 'unixepoch') as hour_bucket,
+                    account_email,
+                    SUM(input_tokens),
+                    SUM(output_tokens),
+                    SUM(total_tokens),
+                    COUNT(*),
+                    SUM(cached_input_tokens),
+                    SUM(reasoning_tokens)
+             FROM token_usage
+             GROUP BY hour_bucket, account_email",
+            [],
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    tx.commit().map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    crate::modules::logger::log_info(&format!(
+        "📊 [Stats] Recomputed {} hourly bucket(s) from raw token_usage",
+        rebuilt
+    ));
+
+    Ok(rebuilt)
+}
+
 /// Rebuild token stats from proxy logs
-pub fn rebuild_from_logs() -> Result<usize, String> {
-    // 1. Clear existing stats
+///
+/// [NEW] `full = true` 为原有行为：清空统计后全量重放所有日志。
+/// `full = false` 为增量模式：只重放水位线（上次已导入的最大日志时间戳）之后的新日志，
+/// 不清空现有数据，适合周期性补账而不产生统计空窗。
+pub fn rebuild_from_logs(full: bool) -> GatewayResult<usize> {
     let conn = connect_db()?;
-    conn.execute("DELETE FROM token_usage", [])
-        .map_err(|e| e.to_string())?;
-    conn.execute("DELETE FROM token_stats_hourly", [])
-        .map_err(|e| e.to_string())?;
 
-    // 2. Get all logs from proxy_db
-    let logs = crate::modules::proxy_db::get_all_logs_for_export()?;
+    let logs = if full {
+        // 1. Clear existing stats
+        conn.execute("DELETE FROM token_usage", [])
+            .map_err(|e| GatewayError::Db(e.to_string()))?;
+        conn.execute("DELETE FROM token_stats_hourly", [])
+            .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+        // 2. Get all logs from proxy_db
+        crate::modules::proxy_db::get_all_logs_for_export()?
+    } else {
+        let watermark = get_meta_i64(&conn, META_LAST_IMPORTED_LOG_TS)?.unwrap_or(0);
+        crate::modules::proxy_db::get_logs_for_export_after(watermark)?
+    };
+
     let mut count = 0;
+    let mut max_ts_ms: Option<i64> = None;
 
     // 3. Replay logs
     for log in logs {
+        max_ts_ms = Some(max_ts_ms.map_or(log.timestamp, |m: i64| m.max(log.timestamp)));
+
         if let (Some(account), Some(input), Some(output)) =
             (log.account_email, log.input_tokens, log.output_tokens)
         {
@@ -200,7 +464,9 @@ pub fn rebuild_from_logs() -> Result<usize, String> {
             let timestamp = log.timestamp / 1000;
             let model = log.model.unwrap_or_else(|| "unknown".to_string());
 
-            if let Err(e) = record_usage_with_time(&account, &model, input, output, timestamp) {
+            if let Err(e) =
+                record_usage_detailed_with_time(&account, &model, input, output, None, None, timestamp)
+            {
                 crate::modules::logger::log_warn(&format!(
                     "Failed to re-record log {}: {}",
                     log.id, e
@@ -211,28 +477,36 @@ pub fn rebuild_from_logs() -> Result<usize, String> {
         }
     }
 
+    // 4. Advance the watermark so the next incremental run skips what we just replayed
+    // （包括无 token 信息的日志，它们重放也不会产生数据）
+    if let Some(max_ts) = max_ts_ms {
+        set_meta_i64(&conn, META_LAST_IMPORTED_LOG_TS, max_ts)?;
+    }
+
     Ok(count)
 }
 
 /// Get hourly aggregated stats for a time range
-pub fn get_hourly_stats(hours: i64) -> Result<Vec<TokenStatsAggregated>, String> {
+pub fn get_hourly_stats(hours: i64) -> GatewayResult<Vec<TokenStatsAggregated>> {
     let conn = connect_db()?;
     let cutoff = chrono::Utc::now() - chrono::Duration::hours(hours);
     let cutoff_bucket = cutoff.format("%Y-%m-%d %H:00").to_string();
 
     let mut stmt = conn
         .prepare(
-            "SELECT hour_bucket, 
-                SUM(total_input_tokens) as input, 
+            "SELECT hour_bucket,
+                SUM(total_input_tokens) as input,
                 SUM(total_output_tokens) as output,
                 SUM(total_tokens) as total,
-                SUM(request_count) as count
-         FROM token_stats_hourly 
+                SUM(request_count) as count,
+                SUM(total_cached_input_tokens) as cached,
+                SUM(total_reasoning_tokens) as reasoning
+         FROM token_stats_hourly
          WHERE hour_bucket >= ?1
          GROUP BY hour_bucket
          ORDER BY hour_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let rows = stmt
         .query_map([cutoff_bucket], |row| {
@@ -242,36 +516,40 @@ pub fn get_hourly_stats(hours: i64) -> Result<Vec<TokenStatsAggregated>, String>
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cached_input_tokens: row.get(5)?,
+                total_reasoning_tokens: row.get(6)?,
             })
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut result = Vec::new();
     for row in rows {
-        result.push(row.map_err(|e| e.to_string())?);
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
     }
     Ok(result)
 }
 
 /// Get daily aggregated stats for a time range
-pub fn get_daily_stats(days: i64) -> Result<Vec<TokenStatsAggregated>, String> {
+pub fn get_daily_stats(days: i64) -> GatewayResult<Vec<TokenStatsAggregated>> {
     let conn = connect_db()?;
     let cutoff = chrono::Utc::now() - chrono::Duration::days(days);
     let cutoff_bucket = cutoff.format("%Y-%m-%d").to_string();
 
     let mut stmt = conn
         .prepare(
-            "SELECT substr(hour_bucket, 1, 10) as day_bucket, 
-                SUM(total_input_tokens) as input, 
+            "SELECT substr(hour_bucket, 1, 10) as day_bucket,
+                SUM(total_input_tokens) as input,
                 SUM(total_output_tokens) as output,
                 SUM(total_tokens) as total,
-                SUM(request_count) as count
-         FROM token_stats_hourly 
+                SUM(request_count) as count,
+                SUM(total_cached_input_tokens) as cached,
+                SUM(total_reasoning_tokens) as reasoning
+         FROM token_stats_hourly
          WHERE substr(hour_bucket, 1, 10) >= ?1
          GROUP BY day_bucket
          ORDER BY day_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let rows = stmt
         .query_map([cutoff_bucket], |row| {
@@ -281,19 +559,21 @@ pub fn get_daily_stats(days: i64) -> Result<Vec<TokenStatsAggregated>, String> {
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cached_input_tokens: row.get(5)?,
+                total_reasoning_tokens: row.get(6)?,
             })
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut result = Vec::new();
     for row in rows {
-        result.push(row.map_err(|e| e.to_string())?);
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
     }
     Ok(result)
 }
 
 /// Get weekly aggregated stats
-pub fn get_weekly_stats(weeks: i64) -> Result<Vec<TokenStatsAggregated>, String> {
+pub fn get_weekly_stats(weeks: i64) -> GatewayResult<Vec<TokenStatsAggregated>> {
     let conn = connect_db()?;
     let cutoff = chrono::Utc::now() - chrono::Duration::weeks(weeks);
     let cutoff_timestamp = cutoff.timestamp();
@@ -301,16 +581,18 @@ pub fn get_weekly_stats(weeks: i64) -> Result<Vec<TokenStatsAggregated>, String>
     let mut stmt = conn
         .prepare(
             "SELECT strftime('%Y-W%W', datetime(timestamp, 'unixepoch')) as week_bucket,
-                SUM(input_tokens) as input, 
+                SUM(input_tokens) as input,
                 SUM(output_tokens) as output,
                 SUM(total_tokens) as total,
-                COUNT(*) as count
-         FROM token_usage 
+                COUNT(*) as count,
+                SUM(cached_input_tokens) as cached,
+                SUM(reasoning_tokens) as reasoning
+         FROM token_usage
          WHERE timestamp >= ?1
          GROUP BY week_bucket
          ORDER BY week_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let rows = stmt
         .query_map([cutoff_timestamp], |row| {
@@ -320,19 +602,21 @@ pub fn get_weekly_stats(weeks: i64) -> Result<Vec<TokenStatsAggregated>, String>
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cached_input_tokens: row.get(5)?,
+                total_reasoning_tokens: row.get(6)?,
             })
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut result = Vec::new();
     for row in rows {
-        result.push(row.map_err(|e| e.to_string())?);
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
     }
     Ok(result)
 }
 
 /// Get per-account statistics for a time range
-pub fn get_account_stats(hours: i64) -> Result<Vec<AccountTokenStats>, String> {
+pub fn get_account_stats(hours: i64) -> GatewayResult<Vec<AccountTokenStats>> {
     let conn = connect_db()?;
     let cutoff = chrono::Utc::now() - chrono::Duration::hours(hours);
     let cutoff_bucket = cutoff.format("%Y-%m-%d %H:00").to_string();
@@ -349,7 +633,7 @@ pub fn get_account_stats(hours: i64) -> Result<Vec<AccountTokenStats>, String> {
          GROUP BY account_email
          ORDER BY total DESC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let rows = stmt
         .query_map([cutoff_bucket], |row| {
@@ -361,33 +645,107 @@ pub fn get_account_stats(hours: i64) -> Result<Vec<AccountTokenStats>, String> {
                 request_count: row.get(4)?,
             })
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
+    }
+    Ok(result)
+}
+
+/// Get per-account statistics limited to a set of emails (account group slicing)
+pub fn get_account_stats_filtered(
+    hours: i64,
+    emails: &[String],
+) -> GatewayResult<Vec<AccountTokenStats>> {
+    if emails.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let conn = connect_db()?;
+    let cutoff = chrono::Utc::now() - chrono::Duration::hours(hours);
+    let cutoff_bucket = cutoff.format("%Y-%m-%d %H:00").to_string();
+
+    // 动态拼接 IN 占位符 (?2, ?3, ...)
+    let placeholders: Vec<String> = (0..emails.len()).map(|i| format!("?{}", i + 2)).collect();
+    let sql = format!(
+        "SELECT account_email,
+                SUM(total_input_tokens) as input,
+                SUM(total_output_tokens) as output,
+                SUM(total_tokens) as total,
+                SUM(request_count) as count
+         FROM token_stats_hourly
+         WHERE hour_bucket >= ?1 AND account_email IN ({})
+         GROUP BY account_email
+         ORDER BY total DESC",
+        placeholders.join(", ")
+    );
+
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let mut params: Vec<&dyn rusqlite::ToSql> = vec![&cutoff_bucket];
+    for email in emails {
+        params.push(email);
+    }
+
+    let rows = stmt
+        .query_map(params.as_slice(), |row| {
+            Ok(AccountTokenStats {
+                account_email: row.get(0)?,
+                total_input_tokens: row.get(1)?,
+                total_output_tokens: row.get(2)?,
+                total_tokens: row.get(3)?,
+                request_count: row.get(4)?,
+            })
+        })
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut result = Vec::new();
     for row in rows {
-        result.push(row.map_err(|e| e.to_string())?);
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
     }
     Ok(result)
 }
 
 /// Get summary statistics for a time range
-pub fn get_summary_stats(hours: i64) -> Result<TokenStatsSummary, String> {
+pub fn get_summary_stats(hours: i64) -> GatewayResult<TokenStatsSummary> {
     let conn = connect_db()?;
     let cutoff = chrono::Utc::now() - chrono::Duration::hours(hours);
     let cutoff_bucket = cutoff.format("%Y-%m-%d %H:00").to_string();
 
-    let (total_input, total_output, total, requests): (u64, u64, u64, u64) = conn
+    let (total_input, total_output, total, requests, cached, reasoning): (
+        u64,
+        u64,
+        u64,
+        u64,
+        Option<u64>,
+        Option<u64>,
+    ) = conn
         .query_row(
             "SELECT COALESCE(SUM(total_input_tokens), 0),
                 COALESCE(SUM(total_output_tokens), 0),
                 COALESCE(SUM(total_tokens), 0),
-                COALESCE(SUM(request_count), 0)
-         FROM token_stats_hourly 
+                COALESCE(SUM(request_count), 0),
+                SUM(total_cached_input_tokens),
+                SUM(total_reasoning_tokens)
+         FROM token_stats_hourly
          WHERE hour_bucket >= ?1",
             [&cutoff_bucket],
-            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            },
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let unique_accounts: u64 = conn
         .query_row(
@@ -395,7 +753,7 @@ pub fn get_summary_stats(hours: i64) -> Result<TokenStatsSummary, String> {
             [&cutoff_bucket],
             |row| row.get(0),
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     Ok(TokenStatsSummary {
         total_input_tokens: total_input,
@@ -403,47 +761,251 @@ pub fn get_summary_stats(hours: i64) -> Result<TokenStatsSummary, String> {
         total_tokens: total,
         total_requests: requests,
         unique_accounts,
+        total_cached_input_tokens: cached,
+        total_reasoning_tokens: reasoning,
     })
 }
 
-pub fn get_model_stats(hours: i64) -> Result<Vec<ModelTokenStats>, String> {
+/// [NEW] Per-account statistics for an explicit [from_ts, to_ts] range (unix seconds, inclusive)
+/// Queries the raw token_usage table so arbitrary boundaries are exact, not hour-bucket aligned
+pub fn get_account_stats_range(from_ts: i64, to_ts: i64) -> GatewayResult<Vec<AccountTokenStats>> {
     let conn = connect_db()?;
-    let cutoff = chrono::Utc::now().timestamp() - (hours * 3600);
 
     let mut stmt = conn
         .prepare(
-            "SELECT model,
-                SUM(input_tokens) as input,
-                SUM(output_tokens) as output,
-                SUM(total_tokens) as total,
+            "SELECT account_email,
+                COALESCE(SUM(input_tokens), 0) as input,
+                COALESCE(SUM(output_tokens), 0) as output,
+                COALESCE(SUM(total_tokens), 0) as total,
                 COUNT(*) as count
          FROM token_usage
-         WHERE timestamp >= ?1
-         GROUP BY model
+         WHERE timestamp BETWEEN ?1 AND ?2
+         GROUP BY account_email
          ORDER BY total DESC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let rows = stmt
-        .query_map([cutoff], |row| {
+        .query_map(params![from_ts, to_ts], |row| {
+            Ok(AccountTokenStats {
+                account_email: row.get(0)?,
+                total_input_tokens: row.get(1)?,
+                total_output_tokens: row.get(2)?,
+                total_tokens: row.get(3)?,
+                request_count: row.get(4)?,
+            })
+        })
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
+    }
+    Ok(result)
+}
+
+/// [NEW] Summary statistics for an explicit [from_ts, to_ts] range (unix seconds, inclusive)
+pub fn get_summary_stats_range(from_ts: i64, to_ts: i64) -> GatewayResult<TokenStatsSummary> {
+    let conn = connect_db()?;
+
+    let (total_input, total_output, total, requests, cached, reasoning): (
+        u64,
+        u64,
+        u64,
+        u64,
+        Option<u64>,
+        Option<u64>,
+    ) = conn
+        .query_row(
+            "SELECT COALESCE(SUM(input_tokens), 0),
+                COALESCE(SUM(output_tokens), 0),
+                COALESCE(SUM(total_tokens), 0),
+                COUNT(*),
+                SUM(cached_input_tokens),
+                SUM(reasoning_tokens)
+         FROM token_usage
+         WHERE timestamp BETWEEN ?1 AND ?2",
+            params![from_ts, to_ts],
+            |row| {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            },
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let unique_accounts: u64 = conn
+        .query_row(
+            "SELECT COUNT(DISTINCT account_email) FROM token_usage WHERE timestamp BETWEEN ?1 AND ?2",
+            params![from_ts, to_ts],
+            |row| row.get(0),
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    Ok(TokenStatsSummary {
+        total_input_tokens: total_input,
+        total_output_tokens: total_output,
+        total_tokens: total,
+        total_requests: requests,
+        unique_accounts,
+        total_cached_input_tokens: cached,
+        total_reasoning_tokens: reasoning,
+    })
+}
+
+/// [NEW] 模型统计排序维度 (SQL 侧排序，避免前端对大结果集重排)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSort {
+    /// 按总 Token 数降序 (原有行为)
+    #[default]
+    Total,
+    /// 按输入 Token 数降序
+    Input,
+    /// 按输出 Token 数降序
+    Output,
+    /// 按请求次数降序
+    Requests,
+}
+
+impl ModelSort {
+    /// 对应的 ORDER BY 列 (均为查询内定义的聚合别名，非用户输入)
+    fn order_column(self) -> &'static str {
+        match self {
+            ModelSort::Total => "total",
+            ModelSort::Input => "input",
+            ModelSort::Output => "output",
+            ModelSort::Requests => "count",
+        }
+    }
+}
+
+pub fn get_model_stats(hours: i64) -> GatewayResult<Vec<ModelTokenStats>> {
+    let now = chrono::Utc::now().timestamp();
+    get_model_stats_ex(now - (hours * 3600), now, ModelSort::Total)
+}
+
+/// [NEW] 按显式时间范围 (闭区间, unix 秒) 查询模型统计，并指定排序维度
+pub fn get_model_stats_ex(
+    from_ts: i64,
+    to_ts: i64,
+    sort_by: ModelSort,
+) -> GatewayResult<Vec<ModelTokenStats>> {
+    let conn = connect_db()?;
+
+    let sql = format!(
+        "SELECT COALESCE(normalized_model, model) as model,
+                SUM(input_tokens) as input,
+                SUM(output_tokens) as output,
+                SUM(total_tokens) as total,
+                COUNT(*) as count,
+                SUM(cached_input_tokens) as cached,
+                SUM(reasoning_tokens) as reasoning
+         FROM token_usage
+         WHERE timestamp >= ?1 AND timestamp <= ?2
+         GROUP BY COALESCE(normalized_model, model)
+         ORDER BY {} DESC",
+        sort_by.order_column()
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let rows = stmt
+        .query_map(params![from_ts, to_ts], |row| {
             Ok(ModelTokenStats {
                 model: row.get(0)?,
                 total_input_tokens: row.get(1)?,
                 total_output_tokens: row.get(2)?,
                 total_tokens: row.get(3)?,
                 request_count: row.get(4)?,
+                total_cached_input_tokens: row.get(5)?,
+                total_reasoning_tokens: row.get(6)?,
+            })
+        })
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let mut result = Vec::new();
+    for row in rows {
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
+    }
+    Ok(result)
+}
+
+/// [NEW] 模型全时段统计 (不限时间窗)：总量、请求数与首次/末次使用时间，
+/// 用于容量规划与识别已弃用的模型
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelLifetimeStats {
+    pub model: String,
+    pub total_input_tokens: u64,
+    pub total_output_tokens: u64,
+    pub total_tokens: u64,
+    pub request_count: u64,
+    /// 首次出现时间 (unix 秒)
+    pub first_seen: i64,
+    /// 最后一次出现时间 (unix 秒)
+    pub last_seen: i64,
+}
+
+/// [NEW] 聚合全部 token_usage 按模型分组，按总量降序
+pub fn get_model_lifetime_stats() -> GatewayResult<Vec<ModelLifetimeStats>> {
+    let conn = connect_db()?;
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT COALESCE(normalized_model, model) as model,
+                    SUM(input_tokens) as input,
+                    SUM(output_tokens) as output,
+                    SUM(total_tokens) as total,
+                    COUNT(*) as count,
+                    MIN(timestamp) as first_seen,
+                    MAX(timestamp) as last_seen
+             FROM token_usage
+             GROUP BY COALESCE(normalized_model, model)
+             ORDER BY total DESC",
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let rows = stmt
+        .query_map([], |row| {
+            Ok(ModelLifetimeStats {
+                model: row.get(0)?,
+                total_input_tokens: row.get(1)?,
+                total_output_tokens: row.get(2)?,
+                total_tokens: row.get(3)?,
+                request_count: row.get(4)?,
+                first_seen: row.get(5)?,
+                last_seen: row.get(6)?,
             })
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut result = Vec::new();
     for row in rows {
-        result.push(row.map_err(|e| e.to_string())?);
+        result.push(row.map_err(|e| GatewayError::Db(e.to_string()))?);
     }
     Ok(result)
 }
 
-pub fn get_model_trend_hourly(hours: i64) -> Result<Vec<ModelTrendPoint>, String> {
+/// [NEW] 指定模型自某时间点以来的 Token 总量 (用于日度消费上限检查)
+pub fn get_model_usage_since(model: &str, since_ts: i64) -> GatewayResult<u64> {
+    let conn = connect_db()?;
+    conn.query_row(
+        "SELECT COALESCE(SUM(total_tokens), 0) FROM token_usage
+         WHERE model = ?1 AND timestamp >= ?2",
+        params![model, since_ts],
+        |row| row.get::<_, i64>(0),
+    )
+    .map(|total| total.max(0) as u64)
+    .map_err(|e| GatewayError::Db(e.to_string()))
+}
+
+pub fn get_model_trend_hourly(hours: i64) -> GatewayResult<Vec<ModelTrendPoint>> {
     let conn = connect_db()?;
     let now = chrono::Utc::now();
     let cutoff = now.timestamp() - (hours * 3600);
@@ -452,14 +1014,14 @@ pub fn get_model_trend_hourly(hours: i64) -> Result<Vec<ModelTrendPoint>, String
     let mut stmt = conn
         .prepare(
             "SELECT strftime('%Y-%m-%d %H:00', datetime(timestamp, 'unixepoch')) as hour_bucket,
-                model,
+                COALESCE(normalized_model, model) as model,
                 SUM(total_tokens) as total
          FROM token_usage
          WHERE timestamp >= ?1
-         GROUP BY hour_bucket, model
+         GROUP BY hour_bucket, COALESCE(normalized_model, model)
          ORDER BY hour_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut trend_map: std::collections::BTreeMap<String, std::collections::HashMap<String, u64>> =
         std::collections::BTreeMap::new();
@@ -472,10 +1034,10 @@ pub fn get_model_trend_hourly(hours: i64) -> Result<Vec<ModelTrendPoint>, String
                 row.get::<_, u64>(2)?,
             ))
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     for row in rows {
-        let (period, model, total) = row.map_err(|e| e.to_string())?;
+        let (period, model, total) = row.map_err(|e| GatewayError::Db(e.to_string()))?;
         trend_map.entry(period).or_default().insert(model, total);
     }
 
@@ -492,7 +1054,7 @@ pub fn get_model_trend_hourly(hours: i64) -> Result<Vec<ModelTrendPoint>, String
     Ok(result)
 }
 
-pub fn get_model_trend_minute(minutes: i64) -> Result<Vec<ModelTrendPoint>, String> {
+pub fn get_model_trend_minute(minutes: i64) -> GatewayResult<Vec<ModelTrendPoint>> {
     let conn = connect_db()?;
     let now = chrono::Utc::now();
     let cutoff = now.timestamp() - (minutes * 60);
@@ -501,14 +1063,14 @@ pub fn get_model_trend_minute(minutes: i64) -> Result<Vec<ModelTrendPoint>, Stri
     let mut stmt = conn
         .prepare(
             "SELECT strftime('%Y-%m-%d %H:%M', datetime(timestamp, 'unixepoch')) as minute_bucket,
-                model,
+                COALESCE(normalized_model, model) as model,
                 SUM(total_tokens) as total
          FROM token_usage
          WHERE timestamp >= ?1
-         GROUP BY minute_bucket, model
+         GROUP BY minute_bucket, COALESCE(normalized_model, model)
          ORDER BY minute_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut trend_map: std::collections::BTreeMap<String, std::collections::HashMap<String, u64>> =
         std::collections::BTreeMap::new();
@@ -521,10 +1083,10 @@ pub fn get_model_trend_minute(minutes: i64) -> Result<Vec<ModelTrendPoint>, Stri
                 row.get::<_, u64>(2)?,
             ))
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     for row in rows {
-        let (period, model, total) = row.map_err(|e| e.to_string())?;
+        let (period, model, total) = row.map_err(|e| GatewayError::Db(e.to_string()))?;
         trend_map.entry(period).or_default().insert(model, total);
     }
 
@@ -541,7 +1103,7 @@ pub fn get_model_trend_minute(minutes: i64) -> Result<Vec<ModelTrendPoint>, Stri
     Ok(result)
 }
 
-pub fn get_model_trend_daily(days: i64) -> Result<Vec<ModelTrendPoint>, String> {
+pub fn get_model_trend_daily(days: i64) -> GatewayResult<Vec<ModelTrendPoint>> {
     let conn = connect_db()?;
     let now = chrono::Utc::now();
     let cutoff = now.timestamp() - (days * 24 * 3600);
@@ -550,14 +1112,14 @@ pub fn get_model_trend_daily(days: i64) -> Result<Vec<ModelTrendPoint>, String>
     let mut stmt = conn
         .prepare(
             "SELECT strftime('%Y-%m-%d', datetime(timestamp, 'unixepoch')) as day_bucket,
-                model,
+                COALESCE(normalized_model, model) as model,
                 SUM(total_tokens) as total
          FROM token_usage
          WHERE timestamp >= ?1
-         GROUP BY day_bucket, model
+         GROUP BY day_bucket, COALESCE(normalized_model, model)
          ORDER BY day_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut trend_map: std::collections::BTreeMap<String, std::collections::HashMap<String, u64>> =
         std::collections::BTreeMap::new();
@@ -570,10 +1132,10 @@ pub fn get_model_trend_daily(days: i64) -> Result<Vec<ModelTrendPoint>, String>
                 row.get::<_, u64>(2)?,
             ))
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     for row in rows {
-        let (period, model, total) = row.map_err(|e| e.to_string())?;
+        let (period, model, total) = row.map_err(|e| GatewayError::Db(e.to_string()))?;
         trend_map.entry(period).or_default().insert(model, total);
     }
 
@@ -590,7 +1152,7 @@ pub fn get_model_trend_daily(days: i64) -> Result<Vec<ModelTrendPoint>, String>
     Ok(result)
 }
 
-pub fn get_account_trend_hourly(hours: i64) -> Result<Vec<AccountTrendPoint>, String> {
+pub fn get_account_trend_hourly(hours: i64) -> GatewayResult<Vec<AccountTrendPoint>> {
     let conn = connect_db()?;
     let cutoff = chrono::Utc::now().timestamp() - (hours * 3600);
 
@@ -604,7 +1166,7 @@ pub fn get_account_trend_hourly(hours: i64) -> Result<Vec<AccountTrendPoint>, St
          GROUP BY hour_bucket, account_email
          ORDER BY hour_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut trend_map: std::collections::BTreeMap<String, std::collections::HashMap<String, u64>> =
         std::collections::BTreeMap::new();
@@ -617,10 +1179,10 @@ pub fn get_account_trend_hourly(hours: i64) -> Result<Vec<AccountTrendPoint>, St
                 row.get::<_, u64>(2)?,
             ))
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     for row in rows {
-        let (period, account, total) = row.map_err(|e| e.to_string())?;
+        let (period, account, total) = row.map_err(|e| GatewayError::Db(e.to_string()))?;
         trend_map.entry(period).or_default().insert(account, total);
     }
 
@@ -633,7 +1195,7 @@ pub fn get_account_trend_hourly(hours: i64) -> Result<Vec<AccountTrendPoint>, St
         .collect())
 }
 
-pub fn get_account_trend_daily(days: i64) -> Result<Vec<AccountTrendPoint>, String> {
+pub fn get_account_trend_daily(days: i64) -> GatewayResult<Vec<AccountTrendPoint>> {
     let conn = connect_db()?;
     let cutoff = chrono::Utc::now().timestamp() - (days * 24 * 3600);
 
@@ -647,7 +1209,7 @@ pub fn get_account_trend_daily(days: i64) -> Result<Vec<AccountTrendPoint>, Stri
          GROUP BY day_bucket, account_email
          ORDER BY day_bucket ASC",
         )
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     let mut trend_map: std::collections::BTreeMap<String, std::collections::HashMap<String, u64>> =
         std::collections::BTreeMap::new();
@@ -660,10 +1222,10 @@ pub fn get_account_trend_daily(days: i64) -> Result<Vec<AccountTrendPoint>, Stri
                 row.get::<_, u64>(2)?,
             ))
         })
-        .map_err(|e| e.to_string())?;
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
 
     for row in rows {
-        let (period, account, total) = row.map_err(|e| e.to_string())?;
+        let (period, account, total) = row.map_err(|e| GatewayError::Db(e.to_string()))?;
         trend_map.entry(period).or_default().insert(account, total);
     }
 
@@ -676,6 +1238,29 @@ pub fn get_account_trend_daily(days: i64) -> Result<Vec<AccountTrendPoint>, Stri
         .collect())
 }
 
+/// [NEW] 删除指定账号在统计库中的全部记录 (token_usage + token_stats_hourly)。
+/// 与账号删除解耦：用户可以删号留统计，也可以单独清隐私数据。
+/// 返回 (token_usage 删除行数, token_stats_hourly 删除行数)
+pub fn purge_account_usage(account_email: &str) -> GatewayResult<(u64, u64)> {
+    let conn = connect_db()?;
+
+    let usage_deleted = conn
+        .execute(
+            "DELETE FROM token_usage WHERE account_email = ?1",
+            params![account_email],
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    let hourly_deleted = conn
+        .execute(
+            "DELETE FROM token_stats_hourly WHERE account_email = ?1",
+            params![account_email],
+        )
+        .map_err(|e| GatewayError::Db(e.to_string()))?;
+
+    Ok((usage_deleted as u64, hourly_deleted as u64))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -686,4 +1271,37 @@ mod tests {
         // For now, just verify the module compiles
         assert!(true);
     }
+
+    #[test]
+    fn test_normalize_model_name_builtin_rules() {
+        // Dated suffixes collapse into the base name
+        assert_eq!(normalize_model_name("gpt-4o-2024-08-06"), "gpt-4o");
+        assert_eq!(normalize_model_name("gpt-4o-20240806"), "gpt-4o");
+        // Provider prefixes are stripped
+        assert_eq!(normalize_model_name("openai/gpt-4o"), "gpt-4o");
+        assert_eq!(
+            normalize_model_name("anthropic/claude-sonnet-4-5"),
+            "claude-sonnet-4-5"
+        );
+        // Both at once
+        assert_eq!(normalize_model_name("openai/gpt-4o-2024-08-06"), "gpt-4o");
+        // Already-canonical names pass through unchanged
+        assert_eq!(normalize_model_name("gpt-4o"), "gpt-4o");
+        assert_eq!(
+            normalize_model_name("gemini-3-pro-high"),
+            "gemini-3-pro-high"
+        );
+    }
+
+    #[test]
+    fn test_strip_date_suffix_non_dates_untouched() {
+        // Version-ish suffixes that are not dates must survive
+        assert_eq!(strip_date_suffix("claude-sonnet-4-5"), "claude-sonnet-4-5");
+        assert_eq!(
+            strip_date_suffix("gemini-1-5-pro-002"),
+            "gemini-1-5-pro-002"
+        );
+        // Too-short names don't panic
+        assert_eq!(strip_date_suffix("x"), "x");
+    }
 }
@@ -20,6 +20,7 @@ pub struct UserToken {
     pub max_ips: i32,              // 0 = unlimited
     pub curfew_start: Option<String>, // "HH:MM" 宵禁开始时间
     pub curfew_end: Option<String>,   // "HH:MM" 宵禁结束时间
+    pub pinned_account_id: Option<String>, // [NEW] 固定路由到指定账号 (不可用时回退正常调度)
     pub created_at: i64,
     pub updated_at: i64,
     pub last_used_at: Option<i64>,
@@ -102,6 +103,7 @@ pub fn init_db() -> Result<(), String> {
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN last_used_at INTEGER", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_start TEXT", []);
     let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN curfew_end TEXT", []);
+    let _ = conn.execute("ALTER TABLE user_tokens ADD COLUMN pinned_account_id TEXT", []);
 
     // 创建 token_ip_bindings 表
     conn.execute(
@@ -142,6 +144,53 @@ pub fn init_db() -> Result<(), String> {
     Ok(())
 }
 
+/// [NEW] 校验 HH:MM (24 小时制)，如 "08:30" / "23:59"；"99:99"、"8:30" 均拒绝
+fn validate_hhmm(field: &str, value: &str) -> Result<(), String> {
+    let valid = match value.split_once(':') {
+        Some((hours, minutes)) => {
+            hours.len() == 2
+                && minutes.len() == 2
+                && hours.chars().all(|c| c.is_ascii_digit())
+                && minutes.chars().all(|c| c.is_ascii_digit())
+                && hours.parse::<u32>().map_or(false, |h| h < 24)
+                && minutes.parse::<u32>().map_or(false, |m| m < 60)
+        }
+        None => false,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} 必须是 24 小时制 HH:MM 格式 (收到 \"{}\")",
+            field, value
+        ))
+    }
+}
+
+/// [NEW] 创建/更新令牌前的输入校验：负数 max_ips 和畸形宵禁时间
+/// 会让后续的 enforcement 逻辑崩溃，这里提前以明确错误拒绝
+fn validate_token_limits(
+    max_ips: i32,
+    curfew_start: &Option<String>,
+    curfew_end: &Option<String>,
+) -> Result<(), String> {
+    if max_ips < 0 {
+        return Err(format!(
+            "max_ips 不能为负数 (收到 {})；0 表示不限制 IP 数量",
+            max_ips
+        ));
+    }
+    match (curfew_start, curfew_end) {
+        (Some(start), Some(end)) => {
+            validate_hhmm("curfew_start", start)?;
+            validate_hhmm("curfew_end", end)?;
+            Ok(())
+        }
+        (None, None) => Ok(()),
+        _ => Err("curfew_start 与 curfew_end 必须同时提供或同时省略".to_string()),
+    }
+}
+
 /// 创建新令牌
 pub fn create_token(
     username: String,
@@ -149,8 +198,12 @@ pub fn create_token(
     description: Option<String>,
     max_ips: i32,
     curfew_start: Option<String>,
-    curfew_end: Option<String>
+    curfew_end: Option<String>,
+    pinned_account_id: Option<String>
 ) -> Result<UserToken, String> {
+    // [NEW] 先校验再落库，避免创建出让 enforcement 逻辑崩溃的令牌
+    validate_token_limits(max_ips, &curfew_start, &curfew_end)?;
+
     let conn = connect_db()?;
     let id = Uuid::new_v4().to_string();
     let token = format!("sk-{}", Uuid::new_v4().to_string().replace("-", ""));
@@ -174,6 +227,7 @@ pub fn create_token(
         max_ips,
         curfew_start: curfew_start.clone(),
         curfew_end: curfew_end.clone(),
+        pinned_account_id: pinned_account_id.clone(),
         created_at: now,
         updated_at: now,
         last_used_at: None,
@@ -184,9 +238,9 @@ pub fn create_token(
     conn.execute(
         "INSERT INTO user_tokens (
             id, token, username, description, enabled, expires_type, expires_at, max_ips,
-            curfew_start, curfew_end,
+            curfew_start, curfew_end, pinned_account_id,
             created_at, updated_at, total_requests, total_tokens_used
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
         params![
             user_token.id,
             user_token.token,
@@ -198,6 +252,7 @@ pub fn create_token(
             user_token.max_ips,
             user_token.curfew_start,
             user_token.curfew_end,
+            user_token.pinned_account_id,
             user_token.created_at,
             user_token.updated_at,
             user_token.total_requests,
@@ -226,6 +281,7 @@ pub fn list_tokens() -> Result<Vec<UserToken>, String> {
             max_ips: row.get("max_ips")?,
             curfew_start: row.get("curfew_start").unwrap_or(None),
             curfew_end: row.get("curfew_end").unwrap_or(None),
+            pinned_account_id: row.get("pinned_account_id").unwrap_or(None),
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
             last_used_at: row.get("last_used_at")?,
@@ -260,6 +316,7 @@ pub fn get_token_by_id(id: &str) -> Result<Option<UserToken>, String> {
             max_ips: row.get("max_ips")?,
             curfew_start: row.get("curfew_start").unwrap_or(None),
             curfew_end: row.get("curfew_end").unwrap_or(None),
+            pinned_account_id: row.get("pinned_account_id").unwrap_or(None),
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
             last_used_at: row.get("last_used_at")?,
@@ -289,6 +346,7 @@ pub fn get_token_by_value(token: &str) -> Result<Option<UserToken>, String> {
             max_ips: row.get("max_ips")?,
             curfew_start: row.get("curfew_start").unwrap_or(None),
             curfew_end: row.get("curfew_end").unwrap_or(None),
+            pinned_account_id: row.get("pinned_account_id").unwrap_or(None),
             created_at: row.get("created_at")?,
             updated_at: row.get("updated_at")?,
             last_used_at: row.get("last_used_at")?,
@@ -308,8 +366,25 @@ pub fn update_token(
     enabled: Option<bool>,
     max_ips: Option<i32>,
     curfew_start: Option<Option<String>>,
-    curfew_end: Option<Option<String>>
+    curfew_end: Option<Option<String>>,
+    pinned_account_id: Option<Option<String>>
 ) -> Result<(), String> {
+    // [NEW] 对提供的字段做与创建时相同的校验 (部分更新时仅校验给定值)
+    if let Some(ips) = max_ips {
+        if ips < 0 {
+            return Err(format!(
+                "max_ips 不能为负数 (收到 {})；0 表示不限制 IP 数量",
+                ips
+            ));
+        }
+    }
+    if let Some(Some(start)) = &curfew_start {
+        validate_hhmm("curfew_start", start)?;
+    }
+    if let Some(Some(end)) = &curfew_end {
+        validate_hhmm("curfew_end", end)?;
+    }
+
     let conn = connect_db()?;
     let now = Utc::now().timestamp();
 
@@ -353,6 +428,12 @@ pub fn update_token(
         param_idx += 1;
     }
 
+    if let Some(pinned) = pinned_account_id {
+        query.push_str(&format!(", pinned_account_id = ?{}", param_idx));
+        params_vec.push(Box::new(pinned));
+        param_idx += 1;
+    }
+
     query.push_str(&format!(" WHERE id = ?{}", param_idx));
     params_vec.push(Box::new(id.to_string()));
 
@@ -580,7 +661,7 @@ mod tests {
         
         // Use a random username to avoid collisions in existing DB runs during dev
         let username = format!("TestUser_{}", Uuid::new_v4());
-        let token_res = create_token(username.clone(), "day".to_string(), Some("Test token".to_string()), 0, None, None);
+        let token_res = create_token(username.clone(), "day".to_string(), Some("Test token".to_string()), 0, None, None, None);
         assert!(token_res.is_ok());
 
         let token = token_res.unwrap();
@@ -591,4 +672,41 @@ mod tests {
         assert!(fetched.is_ok());
         assert_eq!(fetched.unwrap().unwrap().username, username);
     }
+
+    #[test]
+    fn test_validate_token_limits_rejects_bad_input() {
+        // 负数 max_ips
+        assert!(validate_token_limits(-5, &None, &None).is_err());
+        // 宵禁只给一端
+        assert!(validate_token_limits(0, &Some("08:00".to_string()), &None).is_err());
+        assert!(validate_token_limits(0, &None, &Some("22:00".to_string())).is_err());
+        // 畸形时间
+        assert!(
+            validate_token_limits(0, &Some("99:99".to_string()), &Some("22:00".to_string()))
+                .is_err()
+        );
+        assert!(
+            validate_token_limits(0, &Some("8:30".to_string()), &Some("22:00".to_string()))
+                .is_err()
+        );
+        assert!(
+            validate_token_limits(0, &Some("+8:30".to_string()), &Some("22:00".to_string()))
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_token_limits_accepts_sane_input() {
+        assert!(validate_token_limits(0, &None, &None).is_ok());
+        assert!(validate_token_limits(3, &None, &None).is_ok());
+        assert!(
+            validate_token_limits(0, &Some("08:30".to_string()), &Some("23:59".to_string()))
+                .is_ok()
+        );
+        // 跨午夜宵禁 (start > end) 是合法语义，不在此处拒绝
+        assert!(
+            validate_token_limits(0, &Some("22:00".to_string()), &Some("06:00".to_string()))
+                .is_ok()
+        );
+    }
 }
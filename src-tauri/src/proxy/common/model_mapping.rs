@@ -142,7 +142,7 @@ pub async fn get_all_dynamic_models(
 /// - `claude-*-sonnet-*` matches `claude-3-5-sonnet-20241022` ✓
 /// - `*-thinking` matches `claude-opus-4-5-thinking` ✓
 /// - `a*b*c` matches `a123b456c` ✓
-fn wildcard_match(pattern: &str, text: &str) -> bool {
+pub(crate) fn wildcard_match(pattern: &str, text: &str) -> bool {
     let parts: Vec<&str> = pattern.split('*').collect();
 
     // No wildcard - exact match
@@ -192,13 +192,37 @@ pub fn resolve_model_route(
     original_model: &str,
     custom_mapping: &std::collections::HashMap<String, String>,
 ) -> String {
+    resolve_model_route_explained(original_model, custom_mapping).resolved
+}
+
+/// [NEW] 别名解析结果（配置调试用）：resolve_model_route 的可解释版本
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ModelRouteResolution {
+    /// 原始请求的模型名
+    pub requested: String,
+    /// 解析后的目标模型名（无规则命中时等于 requested）
+    pub resolved: String,
+    /// 命中的规则："exact:<key>" / "wildcard:<pattern>" / "system_default" / "none"
+    pub matched_rule: String,
+}
+
+/// [NEW] 与 resolve_model_route 完全相同的解析逻辑，额外返回命中的规则，
+/// 供 UI 在不发真实流量的情况下验证映射配置
+pub fn resolve_model_route_explained(
+    original_model: &str,
+    custom_mapping: &std::collections::HashMap<String, String>,
+) -> ModelRouteResolution {
     // 1. 精确匹配 (最高优先级)
     if let Some(target) = custom_mapping.get(original_model) {
         crate::modules::logger::log_info(&format!(
             "[Router] 精确映射: {} -> {}",
             original_model, target
         ));
-        return target.clone();
+        return ModelRouteResolution {
+            requested: original_model.to_string(),
+            resolved: target.clone(),
+            matched_rule: format!("exact:{}", original_model),
+        };
     }
 
     // 2. Wildcard match - most specific (highest non-wildcard chars) wins
@@ -221,7 +245,11 @@ pub fn resolve_model_route(
             "[Router] Wildcard match: {} -> {} (rule: {})",
             original_model, target, pattern
         ));
-        return target.to_string();
+        return ModelRouteResolution {
+            requested: original_model.to_string(),
+            resolved: target.to_string(),
+            matched_rule: format!("wildcard:{}", pattern),
+        };
     }
 
     // 3. 系统默认映射
@@ -232,7 +260,16 @@ pub fn resolve_model_route(
             original_model, result
         ));
     }
-    result
+    let matched_rule = if result != original_model {
+        "system_default".to_string()
+    } else {
+        "none".to_string()
+    };
+    ModelRouteResolution {
+        requested: original_model.to_string(),
+        resolved: result,
+        matched_rule,
+    }
 }
 
 /// Normalize any physical model name to one of the 3 standard protection IDs.
@@ -263,6 +300,33 @@ pub fn normalize_to_standard_id(model_name: &str) -> Option<String> {
     }
 }
 
+/// [NEW] 判断上游错误是否为"模型不存在/不可用" (可用配置的回退模型重试一次)。
+/// 仅认 400/404：限流与鉴权类错误由既有重试策略处理，不触发模型回退
+pub fn is_model_unavailable_error(status_code: u16, error_text: &str) -> bool {
+    if status_code != 400 && status_code != 404 {
+        return false;
+    }
+    let text = error_text.to_lowercase();
+    text.contains("model")
+        && (text.contains("not found")
+            || text.contains("not_found")
+            || text.contains("does not exist")
+            || text.contains("not supported")
+            || text.contains("unsupported")
+            || text.contains("unknown model")
+            || text.contains("invalid model"))
+}
+
+/// [NEW] 查找模型配置的回退目标。
+/// 未配置、回退到自身或空值均视为无回退 (直接透传原错误)
+pub fn fallback_model_for(model: &str, fallbacks: &HashMap<String, String>) -> Option<String> {
+    fallbacks
+        .get(model)
+        .map(|f| f.trim())
+        .filter(|f| !f.is_empty() && *f != model)
+        .map(|f| f.to_string())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -361,4 +425,28 @@ mod tests {
         // Multi-wildcard: "a*b*c" (3)
         assert_eq!(resolve_model_route("a-test-b-foo-c", &custom), "multi-wild");
     }
+
+    #[test]
+    fn test_resolve_model_route_explained_reports_matched_rule() {
+        let mut custom = HashMap::new();
+        custom.insert("gpt-4".to_string(), "exact-target".to_string());
+        custom.insert("gpt-4*".to_string(), "wild-target".to_string());
+
+        let exact = resolve_model_route_explained("gpt-4", &custom);
+        assert_eq!(exact.resolved, "exact-target");
+        assert_eq!(exact.matched_rule, "exact:gpt-4");
+
+        let wild = resolve_model_route_explained("gpt-4-turbo", &custom);
+        assert_eq!(wild.resolved, "wild-target");
+        assert_eq!(wild.matched_rule, "wildcard:gpt-4*");
+
+        // System default mapping kicks in for known aliases
+        let system = resolve_model_route_explained("claude-3-5-sonnet-20241022", &custom);
+        assert_eq!(system.matched_rule, "system_default");
+
+        // Unknown model passes through unchanged
+        let none = resolve_model_route_explained("gemini-3-pro-high", &custom);
+        assert_eq!(none.resolved, "gemini-3-pro-high");
+        assert_eq!(none.matched_rule, "none");
+    }
 }
@@ -0,0 +1,148 @@
+// [NEW] 上游并发限制 (Semaphore)
+//
+// 突发流量下无限制地向上游开连接容易触发供应商限流，配置
+// `proxy.max_concurrent_upstream` (0 = 不限制) 后，所有 v1internal 转发在
+// 发起请求前需先取得许可；饱和时按 `proxy.on_saturation` 模式处理：
+// - queue: 有界排队等待 (超时后 503)
+// - reject: 立即返回 503 + Retry-After
+//
+// 许可覆盖连接建立与响应头返回阶段 (与上游请求超时的约束范围一致)，
+// 流式响应体阶段不占用许可。
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock, RwLock};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::proxy::config::OnSaturationMode;
+
+/// queue 模式下的最长排队等待时间
+const QUEUE_MAX_WAIT_MS: u64 = 15_000;
+
+/// 503 响应中建议客户端的重试间隔 (秒)
+pub const RETRY_AFTER_SECS: u64 = 5;
+
+struct LimiterState {
+    semaphore: Option<Arc<Semaphore>>,
+    mode: OnSaturationMode,
+}
+
+static LIMITER: OnceLock<RwLock<LimiterState>> = OnceLock::new();
+static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+fn limiter() -> &'static RwLock<LimiterState> {
+    LIMITER.get_or_init(|| {
+        RwLock::new(LimiterState {
+            semaphore: None,
+            mode: OnSaturationMode::default(),
+        })
+    })
+}
+
+/// 更新全局并发限制配置 (0 = 不限制)；热更新会替换信号量，
+/// 旧许可在原信号量上释放，不影响正确性
+pub fn configure(max_concurrent: usize, mode: OnSaturationMode) {
+    if let Ok(mut state) = limiter().write() {
+        state.semaphore = if max_concurrent > 0 {
+            Some(Arc::new(Semaphore::new(max_concurrent)))
+        } else {
+            None
+        };
+        state.mode = mode;
+    }
+    tracing::info!(
+        "[Concurrency] Upstream limit configured: max={} ({})",
+        max_concurrent,
+        if max_concurrent > 0 { "enabled" } else { "unlimited" }
+    );
+}
+
+/// 当前在途上游请求数 (连接建立 ~ 响应头返回阶段)
+pub fn in_flight_count() -> usize {
+    IN_FLIGHT.load(Ordering::Relaxed)
+}
+
+/// 判断错误消息是否来自并发饱和 (handlers 据此返回 503 + Retry-After)
+pub fn is_saturation_error(err: &str) -> bool {
+    err.contains("upstream_saturated")
+}
+
+/// 上游请求许可；Drop 时自动释放并发计数
+#[derive(Debug)]
+pub struct UpstreamPermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Drop for UpstreamPermit {
+    fn drop(&mut self) {
+        IN_FLIGHT.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 获取上游请求许可。未配置限制时直接放行 (仅计数)。
+pub async fn acquire_upstream_permit() -> Result<UpstreamPermit, String> {
+    let (semaphore, mode) = {
+        let state = limiter()
+            .read()
+            .map_err(|_| "Concurrency limiter lock corrupted".to_string())?;
+        (state.semaphore.clone(), state.mode.clone())
+    };
+
+    let permit = match semaphore {
+        None => None,
+        Some(sem) => match mode {
+            OnSaturationMode::Reject => match sem.try_acquire_owned() {
+                Ok(p) => Some(p),
+                Err(_) => {
+                    return Err(format!(
+                        "upstream_saturated: concurrency limit reached, retry after {}s",
+                        RETRY_AFTER_SECS
+                    ));
+                }
+            },
+            OnSaturationMode::Queue => {
+                match tokio::time::timeout(
+                    tokio::time::Duration::from_millis(QUEUE_MAX_WAIT_MS),
+                    sem.acquire_owned(),
+                )
+                .await
+                {
+                    Ok(Ok(p)) => Some(p),
+                    Ok(Err(_)) => {
+                        // 信号量被热更新替换后关闭，放行避免误伤
+                        None
+                    }
+                    Err(_) => {
+                        return Err(format!(
+                            "upstream_saturated: queued {}ms without a free slot, retry after {}s",
+                            QUEUE_MAX_WAIT_MS, RETRY_AFTER_SECS
+                        ));
+                    }
+                }
+            }
+        },
+    };
+
+    IN_FLIGHT.fetch_add(1, Ordering::Relaxed);
+    Ok(UpstreamPermit { _permit: permit })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_reject_mode_returns_saturation_error() {
+        configure(1, OnSaturationMode::Reject);
+        let held = acquire_upstream_permit().await.unwrap();
+        let err = acquire_upstream_permit().await.unwrap_err();
+        assert!(is_saturation_error(&err));
+
+        // 释放后恢复可用
+        drop(held);
+        let reacquired = acquire_upstream_permit().await;
+        assert!(reacquired.is_ok());
+
+        // 恢复默认，避免影响其他测试
+        configure(0, OnSaturationMode::Queue);
+    }
+}
@@ -40,6 +40,312 @@ pub fn update_thinking_budget_config(config: ThinkingBudgetConfig) {
     }
 }
 
+// ============================================================================
+// [NEW] 全局上游超时配置存储
+// UpstreamClient 在构建 HTTP 客户端与发起请求时读取（无需修改构造签名）
+// ============================================================================
+static GLOBAL_UPSTREAM_TIMEOUTS: OnceLock<RwLock<(u64, u64)>> = OnceLock::new();
+
+/// 获取当前上游超时配置 (connect_ms, request_ms)
+pub fn get_upstream_timeouts() -> (u64, u64) {
+    GLOBAL_UPSTREAM_TIMEOUTS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|t| *t)
+        .unwrap_or((
+            default_upstream_connect_timeout_ms(),
+            default_upstream_request_timeout_ms(),
+        ))
+}
+
+/// 更新全局上游超时配置
+pub fn update_upstream_timeouts(connect_ms: u64, request_ms: u64) {
+    if let Some(lock) = GLOBAL_UPSTREAM_TIMEOUTS.get() {
+        if let Ok(mut t) = lock.write() {
+            *t = (connect_ms, request_ms);
+        }
+    } else {
+        let _ = GLOBAL_UPSTREAM_TIMEOUTS.set(RwLock::new((connect_ms, request_ms)));
+    }
+    tracing::info!(
+        "[Upstream] Timeouts updated: connect={}ms, request={}ms",
+        connect_ms,
+        request_ms
+    );
+}
+
+// ============================================================================
+// [NEW] 全局模型日度 Token 上限存储 (spend cap)
+// 请求 handler 在转发前读取（无需修改函数签名），保存配置时热更新
+// ============================================================================
+static GLOBAL_MODEL_DAILY_TOKEN_CAP: OnceLock<RwLock<HashMap<String, u64>>> = OnceLock::new();
+
+/// 获取当前模型日度 Token 上限配置 (model -> cap)
+pub fn get_model_daily_token_cap() -> HashMap<String, u64> {
+    GLOBAL_MODEL_DAILY_TOKEN_CAP
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|m| m.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局模型日度 Token 上限配置
+pub fn update_model_daily_token_cap(caps: HashMap<String, u64>) {
+    if let Some(lock) = GLOBAL_MODEL_DAILY_TOKEN_CAP.get() {
+        if let Ok(mut m) = lock.write() {
+            *m = caps.clone();
+        }
+    } else {
+        let _ = GLOBAL_MODEL_DAILY_TOKEN_CAP.set(RwLock::new(caps.clone()));
+    }
+    tracing::info!("[SpendCap] Model daily token caps updated: {} model(s)", caps.len());
+}
+
+// ============================================================================
+// [NEW] 全局"采信转发头"开关存储
+// 中间件提取 client_ip 时读取；默认 true 保持原有行为（总是读 X-Forwarded-For）
+// ============================================================================
+static GLOBAL_TRUST_FORWARDED_HEADERS: OnceLock<RwLock<bool>> = OnceLock::new();
+
+/// 是否采信 X-Forwarded-For / X-Real-IP 转发头
+pub fn get_trust_forwarded_headers() -> bool {
+    GLOBAL_TRUST_FORWARDED_HEADERS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|v| *v)
+        .unwrap_or(true)
+}
+
+/// 更新"采信转发头"开关
+pub fn update_trust_forwarded_headers(trust: bool) {
+    if let Some(lock) = GLOBAL_TRUST_FORWARDED_HEADERS.get() {
+        if let Ok(mut v) = lock.write() {
+            *v = trust;
+        }
+    } else {
+        let _ = GLOBAL_TRUST_FORWARDED_HEADERS.set(RwLock::new(trust));
+    }
+    tracing::info!("[Security] trust_forwarded_headers = {}", trust);
+}
+
+// ============================================================================
+// [NEW] 全局账号限速间隔存储 (毫秒)
+// token_manager 发出 Token 前读取；0 = 关闭
+// ============================================================================
+static GLOBAL_PER_ACCOUNT_MIN_INTERVAL_MS: OnceLock<RwLock<u64>> = OnceLock::new();
+
+/// 同一账号两次转发之间的最小间隔毫秒数 (0 = 关闭)
+pub fn get_per_account_min_interval_ms() -> u64 {
+    GLOBAL_PER_ACCOUNT_MIN_INTERVAL_MS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|v| *v)
+        .unwrap_or(0)
+}
+
+/// 更新账号限速间隔
+pub fn update_per_account_min_interval_ms(interval_ms: u64) {
+    if let Some(lock) = GLOBAL_PER_ACCOUNT_MIN_INTERVAL_MS.get() {
+        if let Ok(mut v) = lock.write() {
+            *v = interval_ms;
+        }
+    } else {
+        let _ = GLOBAL_PER_ACCOUNT_MIN_INTERVAL_MS.set(RwLock::new(interval_ms));
+    }
+    tracing::info!("[Pacing] per_account_min_interval_ms = {}", interval_ms);
+}
+
+// ============================================================================
+// [NEW] 全局统计模型名归一化规则存储 (pattern -> canonical)
+// token_stats 记录用量前读取，保存配置时热更新
+// ============================================================================
+static GLOBAL_STATS_MODEL_NORMALIZATION: OnceLock<RwLock<HashMap<String, String>>> =
+    OnceLock::new();
+
+/// 获取当前统计模型名归一化规则 (支持通配符 pattern)
+pub fn get_stats_model_normalization() -> HashMap<String, String> {
+    GLOBAL_STATS_MODEL_NORMALIZATION
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|m| m.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局统计模型名归一化规则
+pub fn update_stats_model_normalization(rules: HashMap<String, String>) {
+    if let Some(lock) = GLOBAL_STATS_MODEL_NORMALIZATION.get() {
+        if let Ok(mut m) = lock.write() {
+            *m = rules.clone();
+        }
+    } else {
+        let _ = GLOBAL_STATS_MODEL_NORMALIZATION.set(RwLock::new(rules.clone()));
+    }
+    tracing::info!(
+        "[Stats] Model normalization rules updated: {} rule(s)",
+        rules.len()
+    );
+}
+
+// ============================================================================
+// [NEW] 全局模型回退表存储 (requested model -> fallback model)
+// handler 收到"模型不存在"类上游错误时读取，保存配置时热更新
+// ============================================================================
+static GLOBAL_MODEL_FALLBACKS: OnceLock<RwLock<HashMap<String, String>>> = OnceLock::new();
+
+/// 获取当前模型回退表
+pub fn get_model_fallbacks() -> HashMap<String, String> {
+    GLOBAL_MODEL_FALLBACKS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|m| m.clone())
+        .unwrap_or_default()
+}
+
+/// 更新全局模型回退表
+pub fn update_model_fallbacks(rules: HashMap<String, String>) {
+    if let Some(lock) = GLOBAL_MODEL_FALLBACKS.get() {
+        if let Ok(mut m) = lock.write() {
+            *m = rules.clone();
+        }
+    } else {
+        let _ = GLOBAL_MODEL_FALLBACKS.set(RwLock::new(rules.clone()));
+    }
+    tracing::info!("[Fallback] Model fallback rules updated: {} rule(s)", rules.len());
+}
+
+// ============================================================================
+// [NEW] 全局请求体大小上限存储 (字节)
+// 监控中间件缓冲请求体前读取；0 = 不限制
+// ============================================================================
+static GLOBAL_MAX_REQUEST_BODY_BYTES: OnceLock<RwLock<u64>> = OnceLock::new();
+
+/// 反代接受的最大请求体字节数 (0 = 不限制)
+pub fn get_max_request_body_bytes() -> u64 {
+    GLOBAL_MAX_REQUEST_BODY_BYTES
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|v| *v)
+        .unwrap_or(0)
+}
+
+/// 更新请求体大小上限
+pub fn update_max_request_body_bytes(max_bytes: u64) {
+    if let Some(lock) = GLOBAL_MAX_REQUEST_BODY_BYTES.get() {
+        if let Ok(mut v) = lock.write() {
+            *v = max_bytes;
+        }
+    } else {
+        let _ = GLOBAL_MAX_REQUEST_BODY_BYTES.set(RwLock::new(max_bytes));
+    }
+    tracing::info!("[Limits] max_request_body_bytes = {}", max_bytes);
+}
+
+// ============================================================================
+// [NEW] 全局粘性会话配置存储
+// 粘性会话中间件每个请求读取；支持保存配置时热更新
+// ============================================================================
+static GLOBAL_STICKY_SESSIONS: OnceLock<RwLock<StickySessionsConfig>> = OnceLock::new();
+
+/// 当前粘性会话配置 (未初始化时为默认关闭)
+pub fn get_sticky_sessions() -> StickySessionsConfig {
+    GLOBAL_STICKY_SESSIONS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|v| v.clone())
+        .unwrap_or_default()
+}
+
+/// 更新粘性会话配置
+pub fn update_sticky_sessions(config: StickySessionsConfig) {
+    tracing::info!(
+        "[Sticky] sticky_sessions enabled = {}, ttl = {}s",
+        config.enabled,
+        config.ttl_secs
+    );
+    if let Some(lock) = GLOBAL_STICKY_SESSIONS.get() {
+        if let Ok(mut v) = lock.write() {
+            *v = config;
+        }
+    } else {
+        let _ = GLOBAL_STICKY_SESSIONS.set(RwLock::new(config));
+    }
+}
+
+// ============================================================================
+// [NEW] 全局响应缓存配置存储
+// 响应缓存中间件每个请求读取；支持保存配置时热更新
+// ============================================================================
+static GLOBAL_RESPONSE_CACHE: OnceLock<RwLock<ResponseCacheConfig>> = OnceLock::new();
+
+/// 当前响应缓存配置 (未初始化时为默认关闭)
+pub fn get_response_cache() -> ResponseCacheConfig {
+    GLOBAL_RESPONSE_CACHE
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|v| v.clone())
+        .unwrap_or_default()
+}
+
+/// 更新响应缓存配置
+pub fn update_response_cache(config: ResponseCacheConfig) {
+    tracing::info!(
+        "[Cache] response_cache enabled = {}, ttl = {}s, max_entries = {}",
+        config.enabled,
+        config.ttl_secs,
+        config.max_entries
+    );
+    if let Some(lock) = GLOBAL_RESPONSE_CACHE.get() {
+        if let Ok(mut v) = lock.write() {
+            *v = config;
+        }
+    } else {
+        let _ = GLOBAL_RESPONSE_CACHE.set(RwLock::new(config));
+    }
+}
+
+// ============================================================================
+// [NEW] 全局自动封禁默认 TTL 存储 (秒)
+// security_db 写入 source = "auto" 的黑名单条目时读取；0 = 永久
+// ============================================================================
+static GLOBAL_DEFAULT_BLACKLIST_TTL_SECS: OnceLock<RwLock<u64>> = OnceLock::new();
+
+/// 自动封禁条目的默认 TTL 秒数 (未热更新前回退到配置默认值 24h)
+pub fn get_default_blacklist_ttl_secs() -> u64 {
+    GLOBAL_DEFAULT_BLACKLIST_TTL_SECS
+        .get()
+        .and_then(|lock| lock.read().ok())
+        .map(|v| *v)
+        .unwrap_or_else(default_blacklist_ttl_secs)
+}
+
+/// 更新自动封禁默认 TTL
+pub fn update_default_blacklist_ttl_secs(ttl_secs: u64) {
+    if let Some(lock) = GLOBAL_DEFAULT_BLACKLIST_TTL_SECS.get() {
+        if let Ok(mut v) = lock.write() {
+            *v = ttl_secs;
+        }
+    } else {
+        let _ = GLOBAL_DEFAULT_BLACKLIST_TTL_SECS.set(RwLock::new(ttl_secs));
+    }
+    tracing::info!("[Security] default_blacklist_ttl_secs = {}", ttl_secs);
+}
+
+/// [NEW] 上游并发饱和时的处理模式
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OnSaturationMode {
+    /// 有界排队等待空闲额度 (超时后 503)
+    Queue,
+    /// 立即返回 503 + Retry-After
+    Reject,
+}
+
+impl Default for OnSaturationMode {
+    fn default() -> Self {
+        Self::Queue
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum ProxyAuthMode {
@@ -290,6 +596,11 @@ pub struct IpBlacklistConfig {
     /// 自定义封禁消息
     #[serde(default = "default_block_message")]
     pub block_message: String,
+
+    /// [NEW] 自动封禁条目 (source = "auto") 未显式给出过期时间时的默认 TTL 秒数。
+    /// 0 = 自动条目也永久；手动条目不受影响，始终按显式 expires_at 处理
+    #[serde(default = "default_blacklist_ttl_secs")]
+    pub default_blacklist_ttl_secs: u64,
 }
 
 impl Default for IpBlacklistConfig {
@@ -297,6 +608,7 @@ impl Default for IpBlacklistConfig {
         Self {
             enabled: false,
             block_message: default_block_message(),
+            default_blacklist_ttl_secs: default_blacklist_ttl_secs(),
         }
     }
 }
@@ -305,6 +617,11 @@ fn default_block_message() -> String {
     "Access denied".to_string()
 }
 
+/// 默认 24 小时：配合过期感知匹配，黑名单可自清理
+fn default_blacklist_ttl_secs() -> u64 {
+    24 * 60 * 60
+}
+
 /// IP 白名单配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IpWhitelistConfig {
@@ -347,6 +664,64 @@ impl Default for SecurityMonitorConfig {
     }
 }
 
+/// [NEW] SSE 事件流配置 (供外部仪表盘实时消费请求摘要)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventStreamConfig {
+    /// 是否启用 /events SSE 端点
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 访问令牌 (未设置时回退为 api_key)
+    #[serde(default)]
+    pub token: Option<String>,
+}
+
+impl Default for EventStreamConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            token: None,
+        }
+    }
+}
+
+/// [NEW] Prometheus 指标端点配置 (独立监听地址，供运维抓取)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsConfig {
+    /// 是否启用 /metrics 端点
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 监听地址 (默认仅本机)
+    #[serde(default = "default_metrics_bind")]
+    pub bind: String,
+}
+
+fn default_metrics_bind() -> String {
+    "127.0.0.1:9100".to_string()
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: default_metrics_bind(),
+        }
+    }
+}
+
+/// [NEW] 健康探针配置 (容器编排 liveness/readiness)
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct HealthProbeConfig {
+    /// 是否启用 /healthz 与 /readyz 探针端点
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// 独立监听地址 (未设置时端点挂载在反代服务本身)
+    #[serde(default)]
+    pub bind: Option<String>,
+}
+
 /// 反代服务配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyConfig {
@@ -359,6 +734,22 @@ pub struct ProxyConfig {
     #[serde(default)]
     pub allow_lan_access: bool,
 
+    /// [NEW] 显式监听地址 (设置后优先于 allow_lan_access)
+    #[serde(default)]
+    pub listen_addr: Option<String>,
+
+    /// [NEW] 显式监听端口 (设置后优先于 port)
+    #[serde(default)]
+    pub listen_port: Option<u16>,
+
+    /// [NEW] TLS 证书路径 (PEM)；与 tls_key_path 同时设置时以 HTTPS 提供服务
+    #[serde(default)]
+    pub tls_cert_path: Option<String>,
+
+    /// [NEW] TLS 私钥路径 (PEM)
+    #[serde(default)]
+    pub tls_key_path: Option<String>,
+
     /// Authorization policy for the proxy.
     /// - off: no auth required
     /// - strict: auth required for all routes
@@ -387,10 +778,80 @@ pub struct ProxyConfig {
     #[serde(default = "default_request_timeout")]
     pub request_timeout: u64,
 
+    /// [NEW] 上游连接超时(毫秒)
+    #[serde(default = "default_upstream_connect_timeout_ms")]
+    pub upstream_connect_timeout_ms: u64,
+
+    /// [NEW] 上游请求超时(毫秒)；流式响应仅约束响应头返回，不限制整个流
+    #[serde(default = "default_upstream_request_timeout_ms")]
+    pub upstream_request_timeout_ms: u64,
+
     /// 是否开启请求日志记录 (监控)
     #[serde(default)]
     pub enable_logging: bool,
 
+    /// [NEW] 监控内存日志条数上限 (运行时可调)
+    #[serde(default = "default_monitor_max_logs")]
+    pub monitor_max_logs: usize,
+
+    /// [NEW] 计入 warning 而非 error 的状态码 (如 429/499)，空 = 保持原有分类
+    #[serde(default)]
+    pub neutral_status_codes: Vec<u16>,
+
+    /// [NEW] 模型日度 Token 上限 (key: 模型名, value: 每日 Token 总量上限)
+    /// 当日用量超出后返回 429，按本地时区自然日重置；空 = 不限制
+    #[serde(default)]
+    pub model_daily_token_cap: std::collections::HashMap<String, u64>,
+
+    /// [NEW] 上游请求最大并发数 (0 = 不限制)
+    #[serde(default)]
+    pub max_concurrent_upstream: usize,
+
+    /// [NEW] 上游并发饱和时的处理模式 (queue/reject)
+    #[serde(default)]
+    pub on_saturation: OnSaturationMode,
+
+    /// [NEW] 统计模型名归一化规则 (key: 通配符 pattern, value: 归一化名称)
+    /// 如 "gpt-4o-*" -> "gpt-4o"；原始模型名仍保留在 token_usage.model 列
+    #[serde(default)]
+    pub stats_model_normalization: std::collections::HashMap<String, String>,
+
+    /// [NEW] 是否采信 X-Forwarded-For / X-Real-IP 提取客户端 IP
+    /// 默认 true (保持原有行为)；服务直接暴露公网时建议关闭，防止伪造头绕过黑名单
+    #[serde(default = "default_trust_forwarded_headers")]
+    pub trust_forwarded_headers: bool,
+
+    /// [NEW] 上游 429 后账号的持久化冷却秒数 (无 Retry-After header 时的默认值)
+    /// 冷却写入账号文件 (reason = upstream_rate_limited)，跨重启生效，过期自动清除；
+    /// 0 = 不持久化 (仅内存限流跟踪器生效)
+    #[serde(default = "default_rate_limit_cooldown_secs")]
+    pub rate_limit_cooldown_secs: u64,
+
+    /// [NEW] 同一账号两次转发之间的最小间隔毫秒数 (抗模式检测)
+    /// 间隔不足时在转发前补齐差值并叠加少量随机抖动；0 = 关闭 (默认，保持原有行为)
+    #[serde(default)]
+    pub per_account_min_interval_ms: u64,
+
+    /// [NEW] 模型回退表 (key: 请求的模型名, value: 回退模型名)
+    /// 上游返回"模型不存在/不可用"错误时，用回退模型重试一次；空 = 直接透传错误
+    #[serde(default)]
+    pub model_fallbacks: std::collections::HashMap<String, String>,
+
+    /// [NEW] 反代接受的最大请求体字节数 (0 = 不限制，保持原有行为)
+    /// 超限请求在缓冲前直接以 413 拒绝，防止超大多模态请求把进程内存打爆
+    #[serde(default)]
+    pub max_request_body_bytes: u64,
+
+    /// [NEW] 客户端粘性会话：按用户令牌/客户端 IP 绑定账号，
+    /// 绑定在账号不可用或 TTL 到期前一直复用
+    #[serde(default)]
+    pub sticky_sessions: StickySessionsConfig,
+
+    /// [NEW] 幂等重复请求的响应缓存：按 方法+URL+请求体+模型 哈希命中，
+    /// 命中时直接返回缓存响应，不消耗上游配额
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+
     /// 调试日志配置 (保存完整链路)
     #[serde(default)]
     pub debug_logging: DebugLoggingConfig,
@@ -437,6 +898,76 @@ pub struct ProxyConfig {
     /// 代理池配置
     #[serde(default)]
     pub proxy_pool: ProxyPoolConfig,
+
+    /// [NEW] SSE 事件流配置
+    #[serde(default)]
+    pub event_stream: EventStreamConfig,
+
+    /// [NEW] Prometheus 指标端点配置
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+
+    /// [NEW] 健康探针配置
+    #[serde(default)]
+    pub health_probes: HealthProbeConfig,
+}
+
+/// [NEW] 客户端粘性会话配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StickySessionsConfig {
+    /// 是否启用 (默认关闭，保持原有调度行为)
+    #[serde(default)]
+    pub enabled: bool,
+    /// 绑定存活时间 (秒)，到期后下一次请求重新选择账号
+    #[serde(default = "default_sticky_sessions_ttl_secs")]
+    pub ttl_secs: u64,
+}
+
+/// [NEW] 粘性绑定默认 30 分钟：覆盖一轮对话，又不会把流量长期钉死在单账号
+fn default_sticky_sessions_ttl_secs() -> u64 {
+    30 * 60
+}
+
+impl Default for StickySessionsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_sticky_sessions_ttl_secs(),
+        }
+    }
+}
+
+/// [NEW] 响应缓存配置 (仅缓存成功的非流式响应)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    /// 是否启用 (默认关闭；LLM 响应通常不幂等，仅建议用于健康探测类请求)
+    #[serde(default)]
+    pub enabled: bool,
+    /// 缓存存活时间 (秒)
+    #[serde(default = "default_response_cache_ttl_secs")]
+    pub ttl_secs: u64,
+    /// 最大缓存条数，超出后淘汰最先过期的条目
+    #[serde(default = "default_response_cache_max_entries")]
+    pub max_entries: usize,
+}
+
+/// [NEW] 响应缓存默认 TTL 5 分钟：足够吸收健康探测的重复轮询
+fn default_response_cache_ttl_secs() -> u64 {
+    5 * 60
+}
+
+fn default_response_cache_max_entries() -> usize {
+    256
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            ttl_secs: default_response_cache_ttl_secs(),
+            max_entries: default_response_cache_max_entries(),
+        }
+    }
 }
 
 /// 上游代理配置
@@ -453,6 +984,10 @@ impl Default for ProxyConfig {
         Self {
             enabled: false,
             allow_lan_access: false, // 默认仅本机访问，隐私优先
+            listen_addr: None,
+            listen_port: None,
+            tls_cert_path: None,
+            tls_key_path: None,
             auth_mode: ProxyAuthMode::default(),
             port: 8045,
             api_key: format!("sk-{}", uuid::Uuid::new_v4().simple()),
@@ -460,7 +995,22 @@ impl Default for ProxyConfig {
             auto_start: false,
             custom_mapping: std::collections::HashMap::new(),
             request_timeout: default_request_timeout(),
+            upstream_connect_timeout_ms: default_upstream_connect_timeout_ms(),
+            upstream_request_timeout_ms: default_upstream_request_timeout_ms(),
             enable_logging: true, // 默认开启，支持 token 统计功能
+            monitor_max_logs: default_monitor_max_logs(),
+            neutral_status_codes: Vec::new(),
+            model_daily_token_cap: std::collections::HashMap::new(),
+            max_concurrent_upstream: 0, // 默认不限制
+            on_saturation: OnSaturationMode::default(),
+            stats_model_normalization: std::collections::HashMap::new(),
+            trust_forwarded_headers: default_trust_forwarded_headers(),
+            rate_limit_cooldown_secs: default_rate_limit_cooldown_secs(),
+            per_account_min_interval_ms: 0,
+            model_fallbacks: std::collections::HashMap::new(),
+            max_request_body_bytes: 0,
+            sticky_sessions: StickySessionsConfig::default(),
+            response_cache: ResponseCacheConfig::default(),
             debug_logging: DebugLoggingConfig::default(),
             upstream_proxy: UpstreamProxyConfig::default(),
             zai: ZaiConfig::default(),
@@ -472,6 +1022,9 @@ impl Default for ProxyConfig {
             saved_user_agent: None,
             thinking_budget: ThinkingBudgetConfig::default(),
             proxy_pool: ProxyPoolConfig::default(),
+            event_stream: EventStreamConfig::default(),
+            metrics: MetricsConfig::default(),
+            health_probes: HealthProbeConfig::default(),
         }
     }
 }
@@ -480,6 +1033,26 @@ fn default_request_timeout() -> u64 {
     120 // 默认 120 秒,原来 60 秒太短
 }
 
+fn default_monitor_max_logs() -> usize {
+    1000
+}
+
+fn default_trust_forwarded_headers() -> bool {
+    true
+}
+
+fn default_rate_limit_cooldown_secs() -> u64 {
+    60
+}
+
+fn default_upstream_connect_timeout_ms() -> u64 {
+    20_000
+}
+
+fn default_upstream_request_timeout_ms() -> u64 {
+    600_000
+}
+
 fn default_zai_base_url() -> String {
     "https://api.z.ai/api/anthropic".to_string()
 }
@@ -501,12 +1074,23 @@ impl ProxyConfig {
     /// - allow_lan_access = false: 返回 "127.0.0.1"（默认，隐私优先）
     /// - allow_lan_access = true: 返回 "0.0.0.0"（允许局域网访问）
     pub fn get_bind_address(&self) -> &str {
+        // [NEW] 显式 listen_addr 优先
+        if let Some(addr) = &self.listen_addr {
+            if !addr.is_empty() {
+                return addr;
+            }
+        }
         if self.allow_lan_access {
             "0.0.0.0"
         } else {
             "127.0.0.1"
         }
     }
+
+    /// [NEW] 获取实际的监听端口 (显式 listen_port 优先于 port)
+    pub fn get_listen_port(&self) -> u16 {
+        self.listen_port.unwrap_or(self.port)
+    }
 }
 
 
@@ -10,7 +10,7 @@ use bytes::Bytes;
 use futures::StreamExt;
 use serde_json::{json, Value};
 use tokio::time::Duration;
-use tracing::{debug, error, info};
+use tracing::{debug, error, info, warn};
 
 use crate::proxy::mappers::claude::{
     transform_claude_request_in, transform_response, create_claude_sse_stream, ClaudeRequest,
@@ -376,11 +376,38 @@ pub async fn handle_messages(
 
     // 2. 获取 UpstreamClient
     let upstream = state.upstream.clone();
-    
+
     // 3. 准备闭包
     let mut request_for_body = request.clone();
     let token_manager = state.token_manager;
-    
+
+    // [NEW] 用户令牌固定路由账号 (认证中间件注入)
+    let pinned_account = crate::proxy::middleware::auth::pinned_account_from_headers(&headers);
+
+    // [NEW] 模型日度 Token 上限检查 (spend cap)，达到上限直接 429
+    {
+        let mapped = crate::proxy::common::model_mapping::resolve_model_route(
+            &request.model,
+            &*state.custom_mapping.read().await,
+        );
+        if let Err(msg) = crate::proxy::spend_cap::check_model_daily_cap(&[
+            request.model.as_str(),
+            mapped.as_str(),
+        ]) {
+            return (
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({
+                    "type": "error",
+                    "error": {
+                        "type": "rate_limit_error",
+                        "message": msg
+                    }
+                })),
+            )
+                .into_response();
+        }
+    }
+
     let pool_size = token_manager.len();
     // [FIX] Ensure max_attempts is at least 2 to allow for internal retries (e.g. stripping signatures)
     // even if the user has only 1 account.
@@ -391,13 +418,19 @@ pub async fn handle_messages(
     let mut last_email: Option<String> = None;
     let mut last_mapped_model: Option<String> = None;
     let mut last_status = StatusCode::SERVICE_UNAVAILABLE; // Default to 503 if no response reached
-    
+    // [NEW] 模型回退: 上游报"模型不存在"后置为回退模型, 仅触发一次
+    let mut fallback_model_override: Option<String> = None;
+
     for attempt in 0..max_attempts {
         // 2. 模型路由解析
         let mut mapped_model = crate::proxy::common::model_mapping::resolve_model_route(
             &request_for_body.model,
             &*state.custom_mapping.read().await,
         );
+        // [NEW] 上一轮命中"模型不存在"错误时改用配置的回退模型重试
+        if let Some(fb) = &fallback_model_override {
+            mapped_model = fb.clone();
+        }
         last_mapped_model = Some(mapped_model.clone());
         
         // 将 Claude 工具转为 Value 数组以便探测联网
@@ -420,7 +453,7 @@ pub async fn handle_messages(
         let session_id = Some(session_id_str.as_str());
 
         let force_rotate_token = attempt > 0;
-        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager.get_token(&config.request_type, force_rotate_token, session_id, &config.final_model).await {
+        let (access_token, project_id, email, account_id, _wait_ms) = match token_manager.get_token(&config.request_type, force_rotate_token, session_id, &config.final_model, pinned_account.as_deref()).await {
             Ok(t) => t,
             Err(e) => {
                 let safe_message = if e.contains("invalid_grant") {
@@ -985,6 +1018,24 @@ pub async fn handle_messages(
             token_manager.mark_rate_limited_async(&email, status_code, retry_after.as_deref(), &error_text, Some(&request_with_mapped.model)).await;
         }
 
+        // [NEW] 模型不存在/不可用: 配置了回退模型时用回退模型重试一次
+        // (日志中 model 仍为客户端原始模型, mapped_model 记录实际发出的回退模型)
+        if fallback_model_override.is_none()
+            && crate::proxy::common::model_mapping::is_model_unavailable_error(status_code, &error_text)
+        {
+            let fallbacks = crate::proxy::config::get_model_fallbacks();
+            let fallback = crate::proxy::common::model_mapping::fallback_model_for(&request_with_mapped.model, &fallbacks)
+                .or_else(|| crate::proxy::common::model_mapping::fallback_model_for(&request.model, &fallbacks));
+            if let Some(fb) = fallback {
+                warn!(
+                    "[{}] 🔀 Model {} unavailable upstream, retrying once with fallback model {}",
+                    trace_id, request_with_mapped.model, fb
+                );
+                fallback_model_override = Some(fb);
+                continue;
+            }
+        }
+
         // 4. 处理 400 错误 (Thinking 签名失效 或 块顺序错误)
         if status_code == 400
             && !retried_without_thinking
@@ -1161,6 +1212,26 @@ pub async fn handle_messages(
     }
     
     
+    // [NEW] 上游并发饱和：503 + Retry-After，提示客户端稍后重试
+    if crate::proxy::concurrency::is_saturation_error(&last_error) {
+        let mut resp = (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(json!({
+                "type": "error",
+                "error": {
+                    "type": "overloaded_error",
+                    "message": last_error
+                }
+            })),
+        )
+            .into_response();
+        resp.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from_static("5"),
+        );
+        return resp;
+    }
+
     if let Some(email) = last_email {
         // [FIX] Include X-Mapped-Model in exhaustion error
         let mut headers = HeaderMap::new();
@@ -1181,7 +1252,10 @@ pub async fn handle_messages(
         };
 
         // [FIX] 403 时返回 503，避免 Claude Code 客户端退出到登录页
-        let response_status = if last_status.as_u16() == 403 {
+        // [NEW] 上游超时映射为 504
+        let response_status = if crate::proxy::upstream::client::UpstreamClient::is_timeout_error(&last_error) {
+            StatusCode::GATEWAY_TIMEOUT
+        } else if last_status.as_u16() == 403 {
             StatusCode::SERVICE_UNAVAILABLE
         } else {
             last_status
@@ -1214,7 +1288,10 @@ pub async fn handle_messages(
         };
 
         // [FIX] 403 时返回 503，避免 Claude Code 客户端退出到登录页
-        let response_status = if last_status.as_u16() == 403 {
+        // [NEW] 上游超时映射为 504
+        let response_status = if crate::proxy::upstream::client::UpstreamClient::is_timeout_error(&last_error) {
+            StatusCode::GATEWAY_TIMEOUT
+        } else if last_status.as_u16() == 403 {
             StatusCode::SERVICE_UNAVAILABLE
         } else {
             last_status
@@ -1581,8 +1658,8 @@ async fn call_gemini_sync(
     trace_id: &str,
 ) -> Result<String, String> {
     // Get token and transform request
-    let (access_token, project_id, email, account_id, _wait_ms) = token_manager
-        .get_token("gemini", false, None, model)
+    let (access_token, project_id, _email, _account_id, _wait_ms) = token_manager
+        .get_token("gemini", false, None, model, None)
         .await
         .map_err(|e| format!("Failed to get account: {}", e))?;
     
@@ -0,0 +1,109 @@
+// [NEW] SSE 事件流处理器 - 供外部仪表盘实时消费请求摘要
+// 与 Tauri `proxy://request` 事件共用同一份摘要 (不含请求/响应体)
+// 支持 Last-Event-ID / ?last_event_id= 断线重连续传
+
+use axum::{
+    extract::{Query, State},
+    http::{HeaderMap, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
+    response::{IntoResponse, Response},
+};
+use futures::stream::Stream;
+use serde::Deserialize;
+
+use crate::proxy::server::AppState;
+
+#[derive(Debug, Deserialize)]
+pub struct EventStreamQuery {
+    /// 访问令牌 (EventSource 无法设置请求头时使用)
+    pub token: Option<String>,
+    /// 断线重连起始事件 ID (优先级低于 Last-Event-ID 头)
+    pub last_event_id: Option<u64>,
+}
+
+/// GET /events - 实时请求摘要事件流 (SSE)
+pub async fn handle_events(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<EventStreamQuery>,
+) -> Response {
+    // 1. 功能开关检查
+    let config = state.event_stream.read().await.clone();
+    if !config.enabled {
+        return StatusCode::NOT_FOUND.into_response();
+    }
+
+    // 2. 令牌校验 (未配置独立令牌时回退为 api_key)
+    let expected = match &config.token {
+        Some(t) if !t.is_empty() => t.clone(),
+        _ => {
+            let security = state.security.read().await;
+            security.api_key.clone()
+        }
+    };
+    if expected.is_empty() {
+        tracing::error!("[Events] 事件流已启用但未配置令牌 (token/api_key 均为空)，拒绝访问");
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    let provided = headers
+        .get("authorization")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(|s| s.to_string())
+        .or(query.token.clone());
+
+    if provided.as_deref() != Some(expected.as_str()) {
+        return StatusCode::FORBIDDEN.into_response();
+    }
+
+    // 3. 确定重连回放起点 (Last-Event-ID 头优先)
+    let last_id = headers
+        .get("last-event-id")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+        .or(query.last_event_id)
+        .unwrap_or(0);
+
+    Sse::new(event_stream(state, last_id))
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// 构建 SSE 流: 先回放缓冲中未消费的事件，再转发实时广播
+fn event_stream(
+    state: AppState,
+    last_id: u64,
+) -> impl Stream<Item = Result<Event, std::convert::Infallible>> {
+    async_stream::stream! {
+        let backlog = state.monitor.events_since(last_id).await;
+        let mut rx = state.monitor.subscribe_events();
+        let mut replayed_to = last_id;
+
+        for (id, summary) in backlog {
+            replayed_to = id;
+            if let Ok(event) = Event::default().id(id.to_string()).event("request").json_data(&summary) {
+                yield Ok(event);
+            }
+        }
+
+        loop {
+            match rx.recv().await {
+                Ok((id, summary)) => {
+                    // 回放期间广播的事件可能与缓冲重叠，跳过已发送的
+                    if id <= replayed_to {
+                        continue;
+                    }
+                    if let Ok(event) = Event::default().id(id.to_string()).event("request").json_data(&summary) {
+                        yield Ok(event);
+                    }
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("[Events] SSE 消费过慢，跳过 {} 条事件", skipped);
+                    continue;
+                }
+                Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    }
+}
@@ -24,8 +24,12 @@ const MAX_RETRY_ATTEMPTS: usize = 3;
 pub async fn handle_generate(
     State(state): State<AppState>,
     Path(model_action): Path<String>,
+    headers: axum::http::HeaderMap,
     Json(mut body): Json<Value>, // 改为 mut 以支持修复提示词注入
 ) -> Result<impl IntoResponse, (StatusCode, String)> {
+    // [NEW] 用户令牌固定路由账号 (认证中间件注入)
+    let pinned_account = crate::proxy::middleware::auth::pinned_account_from_headers(&headers);
+
     // 解析 model:method
     let (model_name, method) = if let Some((m, action)) = model_action.rsplit_once(':') {
         (m.to_string(), action.to_string())
@@ -47,6 +51,20 @@ pub async fn handle_generate(
             format!("Unsupported method: {}", method),
         ));
     }
+
+    // [NEW] 模型日度 Token 上限检查 (spend cap)，达到上限直接 429
+    {
+        let mapped = crate::proxy::common::model_mapping::resolve_model_route(
+            &model_name,
+            &*state.custom_mapping.read().await,
+        );
+        if let Err(msg) = crate::proxy::spend_cap::check_model_daily_cap(&[
+            model_name.as_str(),
+            mapped.as_str(),
+        ]) {
+            return Err((StatusCode::TOO_MANY_REQUESTS, msg));
+        }
+    }
     if debug_logger::is_enabled(&debug_cfg) {
         let original_payload = json!({
             "kind": "original_request",
@@ -125,6 +143,7 @@ pub async fn handle_generate(
                 attempt > 0,
                 Some(&session_id),
                 &config.final_model,
+                pinned_account.as_deref(),
             )
             .await
         {
@@ -540,16 +559,32 @@ pub async fn handle_generate(
         return Ok((status, [("X-Account-Email", email.as_str())], error_text).into_response());
     }
 
+    // [NEW] 上游并发饱和：503 + Retry-After，提示客户端稍后重试
+    if crate::proxy::concurrency::is_saturation_error(&last_error) {
+        let mut resp = (StatusCode::SERVICE_UNAVAILABLE, last_error).into_response();
+        resp.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from_static("5"),
+        );
+        return Ok(resp);
+    }
+    // [NEW] 上游超时映射为 504，与配额耗尽 (429) 区分
+    let exhausted_status =
+        if crate::proxy::upstream::client::UpstreamClient::is_timeout_error(&last_error) {
+            StatusCode::GATEWAY_TIMEOUT
+        } else {
+            StatusCode::TOO_MANY_REQUESTS
+        };
     if let Some(email) = last_email {
         Ok((
-            StatusCode::TOO_MANY_REQUESTS,
+            exhausted_status,
             [("X-Account-Email", email)],
             format!("All accounts exhausted. Last error: {}", last_error),
         )
             .into_response())
     } else {
         Ok((
-            StatusCode::TOO_MANY_REQUESTS,
+            exhausted_status,
             format!("All accounts exhausted. Last error: {}", last_error),
         )
             .into_response())
@@ -601,7 +636,7 @@ pub async fn handle_count_tokens(
     let model_group = "gemini";
     let (_access_token, _project_id, _email, _account_id, _wait_ms) = state
         .token_manager
-        .get_token(model_group, false, None, "gemini")
+        .get_token(model_group, false, None, "gemini", None)
         .await
         .map_err(|e| {
             (
@@ -101,6 +101,33 @@ pub async fn handle_chat_completions(
             });
     }
 
+    // [NEW] 用户令牌固定路由账号 (认证中间件注入)
+    let pinned_account = crate::proxy::middleware::auth::pinned_account_from_headers(&headers);
+
+    // [NEW] 模型日度 Token 上限检查 (spend cap)，达到上限直接 429
+    {
+        let mapped = crate::proxy::common::model_mapping::resolve_model_route(
+            &openai_req.model,
+            &*state.custom_mapping.read().await,
+        );
+        if let Err(msg) = crate::proxy::spend_cap::check_model_daily_cap(&[
+            openai_req.model.as_str(),
+            mapped.as_str(),
+        ]) {
+            return Ok((
+                StatusCode::TOO_MANY_REQUESTS,
+                Json(json!({
+                    "error": {
+                        "message": msg,
+                        "type": "rate_limit_exceeded",
+                        "code": "daily_token_cap_reached"
+                    }
+                })),
+            )
+                .into_response());
+        }
+    }
+
     let trace_id = format!("req_{}", chrono::Utc::now().timestamp_subsec_millis());
     info!(
         "[{}] OpenAI Chat Request: {} | {} messages | stream: {}",
@@ -128,6 +155,33 @@ pub async fn handle_chat_completions(
         .await;
     }
 
+    // [NEW] 协议翻译层：OpenAI 客户端 → Anthropic 上游 (z.ai)。
+    // 独占模式或模型显式指向 z.ai (zai:/glm- 前缀) 时经 interop 翻译转发，
+    // 其余请求继续走原有 Google 通路
+    {
+        let zai = state.zai.read().await.clone();
+        let zai_enabled =
+            zai.enabled && !matches!(zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Off);
+        let lower_model = openai_req.model.to_lowercase();
+        let targets_anthropic =
+            lower_model.starts_with("zai:") || lower_model.starts_with("glm-");
+        if zai_enabled
+            && (targets_anthropic
+                || matches!(zai.dispatch_mode, crate::proxy::ZaiDispatchMode::Exclusive))
+        {
+            info!(
+                "[{}] Translating openai -> anthropic for model {} (z.ai upstream)",
+                trace_id, openai_req.model
+            );
+            return Ok(crate::proxy::providers::zai_anthropic::forward_openai_via_anthropic(
+                &state,
+                &headers,
+                original_body,
+            )
+            .await);
+        }
+    }
+
     // [NEW] Detect Client Adapter
     let client_adapter = CLIENT_ADAPTERS
         .iter()
@@ -179,6 +233,7 @@ pub async fn handle_chat_completions(
                 attempt > 0,
                 Some(&session_id),
                 &mapped_model,
+                pinned_account.as_deref(),
             )
             .await
         {
@@ -681,16 +736,32 @@ pub async fn handle_chat_completions(
     }
 
     // 所有尝试均失败
+    // [NEW] 上游并发饱和：503 + Retry-After，提示客户端稍后重试
+    if crate::proxy::concurrency::is_saturation_error(&last_error) {
+        let mut resp = (StatusCode::SERVICE_UNAVAILABLE, last_error).into_response();
+        resp.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from_static("5"),
+        );
+        return Ok(resp);
+    }
+    // [NEW] 上游超时映射为 504，与配额耗尽 (429) 区分
+    let exhausted_status =
+        if crate::proxy::upstream::client::UpstreamClient::is_timeout_error(&last_error) {
+            StatusCode::GATEWAY_TIMEOUT
+        } else {
+            StatusCode::TOO_MANY_REQUESTS
+        };
     if let Some(email) = last_email {
         Ok((
-            StatusCode::TOO_MANY_REQUESTS,
+            exhausted_status,
             [("X-Account-Email", email), ("X-Mapped-Model", mapped_model)],
             format!("All accounts exhausted. Last error: {}", last_error),
         )
             .into_response())
     } else {
         Ok((
-            StatusCode::TOO_MANY_REQUESTS,
+            exhausted_status,
             [("X-Mapped-Model", mapped_model)],
             format!("All accounts exhausted. Last error: {}", last_error),
         )
@@ -702,8 +773,12 @@ pub async fn handle_chat_completions(
 /// 将 Prompt 转换为 Chat Message 格式，复用 handle_chat_completions
 pub async fn handle_completions(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Json(mut body): Json<Value>,
 ) -> Response {
+    // [NEW] 用户令牌固定路由账号 (认证中间件注入)
+    let pinned_account = crate::proxy::middleware::auth::pinned_account_from_headers(&headers);
+
     debug!(
         "Received /v1/completions or /v1/responses payload: {:?}",
         body
@@ -1122,6 +1197,7 @@ pub async fn handle_completions(
                 force_rotate,
                 session_id,
                 &mapped_model,
+                pinned_account.as_deref(),
             )
             .await
         {
@@ -1502,16 +1578,32 @@ pub async fn handle_completions(
     }
 
     // 所有尝试均失败
+    // [NEW] 上游并发饱和：503 + Retry-After，提示客户端稍后重试
+    if crate::proxy::concurrency::is_saturation_error(&last_error) {
+        let mut resp = (StatusCode::SERVICE_UNAVAILABLE, last_error).into_response();
+        resp.headers_mut().insert(
+            axum::http::header::RETRY_AFTER,
+            axum::http::HeaderValue::from_static("5"),
+        );
+        return resp;
+    }
+    // [NEW] 上游超时映射为 504，与配额耗尽 (429) 区分
+    let exhausted_status =
+        if crate::proxy::upstream::client::UpstreamClient::is_timeout_error(&last_error) {
+            StatusCode::GATEWAY_TIMEOUT
+        } else {
+            StatusCode::TOO_MANY_REQUESTS
+        };
     if let Some(email) = last_email {
         (
-            StatusCode::TOO_MANY_REQUESTS,
+            exhausted_status,
             [("X-Account-Email", email), ("X-Mapped-Model", mapped_model)],
             format!("All accounts exhausted. Last error: {}", last_error),
         )
             .into_response()
     } else {
         (
-            StatusCode::TOO_MANY_REQUESTS,
+            exhausted_status,
             [("X-Mapped-Model", mapped_model)],
             format!("All accounts exhausted. Last error: {}", last_error),
         )
@@ -1634,7 +1726,7 @@ pub async fn handle_images_generations(
             for attempt in 0..max_attempts {
                 // 4.1 获取 Token
                 let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-                    .get_token("image_gen", attempt > 0, None, "dall-e-3")
+                    .get_token("image_gen", attempt > 0, None, "dall-e-3", None)
                     .await
                 {
                     Ok(t) => t,
@@ -2031,7 +2123,7 @@ pub async fn handle_images_edits(
             for attempt in 0..max_attempts {
                 // 4.1 获取 Token
                 let (access_token, project_id, email, account_id, _wait_ms) = match token_manager
-                    .get_token("image_gen", attempt > 0, None, "dall-e-3")
+                    .get_token("image_gen", attempt > 0, None, "dall-e-3", None)
                     .await
                 {
                     Ok(t) => t,
@@ -227,6 +227,12 @@ pub async fn handle_warmup(
                 response_body: None,
                 input_tokens: Some(0),
                 output_tokens: Some(0),
+                cached_input_tokens: None,
+                reasoning_tokens: None,
+                tokens_estimated: false,
+                note: None,
+                flagged: false,
+                cached: false,
                 protocol: Some("warmup".to_string()),
                 username: None,
             };
@@ -292,6 +298,12 @@ pub async fn handle_warmup(
                 response_body: None,
                 input_tokens: None,
                 output_tokens: None,
+                cached_input_tokens: None,
+                reasoning_tokens: None,
+                tokens_estimated: false,
+                note: None,
+                flagged: false,
+                cached: false,
                 protocol: Some("warmup".to_string()),
                 username: None,
             };
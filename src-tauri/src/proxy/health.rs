@@ -0,0 +1,71 @@
+// [NEW] 健康探针端点 - liveness (/healthz) 与 readiness (/readyz)
+// 供 Docker/Kubernetes 等容器编排探测，不依赖 Tauri webview，Headless 模式同样可用
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Router};
+use std::sync::Arc;
+
+use crate::proxy::TokenManager;
+
+/// 就绪检查: 账号池中存在至少一个可用账号
+/// 返回可用账号数，否则返回不可用原因
+pub async fn readiness(token_manager: &TokenManager) -> Result<usize, String> {
+    let total = token_manager.len();
+    if total == 0 {
+        return Err("no accounts loaded in the pool".to_string());
+    }
+    let usable = token_manager.usable_account_count().await;
+    if usable == 0 {
+        return Err(format!("all {} account(s) are rate-limited", total));
+    }
+    Ok(usable)
+}
+
+/// 启动独立的健康探针服务器 (未配置独立监听地址时为空操作，端点挂载在主服务上)
+pub async fn start_health_server(
+    config: &crate::proxy::config::HealthProbeConfig,
+    token_manager: Arc<TokenManager>,
+) {
+    if !config.enabled {
+        return;
+    }
+    let Some(bind) = config.bind.clone() else {
+        return;
+    };
+
+    let listener = match tokio::net::TcpListener::bind(&bind).await {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::error!("健康探针绑定 {} 失败: {}", bind, e);
+            return;
+        }
+    };
+
+    tracing::info!("健康探针启动在 http://{}/healthz", bind);
+
+    let app = Router::new()
+        .route("/healthz", get(handle_healthz))
+        .route("/readyz", get(handle_readyz))
+        .with_state(token_manager);
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            tracing::error!("健康探针服务器异常退出: {}", e);
+        }
+    });
+}
+
+/// GET /healthz - 存活探针 (进程在运行即返回 200)
+async fn handle_healthz() -> impl IntoResponse {
+    (StatusCode::OK, "ok")
+}
+
+/// GET /readyz - 就绪探针 (账号池无可用账号时返回 503 及原因)
+async fn handle_readyz(State(token_manager): State<Arc<TokenManager>>) -> impl IntoResponse {
+    match readiness(&token_manager).await {
+        Ok(usable) => (StatusCode::OK, format!("ready: {} usable account(s)", usable)),
+        Err(reason) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("not ready: {}", reason),
+        ),
+    }
+}
@@ -0,0 +1,84 @@
+// Interop 模块 - 跨协议翻译层
+// 按 (客户端协议, 上游协议) 选择翻译器。现有 mappers 各自绑定 Gemini 上游，
+// 这里补齐"客户端协议 ≠ 上游协议"的通用通路；首个实现为
+// OpenAI chat.completions 客户端 ↔ Anthropic /v1/messages 上游 (z.ai)
+
+pub mod openai_anthropic;
+
+use serde_json::Value;
+
+/// 网关可见的线协议
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Protocol {
+    OpenAi,
+    Anthropic,
+    Gemini,
+}
+
+impl Protocol {
+    /// 监控日志 protocol 字段使用的标识 (与 middleware/monitor.rs 的取值一致)
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::OpenAi => "openai",
+            Protocol::Anthropic => "anthropic",
+            Protocol::Gemini => "gemini",
+        }
+    }
+}
+
+/// 单个 (client, upstream) 协议对的翻译器。
+/// 请求/非流式响应按完整 JSON 体翻译；流式按上游 SSE 事件逐个翻译，
+/// 翻译器自身无状态，跨事件状态放在 StreamTranslationContext
+pub trait ProtocolTranslator: Send + Sync {
+    /// 客户端请求体 → 上游请求体 (model 字段由调用方再做映射覆盖)
+    fn translate_request(&self, body: &Value) -> Result<Value, String>;
+
+    /// 上游非流式响应体 → 客户端格式响应体
+    fn translate_response(&self, body: &Value) -> Result<Value, String>;
+
+    /// 上游单个 SSE 事件 → 0..n 个客户端格式事件 (不含 data: 前缀和 [DONE])
+    fn translate_stream_event(
+        &self,
+        event: &Value,
+        ctx: &mut StreamTranslationContext,
+    ) -> Vec<Value>;
+}
+
+/// 流式翻译的跨事件状态 (消息 id / 用量 / 工具块索引映射等)
+pub struct StreamTranslationContext {
+    /// 响应中回显给客户端的模型名 (通常为客户端原始请求的模型)
+    pub model: String,
+    pub message_id: String,
+    pub created: i64,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    /// 上游 content block index → 客户端 tool_calls index
+    pub tool_indices: std::collections::HashMap<u64, u64>,
+}
+
+impl StreamTranslationContext {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            message_id: format!("chatcmpl-{}", uuid::Uuid::new_v4()),
+            created: chrono::Utc::now().timestamp(),
+            input_tokens: None,
+            output_tokens: None,
+            tool_indices: std::collections::HashMap::new(),
+        }
+    }
+}
+
+/// 翻译器注册表：支持的协议对返回翻译器，其余返回 None 由调用方走原有通路。
+/// 新增协议对时在此处补一个分支即可
+pub fn translator_for(
+    client: Protocol,
+    upstream: Protocol,
+) -> Option<&'static dyn ProtocolTranslator> {
+    match (client, upstream) {
+        (Protocol::OpenAi, Protocol::Anthropic) => {
+            Some(&openai_anthropic::OpenAiToAnthropic)
+        }
+        _ => None,
+    }
+}
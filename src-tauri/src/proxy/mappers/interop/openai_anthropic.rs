@@ -0,0 +1,595 @@
+// OpenAI 客户端 ↔ Anthropic 上游翻译器
+// 请求: chat.completions → /v1/messages (system 提取 / 消息体 / 参数 / 工具)
+// 响应: message → chat.completion；SSE: Anthropic 事件流 → chat.completion.chunk
+
+use serde_json::{json, Value};
+
+use super::{ProtocolTranslator, StreamTranslationContext};
+
+/// Anthropic 的 max_tokens 为必填；客户端未指定时使用的兜底值
+const DEFAULT_MAX_TOKENS: u64 = 8192;
+
+pub struct OpenAiToAnthropic;
+
+/// 提取 OpenAI 消息 content 中的纯文本 (字符串或 parts 数组中的 text 部分)
+fn content_text(content: Option<&Value>) -> String {
+    match content {
+        Some(Value::String(s)) => s.clone(),
+        Some(Value::Array(parts)) => parts
+            .iter()
+            .filter_map(|p| {
+                if p.get("type").and_then(|t| t.as_str()) == Some("text") {
+                    p.get("text").and_then(|t| t.as_str()).map(|s| s.to_string())
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+/// OpenAI content part → Anthropic content block (不支持的 part 返回 None 丢弃)
+fn translate_user_part(part: &Value) -> Option<Value> {
+    match part.get("type").and_then(|t| t.as_str()) {
+        Some("text") => {
+            let text = part.get("text").and_then(|t| t.as_str())?;
+            Some(json!({ "type": "text", "text": text }))
+        }
+        Some("image_url") => {
+            // 仅支持 data URL (data:<media_type>;base64,<data>)；远程 URL 需客户端自行内联
+            let url = part
+                .get("image_url")
+                .and_then(|i| i.get("url"))
+                .and_then(|u| u.as_str())?;
+            let rest = url.strip_prefix("data:")?;
+            let (media_type, data) = rest.split_once(";base64,")?;
+            Some(json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": media_type, "data": data }
+            }))
+        }
+        _ => None,
+    }
+}
+
+/// Anthropic stop_reason → OpenAI finish_reason
+fn map_stop_reason(stop_reason: &str) -> &'static str {
+    match stop_reason {
+        "max_tokens" => "length",
+        "tool_use" => "tool_calls",
+        // end_turn / stop_sequence 及未知值统一归为 stop
+        _ => "stop",
+    }
+}
+
+/// 组装一个 chat.completion.chunk (choices[0] 的 delta / finish_reason 由调用方给定)
+fn make_chunk(ctx: &StreamTranslationContext, delta: Value, finish_reason: Option<&str>) -> Value {
+    json!({
+        "id": ctx.message_id,
+        "object": "chat.completion.chunk",
+        "created": ctx.created,
+        "model": ctx.model,
+        "choices": [{
+            "index": 0,
+            "delta": delta,
+            "finish_reason": finish_reason.map(|s| Value::String(s.to_string())).unwrap_or(Value::Null)
+        }]
+    })
+}
+
+impl ProtocolTranslator for OpenAiToAnthropic {
+    fn translate_request(&self, body: &Value) -> Result<Value, String> {
+        let model = body
+            .get("model")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| "missing model".to_string())?;
+        let messages = body
+            .get("messages")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| "missing messages".to_string())?;
+
+        let mut system_parts: Vec<String> = Vec::new();
+        let mut out_messages: Vec<Value> = Vec::new();
+
+        for msg in messages {
+            let role = msg.get("role").and_then(|r| r.as_str()).unwrap_or("user");
+            let content = msg.get("content");
+
+            match role {
+                // developer 是 OpenAI 新版 system 角色别名
+                "system" | "developer" => {
+                    let text = content_text(content);
+                    if !text.is_empty() {
+                        system_parts.push(text);
+                    }
+                }
+                "tool" => {
+                    // 工具结果必须以 user 角色的 tool_result 块回传
+                    let tool_use_id = msg
+                        .get("tool_call_id")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or_default();
+                    out_messages.push(json!({
+                        "role": "user",
+                        "content": [{
+                            "type": "tool_result",
+                            "tool_use_id": tool_use_id,
+                            "content": content_text(content)
+                        }]
+                    }));
+                }
+                "assistant" => {
+                    let mut blocks: Vec<Value> = Vec::new();
+                    let text = content_text(content);
+                    if !text.is_empty() {
+                        blocks.push(json!({ "type": "text", "text": text }));
+                    }
+                    if let Some(tool_calls) = msg.get("tool_calls").and_then(|v| v.as_array()) {
+                        for tc in tool_calls {
+                            let func = tc.get("function").cloned().unwrap_or(Value::Null);
+                            let name = func.get("name").and_then(|n| n.as_str()).unwrap_or_default();
+                            // arguments 是 JSON 字符串；解析失败时以空对象兜底
+                            let input = func
+                                .get("arguments")
+                                .and_then(|a| a.as_str())
+                                .and_then(|a| serde_json::from_str::<Value>(a).ok())
+                                .unwrap_or_else(|| json!({}));
+                            blocks.push(json!({
+                                "type": "tool_use",
+                                "id": tc.get("id").and_then(|i| i.as_str()).unwrap_or_default(),
+                                "name": name,
+                                "input": input
+                            }));
+                        }
+                    }
+                    if !blocks.is_empty() {
+                        out_messages.push(json!({ "role": "assistant", "content": blocks }));
+                    }
+                }
+                _ => {
+                    // user: 字符串直接透传；parts 数组逐个翻译 (text / data URL 图片)
+                    let translated = match content {
+                        Some(Value::Array(parts)) => {
+                            let blocks: Vec<Value> =
+                                parts.iter().filter_map(translate_user_part).collect();
+                            Value::Array(blocks)
+                        }
+                        Some(Value::String(s)) => Value::String(s.clone()),
+                        _ => Value::String(String::new()),
+                    };
+                    out_messages.push(json!({ "role": "user", "content": translated }));
+                }
+            }
+        }
+
+        let max_tokens = body
+            .get("max_tokens")
+            .or_else(|| body.get("max_completion_tokens"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(DEFAULT_MAX_TOKENS);
+
+        let mut out = json!({
+            "model": model,
+            "max_tokens": max_tokens,
+            "messages": out_messages
+        });
+
+        if !system_parts.is_empty() {
+            out["system"] = Value::String(system_parts.join("\n\n"));
+        }
+        if let Some(t) = body.get("temperature").and_then(|v| v.as_f64()) {
+            out["temperature"] = json!(t);
+        }
+        if let Some(p) = body.get("top_p").and_then(|v| v.as_f64()) {
+            out["top_p"] = json!(p);
+        }
+        match body.get("stop") {
+            Some(Value::String(s)) => out["stop_sequences"] = json!([s]),
+            Some(Value::Array(arr)) => out["stop_sequences"] = Value::Array(arr.clone()),
+            _ => {}
+        }
+        if body.get("stream").and_then(|v| v.as_bool()).unwrap_or(false) {
+            out["stream"] = json!(true);
+        }
+
+        // tools: {type:"function", function:{name,description,parameters}} → {name,description,input_schema}
+        if let Some(tools) = body.get("tools").and_then(|v| v.as_array()) {
+            let translated: Vec<Value> = tools
+                .iter()
+                .filter_map(|t| {
+                    let func = t.get("function")?;
+                    let name = func.get("name").and_then(|n| n.as_str())?;
+                    Some(json!({
+                        "name": name,
+                        "description": func.get("description").and_then(|d| d.as_str()).unwrap_or(""),
+                        "input_schema": func.get("parameters").cloned().unwrap_or_else(|| json!({"type": "object"}))
+                    }))
+                })
+                .collect();
+            if !translated.is_empty() {
+                out["tools"] = Value::Array(translated);
+            }
+        }
+        match body.get("tool_choice") {
+            Some(Value::String(s)) if s == "required" => {
+                out["tool_choice"] = json!({ "type": "any" })
+            }
+            Some(Value::String(s)) if s == "auto" => out["tool_choice"] = json!({ "type": "auto" }),
+            Some(tc) if tc.is_object() => {
+                if let Some(name) = tc
+                    .get("function")
+                    .and_then(|f| f.get("name"))
+                    .and_then(|n| n.as_str())
+                {
+                    out["tool_choice"] = json!({ "type": "tool", "name": name });
+                }
+            }
+            _ => {}
+        }
+
+        Ok(out)
+    }
+
+    fn translate_response(&self, body: &Value) -> Result<Value, String> {
+        let content = body
+            .get("content")
+            .and_then(|v| v.as_array())
+            .ok_or_else(|| "missing content in upstream response".to_string())?;
+
+        let mut text_parts: Vec<String> = Vec::new();
+        let mut tool_calls: Vec<Value> = Vec::new();
+
+        for block in content {
+            match block.get("type").and_then(|t| t.as_str()) {
+                Some("text") => {
+                    if let Some(text) = block.get("text").and_then(|t| t.as_str()) {
+                        text_parts.push(text.to_string());
+                    }
+                }
+                Some("tool_use") => {
+                    let arguments = block
+                        .get("input")
+                        .map(|i| i.to_string())
+                        .unwrap_or_else(|| "{}".to_string());
+                    tool_calls.push(json!({
+                        "id": block.get("id").and_then(|i| i.as_str()).unwrap_or_default(),
+                        "type": "function",
+                        "function": {
+                            "name": block.get("name").and_then(|n| n.as_str()).unwrap_or_default(),
+                            "arguments": arguments
+                        }
+                    }));
+                }
+                _ => {}
+            }
+        }
+
+        let stop_reason = body
+            .get("stop_reason")
+            .and_then(|s| s.as_str())
+            .unwrap_or("end_turn");
+
+        let mut message = json!({ "role": "assistant" });
+        message["content"] = if text_parts.is_empty() {
+            Value::Null
+        } else {
+            Value::String(text_parts.join(""))
+        };
+        if !tool_calls.is_empty() {
+            message["tool_calls"] = Value::Array(tool_calls);
+        }
+
+        let input_tokens = body
+            .get("usage")
+            .and_then(|u| u.get("input_tokens"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+        let output_tokens = body
+            .get("usage")
+            .and_then(|u| u.get("output_tokens"))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0);
+
+        Ok(json!({
+            "id": body.get("id").and_then(|i| i.as_str())
+                .map(|i| i.to_string())
+                .unwrap_or_else(|| format!("chatcmpl-{}", uuid::Uuid::new_v4())),
+            "object": "chat.completion",
+            "created": chrono::Utc::now().timestamp(),
+            "model": body.get("model").and_then(|m| m.as_str()).unwrap_or_default(),
+            "choices": [{
+                "index": 0,
+                "message": message,
+                "finish_reason": map_stop_reason(stop_reason)
+            }],
+            "usage": {
+                "prompt_tokens": input_tokens,
+                "completion_tokens": output_tokens,
+                "total_tokens": input_tokens + output_tokens
+            }
+        }))
+    }
+
+    fn translate_stream_event(
+        &self,
+        event: &Value,
+        ctx: &mut StreamTranslationContext,
+    ) -> Vec<Value> {
+        match event.get("type").and_then(|t| t.as_str()) {
+            Some("message_start") => {
+                if let Some(message) = event.get("message") {
+                    if let Some(id) = message.get("id").and_then(|i| i.as_str()) {
+                        ctx.message_id = id.to_string();
+                    }
+                    ctx.input_tokens = message
+                        .get("usage")
+                        .and_then(|u| u.get("input_tokens"))
+                        .and_then(|v| v.as_u64());
+                }
+                vec![make_chunk(
+                    ctx,
+                    json!({ "role": "assistant", "content": "" }),
+                    None,
+                )]
+            }
+            Some("content_block_start") => {
+                let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+                let block = event.get("content_block");
+                if block.and_then(|b| b.get("type")).and_then(|t| t.as_str()) == Some("tool_use") {
+                    let tool_index = ctx.tool_indices.len() as u64;
+                    ctx.tool_indices.insert(index, tool_index);
+                    let block = block.unwrap_or(&Value::Null);
+                    vec![make_chunk(
+                        ctx,
+                        json!({ "tool_calls": [{
+                            "index": tool_index,
+                            "id": block.get("id").and_then(|i| i.as_str()).unwrap_or_default(),
+                            "type": "function",
+                            "function": {
+                                "name": block.get("name").and_then(|n| n.as_str()).unwrap_or_default(),
+                                "arguments": ""
+                            }
+                        }]}),
+                        None,
+                    )]
+                } else {
+                    Vec::new()
+                }
+            }
+            Some("content_block_delta") => {
+                let index = event.get("index").and_then(|i| i.as_u64()).unwrap_or(0);
+                let delta = match event.get("delta") {
+                    Some(d) => d,
+                    None => return Vec::new(),
+                };
+                match delta.get("type").and_then(|t| t.as_str()) {
+                    Some("text_delta") => {
+                        let text = delta.get("text").and_then(|t| t.as_str()).unwrap_or_default();
+                        vec![make_chunk(ctx, json!({ "content": text }), None)]
+                    }
+                    // thinking 以 reasoning_content 透出，与 OpenAI↔Gemini 流保持一致
+                    Some("thinking_delta") => {
+                        let text = delta
+                            .get("thinking")
+                            .and_then(|t| t.as_str())
+                            .unwrap_or_default();
+                        vec![make_chunk(ctx, json!({ "reasoning_content": text }), None)]
+                    }
+                    Some("input_json_delta") => {
+                        let partial = delta
+                            .get("partial_json")
+                            .and_then(|p| p.as_str())
+                            .unwrap_or_default();
+                        let tool_index = ctx.tool_indices.get(&index).copied().unwrap_or(0);
+                        vec![make_chunk(
+                            ctx,
+                            json!({ "tool_calls": [{
+                                "index": tool_index,
+                                "function": { "arguments": partial }
+                            }]}),
+                            None,
+                        )]
+                    }
+                    _ => Vec::new(),
+                }
+            }
+            Some("message_delta") => {
+                ctx.output_tokens = event
+                    .get("usage")
+                    .and_then(|u| u.get("output_tokens"))
+                    .and_then(|v| v.as_u64())
+                    .or(ctx.output_tokens);
+                let stop_reason = event
+                    .get("delta")
+                    .and_then(|d| d.get("stop_reason"))
+                    .and_then(|s| s.as_str());
+                match stop_reason {
+                    Some(reason) => {
+                        let mut chunk =
+                            make_chunk(ctx, json!({}), Some(map_stop_reason(reason)));
+                        let input = ctx.input_tokens.unwrap_or(0);
+                        let output = ctx.output_tokens.unwrap_or(0);
+                        chunk["usage"] = json!({
+                            "prompt_tokens": input,
+                            "completion_tokens": output,
+                            "total_tokens": input + output
+                        });
+                        vec![chunk]
+                    }
+                    None => Vec::new(),
+                }
+            }
+            // ping / content_block_stop / message_stop 无需向客户端透出
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::proxy::mappers::interop::{translator_for, Protocol};
+
+    fn translator() -> &'static dyn ProtocolTranslator {
+        translator_for(Protocol::OpenAi, Protocol::Anthropic).expect("registered")
+    }
+
+    #[test]
+    fn test_request_translation_system_params_and_tools() {
+        let body = json!({
+            "model": "glm-4.6",
+            "max_tokens": 1024,
+            "temperature": 0.7,
+            "stop": ["END"],
+            "messages": [
+                { "role": "system", "content": "You are terse." },
+                { "role": "user", "content": "hi" },
+                { "role": "assistant", "content": "hello", "tool_calls": [
+                    { "id": "call_1", "type": "function",
+                      "function": { "name": "lookup", "arguments": "{\"q\":\"x\"}" } }
+                ]},
+                { "role": "tool", "tool_call_id": "call_1", "content": "result" }
+            ],
+            "tools": [
+                { "type": "function", "function": {
+                    "name": "lookup", "description": "d", "parameters": { "type": "object" } } }
+            ]
+        });
+
+        let out = translator().translate_request(&body).expect("translate");
+
+        assert_eq!(out["system"], "You are terse.");
+        assert_eq!(out["max_tokens"], 1024);
+        assert_eq!(out["temperature"], 0.7);
+        assert_eq!(out["stop_sequences"], json!(["END"]));
+        // system 消息不进入 messages；assistant 的 tool_calls 变为 tool_use 块
+        let messages = out["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(messages[1]["content"][1]["type"], "tool_use");
+        assert_eq!(messages[1]["content"][1]["input"], json!({"q": "x"}));
+        // tool 结果以 user 角色 tool_result 块回传
+        assert_eq!(messages[2]["role"], "user");
+        assert_eq!(messages[2]["content"][0]["type"], "tool_result");
+        assert_eq!(messages[2]["content"][0]["tool_use_id"], "call_1");
+        // tools 转为 input_schema 形式
+        assert_eq!(out["tools"][0]["name"], "lookup");
+        assert!(out["tools"][0].get("input_schema").is_some());
+    }
+
+    #[test]
+    fn test_request_translation_requires_max_tokens_default() {
+        let body = json!({
+            "model": "glm-4.6",
+            "messages": [{ "role": "user", "content": "hi" }]
+        });
+        let out = translator().translate_request(&body).expect("translate");
+        assert_eq!(out["max_tokens"], DEFAULT_MAX_TOKENS);
+    }
+
+    #[test]
+    fn test_response_translation_text_and_usage() {
+        let upstream = json!({
+            "id": "msg_123",
+            "model": "glm-4.6",
+            "content": [
+                { "type": "text", "text": "Hello " },
+                { "type": "text", "text": "world" }
+            ],
+            "stop_reason": "end_turn",
+            "usage": { "input_tokens": 10, "output_tokens": 5 }
+        });
+
+        let out = translator().translate_response(&upstream).expect("translate");
+
+        assert_eq!(out["choices"][0]["message"]["content"], "Hello world");
+        assert_eq!(out["choices"][0]["finish_reason"], "stop");
+        assert_eq!(out["usage"]["prompt_tokens"], 10);
+        assert_eq!(out["usage"]["completion_tokens"], 5);
+        assert_eq!(out["usage"]["total_tokens"], 15);
+    }
+
+    #[test]
+    fn test_response_translation_tool_use_maps_to_tool_calls() {
+        let upstream = json!({
+            "id": "msg_1",
+            "model": "glm-4.6",
+            "content": [
+                { "type": "tool_use", "id": "toolu_1", "name": "lookup", "input": { "q": "x" } }
+            ],
+            "stop_reason": "tool_use",
+            "usage": { "input_tokens": 1, "output_tokens": 1 }
+        });
+
+        let out = translator().translate_response(&upstream).expect("translate");
+
+        let tc = &out["choices"][0]["message"]["tool_calls"][0];
+        assert_eq!(tc["function"]["name"], "lookup");
+        assert_eq!(
+            serde_json::from_str::<Value>(tc["function"]["arguments"].as_str().unwrap()).unwrap(),
+            json!({ "q": "x" })
+        );
+        assert_eq!(out["choices"][0]["finish_reason"], "tool_calls");
+    }
+
+    #[test]
+    fn test_stream_event_sequence() {
+        let t = translator();
+        let mut ctx = StreamTranslationContext::new("glm-4.6");
+
+        let start = t.translate_stream_event(
+            &json!({ "type": "message_start", "message": {
+                "id": "msg_1", "usage": { "input_tokens": 7 } } }),
+            &mut ctx,
+        );
+        assert_eq!(start.len(), 1);
+        assert_eq!(start[0]["choices"][0]["delta"]["role"], "assistant");
+        assert_eq!(ctx.message_id, "msg_1");
+
+        let text = t.translate_stream_event(
+            &json!({ "type": "content_block_delta", "index": 0,
+                "delta": { "type": "text_delta", "text": "hi" } }),
+            &mut ctx,
+        );
+        assert_eq!(text[0]["choices"][0]["delta"]["content"], "hi");
+
+        // ping 不产生输出
+        assert!(t
+            .translate_stream_event(&json!({ "type": "ping" }), &mut ctx)
+            .is_empty());
+
+        let done = t.translate_stream_event(
+            &json!({ "type": "message_delta",
+                "delta": { "stop_reason": "end_turn" },
+                "usage": { "output_tokens": 3 } }),
+            &mut ctx,
+        );
+        assert_eq!(done[0]["choices"][0]["finish_reason"], "stop");
+        assert_eq!(done[0]["usage"]["prompt_tokens"], 7);
+        assert_eq!(done[0]["usage"]["completion_tokens"], 3);
+    }
+
+    #[test]
+    fn test_stream_tool_use_indices() {
+        let t = translator();
+        let mut ctx = StreamTranslationContext::new("glm-4.6");
+
+        let start = t.translate_stream_event(
+            &json!({ "type": "content_block_start", "index": 1,
+                "content_block": { "type": "tool_use", "id": "toolu_1", "name": "lookup" } }),
+            &mut ctx,
+        );
+        assert_eq!(start[0]["choices"][0]["delta"]["tool_calls"][0]["index"], 0);
+
+        let args = t.translate_stream_event(
+            &json!({ "type": "content_block_delta", "index": 1,
+                "delta": { "type": "input_json_delta", "partial_json": "{\"q\"" } }),
+            &mut ctx,
+        );
+        assert_eq!(
+            args[0]["choices"][0]["delta"]["tool_calls"][0]["function"]["arguments"],
+            "{\"q\""
+        );
+    }
+}
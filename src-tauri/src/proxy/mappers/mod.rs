@@ -7,6 +7,7 @@ pub mod context_manager;
 pub mod error_classifier;
 pub mod estimation_calibrator;
 pub mod gemini;
+pub mod interop;
 pub mod openai;
 pub mod signature_store;
 pub mod tool_result_compressor;
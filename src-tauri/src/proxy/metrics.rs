@@ -0,0 +1,145 @@
+// [NEW] Prometheus 指标端点 - 独立监听地址，供运维抓取
+// 手写文本格式 (text exposition format)，不引入 prometheus 依赖
+// 不依赖 Tauri webview，Headless 模式同样可用
+
+use axum::{extract::State, http::StatusCode, response::IntoResponse, routing::get, Router};
+use std::sync::Arc;
+
+use crate::proxy::monitor::ProxyMonitor;
+use crate::proxy::TokenManager;
+
+/// 指标服务器共享状态
+#[derive(Clone)]
+struct MetricsState {
+    monitor: Arc<ProxyMonitor>,
+    token_manager: Arc<TokenManager>,
+}
+
+/// 启动独立的指标服务器 (配置未启用时为空操作)
+pub async fn start_metrics_server(
+    config: &crate::proxy::config::MetricsConfig,
+    monitor: Arc<ProxyMonitor>,
+    token_manager: Arc<TokenManager>,
+) {
+    if !config.enabled {
+        return;
+    }
+
+    let bind = config.bind.clone();
+    let listener = match tokio::net::TcpListener::bind(&bind).await {
+        Ok(l) => l,
+        Err(e) => {
+            tracing::error!("指标端点绑定 {} 失败: {}", bind, e);
+            return;
+        }
+    };
+
+    tracing::info!("指标端点启动在 http://{}/metrics", bind);
+
+    let state = MetricsState {
+        monitor,
+        token_manager,
+    };
+    let app = Router::new()
+        .route("/metrics", get(handle_metrics))
+        .with_state(state);
+
+    tokio::spawn(async move {
+        if let Err(e) = axum::serve(listener, app).await {
+            tracing::error!("指标服务器异常退出: {}", e);
+        }
+    });
+}
+
+/// GET /metrics - Prometheus 文本格式
+async fn handle_metrics(State(state): State<MetricsState>) -> impl IntoResponse {
+    let body = render_metrics(&state).await;
+    (
+        StatusCode::OK,
+        [("content-type", "text/plain; version=0.0.4; charset=utf-8")],
+        body,
+    )
+}
+
+/// 汇总 ProxyStats / token_stats / 账号池 / 黑名单为指标文本
+async fn render_metrics(state: &MetricsState) -> String {
+    let mut out = String::with_capacity(1024);
+
+    // 请求计数 (来自 ProxyStats)
+    let stats = state.monitor.get_stats().await;
+    out.push_str("# HELP topoo_requests_total Total proxied requests.\n");
+    out.push_str("# TYPE topoo_requests_total counter\n");
+    out.push_str(&format!(
+        "topoo_requests_total {}\n",
+        stats.total_requests
+    ));
+    out.push_str("# HELP topoo_requests_success_total Proxied requests with 2xx/3xx status.\n");
+    out.push_str("# TYPE topoo_requests_success_total counter\n");
+    out.push_str(&format!(
+        "topoo_requests_success_total {}\n",
+        stats.success_count
+    ));
+    out.push_str("# HELP topoo_requests_error_total Proxied requests with 4xx/5xx status.\n");
+    out.push_str("# TYPE topoo_requests_error_total counter\n");
+    out.push_str(&format!(
+        "topoo_requests_error_total {}\n",
+        stats.error_count
+    ));
+
+    // 活跃账号数
+    out.push_str("# HELP topoo_active_accounts Accounts currently loaded in the token pool.\n");
+    out.push_str("# TYPE topoo_active_accounts gauge\n");
+    out.push_str(&format!(
+        "topoo_active_accounts {}\n",
+        state.token_manager.len()
+    ));
+
+    // IP 黑名单条目数
+    let blocked_ips = tokio::task::spawn_blocking(|| {
+        crate::modules::security_db::get_blacklist().map(|l| l.len())
+    })
+    .await
+    .ok()
+    .and_then(|r| r.ok())
+    .unwrap_or(0);
+    out.push_str("# HELP topoo_blocked_ips IP blacklist entry count.\n");
+    out.push_str("# TYPE topoo_blocked_ips gauge\n");
+    out.push_str(&format!("topoo_blocked_ips {}\n", blocked_ips));
+
+    // 按模型聚合的 Token 用量 (近 30 天)
+    let model_stats = tokio::task::spawn_blocking(|| {
+        crate::modules::token_stats::get_model_stats(30 * 24)
+    })
+    .await
+    .ok()
+    .and_then(|r| r.ok())
+    .unwrap_or_default();
+    out.push_str("# HELP topoo_model_input_tokens_total Input tokens per model (last 30d).\n");
+    out.push_str("# TYPE topoo_model_input_tokens_total counter\n");
+    for m in &model_stats {
+        out.push_str(&format!(
+            "topoo_model_input_tokens_total{{model=\"{}\"}} {}\n",
+            escape_label(&m.model),
+            m.total_input_tokens
+        ));
+    }
+    out.push_str("# HELP topoo_model_output_tokens_total Output tokens per model (last 30d).\n");
+    out.push_str("# TYPE topoo_model_output_tokens_total counter\n");
+    for m in &model_stats {
+        out.push_str(&format!(
+            "topoo_model_output_tokens_total{{model=\"{}\"}} {}\n",
+            escape_label(&m.model),
+            m.total_output_tokens
+        ));
+    }
+
+    out
+}
+
+/// 转义 Prometheus 标签值中的特殊字符
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -11,6 +11,17 @@ use tokio::sync::RwLock;
 
 use crate::proxy::{ProxyAuthMode, ProxySecurityConfig};
 
+/// [NEW] 内部头: 携带用户令牌固定路由的账号 ID (仅由认证中间件注入，入口处会清除客户端伪造值)
+pub const PINNED_ACCOUNT_HEADER: &str = "x-topoo-pinned-account";
+
+/// [NEW] 从请求头中读取用户令牌固定路由的账号 ID
+pub fn pinned_account_from_headers(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get(PINNED_ACCOUNT_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(|s| s.to_string())
+}
+
 /// API Key 认证中间件 (代理接口使用，遵循 auth_mode)
 pub async fn auth_middleware(
     state: State<Arc<RwLock<ProxySecurityConfig>>>,
@@ -36,6 +47,10 @@ async fn auth_middleware_internal(
     next: Next,
     force_strict: bool,
 ) -> Result<Response, StatusCode> {
+    let mut request = request;
+    // [NEW] 清除客户端伪造的内部固定路由头，只有本中间件可以注入
+    request.headers_mut().remove(PINNED_ACCOUNT_HEADER);
+
     let method = request.method().clone();
     let path = request.uri().path().to_string();
 
@@ -81,9 +96,16 @@ async fn auth_middleware_internal(
                         token_id: user_token.id,
                         token: user_token.token,
                         username: user_token.username,
+                        pinned_account_id: user_token.pinned_account_id.clone(),
                     };
                     // 注入 identity 到请求
                     let (mut parts, body) = request.into_parts();
+                    // [NEW] 固定路由账号通过内部头传递给 handlers (extensions 对 handler 不可见)
+                    if let Some(pinned) = &user_token.pinned_account_id {
+                        if let Ok(value) = axum::http::HeaderValue::from_str(pinned) {
+                            parts.headers.insert(PINNED_ACCOUNT_HEADER, value);
+                        }
+                    }
                     parts.extensions.insert(identity);
                     let request = Request::from_parts(parts, body);
                     return Ok(next.run(request).await);
@@ -166,19 +188,8 @@ async fn auth_middleware_internal(
         // 尝试验证 UserToken
         let token = api_key.unwrap();
         
-        // 提取 IP (复用逻辑)
-        let client_ip = request
-            .headers()
-            .get("x-forwarded-for")
-            .and_then(|v| v.to_str().ok())
-            .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-            .or_else(|| {
-                request
-                    .headers()
-                    .get("x-real-ip")
-                    .and_then(|v| v.to_str().ok())
-                    .map(|s| s.to_string())
-            })
+        // 提取 IP (复用 client_ip 模块：归一化 + 可配置的转发头采信)
+        let client_ip = crate::proxy::middleware::client_ip::extract_client_ip(&request)
             .unwrap_or_else(|| "127.0.0.1".to_string()); // Default fallback
 
         // 验证 Token
@@ -190,17 +201,24 @@ async fn auth_middleware_internal(
                         token_id: user_token.id,
                         token: user_token.token,
                         username: user_token.username,
+                        pinned_account_id: user_token.pinned_account_id.clone(),
                     };
-                    
+
                     // [FIX] 将身份信息注入到请求 extensions 中，而不是响应
                     // 这样 monitor_middleware 在处理请求时就能获取到 identity
                     // 因为中间件执行顺序：auth (外层) -> monitor (内层) -> handler
                     // 响应返回时：handler -> monitor -> auth
                     // 如果注入到 response，monitor 执行时 identity 还不存在
                     let (mut parts, body) = request.into_parts();
+                    // [NEW] 固定路由账号通过内部头传递给 handlers (extensions 对 handler 不可见)
+                    if let Some(pinned) = &identity.pinned_account_id {
+                        if let Ok(value) = axum::http::HeaderValue::from_str(pinned) {
+                            parts.headers.insert(PINNED_ACCOUNT_HEADER, value);
+                        }
+                    }
                     parts.extensions.insert(identity);
                     let request = Request::from_parts(parts, body);
-                    
+
                     // 执行请求
                     let response = next.run(request).await;
                     
@@ -229,6 +247,8 @@ pub struct UserTokenIdentity {
     pub token_id: String,
     pub token: String,
     pub username: String,
+    /// [NEW] 固定路由到指定账号 (不可用时回退正常调度)
+    pub pinned_account_id: Option<String>,
 }
 
 #[cfg(test)]
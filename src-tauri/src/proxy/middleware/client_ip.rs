@@ -0,0 +1,108 @@
+// [NEW] 客户端 IP 提取与归一化 (供 ip_filter / monitor / auth 共用)
+//
+// 解决两个问题：
+// 1. 同一客户端可能同时出现 `::ffff:192.168.1.5` 和 `192.168.1.5` 两种形式，
+//    打散按 IP 的统计并导致黑名单匹配失效 —— IPv4-mapped IPv6 统一归一化为 IPv4。
+// 2. X-Forwarded-For / X-Real-IP 可被客户端伪造，只有在反代后面
+//    (`proxy.trust_forwarded_headers = true`，默认开启以保持原有行为) 才应采信；
+//    直接暴露公网时建议关闭，此时只取 TCP 连接对端 IP。
+
+use axum::extract::Request;
+
+/// 归一化 IP 字符串：IPv4-mapped IPv6 (`::ffff:a.b.c.d`) 转为纯 IPv4 形式，
+/// 其余（含解析失败的原始串）按 trim 后原样返回
+pub fn normalize_ip(raw: &str) -> String {
+    let trimmed = raw.trim();
+    match trimmed.parse::<std::net::IpAddr>() {
+        Ok(std::net::IpAddr::V6(v6)) => {
+            if let Some(v4) = v6.to_ipv4_mapped() {
+                v4.to_string()
+            } else {
+                v6.to_string()
+            }
+        }
+        Ok(std::net::IpAddr::V4(v4)) => v4.to_string(),
+        Err(_) => trimmed.to_string(),
+    }
+}
+
+/// 从转发头中解析客户端 IP：X-Forwarded-For 取最左侧一跳，备选 X-Real-IP
+fn ip_from_forwarded_headers(headers: &axum::http::HeaderMap) -> Option<String> {
+    headers
+        .get("x-forwarded-for")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.split(',').next())
+        .map(|s| s.trim())
+        .filter(|s| !s.is_empty())
+        .map(normalize_ip)
+        .or_else(|| {
+            headers
+                .get("x-real-ip")
+                .and_then(|v| v.to_str().ok())
+                .map(normalize_ip)
+        })
+}
+
+/// 从请求中提取归一化后的客户端 IP。
+/// 采信转发头需 `proxy.trust_forwarded_headers` 开启；否则 (或无转发头时)
+/// 回退到 ConnectInfo 中的 TCP 连接对端 IP
+pub fn extract_client_ip(request: &Request) -> Option<String> {
+    let forwarded = if crate::proxy::config::get_trust_forwarded_headers() {
+        ip_from_forwarded_headers(request.headers())
+    } else {
+        None
+    };
+
+    forwarded.or_else(|| {
+        request
+            .extensions()
+            .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
+            .map(|info| normalize_ip(&info.0.ip().to_string()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_ipv4_mapped_address() {
+        assert_eq!(normalize_ip("::ffff:192.168.1.5"), "192.168.1.5");
+        assert_eq!(normalize_ip(" ::ffff:10.0.0.1 "), "10.0.0.1");
+        // Plain addresses pass through
+        assert_eq!(normalize_ip("192.168.1.5"), "192.168.1.5");
+        assert_eq!(normalize_ip("2001:db8::1"), "2001:db8::1");
+        // Unparseable input is returned trimmed, not dropped
+        assert_eq!(normalize_ip(" not-an-ip "), "not-an-ip");
+    }
+
+    #[test]
+    fn test_forwarded_headers_take_first_hop_and_normalize() {
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert(
+            "x-forwarded-for",
+            "::ffff:203.0.113.7, 10.0.0.2, 10.0.0.3".parse().unwrap(),
+        );
+        assert_eq!(
+            ip_from_forwarded_headers(&headers),
+            Some("203.0.113.7".to_string())
+        );
+
+        // X-Real-IP is the fallback when XFF is absent
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-real-ip", "::ffff:198.51.100.4".parse().unwrap());
+        assert_eq!(
+            ip_from_forwarded_headers(&headers),
+            Some("198.51.100.4".to_string())
+        );
+
+        // Empty XFF entries don't produce an empty IP
+        let mut headers = axum::http::HeaderMap::new();
+        headers.insert("x-forwarded-for", ", 10.0.0.2".parse().unwrap());
+        headers.insert("x-real-ip", "10.0.0.9".parse().unwrap());
+        assert_eq!(
+            ip_from_forwarded_headers(&headers),
+            Some("10.0.0.9".to_string())
+        );
+    }
+}
@@ -145,29 +145,9 @@ pub async fn ip_filter_middleware(
 }
 
 /// 从请求中提取客户端 IP
+/// [FIX] 统一走 client_ip 模块：IPv4-mapped IPv6 归一化 + 可配置的转发头采信
 fn extract_client_ip(request: &Request) -> Option<String> {
-    // 1. 优先从 X-Forwarded-For 提取 (取第一个 IP)
-    request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            // 2. 备选从 X-Real-IP 提取
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        })
-        .or_else(|| {
-            // 3. 最后尝试从 ConnectInfo 获取 (TCP 连接 IP)
-            // 这可以解决本地开发/测试时没有代理头导致 IP 获取失败的问题
-            request
-                .extensions()
-                .get::<axum::extract::ConnectInfo<std::net::SocketAddr>>()
-                .map(|info| info.0.ip().to_string())
-        })
+    crate::proxy::middleware::client_ip::extract_client_ip(request)
 }
 
 /// 创建被封禁的响应
@@ -1,12 +1,15 @@
 // Middleware 模块 - Axum 中间件
 
 pub mod auth;
+pub mod client_ip;
 pub mod cors;
 pub mod logging;
 pub mod monitor;
 pub mod ip_filter;
 
+pub mod response_cache;
 pub mod service_status;
+pub mod sticky_session;
 
 pub use cors::cors_layer;
 pub use monitor::monitor_middleware;
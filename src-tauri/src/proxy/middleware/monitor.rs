@@ -4,7 +4,7 @@ use axum::{
     body::Body,
     extract::{Request, State},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use futures::StreamExt;
 use serde_json::Value;
@@ -13,6 +13,61 @@ use std::time::Instant;
 const MAX_REQUEST_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB
 const MAX_RESPONSE_LOG_SIZE: usize = 100 * 1024 * 1024; // 100MB for image responses
 
+/// [NEW] Anthropic `usage` 对象的结构化解析结果。
+/// `input_tokens` 在 Anthropic 语义下不含缓存读写部分，缓存字段单独计
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct AnthropicUsage {
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cache_creation_input_tokens: Option<u32>,
+    pub cache_read_input_tokens: Option<u32>,
+}
+
+impl AnthropicUsage {
+    /// 缓存读 + 缓存写合计 (两者都不在 input_tokens 中)；都缺省时为 None
+    pub fn cache_total(&self) -> Option<u32> {
+        match (self.cache_creation_input_tokens, self.cache_read_input_tokens) {
+            (None, None) => None,
+            (creation, read) => Some(creation.unwrap_or(0) + read.unwrap_or(0)),
+        }
+    }
+}
+
+/// [NEW] 结构化解析 Anthropic usage (含缓存字段)
+pub fn parse_anthropic_usage(usage: &Value) -> AnthropicUsage {
+    let field = |name: &str| usage.get(name).and_then(|v| v.as_u64()).map(|v| v as u32);
+    AnthropicUsage {
+        input_tokens: field("input_tokens"),
+        output_tokens: field("output_tokens"),
+        cache_creation_input_tokens: field("cache_creation_input_tokens"),
+        cache_read_input_tokens: field("cache_read_input_tokens"),
+    }
+}
+
+/// [NEW] 从 usage 对象提取缓存/推理 token 细分 (OpenAI / Anthropic / Gemini)
+fn extract_token_details(usage: &Value, log: &mut ProxyRequestLog) {
+    // [FIX] Anthropic 的 cache_creation_input_tokens 此前被直接丢弃，
+    // 缓存密集场景下成本统计偏低；读/写缓存合并记入 cached_input_tokens 单列
+    let anthropic = parse_anthropic_usage(usage);
+    log.cached_input_tokens = anthropic
+        .cache_total()
+        .or(usage
+            .get("prompt_tokens_details")
+            .and_then(|d| d.get("cached_tokens"))
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32))
+        .or(usage
+            .get("cachedContentTokenCount")
+            .and_then(|v| v.as_u64())
+            .map(|v| v as u32));
+    log.reasoning_tokens = usage
+        .get("completion_tokens_details")
+        .and_then(|d| d.get("reasoning_tokens"))
+        .or(usage.get("thoughtsTokenCount"))
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32);
+}
+
 pub async fn monitor_middleware(
     State(state): State<AppState>,
     request: Request,
@@ -29,21 +84,10 @@ pub async fn monitor_middleware(
 
     let start = Instant::now();
 
-    // Extract client IP from headers (X-Forwarded-For or X-Real-IP)
+    // Extract client IP (shared logic: mapped-IPv6 normalization + configurable header trust)
     // IMPORTANT: Extract from Request headers, not Response headers (since we want the client's IP)
     // Note: We need to do this BEFORE consuming the request body if possible, or extract it from the original request
-    let client_ip = request
-        .headers()
-        .get("x-forwarded-for")
-        .and_then(|v| v.to_str().ok())
-        .map(|s| s.split(',').next().unwrap_or(s).trim().to_string())
-        .or_else(|| {
-            request
-                .headers()
-                .get("x-real-ip")
-                .and_then(|v| v.to_str().ok())
-                .map(|s| s.to_string())
-        });
+    let client_ip = crate::proxy::middleware::client_ip::extract_client_ip(&request);
 
     let mut model = if uri.contains("/v1beta/models/") {
         uri.split("/v1beta/models/")
@@ -54,28 +98,86 @@ pub async fn monitor_middleware(
         None
     };
 
+    // [NEW] 请求体大小防护：超过 max_request_body_bytes 的请求在缓冲前
+    // 直接 413 拒绝；超过提取阈值但未超上限的不整体缓冲，流式转发并跳过提取
+    let max_body_bytes = crate::proxy::config::get_max_request_body_bytes();
+    let content_length = request
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+
+    if let Some(len) = content_length {
+        if max_body_bytes > 0 && len > max_body_bytes {
+            tracing::warn!(
+                "Rejecting request: body {} bytes exceeds max_request_body_bytes ({})",
+                len,
+                max_body_bytes
+            );
+            return (
+                axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+                format!(
+                    "Request body ({} bytes) exceeds max_request_body_bytes ({})",
+                    len, max_body_bytes
+                ),
+            )
+                .into_response();
+        }
+    }
+
     let request_body_str;
     let request = if method == "POST" {
-        let (parts, body) = request.into_parts();
-        match axum::body::to_bytes(body, MAX_REQUEST_LOG_SIZE).await {
-            Ok(bytes) => {
-                if model.is_none() {
-                    model = serde_json::from_slice::<Value>(&bytes).ok().and_then(|v| {
-                        v.get("model")
-                            .and_then(|m| m.as_str())
-                            .map(|s| s.to_string())
-                    });
-                }
-                request_body_str = if let Ok(s) = std::str::from_utf8(&bytes) {
-                    Some(s.to_string())
-                } else {
-                    Some("[Binary Request Data]".to_string())
-                };
-                Request::from_parts(parts, Body::from(bytes))
+        match content_length {
+            Some(len) if len as usize > MAX_REQUEST_LOG_SIZE => {
+                // 超大请求体：不缓冲，直接流式转发 (token/模型提取跳过)
+                tracing::info!(
+                    "Request body ({} bytes) exceeds extraction threshold ({}), streaming without token extraction",
+                    len,
+                    MAX_REQUEST_LOG_SIZE
+                );
+                request_body_str = Some(format!("[Request body too large to log: {} bytes]", len));
+                request
             }
-            Err(_) => {
-                request_body_str = None;
-                Request::from_parts(parts, Body::empty())
+            _ => {
+                let (parts, body) = request.into_parts();
+                match axum::body::to_bytes(body, MAX_REQUEST_LOG_SIZE).await {
+                    Ok(bytes) => {
+                        // [NEW] Content-Length 缺失/撒谎时的兜底校验
+                        if max_body_bytes > 0 && bytes.len() as u64 > max_body_bytes {
+                            tracing::warn!(
+                                "Rejecting request: buffered body {} bytes exceeds max_request_body_bytes ({})",
+                                bytes.len(),
+                                max_body_bytes
+                            );
+                            return (
+                                axum::http::StatusCode::PAYLOAD_TOO_LARGE,
+                                format!(
+                                    "Request body ({} bytes) exceeds max_request_body_bytes ({})",
+                                    bytes.len(),
+                                    max_body_bytes
+                                ),
+                            )
+                                .into_response();
+                        }
+                        if model.is_none() {
+                            model = serde_json::from_slice::<Value>(&bytes).ok().and_then(|v| {
+                                v.get("model")
+                                    .and_then(|m| m.as_str())
+                                    .map(|s| s.to_string())
+                            });
+                        }
+                        request_body_str = if let Ok(s) = std::str::from_utf8(&bytes) {
+                            Some(s.to_string())
+                        } else {
+                            Some("[Binary Request Data]".to_string())
+                        };
+                        Request::from_parts(parts, Body::from(bytes))
+                    }
+                    Err(_) => {
+                        request_body_str = None;
+                        Request::from_parts(parts, Body::empty())
+                    }
+                }
             }
         }
     } else {
@@ -101,6 +203,13 @@ pub async fn monitor_middleware(
         .unwrap_or("")
         .to_string();
 
+    // [NEW] 响应缓存命中标记 (response_cache 中间件注入)
+    let cache_hit = response
+        .headers()
+        .get(crate::proxy::middleware::response_cache::CACHE_STATUS_HEADER)
+        .map(|v| v == "hit")
+        .unwrap_or(false);
+
     // Extract account email from X-Account-Email header if present
     let account_email = response
         .headers()
@@ -116,7 +225,7 @@ pub async fn monitor_middleware(
         .map(|s| s.to_string());
 
     // Determine protocol from URL path
-    let protocol = if uri.contains("/v1/messages") {
+    let mut protocol = if uri.contains("/v1/messages") {
         Some("anthropic".to_string())
     } else if uri.contains("/v1beta/models") {
         Some("gemini".to_string())
@@ -126,6 +235,20 @@ pub async fn monitor_middleware(
         None
     };
 
+    // [NEW] 协议翻译路径：上游协议与客户端协议不同时记录为 "client->upstream"
+    if let Some(upstream_protocol) = response
+        .headers()
+        .get("X-Upstream-Protocol")
+        .and_then(|v| v.to_str().ok())
+    {
+        match protocol.as_deref() {
+            Some(client_protocol) if client_protocol != upstream_protocol => {
+                protocol = Some(format!("{}->{}", client_protocol, upstream_protocol));
+            }
+            _ => {}
+        }
+    }
+
     // Client IP has been extracted at the beginning of the function
 
     let monitor = state.monitor.clone();
@@ -145,6 +268,12 @@ pub async fn monitor_middleware(
         response_body: None,
         input_tokens: None,
         output_tokens: None,
+        cached_input_tokens: None,
+        reasoning_tokens: None,
+        tokens_estimated: false,
+        note: None,
+        flagged: false,
+        cached: cache_hit,
         protocol,
         username,
     };
@@ -157,6 +286,10 @@ pub async fn monitor_middleware(
         tokio::spawn(async move {
             let mut all_stream_data = Vec::new();
             let mut last_few_bytes = Vec::new();
+            // [NEW] 流式文本的估算 token 数 (仅在未收到 usage 事件时作为兜底)
+            let mut estimated_output_tokens: u32 = 0;
+            // [NEW] 客户端是否中途断开 (send 失败 = 响应侧 Receiver 已被丢弃)
+            let mut client_disconnected = false;
 
             while let Some(chunk_res) = stream.next().await {
                 if let Ok(chunk) = chunk_res {
@@ -170,12 +303,25 @@ pub async fn monitor_middleware(
                             last_few_bytes.drain(0..last_few_bytes.len() - 8192);
                         }
                     }
-                    let _ = tx.send(Ok::<_, axum::Error>(chunk)).await;
+                    if tx.send(Ok::<_, axum::Error>(chunk)).await.is_err() {
+                        client_disconnected = true;
+                        break;
+                    }
                 } else if let Err(e) = chunk_res {
-                    let _ = tx.send(Err(axum::Error::new(e))).await;
+                    if tx.send(Err(axum::Error::new(e))).await.is_err() {
+                        client_disconnected = true;
+                        break;
+                    }
                 }
             }
 
+            // [NEW] 客户端断开后立即丢弃上游流: 底层连接关闭即取消上游请求，
+            // 不再白白读完剩余内容消耗账号配额
+            if client_disconnected {
+                drop(stream);
+                tracing::info!("Client disconnected mid-stream, aborting upstream request");
+            }
+
             // Parse and consolidate stream data into readable format
             if let Ok(full_response) = std::str::from_utf8(&all_stream_data) {
                 let mut thinking_content = String::new();
@@ -254,10 +400,16 @@ pub async fn monitor_middleware(
                                     .and_then(|v| v.as_u64())
                                     .map(|v| v as u32);
                             }
+                            extract_token_details(usage, &mut log);
                         }
                     }
                 }
 
+                // [NEW] Estimate from concatenated streamed text, applied later only
+                // if no terminal usage event showed up
+                estimated_output_tokens = crate::proxy::mappers::context_manager::estimate_tokens_from_str(&response_content)
+                    + crate::proxy::mappers::context_manager::estimate_tokens_from_str(&thinking_content);
+
                 // Build consolidated response object
                 let mut consolidated = serde_json::Map::new();
 
@@ -322,6 +474,7 @@ pub async fn monitor_middleware(
                                         .or(usage.get("candidatesTokenCount"))
                                         .and_then(|v| v.as_u64())
                                         .map(|v| v as u32);
+                                    extract_token_details(usage, &mut log);
                                     break;
                                 }
                             }
@@ -330,6 +483,15 @@ pub async fn monitor_middleware(
                 }
             }
 
+            // [NEW] 兜底: 整条流都没有 usage 事件时, 用流式文本估算输出 token 并标记为估算值
+            if log.input_tokens.is_none()
+                && log.output_tokens.is_none()
+                && estimated_output_tokens > 0
+            {
+                log.output_tokens = Some(estimated_output_tokens);
+                log.tokens_estimated = true;
+            }
+
             if log.status >= 400 {
                 log.error = Some("Stream Error or Failed".to_string());
 
@@ -357,6 +519,12 @@ pub async fn monitor_middleware(
                     }
                 }
             }
+
+            // [NEW] 客户端中途断开: 记为 499，保留已解析/估算出的部分 token 计数
+            if client_disconnected {
+                log.status = 499;
+                log.error = Some("client_disconnected".to_string());
+            }
             monitor.log_request(log).await;
         });
 
@@ -392,6 +560,7 @@ pub async fn monitor_middleware(
                                     .and_then(|v| v.as_u64())
                                     .map(|v| v as u32);
                             }
+                            extract_token_details(usage, &mut log);
                         }
                     }
                     log.response_body = Some(s.to_string());
@@ -399,6 +568,14 @@ pub async fn monitor_middleware(
                     log.response_body = Some("[Binary Response Data]".to_string());
                 }
 
+                // [NEW] 缓存命中不计 token：未调用上游，不应计入配额统计
+                if log.cached {
+                    log.input_tokens = None;
+                    log.output_tokens = None;
+                    log.cached_input_tokens = None;
+                    log.reasoning_tokens = None;
+                }
+
                 if log.status >= 400 {
                     log.error = log.response_body.clone();
 
@@ -442,3 +619,65 @@ pub async fn monitor_middleware(
         response
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_anthropic_usage, AnthropicUsage};
+
+    // ==================================================================================
+    // Anthropic usage 样例解析: 缓存读/写字段不再丢弃，也不混入普通 input
+    // ==================================================================================
+
+    #[test]
+    fn test_parse_anthropic_usage_with_cache_fields() {
+        let body: serde_json::Value = serde_json::from_str(
+            r#"{
+                "id": "msg_01XFDUDYJgAACzvnptvVoYEL",
+                "type": "message",
+                "role": "assistant",
+                "model": "claude-sonnet-4-5",
+                "usage": {
+                    "input_tokens": 21,
+                    "output_tokens": 305,
+                    "cache_creation_input_tokens": 1024,
+                    "cache_read_input_tokens": 2048
+                }
+            }"#,
+        )
+        .unwrap();
+
+        let usage = parse_anthropic_usage(body.get("usage").unwrap());
+        assert_eq!(
+            usage,
+            AnthropicUsage {
+                input_tokens: Some(21),
+                output_tokens: Some(305),
+                cache_creation_input_tokens: Some(1024),
+                cache_read_input_tokens: Some(2048),
+            }
+        );
+        // 读 + 写合计记入 cached_input_tokens；input 保持 Anthropic 原义不含缓存
+        assert_eq!(usage.cache_total(), Some(3072));
+    }
+
+    #[test]
+    fn test_parse_anthropic_usage_without_cache_fields() {
+        let usage_json: serde_json::Value =
+            serde_json::from_str(r#"{"input_tokens": 10, "output_tokens": 20}"#).unwrap();
+        let usage = parse_anthropic_usage(&usage_json);
+        assert_eq!(usage.input_tokens, Some(10));
+        assert_eq!(usage.output_tokens, Some(20));
+        // 无缓存字段时不得伪造 0 值，避免把 OpenAI/Gemini 的缓存来源覆盖掉
+        assert_eq!(usage.cache_total(), None);
+    }
+
+    #[test]
+    fn test_parse_anthropic_usage_cache_read_only() {
+        let usage_json: serde_json::Value = serde_json::from_str(
+            r#"{"input_tokens": 5, "output_tokens": 7, "cache_read_input_tokens": 512}"#,
+        )
+        .unwrap();
+        let usage = parse_anthropic_usage(&usage_json);
+        assert_eq!(usage.cache_total(), Some(512));
+    }
+}
@@ -0,0 +1,263 @@
+// [NEW] 幂等请求响应缓存中间件
+//
+// 启用 response_cache 后，按 方法+URL+请求体+模型 的 SHA-256 哈希缓存
+// 成功的非流式响应；TTL 内完全相同的请求直接返回缓存，不调用上游、
+// 不消耗 token。命中响应带缓存标记头，monitor 据此记 cached: true
+// 并跳过 token 计数。
+
+use axum::{
+    body::{Body, Bytes},
+    extract::Request,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use dashmap::DashMap;
+use futures::StreamExt;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::LazyLock;
+
+/// 缓存命中标记头 (仅内部使用，monitor 读取后对外仍可见，便于客户端排查)
+pub const CACHE_STATUS_HEADER: &str = "x-topoo-response-cache";
+
+/// 单条可缓存请求/响应体上限：过大的 body 不值得进内存缓存
+const MAX_CACHEABLE_BODY_BYTES: usize = 4 * 1024 * 1024;
+
+/// 缓存的响应快照
+#[derive(Debug, Clone)]
+struct CachedResponse {
+    status: u16,
+    content_type: String,
+    body: Vec<u8>,
+    expires_at: i64,
+}
+
+static RESPONSE_CACHE: LazyLock<DashMap<String, CachedResponse>> = LazyLock::new(DashMap::new);
+
+static CACHE_HITS: AtomicU64 = AtomicU64::new(0);
+static CACHE_MISSES: AtomicU64 = AtomicU64::new(0);
+
+/// [NEW] 缓存命中统计 (进程生命周期内累计)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct ResponseCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+/// 当前缓存统计 (顺带淘汰过期条目，entries 只计有效条目)
+pub fn cache_stats() -> ResponseCacheStats {
+    let now = chrono::Utc::now().timestamp();
+    RESPONSE_CACHE.retain(|_, entry| entry.expires_at > now);
+    ResponseCacheStats {
+        hits: CACHE_HITS.load(Ordering::Relaxed),
+        misses: CACHE_MISSES.load(Ordering::Relaxed),
+        entries: RESPONSE_CACHE.len(),
+    }
+}
+
+/// 清空缓存，返回清除条数 (配置变更或排查时用)
+pub fn clear_cache() -> usize {
+    let count = RESPONSE_CACHE.len();
+    RESPONSE_CACHE.clear();
+    count
+}
+
+/// 缓存键：方法 + URL + 请求体 + 模型 的 SHA-256
+fn cache_key(method: &str, uri: &str, body: &[u8], model: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(method.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(uri.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(model.as_bytes());
+    hasher.update(b"\n");
+    hasher.update(body);
+    format!("{:x}", hasher.finalize())
+}
+
+fn lookup(key: &str) -> Option<CachedResponse> {
+    let now = chrono::Utc::now().timestamp();
+    let entry = RESPONSE_CACHE.get(key).map(|e| e.value().clone())?;
+    if entry.expires_at > now {
+        Some(entry)
+    } else {
+        RESPONSE_CACHE.remove(key);
+        None
+    }
+}
+
+/// [FIX] 有上限地缓冲 body：不超过 cap 时返回完整字节；超过时返回
+/// 已读前缀与剩余流拼接成的透传 body，避免把超大 body 整段读进内存
+enum BufferedBody {
+    Complete(Bytes),
+    TooLarge(Body),
+    ReadError,
+}
+
+async fn buffer_body_capped(body: Body, cap: usize) -> BufferedBody {
+    let mut stream = body.into_data_stream();
+    let mut buffered: Vec<u8> = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => {
+                if buffered.len() + chunk.len() > cap {
+                    let prefix =
+                        futures::stream::iter(vec![Ok::<_, axum::Error>(Bytes::from(buffered)), Ok(chunk)]);
+                    return BufferedBody::TooLarge(Body::from_stream(prefix.chain(stream)));
+                }
+                buffered.extend_from_slice(&chunk);
+            }
+            Err(_) => return BufferedBody::ReadError,
+        }
+    }
+    BufferedBody::Complete(Bytes::from(buffered))
+}
+
+fn store(key: String, entry: CachedResponse, max_entries: usize) {
+    let now = chrono::Utc::now().timestamp();
+    RESPONSE_CACHE.retain(|_, e| e.expires_at > now);
+    // 容量满时淘汰最先过期的条目，保证新响应总能进缓存
+    if max_entries > 0 && RESPONSE_CACHE.len() >= max_entries {
+        // 先结束迭代再 remove，避免持有分片引用时写同一分片
+        let oldest = RESPONSE_CACHE
+            .iter()
+            .min_by_key(|e| e.value().expires_at)
+            .map(|e| e.key().clone());
+        if let Some(oldest) = oldest {
+            RESPONSE_CACHE.remove(&oldest);
+        }
+    }
+    RESPONSE_CACHE.insert(key, entry);
+}
+
+pub async fn response_cache_middleware(request: Request, next: Next) -> Response {
+    let config = crate::proxy::config::get_response_cache();
+    if !config.enabled || request.method() != axum::http::Method::POST {
+        return next.run(request).await;
+    }
+
+    let method = request.method().to_string();
+    let uri = request.uri().to_string();
+
+    // [FIX] 超过可缓存上限的请求体不整体缓冲：声明长度超限的直接透传，
+    // 避免把 monitor 层刻意流式放行的超大请求又整段读进内存 (OOM 向量)
+    let max_body_bytes = crate::proxy::config::get_max_request_body_bytes();
+    let cache_cap = if max_body_bytes > 0 {
+        MAX_CACHEABLE_BODY_BYTES.min(max_body_bytes as usize)
+    } else {
+        MAX_CACHEABLE_BODY_BYTES
+    };
+    let content_length = request
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+    if content_length.is_some_and(|len| len > cache_cap as u64) {
+        return next.run(request).await;
+    }
+
+    // 缓冲请求体以计算哈希，上限即可缓存上限；Content-Length 缺失/撒谎
+    // 导致超限时把已读前缀与剩余流拼回去透传
+    let (parts, body) = request.into_parts();
+    let bytes = match buffer_body_capped(body, cache_cap).await {
+        BufferedBody::Complete(bytes) => bytes,
+        BufferedBody::TooLarge(body) => {
+            return next.run(Request::from_parts(parts, body)).await;
+        }
+        BufferedBody::ReadError => {
+            return (
+                axum::http::StatusCode::BAD_REQUEST,
+                "Failed to read request body",
+            )
+                .into_response();
+        }
+    };
+
+    // 流式请求不缓存：stream 标志或 Gemini 流式端点
+    let json = serde_json::from_slice::<Value>(&bytes).ok();
+    let is_stream = json
+        .as_ref()
+        .and_then(|v| v.get("stream"))
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false)
+        || uri.contains(":streamGenerateContent");
+    let model = json
+        .as_ref()
+        .and_then(|v| v.get("model"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let request = Request::from_parts(parts, Body::from(bytes.clone()));
+    if is_stream {
+        return next.run(request).await;
+    }
+
+    let key = cache_key(&method, &uri, &bytes, &model);
+    if let Some(entry) = lookup(&key) {
+        CACHE_HITS.fetch_add(1, Ordering::Relaxed);
+        tracing::info!("[Cache] hit for {} {} (model: {})", method, uri, model);
+        let mut response = Response::builder()
+            .status(entry.status)
+            .body(Body::from(entry.body))
+            .unwrap_or_else(|_| axum::http::StatusCode::INTERNAL_SERVER_ERROR.into_response());
+        if let Ok(value) = axum::http::HeaderValue::from_str(&entry.content_type) {
+            response
+                .headers_mut()
+                .insert(axum::http::header::CONTENT_TYPE, value);
+        }
+        response.headers_mut().insert(
+            CACHE_STATUS_HEADER,
+            axum::http::HeaderValue::from_static("hit"),
+        );
+        return response;
+    }
+    CACHE_MISSES.fetch_add(1, Ordering::Relaxed);
+
+    let response = next.run(request).await;
+
+    // 仅缓存成功的非流式 JSON 响应
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(axum::http::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or("")
+        .to_string();
+    if !status.is_success() || !content_type.contains("application/json") {
+        return response;
+    }
+
+    // [FIX] 超大响应体同样不整体缓冲：声明长度超限的原样透传，
+    // 读取时超限的拼回前缀继续流式返回
+    let response_length = response
+        .headers()
+        .get(axum::http::header::CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|s| s.parse::<u64>().ok());
+    if response_length.is_some_and(|len| len > MAX_CACHEABLE_BODY_BYTES as u64) {
+        return response;
+    }
+
+    let (parts, body) = response.into_parts();
+    let body_bytes = match buffer_body_capped(body, MAX_CACHEABLE_BODY_BYTES).await {
+        BufferedBody::Complete(bytes) => bytes,
+        BufferedBody::TooLarge(body) => return Response::from_parts(parts, body),
+        BufferedBody::ReadError => return Response::from_parts(parts, Body::empty()),
+    };
+
+    store(
+        key,
+        CachedResponse {
+            status: status.as_u16(),
+            content_type,
+            body: body_bytes.to_vec(),
+            expires_at: chrono::Utc::now().timestamp() + config.ttl_secs as i64,
+        },
+        config.max_entries,
+    );
+
+    Response::from_parts(parts, Body::from(body_bytes))
+}
@@ -14,7 +14,13 @@ pub async fn service_status_middleware(
     let path = request.uri().path();
     
     // Always allow Admin API and Auth callback
-    if path.starts_with("/api/") || path == "/auth/callback" || path == "/health" {
+    // [NEW] 探针端点始终放行: liveness 需在服务停止时也返回 200
+    if path.starts_with("/api/")
+        || path == "/auth/callback"
+        || path == "/health"
+        || path == "/healthz"
+        || path == "/readyz"
+    {
         return next.run(request).await;
     }
 
@@ -0,0 +1,148 @@
+// [NEW] 客户端粘性会话中间件
+//
+// 启用 sticky_sessions 后，按客户端身份 (优先用户令牌，匿名时退回客户端 IP)
+// 记住首次选中的账号，并在后续请求中通过内部固定路由头复用该账号。
+// 绑定存内存、带 TTL；账号不可用时 get_token 的固定路由路径会自动回退
+// 正常调度，响应头里的实际账号会把绑定刷新到新账号。
+
+use crate::proxy::middleware::auth;
+use crate::proxy::middleware::client_ip;
+use crate::proxy::server::AppState;
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response,
+};
+use dashmap::DashMap;
+use std::sync::LazyLock;
+
+/// 单条粘性绑定
+#[derive(Debug, Clone)]
+struct StickyEntry {
+    account_id: String,
+    email: String,
+    expires_at: i64,
+}
+
+/// [NEW] 粘性映射视图 (供前端展示)
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct StickyMappingView {
+    pub key: String,
+    pub account_id: String,
+    pub email: String,
+    pub expires_at: i64,
+}
+
+/// 绑定表：key = "user:{username}" 或 "ip:{addr}"；跨代理实例共享
+static STICKY_MAP: LazyLock<DashMap<String, StickyEntry>> = LazyLock::new(DashMap::new);
+
+fn lookup(key: &str) -> Option<StickyEntry> {
+    let now = chrono::Utc::now().timestamp();
+    let entry = STICKY_MAP.get(key).map(|e| e.value().clone())?;
+    if entry.expires_at > now {
+        Some(entry)
+    } else {
+        // TTL 到期：惰性淘汰，下一次请求重新选择账号
+        STICKY_MAP.remove(key);
+        None
+    }
+}
+
+fn bind(key: &str, account_id: &str, email: &str, ttl_secs: u64) {
+    STICKY_MAP.insert(
+        key.to_string(),
+        StickyEntry {
+            account_id: account_id.to_string(),
+            email: email.to_string(),
+            expires_at: chrono::Utc::now().timestamp() + ttl_secs as i64,
+        },
+    );
+}
+
+/// [NEW] 列出当前有效的粘性绑定 (顺带淘汰过期条目)
+pub fn list_mappings() -> Vec<StickyMappingView> {
+    let now = chrono::Utc::now().timestamp();
+    STICKY_MAP.retain(|_, entry| entry.expires_at > now);
+    STICKY_MAP
+        .iter()
+        .map(|e| StickyMappingView {
+            key: e.key().clone(),
+            account_id: e.value().account_id.clone(),
+            email: e.value().email.clone(),
+            expires_at: e.value().expires_at,
+        })
+        .collect()
+}
+
+/// [NEW] 清除粘性绑定：key = None 时全部清除，返回清除条数
+pub fn clear_mappings(key: Option<&str>) -> usize {
+    match key {
+        Some(k) => {
+            if STICKY_MAP.remove(k).is_some() {
+                1
+            } else {
+                0
+            }
+        }
+        None => {
+            let count = STICKY_MAP.len();
+            STICKY_MAP.clear();
+            count
+        }
+    }
+}
+
+/// 计算请求的粘性键：优先用户令牌身份 (同一令牌跨 IP 也保持一致)，
+/// 匿名请求退回归一化后的客户端 IP
+fn sticky_key(request: &Request) -> Option<String> {
+    if let Some(identity) = request.extensions().get::<auth::UserTokenIdentity>() {
+        return Some(format!("user:{}", identity.username));
+    }
+    client_ip::extract_client_ip(request).map(|ip| format!("ip:{}", ip))
+}
+
+pub async fn sticky_session_middleware(
+    State(state): State<AppState>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let config = crate::proxy::config::get_sticky_sessions();
+    if !config.enabled {
+        return next.run(request).await;
+    }
+
+    let Some(key) = sticky_key(&request) else {
+        return next.run(request).await;
+    };
+
+    // 用户令牌显式固定账号的请求不参与粘性 (auth 中间件已注入固定路由头)
+    let had_pinned_header = request.headers().contains_key(auth::PINNED_ACCOUNT_HEADER);
+    if !had_pinned_header {
+        if let Some(entry) = lookup(&key) {
+            if let Ok(value) = axum::http::HeaderValue::from_str(&entry.account_id) {
+                tracing::debug!("[Sticky] {} -> {} (reuse)", key, entry.email);
+                request
+                    .headers_mut()
+                    .insert(auth::PINNED_ACCOUNT_HEADER, value);
+            }
+        }
+    }
+
+    let response = next.run(request).await;
+
+    // 以响应头中实际使用的账号建立/刷新绑定：
+    // 固定账号不可用被回退时，这里会自动把绑定切到新账号
+    if !had_pinned_header {
+        if let Some(email) = response
+            .headers()
+            .get("X-Account-Email")
+            .and_then(|v| v.to_str().ok())
+        {
+            if let Some(account_id) = state.token_manager.email_to_account_id(email) {
+                bind(&key, &account_id, email, config.ttl_secs);
+            }
+        }
+    }
+
+    response
+}
@@ -12,15 +12,19 @@ pub mod audio; // 音频处理模块
 pub mod cli_sync; // CLI 配置同步 (v3.3.35)
 pub mod opencode_sync; // OpenCode 配置同步
 pub mod common; // 公共工具
+pub mod concurrency; // 上游并发限制
 pub mod debug_logger;
 pub mod handlers; // API 端点处理器
 pub mod mappers; // 协议转换器
+pub mod health; // 健康探针端点 (liveness/readiness)
+pub mod metrics; // Prometheus 指标端点
 pub mod middleware; // Axum 中间件
 pub mod monitor; // 监控
 pub mod providers; // Extra upstream providers (z.ai, etc.)
 pub mod rate_limit; // 限流跟踪
 pub mod session_manager; // 会话指纹管理
 pub mod signature_cache; // Signature Cache (v3.3.16)
+pub mod spend_cap; // 模型日度 Token 上限
 pub mod sticky_config; // 粘性调度配置
 pub mod upstream; // 上游客户端
 pub mod proxy_pool; // 代理池管理器
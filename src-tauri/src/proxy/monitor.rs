@@ -1,9 +1,12 @@
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use tauri::Emitter;
 use tokio::sync::RwLock;
 
+/// [NEW] SSE 事件流重连回放缓冲区大小
+const EVENT_BUFFER_SIZE: usize = 256;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProxyRequestLog {
     pub id: String,
@@ -21,6 +24,18 @@ pub struct ProxyRequestLog {
     pub response_body: Option<String>,
     pub input_tokens: Option<u32>,
     pub output_tokens: Option<u32>,
+    #[serde(default)]
+    pub cached_input_tokens: Option<u32>, // [NEW] 缓存命中的输入 token 数
+    #[serde(default)]
+    pub reasoning_tokens: Option<u32>, // [NEW] 推理 (thinking) token 数
+    #[serde(default)]
+    pub tokens_estimated: bool, // [NEW] token 数为估算值 (流式响应未携带 usage 事件)
+    #[serde(default)]
+    pub note: Option<String>, // [NEW] 人工备注 (仅通过 set_log_note 写入)
+    #[serde(default)]
+    pub flagged: bool, // [NEW] 人工标记 (已排查/需关注)
+    #[serde(default)]
+    pub cached: bool, // [NEW] 响应缓存命中 (未调用上游，token 不计费)
     pub protocol: Option<String>, // 协议类型: "openai", "anthropic", "gemini"
     pub username: Option<String>, // User token username
 }
@@ -30,14 +45,25 @@ pub struct ProxyStats {
     pub total_requests: u64,
     pub success_count: u64,
     pub error_count: u64,
+    /// [NEW] 命中 neutral_status_codes 的请求数 (如 429/499)，不计入 error
+    #[serde(default)]
+    pub warning_count: u64,
 }
 
 pub struct ProxyMonitor {
     pub logs: RwLock<VecDeque<ProxyRequestLog>>,
     pub stats: RwLock<ProxyStats>,
-    pub max_logs: usize,
+    pub max_logs: AtomicUsize,
     pub enabled: AtomicBool,
     app_handle: Option<tauri::AppHandle>,
+    /// [NEW] SSE 事件流: 递增事件 ID
+    event_seq: AtomicU64,
+    /// [NEW] SSE 事件流: 重连回放缓冲 (id, 摘要)
+    event_buffer: RwLock<VecDeque<(u64, ProxyRequestLog)>>,
+    /// [NEW] SSE 事件流: 实时广播通道
+    event_tx: tokio::sync::broadcast::Sender<(u64, ProxyRequestLog)>,
+    /// [NEW] 计入 warning 而非 error 的状态码 (来自配置，可热更新)
+    neutral_codes: RwLock<std::collections::HashSet<u16>>,
 }
 
 impl ProxyMonitor {
@@ -61,15 +87,27 @@ impl ProxyMonitor {
             }
         });
 
+        let (event_tx, _) = tokio::sync::broadcast::channel(EVENT_BUFFER_SIZE);
+
         Self {
             logs: RwLock::new(VecDeque::with_capacity(max_logs)),
             stats: RwLock::new(ProxyStats::default()),
-            max_logs,
+            max_logs: AtomicUsize::new(max_logs),
             enabled: AtomicBool::new(false), // Default to disabled
             app_handle,
+            event_seq: AtomicU64::new(0),
+            event_buffer: RwLock::new(VecDeque::with_capacity(EVENT_BUFFER_SIZE)),
+            event_tx,
+            neutral_codes: RwLock::new(std::collections::HashSet::new()),
         }
     }
 
+    /// [NEW] 热更新 warning 状态码集合 (空 = 保持原有 success/error 二分类)
+    pub async fn set_neutral_codes(&self, codes: &[u16]) {
+        let mut neutral = self.neutral_codes.write().await;
+        *neutral = codes.iter().copied().collect();
+    }
+
     pub fn set_enabled(&self, enabled: bool) {
         self.enabled.store(enabled, Ordering::Relaxed);
     }
@@ -78,6 +116,21 @@ impl ProxyMonitor {
         self.enabled.load(Ordering::Relaxed)
     }
 
+    /// [NEW] 运行时调整内存日志上限，缩小时截断最旧的日志。
+    /// 持有写锁完成整个调整，避免并发 log_request 观察到不一致的队列。
+    pub async fn set_max_logs(&self, max_logs: usize) {
+        let mut logs = self.logs.write().await;
+        self.max_logs.store(max_logs, Ordering::Relaxed);
+        while logs.len() > max_logs {
+            logs.pop_back();
+        }
+    }
+
+    /// [NEW] 当前内存日志上限
+    pub fn get_max_logs(&self) -> usize {
+        self.max_logs.load(Ordering::Relaxed)
+    }
+
     pub async fn log_request(&self, log: ProxyRequestLog) {
         if !self.is_enabled() {
             return;
@@ -85,10 +138,14 @@ impl ProxyMonitor {
         tracing::info!("[Monitor] Logging request: {} {}", log.method, log.url);
         // Update stats
         {
+            let is_neutral = self.neutral_codes.read().await.contains(&log.status);
             let mut stats = self.stats.write().await;
             stats.total_requests += 1;
             if log.status >= 200 && log.status < 400 {
                 stats.success_count += 1;
+            } else if is_neutral {
+                // [NEW] 配置为中性的状态码 (如 429/499) 单独统计，不污染错误率
+                stats.warning_count += 1;
             } else {
                 stats.error_count += 1;
             }
@@ -97,18 +154,29 @@ impl ProxyMonitor {
         // Add log to memory
         {
             let mut logs = self.logs.write().await;
-            if logs.len() >= self.max_logs {
-                logs.pop_back();
+            let max_logs = self.max_logs.load(Ordering::Relaxed);
+            while logs.len() >= max_logs {
+                if logs.pop_back().is_none() {
+                    break;
+                }
             }
             logs.push_front(log.clone());
         }
 
         // Save to DB
         let log_to_save = log.clone();
-        tokio::task::spawn_blocking(move || {
-            if let Err(e) = crate::modules::proxy_db::save_log(&log_to_save) {
-                tracing::error!("Failed to save proxy log to DB: {}", e);
-            }
+        // [NEW] 经 run_blocking 执行：闭包内 DB 调用 panic 时记录具体原因，
+        // 而不是被丢弃的 JoinHandle 静默吞掉
+        let save_task = move || {
+            // [FIX] save_log upserts on id; only count token stats for newly
+            // inserted ids so retries/reconnects don't inflate stats
+            let newly_inserted = match crate::modules::proxy_db::save_log(&log_to_save) {
+                Ok(inserted) => inserted,
+                Err(e) => {
+                    tracing::error!("Failed to save proxy log to DB: {}", e);
+                    true
+                }
+            };
 
             // Sync to Security DB (IpAccessLogs) so it appears in Security Monitor
             if let Some(ip) = &log_to_save.client_ip {
@@ -132,7 +200,18 @@ impl ProxyMonitor {
                 }
             }
 
-            // Record token stats if available
+            // Record token stats if available (once per unique request id)
+            if !newly_inserted {
+                return Ok(());
+            }
+
+            // [NEW] 实时计数：无 token 信息的请求也计入请求数
+            if let Some(account) = &log_to_save.account_email {
+                let tokens = log_to_save.input_tokens.unwrap_or(0) as u64
+                    + log_to_save.output_tokens.unwrap_or(0) as u64;
+                record_live_usage(account, tokens);
+            }
+
             if let (Some(account), Some(input), Some(output)) = (
                 &log_to_save.account_email,
                 log_to_save.input_tokens,
@@ -142,71 +221,124 @@ impl ProxyMonitor {
                     .model
                     .clone()
                     .unwrap_or_else(|| "unknown".to_string());
-                if let Err(e) =
-                    crate::modules::token_stats::record_usage(account, &model, input, output)
-                {
+                if let Err(e) = crate::modules::token_stats::record_usage_detailed(
+                    account,
+                    &model,
+                    input,
+                    output,
+                    log_to_save.cached_input_tokens,
+                    log_to_save.reasoning_tokens,
+                ) {
                     tracing::debug!("Failed to record token stats: {}", e);
                 }
             }
+
+            Ok::<(), crate::error::GatewayError>(())
+        };
+        tokio::spawn(async move {
+            let _ = crate::error::run_blocking(save_task).await;
         });
 
         // Emit event (send summary only, without body to reduce memory)
+        let log_summary = Self::summarize(&log);
+
+        // [NEW] 推送到 SSE 事件流 (与 Tauri 事件相同的摘要，不含请求/响应体)
+        {
+            let seq = self.event_seq.fetch_add(1, Ordering::Relaxed) + 1;
+            let mut buffer = self.event_buffer.write().await;
+            if buffer.len() >= EVENT_BUFFER_SIZE {
+                buffer.pop_front();
+            }
+            buffer.push_back((seq, log_summary.clone()));
+            let _ = self.event_tx.send((seq, log_summary.clone()));
+        }
+
         if let Some(app) = &self.app_handle {
-            let log_summary = ProxyRequestLog {
-                id: log.id.clone(),
-                timestamp: log.timestamp,
-                method: log.method.clone(),
-                url: log.url.clone(),
-                status: log.status,
-                duration: log.duration,
-                model: log.model.clone(),
-                mapped_model: log.mapped_model.clone(),
-                account_email: log.account_email.clone(),
-                client_ip: log.client_ip.clone(),
-                error: log.error.clone(),
-                request_body: None,  // Don't send body in event
-                response_body: None, // Don't send body in event
-                input_tokens: log.input_tokens,
-                output_tokens: log.output_tokens,
-                protocol: log.protocol.clone(),
-                username: log.username.clone(),
-            };
             let _ = app.emit("proxy://request", &log_summary);
         }
     }
 
+    /// 构造不含请求/响应体的日志摘要 (Tauri 事件与 SSE 事件流共用)
+    fn summarize(log: &ProxyRequestLog) -> ProxyRequestLog {
+        ProxyRequestLog {
+            id: log.id.clone(),
+            timestamp: log.timestamp,
+            method: log.method.clone(),
+            url: log.url.clone(),
+            status: log.status,
+            duration: log.duration,
+            model: log.model.clone(),
+            mapped_model: log.mapped_model.clone(),
+            account_email: log.account_email.clone(),
+            client_ip: log.client_ip.clone(),
+            error: log.error.clone(),
+            request_body: None,  // Don't send body in event
+            response_body: None, // Don't send body in event
+            input_tokens: log.input_tokens,
+            output_tokens: log.output_tokens,
+            cached_input_tokens: log.cached_input_tokens,
+            reasoning_tokens: log.reasoning_tokens,
+            tokens_estimated: log.tokens_estimated,
+            note: log.note.clone(),
+            flagged: log.flagged,
+            cached: log.cached,
+            protocol: log.protocol.clone(),
+            username: log.username.clone(),
+        }
+    }
+
+    /// [NEW] 回放缓冲中 id 大于 last_id 的事件 (SSE 重连续传)
+    pub async fn events_since(&self, last_id: u64) -> Vec<(u64, ProxyRequestLog)> {
+        self.event_buffer
+            .read()
+            .await
+            .iter()
+            .filter(|(id, _)| *id > last_id)
+            .cloned()
+            .collect()
+    }
+
+    /// [NEW] 订阅实时事件流
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<(u64, ProxyRequestLog)> {
+        self.event_tx.subscribe()
+    }
+
     pub async fn get_logs(&self, limit: usize) -> Vec<ProxyRequestLog> {
         // Try to get from DB first for true history
+        // [NEW] run_blocking 捕获阻塞闭包内的 panic 并带上具体原因
         let db_result =
-            tokio::task::spawn_blocking(move || crate::modules::proxy_db::get_logs(limit)).await;
+            crate::error::run_blocking(move || crate::modules::proxy_db::get_logs(limit)).await;
 
         match db_result {
-            Ok(Ok(logs)) => logs,
-            Ok(Err(e)) => {
+            Ok(logs) => logs,
+            Err(e) => {
                 tracing::error!("Failed to get logs from DB: {}", e);
                 // Fallback to memory
                 let logs = self.logs.read().await;
                 logs.iter().take(limit).cloned().collect()
             }
-            Err(e) => {
-                tracing::error!("Spawn blocking failed for get_logs: {}", e);
-                let logs = self.logs.read().await;
-                logs.iter().take(limit).cloned().collect()
-            }
         }
     }
 
+    /// [NEW] 游标分页读取历史日志 (keyset)，深翻页不随行数变慢。
+    /// 返回的 next_cursor 传回下一次调用即可继续向更早的记录滚动
+    pub async fn get_logs_before(
+        &self,
+        cursor: Option<crate::modules::proxy_db::LogCursor>,
+        limit: usize,
+    ) -> Result<crate::modules::proxy_db::LogPage, String> {
+        crate::error::run_blocking(move || crate::modules::proxy_db::get_logs_before(cursor, limit))
+            .await
+            .map_err(String::from)
+    }
+
     pub async fn get_stats(&self) -> ProxyStats {
-        let db_result = tokio::task::spawn_blocking(|| crate::modules::proxy_db::get_stats()).await;
+        let db_result = crate::error::run_blocking(crate::modules::proxy_db::get_stats).await;
 
         match db_result {
-            Ok(Ok(stats)) => stats,
-            Ok(Err(e)) => {
-                tracing::error!("Failed to get stats from DB: {}", e);
-                self.stats.read().await.clone()
-            }
+            Ok(stats) => stats,
             Err(e) => {
-                tracing::error!("Spawn blocking failed for get_stats: {}", e);
+                tracing::error!("Failed to get stats from DB: {}", e);
                 self.stats.read().await.clone()
             }
         }
@@ -218,20 +350,27 @@ impl ProxyMonitor {
         page_size: usize,
         search_text: Option<String>,
         level: Option<String>,
+        account_email: Option<String>,
+        status_min: Option<u16>,
+        status_max: Option<u16>,
     ) -> Result<Vec<ProxyRequestLog>, String> {
         let offset = (page.max(1) - 1) * page_size;
         let errors_only = level.as_deref() == Some("error");
         let search = search_text.unwrap_or_default();
 
-        let res = tokio::task::spawn_blocking(move || {
-            crate::modules::proxy_db::get_logs_filtered(&search, errors_only, page_size, offset)
+        crate::error::run_blocking(move || {
+            crate::modules::proxy_db::get_logs_filtered(
+                &search,
+                errors_only,
+                account_email,
+                status_min,
+                status_max,
+                page_size,
+                offset,
+            )
         })
-        .await;
-
-        match res {
-            Ok(r) => r,
-            Err(e) => Err(format!("Spawn blocking failed: {}", e)),
-        }
+        .await
+        .map_err(String::from)
     }
 
     pub async fn clear(&self) {
@@ -240,11 +379,78 @@ impl ProxyMonitor {
         let mut stats = self.stats.write().await;
         *stats = ProxyStats::default();
 
-        let _ = tokio::task::spawn_blocking(|| {
-            if let Err(e) = crate::modules::proxy_db::clear_logs() {
-                tracing::error!("Failed to clear logs in DB: {}", e);
-            }
-        })
-        .await;
+        if let Err(e) = crate::error::run_blocking(crate::modules::proxy_db::clear_logs).await {
+            tracing::error!("Failed to clear logs in DB: {}", e);
+        }
+    }
+}
+
+// ==================================================================================
+// [NEW] 实时账号计数器（内存级，近似值）
+// 账号池视图每次刷新都查 DB 代价太高；这里在记录请求的同一路径上维护
+// 每账号的"今日请求数 / 今日 Token 数"原子计数，跨天自动清零、重启丢失，
+// 仅作为权威 DB 统计 (token_stats) 的零查询快速补充
+// ==================================================================================
+
+/// 单账号的实时计数（原子更新，读取无锁）
+struct LiveAccountCounter {
+    requests: AtomicU64,
+    tokens: AtomicU64,
+}
+
+/// [NEW] 实时计数查询结果（供前端池视图展示）
+#[derive(Debug, Clone, Serialize)]
+pub struct LiveAccountCounters {
+    pub account_email: String,
+    pub requests_today: u64,
+    pub tokens_today: u64,
+}
+
+static LIVE_COUNTERS: std::sync::OnceLock<dashmap::DashMap<String, LiveAccountCounter>> =
+    std::sync::OnceLock::new();
+
+/// 计数归属的本地日序号，用于跨天整体清零
+static LIVE_COUNTER_DAY: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+fn live_counters() -> &'static dashmap::DashMap<String, LiveAccountCounter> {
+    LIVE_COUNTERS.get_or_init(dashmap::DashMap::new)
+}
+
+/// 跨天时清空计数（近似语义：并发窗口内少量计数落入新的一天可接受）
+fn roll_over_if_new_day() {
+    use chrono::Datelike;
+    let today = chrono::Local::now().date_naive().num_days_from_ce();
+    let stored = LIVE_COUNTER_DAY.swap(today, Ordering::Relaxed);
+    if stored != 0 && stored != today {
+        live_counters().clear();
     }
 }
+
+/// 在请求记录路径上累加实时计数（tokens 传 0 表示该请求无 token 信息）
+pub fn record_live_usage(account_email: &str, tokens: u64) {
+    roll_over_if_new_day();
+    let counters = live_counters();
+    let entry = counters
+        .entry(account_email.to_string())
+        .or_insert_with(|| LiveAccountCounter {
+            requests: AtomicU64::new(0),
+            tokens: AtomicU64::new(0),
+        });
+    entry.requests.fetch_add(1, Ordering::Relaxed);
+    entry.tokens.fetch_add(tokens, Ordering::Relaxed);
+}
+
+/// [NEW] 当前所有账号的实时计数快照（按今日 Token 数降序）
+pub fn get_live_account_counters() -> Vec<LiveAccountCounters> {
+    roll_over_if_new_day();
+    let mut result: Vec<LiveAccountCounters> = live_counters()
+        .iter()
+        .map(|entry| LiveAccountCounters {
+            account_email: entry.key().clone(),
+            requests_today: entry.value().requests.load(Ordering::Relaxed),
+            tokens_today: entry.value().tokens.load(Ordering::Relaxed),
+        })
+        .collect();
+    result.sort_by(|a, b| b.tokens_today.cmp(&a.tokens_today));
+    result
+}
@@ -130,6 +130,209 @@ pub fn deep_remove_cache_control(value: &mut Value) {
     }
 }
 
+/// [NEW] 协议翻译转发：OpenAI 格式客户端 → z.ai (Anthropic) 上游。
+/// 请求经 interop 翻译层转为 /v1/messages 格式，响应及 SSE 流翻译回
+/// chat.completions 格式。X-Mapped-Model / X-Upstream-Protocol 响应头
+/// 供监控中间件把翻译后的模型与协议对写入请求日志
+pub async fn forward_openai_via_anthropic(
+    state: &AppState,
+    incoming_headers: &HeaderMap,
+    body: Value,
+) -> Response {
+    use crate::proxy::mappers::interop::{self, Protocol};
+
+    let zai = state.zai.read().await.clone();
+    if !zai.enabled || zai.dispatch_mode == crate::proxy::ZaiDispatchMode::Off {
+        return (StatusCode::BAD_REQUEST, "z.ai is disabled").into_response();
+    }
+    if zai.api_key.trim().is_empty() {
+        return (StatusCode::BAD_REQUEST, "z.ai api_key is not set").into_response();
+    }
+
+    let translator = match interop::translator_for(Protocol::OpenAi, Protocol::Anthropic) {
+        Some(t) => t,
+        None => {
+            return (
+                StatusCode::NOT_IMPLEMENTED,
+                "No translator registered for openai -> anthropic",
+            )
+                .into_response()
+        }
+    };
+
+    let stream_requested = body
+        .get("stream")
+        .and_then(|v| v.as_bool())
+        .unwrap_or(false);
+    let original_model = body
+        .get("model")
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .to_string();
+
+    let mut anthropic_body = match translator.translate_request(&body) {
+        Ok(b) => b,
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                format!("Request translation failed: {}", e),
+            )
+                .into_response()
+        }
+    };
+
+    let mapped_model = map_model_for_zai(&original_model, &zai);
+    anthropic_body["model"] = Value::String(mapped_model.clone());
+    deep_remove_cache_control(&mut anthropic_body);
+
+    let url = match join_base_url(&zai.base_url, "/v1/messages") {
+        Ok(u) => u,
+        Err(e) => return (StatusCode::BAD_REQUEST, e).into_response(),
+    };
+
+    let timeout_secs = state.request_timeout.max(5);
+    let upstream_proxy = state.upstream_proxy.read().await.clone();
+    let client = match build_client(Some(upstream_proxy), timeout_secs) {
+        Ok(c) => c,
+        Err(e) => return (StatusCode::INTERNAL_SERVER_ERROR, e).into_response(),
+    };
+
+    let mut headers = copy_passthrough_headers(incoming_headers);
+    set_zai_auth(&mut headers, incoming_headers, &zai.api_key);
+    headers
+        .entry(header::CONTENT_TYPE)
+        .or_insert(HeaderValue::from_static("application/json"));
+    headers
+        .entry("anthropic-version")
+        .or_insert(HeaderValue::from_static("2023-06-01"));
+
+    let body_bytes = serde_json::to_vec(&anthropic_body).unwrap_or_default();
+    tracing::debug!(
+        "Forwarding translated openai->anthropic request ({} -> {}): {}",
+        original_model,
+        mapped_model,
+        url
+    );
+
+    let resp = match client
+        .request(Method::POST, &url)
+        .headers(headers)
+        .body(body_bytes)
+        .send()
+        .await
+    {
+        Ok(r) => r,
+        Err(e) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Upstream request failed: {}", e),
+            )
+                .into_response();
+        }
+    };
+
+    let status = StatusCode::from_u16(resp.status().as_u16()).unwrap_or(StatusCode::BAD_GATEWAY);
+
+    // 上游错误不做格式翻译，包成 OpenAI 风格的 error 对象透传
+    if !status.is_success() {
+        let text = resp.text().await.unwrap_or_default();
+        return (
+            status,
+            axum::Json(serde_json::json!({
+                "error": { "message": text, "type": "upstream_error" }
+            })),
+        )
+            .into_response();
+    }
+
+    if stream_requested {
+        let mut ctx = interop::StreamTranslationContext::new(&original_model);
+        let (tx, rx) = tokio::sync::mpsc::channel::<Result<Bytes, std::io::Error>>(64);
+        let mut upstream = resp.bytes_stream();
+
+        tokio::spawn(async move {
+            // SSE 事件可能被任意切分，按空行分隔缓冲重组后再解析 data: 行
+            let mut buffer = String::new();
+            while let Some(chunk) = upstream.next().await {
+                let chunk = match chunk {
+                    Ok(b) => b,
+                    Err(e) => {
+                        tracing::warn!("Upstream stream error during translation: {}", e);
+                        break;
+                    }
+                };
+                buffer.push_str(&String::from_utf8_lossy(&chunk));
+
+                while let Some(pos) = buffer.find("\n\n") {
+                    let event_text = buffer[..pos].to_string();
+                    buffer.drain(..pos + 2);
+
+                    for line in event_text.lines() {
+                        let Some(data) = line.strip_prefix("data: ") else {
+                            continue;
+                        };
+                        let Ok(event) = serde_json::from_str::<Value>(data) else {
+                            continue;
+                        };
+                        for out in translator.translate_stream_event(&event, &mut ctx) {
+                            let payload = format!("data: {}\n\n", out);
+                            if tx.send(Ok(Bytes::from(payload))).await.is_err() {
+                                return;
+                            }
+                        }
+                    }
+                }
+            }
+            let _ = tx.send(Ok(Bytes::from("data: [DONE]\n\n"))).await;
+        });
+
+        let stream = tokio_stream::wrappers::ReceiverStream::new(rx);
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(header::CONTENT_TYPE, "text/event-stream")
+            .header("X-Mapped-Model", mapped_model)
+            .header("X-Upstream-Protocol", "anthropic")
+            .body(Body::from_stream(stream))
+            .unwrap_or_else(|_| {
+                (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+            });
+    }
+
+    let upstream_json: Value = match resp.json().await {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Failed to read upstream response: {}", e),
+            )
+                .into_response();
+        }
+    };
+
+    let mut translated = match translator.translate_response(&upstream_json) {
+        Ok(v) => v,
+        Err(e) => {
+            return (
+                StatusCode::BAD_GATEWAY,
+                format!("Response translation failed: {}", e),
+            )
+                .into_response();
+        }
+    };
+    // 客户端看到自己请求的模型名；真实上游模型记录在 X-Mapped-Model
+    translated["model"] = Value::String(original_model);
+
+    Response::builder()
+        .status(StatusCode::OK)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header("X-Mapped-Model", mapped_model)
+        .header("X-Upstream-Protocol", "anthropic")
+        .body(Body::from(translated.to_string()))
+        .unwrap_or_else(|_| {
+            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to build response").into_response()
+        })
+}
+
 pub async fn forward_anthropic_json(
     state: &AppState,
     method: Method,
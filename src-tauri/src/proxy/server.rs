@@ -113,6 +113,7 @@ pub struct AppState {
     pub port: u16,                     // [NEW] 本地监听端口 (v4.0.8 修复)
     pub proxy_pool_state: Arc<tokio::sync::RwLock<crate::proxy::config::ProxyPoolConfig>>, // [FIX Web Mode]
     pub proxy_pool_manager: Arc<crate::proxy::proxy_pool::ProxyPoolManager>, // [FIX Web Mode]
+    pub event_stream: Arc<RwLock<crate::proxy::config::EventStreamConfig>>, // [NEW] SSE 事件流配置
 }
 
 // 为 AppState 实现 FromRef，以便中间件提取 security 状态
@@ -215,7 +216,7 @@ pub struct AxumServer {
     shutdown_tx: Arc<tokio::sync::Mutex<Option<oneshot::Sender<()>>>>,
     custom_mapping: Arc<tokio::sync::RwLock<std::collections::HashMap<String, String>>>,
     proxy_state: Arc<tokio::sync::RwLock<crate::proxy::config::UpstreamProxyConfig>>,
-    upstream: Arc<crate::proxy::upstream::client::UpstreamClient>,
+    pub upstream: Arc<crate::proxy::upstream::client::UpstreamClient>, // [NEW] 暴露 UpstreamClient 供日志重放命令复用
     security_state: Arc<RwLock<crate::proxy::ProxySecurityConfig>>,
     zai_state: Arc<RwLock<crate::proxy::ZaiConfig>>,
     experimental: Arc<RwLock<crate::proxy::config::ExperimentalConfig>>,
@@ -225,6 +226,7 @@ pub struct AxumServer {
     pub token_manager: Arc<TokenManager>, // [NEW] 暴露出 TokenManager 供反代服务复用
     pub proxy_pool_state: Arc<tokio::sync::RwLock<crate::proxy::config::ProxyPoolConfig>>, // [NEW] 代理池配置状态
     pub proxy_pool_manager: Arc<crate::proxy::proxy_pool::ProxyPoolManager>, // [NEW] 暴露代理池管理器供命令调用
+    event_stream: Arc<RwLock<crate::proxy::config::EventStreamConfig>>, // [NEW] SSE 事件流配置状态
 }
 
 impl AxumServer {
@@ -274,6 +276,12 @@ impl AxumServer {
         tracing::info!("调试日志配置已热更新");
     }
 
+    pub async fn update_event_stream(&self, config: &crate::proxy::config::ProxyConfig) {
+        let mut es = self.event_stream.write().await;
+        *es = config.event_stream.clone();
+        tracing::info!("SSE 事件流配置已热更新");
+    }
+
     pub async fn update_user_agent(&self, config: &crate::proxy::config::ProxyConfig) {
         self.upstream
             .set_user_agent_override(config.user_agent_override.clone())
@@ -305,7 +313,22 @@ impl AxumServer {
         integration: crate::modules::integration::SystemManager,
         cloudflared_state: Arc<crate::commands::cloudflared::CloudflaredState>,
         proxy_pool_config: crate::proxy::config::ProxyPoolConfig, // [NEW]
+        event_stream_config: crate::proxy::config::EventStreamConfig, // [NEW]
+        metrics_config: crate::proxy::config::MetricsConfig, // [NEW]
+        health_probes_config: crate::proxy::config::HealthProbeConfig, // [NEW]
+        tls_cert_path: Option<String>, // [NEW]
+        tls_key_path: Option<String>, // [NEW]
     ) -> Result<(Self, tokio::task::JoinHandle<()>), String> {
+        // [NEW] TLS: 证书与私钥必须成对提供，配置无效时拒绝启动
+        let tls_acceptor = match (&tls_cert_path, &tls_key_path) {
+            (Some(cert), Some(key)) => Some(Self::build_tls_acceptor(cert, key)?),
+            (None, None) => None,
+            _ => {
+                return Err(
+                    "TLS 配置不完整: tls_cert_path 与 tls_key_path 必须同时设置".to_string()
+                )
+            }
+        };
         let custom_mapping_state = Arc::new(tokio::sync::RwLock::new(custom_mapping));
         let proxy_state = Arc::new(tokio::sync::RwLock::new(upstream_proxy.clone()));
         let proxy_pool_state = Arc::new(tokio::sync::RwLock::new(proxy_pool_config));
@@ -321,6 +344,7 @@ impl AxumServer {
         let experimental_state = Arc::new(RwLock::new(experimental_config));
         let debug_logging_state = Arc::new(RwLock::new(debug_logging));
         let is_running_state = Arc::new(RwLock::new(true));
+        let event_stream_state = Arc::new(RwLock::new(event_stream_config));
 
         let state = AppState {
             token_manager: token_manager.clone(),
@@ -358,6 +382,7 @@ impl AxumServer {
             port,
             proxy_pool_state: proxy_pool_state.clone(),
             proxy_pool_manager: proxy_pool_manager.clone(),
+            event_stream: event_stream_state.clone(),
         };
 
         // 构建路由 - 使用新架构的 handlers！
@@ -434,13 +459,22 @@ impl AxumServer {
             .route("/v1/api/event_logging", post(silent_ok_handler))
             // 应用 AI 服务特定的层
             // 注意：Axum layer 执行顺序是从下往上（洋葱模型）
-            // 请求: ip_filter -> auth -> monitor -> handler
-            // 响应: handler -> monitor -> auth -> ip_filter
-            // monitor 需要在 auth 之后执行才能获取 UserTokenIdentity
+            // 请求: ip_filter -> auth -> sticky -> monitor -> cache -> handler
+            // 响应: handler -> cache -> monitor -> sticky -> auth -> ip_filter
+            // monitor 需要在 auth 之后执行才能获取 UserTokenIdentity；
+            // sticky 需要在 auth 之后注入固定路由头 (auth 会清除客户端伪造的该头)；
+            // cache 在 monitor 内侧，命中也会被正常记录 (带 cached 标记)
+            .layer(axum::middleware::from_fn(
+                crate::proxy::middleware::response_cache::response_cache_middleware,
+            ))
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 monitor_middleware,
             ))
+            .layer(axum::middleware::from_fn_with_state(
+                state.clone(),
+                crate::proxy::middleware::sticky_session::sticky_session_middleware,
+            ))
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
                 auth_middleware,
@@ -700,11 +734,20 @@ impl AxumServer {
             .unwrap_or(100 * 1024 * 1024); // 默认 100MB
         tracing::info!("请求体大小限制: {} MB", max_body_size / 1024 / 1024);
 
-        let app = Router::new()
+        let mut app = Router::new()
             .nest("/api", admin_routes)
             .merge(proxy_routes)
             // 公开路由 (无需鉴权)
             .route("/auth/callback", get(handle_oauth_callback))
+            // SSE 事件流 (处理器内部自行校验令牌，EventSource 无法携带自定义头)
+            .route("/events", get(handlers::events::handle_events));
+
+        // [NEW] 就绪探针挂载在主服务上 (未配置独立监听地址时)
+        if health_probes_config.enabled && health_probes_config.bind.is_none() {
+            app = app.route("/readyz", get(readyz_handler));
+        }
+
+        let app = app
             // 应用全局监控与状态层 (外层)
             .layer(axum::middleware::from_fn_with_state(
                 state.clone(),
@@ -731,7 +774,24 @@ impl AxumServer {
             .await
             .map_err(|e| format!("地址 {} 绑定失败: {}", addr, e))?;
 
-        tracing::info!("反代服务器启动在 http://{}", addr);
+        let scheme = if tls_acceptor.is_some() {
+            "https"
+        } else {
+            "http"
+        };
+        tracing::info!("反代服务器启动在 {}://{}", scheme, addr);
+
+        // [NEW] 可选的 Prometheus 指标端点 (独立监听地址)
+        crate::proxy::metrics::start_metrics_server(
+            &metrics_config,
+            monitor.clone(),
+            token_manager.clone(),
+        )
+        .await;
+
+        // [NEW] 可选的独立健康探针端点
+        crate::proxy::health::start_health_server(&health_probes_config, token_manager.clone())
+            .await;
 
         // 创建关闭通道
         let (shutdown_tx, mut shutdown_rx) = oneshot::channel::<()>();
@@ -750,6 +810,7 @@ impl AxumServer {
             token_manager: token_manager.clone(),
             proxy_pool_state,
             proxy_pool_manager,
+            event_stream: event_stream_state,
         };
 
         // 在新任务中启动服务器
@@ -763,8 +824,6 @@ impl AxumServer {
                     res = listener.accept() => {
                         match res {
                             Ok((stream, remote_addr)) => {
-                                let io = TokioIo::new(stream);
-
                                 // 注入 ConnectInfo (用于获取真实 IP)
                                 use tower::ServiceExt;
                                 use hyper::body::Incoming;
@@ -774,10 +833,27 @@ impl AxumServer {
                                 });
 
                                 let service = TowerToHyperService::new(app_with_info);
+                                let acceptor = tls_acceptor.clone();
 
                                 tokio::task::spawn(async move {
-                                    if let Err(err) = http1::Builder::new()
-                                        .serve_connection(io, service)
+                                    // [NEW] 配置了证书时先完成 TLS 握手
+                                    if let Some(acceptor) = acceptor {
+                                        match acceptor.accept(stream).await {
+                                            Ok(tls_stream) => {
+                                                if let Err(err) = http1::Builder::new()
+                                                    .serve_connection(TokioIo::new(tls_stream), service)
+                                                    .with_upgrades()
+                                                    .await
+                                                {
+                                                    debug!("连接处理结束或出错: {:?}", err);
+                                                }
+                                            }
+                                            Err(e) => {
+                                                debug!("TLS 握手失败 ({}): {:?}", remote_addr, e);
+                                            }
+                                        }
+                                    } else if let Err(err) = http1::Builder::new()
+                                        .serve_connection(TokioIo::new(stream), service)
                                         .with_upgrades() // 支持 WebSocket (如果以后需要)
                                         .await
                                     {
@@ -801,6 +877,35 @@ impl AxumServer {
         Ok((server_instance, handle))
     }
 
+    /// [NEW] 加载 PEM 证书/私钥并构建 TLS acceptor
+    /// 证书不可读、无法解析或与私钥不匹配时返回错误 (拒绝启动)
+    fn build_tls_acceptor(
+        cert_path: &str,
+        key_path: &str,
+    ) -> Result<tokio_rustls::TlsAcceptor, String> {
+        let cert_file = std::fs::File::open(cert_path)
+            .map_err(|e| format!("无法读取 TLS 证书 {}: {}", cert_path, e))?;
+        let certs: Vec<_> = rustls_pemfile::certs(&mut std::io::BufReader::new(cert_file))
+            .collect::<Result<_, _>>()
+            .map_err(|e| format!("TLS 证书解析失败 {}: {}", cert_path, e))?;
+        if certs.is_empty() {
+            return Err(format!("TLS 证书 {} 中未找到有效证书", cert_path));
+        }
+
+        let key_file = std::fs::File::open(key_path)
+            .map_err(|e| format!("无法读取 TLS 私钥 {}: {}", key_path, e))?;
+        let key = rustls_pemfile::private_key(&mut std::io::BufReader::new(key_file))
+            .map_err(|e| format!("TLS 私钥解析失败 {}: {}", key_path, e))?
+            .ok_or_else(|| format!("TLS 私钥 {} 中未找到有效私钥", key_path))?;
+
+        let tls_config = tokio_rustls::rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map_err(|e| format!("TLS 证书/私钥不匹配或无效: {}", e))?;
+
+        Ok(tokio_rustls::TlsAcceptor::from(Arc::new(tls_config)))
+    }
+
     /// 停止服务器
     pub fn stop(&self) {
         let tx_mutex = self.shutdown_tx.clone();
@@ -825,6 +930,22 @@ async fn health_check_handler() -> Response {
     .into_response()
 }
 
+/// [NEW] 就绪探针处理器 (账号池无可用账号时返回 503 及原因)
+async fn readyz_handler(State(state): State<AppState>) -> Response {
+    match crate::proxy::health::readiness(&state.token_manager).await {
+        Ok(usable) => (
+            StatusCode::OK,
+            format!("ready: {} usable account(s)", usable),
+        )
+            .into_response(),
+        Err(reason) => (
+            StatusCode::SERVICE_UNAVAILABLE,
+            format!("not ready: {}", reason),
+        )
+            .into_response(),
+    }
+}
+
 /// 静默成功处理器 (用于拦截遥测日志等)
 async fn silent_ok_handler() -> Response {
     StatusCode::OK.into_response()
@@ -1246,27 +1367,48 @@ struct LogsRequest {
     filter: String,
     #[serde(default)]
     errors_only: bool,
+    /// [NEW] 按账号邮箱精确过滤
+    #[serde(default)]
+    account_email: Option<String>,
+    /// [NEW] 状态码范围过滤 (含边界)
+    #[serde(default)]
+    status_min: Option<u16>,
+    #[serde(default)]
+    status_max: Option<u16>,
 }
 
 async fn admin_get_logs(
     Query(params): Query<LogsRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let limit = if params.limit == 0 { 50 } else { params.limit };
-    let total =
-        proxy_db::get_logs_count_filtered(&params.filter, params.errors_only).map_err(|e| {
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                Json(ErrorResponse { error: e }),
-            )
-        })?;
-    let logs =
-        proxy_db::get_logs_filtered(&params.filter, params.errors_only, limit, params.offset)
-            .map_err(|e| {
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse { error: e }),
-                )
-            })?;
+    let total = proxy_db::get_logs_count_filtered(
+        &params.filter,
+        params.errors_only,
+        params.account_email.clone(),
+        params.status_min,
+        params.status_max,
+    )
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
+    let logs = proxy_db::get_logs_filtered(
+        &params.filter,
+        params.errors_only,
+        params.account_email.clone(),
+        params.status_min,
+        params.status_max,
+        limit,
+        params.offset,
+    )
+    .map_err(|e| {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse { error: e }),
+        )
+    })?;
 
     Ok(Json(serde_json::json!({
         "total": total,
@@ -1341,6 +1483,12 @@ async fn admin_save_config(
         *exp = new_config.clone().proxy.experimental;
     }
 
+    // [NEW] 更新 SSE 事件流配置
+    {
+        let mut es = state.event_stream.write().await;
+        *es = new_config.clone().proxy.event_stream;
+    }
+
     Ok(StatusCode::OK)
 }
 
@@ -1659,7 +1807,13 @@ async fn admin_get_proxy_logs_count_filtered(
     Query(params): Query<LogsRequest>,
 ) -> Result<impl IntoResponse, (StatusCode, Json<ErrorResponse>)> {
     let res = tokio::task::spawn_blocking(move || {
-        proxy_db::get_logs_count_filtered(&params.filter, params.errors_only)
+        proxy_db::get_logs_count_filtered(
+            &params.filter,
+            params.errors_only,
+            params.account_email,
+            params.status_min,
+            params.status_max,
+        )
     })
     .await;
 
@@ -1718,6 +1872,14 @@ struct LogsFilterQuery {
     filter: String,
     #[serde(default)]
     errors_only: bool,
+    /// [NEW] 按账号邮箱精确过滤
+    #[serde(default)]
+    account_email: Option<String>,
+    /// [NEW] 状态码范围过滤 (含边界)
+    #[serde(default)]
+    status_min: Option<u16>,
+    #[serde(default)]
+    status_max: Option<u16>,
     #[serde(default)]
     limit: usize,
     #[serde(default)]
@@ -1731,6 +1893,9 @@ async fn admin_get_proxy_logs_filtered(
         crate::modules::proxy_db::get_logs_filtered(
             &params.filter,
             params.errors_only,
+            params.account_email,
+            params.status_min,
+            params.status_max,
             params.limit,
             params.offset,
         )
@@ -1948,7 +2113,9 @@ async fn admin_get_token_stats_hourly(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1969,7 +2136,9 @@ async fn admin_get_token_stats_daily(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -1990,7 +2159,9 @@ async fn admin_get_token_stats_weekly(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -2011,7 +2182,9 @@ async fn admin_get_token_stats_by_account(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -2032,7 +2205,9 @@ async fn admin_get_token_stats_summary(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -2053,7 +2228,9 @@ async fn admin_get_token_stats_by_model(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -2075,7 +2252,9 @@ async fn admin_get_token_stats_model_trend_hourly(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -2097,7 +2276,9 @@ async fn admin_get_token_stats_model_trend_daily(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -2119,7 +2300,9 @@ async fn admin_get_token_stats_account_trend_hourly(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -2141,7 +2324,9 @@ async fn admin_get_token_stats_account_trend_daily(
         Ok(Ok(stats)) => Ok(Json(stats)),
         Ok(Err(e)) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
-            Json(ErrorResponse { error: e }),
+            Json(ErrorResponse {
+                error: e.to_string(),
+            }),
         )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
@@ -0,0 +1,84 @@
+// [NEW] 模型日度 Token 消费上限 (spend cap)
+//
+// 配置 `proxy.model_daily_token_cap` 为 model -> 每日 Token 总量上限的映射。
+// handler 在转发前调用 `check_model_daily_cap`，当日累计用量 (token_stats)
+// 达到上限后返回 429；上限按本地时区自然日在午夜自动重置。
+
+use serde::Serialize;
+
+/// 单个模型的用量与上限状态 (供前端展示)
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelCapUsage {
+    pub model: String,
+    /// 配置的日度上限
+    pub cap: u64,
+    /// 今日 (本地自然日) 已消费 Token 总量
+    pub used_today: u64,
+    /// 是否已达到/超出上限
+    pub exceeded: bool,
+}
+
+/// 本地时区今日零点的 Unix 时间戳 (秒)
+pub fn local_midnight_timestamp() -> i64 {
+    let now = chrono::Local::now();
+    now.date_naive()
+        .and_hms_opt(0, 0, 0)
+        .and_then(|naive| naive.and_local_timezone(chrono::Local).single())
+        .map(|dt| dt.timestamp())
+        .unwrap_or_else(|| now.timestamp() - (now.timestamp() % 86_400))
+}
+
+/// 检查候选模型名是否触发日度上限。
+///
+/// `candidate_models` 通常为 [原始请求模型, 映射后模型]；上限键命中任一候选时，
+/// 以该键本身查询今日用量 (与 token_stats 记录的模型名一致)。
+/// 返回 Err(消息) 表示应以 429 拒绝请求。
+pub fn check_model_daily_cap(candidate_models: &[&str]) -> Result<(), String> {
+    let caps = crate::proxy::config::get_model_daily_token_cap();
+    if caps.is_empty() {
+        return Ok(());
+    }
+
+    let since = local_midnight_timestamp();
+    for model in candidate_models {
+        if let Some(cap) = caps.get(*model) {
+            let used = crate::modules::token_stats::get_model_usage_since(model, since)
+                .unwrap_or_else(|e| {
+                    tracing::warn!("[SpendCap] Failed to query usage for {}: {}", model, e);
+                    0 // 统计不可用时放行，避免误伤
+                });
+            if used >= *cap {
+                tracing::warn!(
+                    "[SpendCap] Model {} daily cap reached: {}/{} tokens",
+                    model,
+                    used,
+                    cap
+                );
+                return Err(format!(
+                    "Daily token cap reached for model {}: {} of {} tokens used today. The cap resets at local midnight.",
+                    model, used, cap
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 所有已配置上限的模型的用量状态 (供命令/前端查询)
+pub fn get_model_cap_usage() -> Result<Vec<ModelCapUsage>, String> {
+    let caps = crate::proxy::config::get_model_daily_token_cap();
+    let since = local_midnight_timestamp();
+
+    let mut result: Vec<ModelCapUsage> = Vec::with_capacity(caps.len());
+    for (model, cap) in caps {
+        let used_today = crate::modules::token_stats::get_model_usage_since(&model, since)?;
+        result.push(ModelCapUsage {
+            exceeded: used_today >= cap,
+            model,
+            cap,
+            used_today,
+        });
+    }
+    result.sort_by(|a, b| a.model.cmp(&b.model));
+    Ok(result)
+}
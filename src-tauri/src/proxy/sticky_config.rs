@@ -9,6 +9,12 @@ pub enum SchedulingMode {
     Balance,
     /// 性能优先 (Performance-first): 纯轮询模式 (Round-robin)，账号负载最均衡，但不利用缓存
     PerformanceFirst,
+    /// 加权模式 (Weighted): 在可用账号中按 weight 比例随机分配流量，
+    /// 用于向高等级套餐账号倾斜；weight = 0 的账号不参与自动调度
+    Weighted,
+    /// 优先级模式 (Priority): 按账号 order_index 升序依次尝试，跳过不可用账号，
+    /// 提供确定性的故障转移顺序 (操作员完全控制先用哪个账号)
+    Priority,
 }
 
 impl Default for SchedulingMode {
@@ -17,6 +23,13 @@ impl Default for SchedulingMode {
     }
 }
 
+impl SchedulingMode {
+    /// 是否启用粘性会话与 60s 锁定 (PerformanceFirst/Weighted/Priority 每次独立选择账号)
+    pub fn is_sticky(&self) -> bool {
+        !matches!(self, Self::PerformanceFirst | Self::Weighted | Self::Priority)
+    }
+}
+
 /// 粘性会话配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(default)]
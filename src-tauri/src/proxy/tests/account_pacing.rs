@@ -0,0 +1,57 @@
+// ==================================================================================
+// 账号限速 (per_account_min_interval_ms) 测试
+// 验证同账号连续请求被拉开到至少配置的最小间隔，关闭时不引入等待
+// ==================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::proxy::token_manager::pacing_delay_ms;
+
+    #[test]
+    fn test_pacing_disabled_never_waits() {
+        // min_interval = 0 表示关闭，任何时间差都不等待
+        assert_eq!(pacing_delay_ms(0, 1000, 0, 0), 0);
+        assert_eq!(pacing_delay_ms(999, 1000, 0, 5), 0);
+    }
+
+    #[test]
+    fn test_pacing_no_wait_when_interval_elapsed() {
+        // 距上次转发已满最小间隔，不等待
+        assert_eq!(pacing_delay_ms(1000, 1200, 200, 0), 0);
+        assert_eq!(pacing_delay_ms(1000, 5000, 200, 10), 0);
+        // 首次转发 (无记录) 也不等待
+        assert_eq!(pacing_delay_ms(0, 5000, 200, 10), 0);
+    }
+
+    #[test]
+    fn test_pacing_fills_gap_plus_jitter() {
+        // 间隔不足时补齐差值并叠加抖动
+        assert_eq!(pacing_delay_ms(1000, 1050, 200, 0), 150);
+        assert_eq!(pacing_delay_ms(1000, 1050, 200, 15), 165);
+        // 时钟回拨时按完整间隔等待
+        assert_eq!(pacing_delay_ms(1000, 900, 200, 0), 200);
+    }
+
+    #[tokio::test]
+    async fn test_back_to_back_requests_are_spaced() {
+        // 模拟同账号两次连续转发：第二次必须被拉开到至少最小间隔
+        let min_interval_ms: u64 = 50;
+        let start = std::time::Instant::now();
+        let mut last_dispatch_ms: i64 = 0;
+
+        for _ in 0..2 {
+            let now_ms = chrono::Utc::now().timestamp_millis();
+            let delay = pacing_delay_ms(last_dispatch_ms, now_ms, min_interval_ms, 0);
+            if delay > 0 {
+                tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+            }
+            last_dispatch_ms = chrono::Utc::now().timestamp_millis();
+        }
+
+        assert!(
+            start.elapsed() >= std::time::Duration::from_millis(min_interval_ms),
+            "两次连续请求的间隔不足 {} ms",
+            min_interval_ms
+        );
+    }
+}
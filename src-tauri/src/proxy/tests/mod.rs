@@ -2,3 +2,7 @@ pub mod comprehensive;
 pub mod security_ip_tests;
 pub mod security_integration_tests;
 pub mod quota_protection;
+pub mod user_token_pinning;
+pub mod weighted_selection;
+pub mod account_pacing;
+pub mod model_fallback;
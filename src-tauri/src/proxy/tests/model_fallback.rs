@@ -0,0 +1,124 @@
+// ==================================================================================
+// 模型回退 (model_fallbacks) 测试
+// 验证"模型不存在"错误识别、回退表查找与 handler 重试路径的单次触发语义
+// ==================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::proxy::common::model_mapping::{fallback_model_for, is_model_unavailable_error};
+    use std::collections::HashMap;
+
+    fn fallbacks() -> HashMap<String, String> {
+        let mut m = HashMap::new();
+        m.insert("gemini-3-pro-high".to_string(), "gemini-3-flash".to_string());
+        m.insert("self-loop".to_string(), "self-loop".to_string());
+        m.insert("empty-target".to_string(), "   ".to_string());
+        m
+    }
+
+    // ==================================================================================
+    // 测试 1: 错误识别 — 仅 400/404 且文案指向模型不存在时触发
+    // ==================================================================================
+
+    #[test]
+    fn test_model_unavailable_error_detection() {
+        assert!(is_model_unavailable_error(404, "model gemini-9 not found"));
+        assert!(is_model_unavailable_error(400, "The requested MODEL does not exist"));
+        assert!(is_model_unavailable_error(400, "model is not supported in this region"));
+        assert!(is_model_unavailable_error(404, r#"{"error":{"status":"NOT_FOUND","message":"unknown model"}}"#));
+
+        // 400 但与模型无关 (thinking 签名等) 不触发
+        assert!(!is_model_unavailable_error(400, "Invalid `signature` in thinking block"));
+        // 限流/鉴权/服务端错误走既有重试策略
+        assert!(!is_model_unavailable_error(429, "model not found"));
+        assert!(!is_model_unavailable_error(401, "model not found"));
+        assert!(!is_model_unavailable_error(500, "model not found"));
+        // 文案没提 model 不触发
+        assert!(!is_model_unavailable_error(404, "resource not found"));
+    }
+
+    // ==================================================================================
+    // 测试 2: 回退表查找 — 未配置/自回退/空目标均视为无回退
+    // ==================================================================================
+
+    #[test]
+    fn test_fallback_lookup() {
+        let map = fallbacks();
+        assert_eq!(
+            fallback_model_for("gemini-3-pro-high", &map),
+            Some("gemini-3-flash".to_string())
+        );
+        // 未配置的模型直接透传错误
+        assert_eq!(fallback_model_for("claude-sonnet-4-5", &map), None);
+        // 回退到自身会造成无意义重试，视为未配置
+        assert_eq!(fallback_model_for("self-loop", &map), None);
+        // 空白目标视为未配置
+        assert_eq!(fallback_model_for("empty-target", &map), None);
+    }
+
+    // ==================================================================================
+    // 测试 3: 重试路径 — 与 handler 相同的判定逻辑，回退只触发一次
+    // ==================================================================================
+
+    /// 模拟 handler 中的决策: 返回每轮实际发出的模型序列
+    fn simulate_attempts(
+        requested: &str,
+        map: &HashMap<String, String>,
+        upstream_responses: &[(u16, &str)],
+    ) -> Vec<String> {
+        let mut fallback_model_override: Option<String> = None;
+        let mut sent = Vec::new();
+        for (status, error_text) in upstream_responses {
+            let mapped = fallback_model_override
+                .clone()
+                .unwrap_or_else(|| requested.to_string());
+            sent.push(mapped.clone());
+            if fallback_model_override.is_none()
+                && is_model_unavailable_error(*status, error_text)
+            {
+                if let Some(fb) = fallback_model_for(&mapped, map) {
+                    fallback_model_override = Some(fb);
+                    continue;
+                }
+            }
+            break;
+        }
+        sent
+    }
+
+    #[test]
+    fn test_retry_once_with_fallback_model() {
+        let map = fallbacks();
+        // 第一轮: 原模型报 404 不存在 → 第二轮换回退模型
+        let sent = simulate_attempts(
+            "gemini-3-pro-high",
+            &map,
+            &[(404, "model not found"), (200, "")],
+        );
+        assert_eq!(sent, vec!["gemini-3-pro-high", "gemini-3-flash"]);
+    }
+
+    #[test]
+    fn test_fallback_triggers_at_most_once() {
+        let map = fallbacks();
+        // 回退模型也报不存在时不再继续回退，错误透传
+        let sent = simulate_attempts(
+            "gemini-3-pro-high",
+            &map,
+            &[(404, "model not found"), (404, "model not found"), (200, "")],
+        );
+        assert_eq!(sent, vec!["gemini-3-pro-high", "gemini-3-flash"]);
+    }
+
+    #[test]
+    fn test_no_fallback_configured_passes_error_through() {
+        let map = fallbacks();
+        // 未配置回退: 不重试，第一轮即结束
+        let sent = simulate_attempts(
+            "claude-sonnet-4-5",
+            &map,
+            &[(404, "model not found"), (200, "")],
+        );
+        assert_eq!(sent, vec!["claude-sonnet-4-5"]);
+    }
+}
@@ -34,10 +34,9 @@ mod tests {
             remaining_quota,
             protected_models: protected_models.iter().map(|s| s.to_string()).collect(),
             health_score: 1.0,
-            reset_time: None,
-            validation_blocked: false,
-            validation_blocked_until: 0,
-            model_quotas: std::collections::HashMap::new(),
+            group: None,
+            weight: 1,
+            order_index: 0,
         }
     }
 
@@ -265,6 +264,7 @@ mod tests {
                 "gemini-3-pro-high".to_string(),
                 "gemini-3-flash".to_string(),
             ],
+            protected_models_mode: crate::models::ProtectedModelsMode::Exclude,
         };
 
         // 测试各种模型名归一化后是否在 monitored_models 中
@@ -420,12 +420,14 @@ mod tests {
             enabled: true,
             threshold_percentage: 60,
             monitored_models: vec!["claude-sonnet-4-5".to_string()],
+            protected_models_mode: crate::models::ProtectedModelsMode::Exclude,
         };
 
         let config_disabled = QuotaProtectionConfig {
             enabled: false,
             threshold_percentage: 60,
             monitored_models: vec!["claude-sonnet-4-5".to_string()],
+            protected_models_mode: crate::models::ProtectedModelsMode::Exclude,
         };
 
         let token = create_mock_token(
@@ -465,6 +467,7 @@ mod tests {
                 "claude-sonnet-4-5".to_string(),
                 "gemini-3-flash".to_string(),
             ],
+            protected_models_mode: crate::models::ProtectedModelsMode::Exclude,
         };
 
         // 2. 创建多个账号，模拟不同配额状态
@@ -1028,9 +1031,27 @@ mod tests {
 
         // 创建 tokens，remaining_quota 使用 max 值（模拟旧逻辑）
         let mut tokens = vec![
-            create_mock_token_with_path("a", "carmelioventori@example.com", vec![], Some(100), path_a.clone()),
-            create_mock_token_with_path("b", "kiriyamaleo@example.com", vec![], Some(100), path_b.clone()),
-            create_mock_token_with_path("c", "mizusawakai9@example.com", vec![], Some(100), path_c.clone()),
+            create_mock_token_with_path(
+                "a",
+                "carmelioventori@example.com",
+                vec![],
+                Some(100),
+                path_a.clone(),
+            ),
+            create_mock_token_with_path(
+                "b",
+                "kiriyamaleo@example.com",
+                vec![],
+                Some(100),
+                path_b.clone(),
+            ),
+            create_mock_token_with_path(
+                "c",
+                "mizusawakai9@example.com",
+                vec![],
+                Some(100),
+                path_c.clone(),
+            ),
         ];
 
         // 目标模型: claude-sonnet-4-5
@@ -1038,16 +1059,18 @@ mod tests {
 
         // 使用修复后的排序逻辑：读取目标模型的 quota
         tokens.sort_by(|a, b| {
-            let quota_a = crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-                &a.account_path,
-                target_model,
-            )
-            .unwrap_or(0);
-            let quota_b = crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
-                &b.account_path,
-                target_model,
-            )
-            .unwrap_or(0);
+            let quota_a =
+                crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
+                    &a.account_path,
+                    target_model,
+                )
+                .unwrap_or(0);
+            let quota_b =
+                crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
+                    &b.account_path,
+                    target_model,
+                )
+                .unwrap_or(0);
             quota_b.cmp(&quota_a) // 高 quota 优先
         });
 
@@ -1093,10 +1116,13 @@ mod tests {
 
         // 请求 claude-opus-4-5-thinking，应该归一化为 claude-sonnet-4-5
         let request_model = "claude-opus-4-5-thinking";
-        let normalized = normalize_to_standard_id(request_model)
-            .unwrap_or_else(|| request_model.to_string());
+        let normalized =
+            normalize_to_standard_id(request_model).unwrap_or_else(|| request_model.to_string());
 
-        assert_eq!(normalized, "claude-sonnet-4-5", "应该归一化为 claude-sonnet-4-5");
+        assert_eq!(
+            normalized, "claude-sonnet-4-5",
+            "应该归一化为 claude-sonnet-4-5"
+        );
 
         // 读取归一化后模型的 quota
         let quota = crate::proxy::token_manager::TokenManager::get_model_quota_from_json_for_test(
@@ -1114,6 +1140,63 @@ mod tests {
         let _ = std::fs::remove_file(&account_path);
     }
 
+    // ==================================================================================
+    // 测试: protected_models 解释模式 (Exclude 排除名单 / Reserve 独占名单)
+    // ==================================================================================
+
+    #[test]
+    fn test_protected_models_mode_exclude_and_reserve() {
+        use crate::models::ProtectedModelsMode;
+        use crate::proxy::token_manager::quota_protection_blocks;
+
+        let account_a =
+            create_mock_token("acc_a", "a@test.com", vec!["claude-sonnet-4-5"], Some(80));
+        let account_b = create_mock_token("acc_b", "b@test.com", vec![], Some(90));
+        let pool = vec![account_a.clone(), account_b.clone()];
+
+        // Exclude (默认): 列入名单的账号不服务该模型，未列入的账号不受影响
+        assert!(quota_protection_blocks(
+            ProtectedModelsMode::Exclude,
+            &pool,
+            &account_a.protected_models,
+            "claude-sonnet-4-5",
+        ));
+        assert!(!quota_protection_blocks(
+            ProtectedModelsMode::Exclude,
+            &pool,
+            &account_b.protected_models,
+            "claude-sonnet-4-5",
+        ));
+
+        // Reserve: 模型被 A 独占后，仅 A 可服务，B 被跳过
+        assert!(!quota_protection_blocks(
+            ProtectedModelsMode::Reserve,
+            &pool,
+            &account_a.protected_models,
+            "claude-sonnet-4-5",
+        ));
+        assert!(quota_protection_blocks(
+            ProtectedModelsMode::Reserve,
+            &pool,
+            &account_b.protected_models,
+            "claude-sonnet-4-5",
+        ));
+
+        // Reserve: 未被任何账号列入的模型不受影响，两个账号都可服务
+        assert!(!quota_protection_blocks(
+            ProtectedModelsMode::Reserve,
+            &pool,
+            &account_a.protected_models,
+            "gemini-3-flash",
+        ));
+        assert!(!quota_protection_blocks(
+            ProtectedModelsMode::Reserve,
+            &pool,
+            &account_b.protected_models,
+            "gemini-3-flash",
+        ));
+    }
+
     /// 辅助函数：创建带有自定义 account_path 的 mock token
     fn create_mock_token_with_path(
         account_id: &str,
@@ -1135,10 +1218,9 @@ mod tests {
             remaining_quota,
             protected_models: protected_models.iter().map(|s| s.to_string()).collect(),
             health_score: 1.0,
-            reset_time: None,
-            validation_blocked: false,
-            validation_blocked_until: 0,
-            model_quotas: std::collections::HashMap::new(),
+            group: None,
+            weight: 1,
+            order_index: 0,
         }
     }
 }
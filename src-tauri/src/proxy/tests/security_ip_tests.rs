@@ -113,6 +113,79 @@ mod security_db_tests {
         cleanup_test_data();
     }
 
+    #[test]
+    fn test_blacklist_remove_by_pattern() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        let _ = add_to_blacklist("10.0.0.6", Some("Pattern remove"), None, "test");
+        assert!(is_ip_in_blacklist("10.0.0.6").unwrap());
+
+        // 按模式移除
+        let result = security_db::remove_from_blacklist_by_pattern("10.0.0.6");
+        assert!(result.is_ok());
+        assert!(!is_ip_in_blacklist("10.0.0.6").unwrap());
+
+        // 不存在的模式应该报错
+        let missing = security_db::remove_from_blacklist_by_pattern("10.0.0.6");
+        assert!(missing.is_err(), "Removing a missing pattern should fail");
+
+        cleanup_test_data();
+    }
+
+    #[test]
+    fn test_clear_blacklist_by_ids() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        // 填充多个条目
+        for i in 0..5 {
+            let _ = add_to_blacklist(
+                &format!("clear.test.{}", i),
+                Some("Clear test"),
+                None,
+                "test",
+            );
+        }
+        assert_eq!(get_blacklist().unwrap().len(), 5);
+
+        // 按 id 逐个删除 (与 clear_ip_blacklist 命令相同的逻辑)
+        for entry in get_blacklist().unwrap() {
+            remove_from_blacklist(&entry.id).unwrap();
+        }
+
+        assert!(get_blacklist().unwrap().is_empty(), "Blacklist should be empty after clear");
+
+        cleanup_test_data();
+    }
+
+    #[test]
+    fn test_blacklist_upsert_on_duplicate_pattern() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        let first = add_to_blacklist("10.0.0.7", Some("First reason"), None, "test").unwrap();
+
+        // 重复添加相同模式: 不产生重复行，reason/expires_at 被更新
+        let second = add_to_blacklist("10.0.0.7", Some("Updated reason"), Some(now_timestamp() + 3600), "test").unwrap();
+        assert_eq!(first.id, second.id, "Upsert should keep the original entry id");
+        assert_eq!(second.reason.as_deref(), Some("Updated reason"));
+        assert!(second.expires_at.is_some());
+
+        let entries: Vec<_> = get_blacklist()
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.ip_pattern == "10.0.0.7")
+            .collect();
+        assert_eq!(entries.len(), 1, "Duplicate add should not create a second row");
+
+        // 按模式删除一次即可完全移除
+        security_db::remove_from_blacklist_by_pattern("10.0.0.7").unwrap();
+        assert!(!is_ip_in_blacklist("10.0.0.7").unwrap());
+
+        cleanup_test_data();
+    }
+
     #[test]
     fn test_blacklist_get_entry_details() {
         let _ = init_db();
@@ -551,9 +624,18 @@ mod security_db_tests {
         let result1 = add_to_blacklist("duplicate.test.ip", Some("First"), None, "test");
         assert!(result1.is_ok());
 
-        // 第二次添加相同 IP 应该失败 (UNIQUE constraint)
+        // 第二次添加相同 IP 走 upsert：更新原条目而非新增/报错
         let result2 = add_to_blacklist("duplicate.test.ip", Some("Second"), None, "test");
-        assert!(result2.is_err(), "Duplicate IP should fail");
+        assert!(result2.is_ok(), "Duplicate IP should upsert, not fail");
+        assert_eq!(result2.unwrap().reason.as_deref(), Some("Second"));
+
+        // 仍然只有一条记录
+        let matches = get_blacklist()
+            .unwrap()
+            .into_iter()
+            .filter(|e| e.ip_pattern == "duplicate.test.ip")
+            .count();
+        assert_eq!(matches, 1, "Upsert should not create a second row");
 
         cleanup_test_data();
     }
@@ -609,6 +691,40 @@ mod security_db_tests {
 
         cleanup_test_data();
     }
+
+    // ============================================================================
+    // 测试类别: 自动封禁默认 TTL
+    // ============================================================================
+
+    #[test]
+    fn test_auto_blacklist_entry_gets_default_ttl() {
+        let _ = init_db();
+        cleanup_test_data();
+
+        crate::proxy::config::update_default_blacklist_ttl_secs(3600);
+
+        // 自动条目未给 expires_at 时应用默认 TTL
+        let before = now_timestamp();
+        let entry = add_to_blacklist("10.99.99.1", Some("auto-ban"), None, "auto").unwrap();
+        let after = now_timestamp();
+
+        let expires_at = entry.expires_at.expect("auto entry should get default TTL");
+        assert!(
+            expires_at >= before + 3600 && expires_at <= after + 3600,
+            "expires_at should be ~now + 3600, got {}",
+            expires_at
+        );
+
+        // 手动条目不受影响，未给 expires_at 时保持永久
+        let manual = add_to_blacklist("10.99.99.2", Some("manual"), None, "manual").unwrap();
+        assert!(manual.expires_at.is_none(), "manual entry should stay permanent");
+
+        // 显式 expires_at 的自动条目按显式值处理
+        let explicit = add_to_blacklist("10.99.99.3", None, Some(before + 60), "auto").unwrap();
+        assert_eq!(explicit.expires_at, Some(before + 60));
+
+        cleanup_test_data();
+    }
 }
 
 // ============================================================================
@@ -743,4 +859,5 @@ mod performance_benchmarks {
             }
         }
     }
+
 }
@@ -0,0 +1,141 @@
+// ==================================================================================
+// 用户令牌固定路由 (pinned_account_id) 测试
+// 验证固定账号可用时被优先使用，不可用/不在池中时回退正常调度
+// ==================================================================================
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::models::ProtectedModelsMode;
+    use crate::proxy::common::model_mapping::normalize_to_standard_id;
+    use crate::proxy::token_manager::{resolve_pinned_route, PinnedRoute, ProxyToken};
+
+    fn create_mock_token(account_id: &str, email: &str, protected_models: Vec<&str>) -> ProxyToken {
+        ProxyToken {
+            account_id: account_id.to_string(),
+            access_token: format!("mock_access_token_{}", account_id),
+            refresh_token: format!("mock_refresh_token_{}", account_id),
+            expires_in: 3600,
+            timestamp: chrono::Utc::now().timestamp() + 3600,
+            email: email.to_string(),
+            account_path: PathBuf::from(format!("/tmp/test_accounts/{}.json", account_id)),
+            project_id: Some("test-project".to_string()),
+            subscription_tier: Some("PRO".to_string()),
+            remaining_quota: Some(80),
+            protected_models: protected_models.iter().map(|s| s.to_string()).collect(),
+            health_score: 1.0,
+            group: None,
+            weight: 1,
+            order_index: 0,
+        }
+    }
+
+    // ==================================================================================
+    // 测试 1: 固定账号在池中且可用 -> 直接命中
+    // ==================================================================================
+
+    #[test]
+    fn test_pinned_account_usable_is_selected() {
+        let tokens = vec![
+            create_mock_token("account-a", "a@example.com", vec![]),
+            create_mock_token("account-vip", "vip@example.com", vec![]),
+        ];
+
+        let route = resolve_pinned_route(
+            &tokens,
+            "account-vip",
+            "claude-sonnet-4-5",
+            true,
+            ProtectedModelsMode::Exclude,
+        );
+
+        match route {
+            PinnedRoute::Use(idx) => assert_eq!(tokens[idx].email, "vip@example.com"),
+            _ => panic!("可用的固定账号应该被直接选中"),
+        }
+    }
+
+    // ==================================================================================
+    // 测试 2: 固定账号被禁用 (禁用账号不会加载进池) -> 回退正常调度
+    // ==================================================================================
+
+    #[test]
+    fn test_pinned_but_disabled_account_falls_back() {
+        // 被禁用的 account-vip 不在池快照中
+        let tokens = vec![
+            create_mock_token("account-a", "a@example.com", vec![]),
+            create_mock_token("account-b", "b@example.com", vec![]),
+        ];
+
+        let route = resolve_pinned_route(
+            &tokens,
+            "account-vip",
+            "claude-sonnet-4-5",
+            true,
+            ProtectedModelsMode::Exclude,
+        );
+
+        // 找不到固定账号，应该回退到正常选择而非报错
+        assert!(
+            matches!(route, PinnedRoute::NotInPool),
+            "禁用账号不应出现在池快照中"
+        );
+    }
+
+    // ==================================================================================
+    // 测试 3: 固定账号被配额保护 -> 回退正常调度
+    // ==================================================================================
+
+    #[test]
+    fn test_pinned_quota_protected_account_falls_back() {
+        let tokens = vec![
+            create_mock_token("account-vip", "vip@example.com", vec!["claude-sonnet-4-5"]),
+            create_mock_token("account-b", "b@example.com", vec![]),
+        ];
+
+        // claude-opus-4-5-thinking 归一化后命中 account-vip 的保护名单
+        let target_model = "claude-opus-4-5-thinking";
+        let normalized =
+            normalize_to_standard_id(target_model).unwrap_or_else(|| target_model.to_string());
+
+        let route = resolve_pinned_route(
+            &tokens,
+            "account-vip",
+            &normalized,
+            true,
+            ProtectedModelsMode::Exclude,
+        );
+
+        match route {
+            PinnedRoute::QuotaProtected(idx) => assert_eq!(tokens[idx].email, "vip@example.com"),
+            _ => panic!("被配额保护的固定账号应该回退正常调度"),
+        }
+    }
+
+    // ==================================================================================
+    // 测试 4: 配额保护总开关关闭时，保护名单不拦截固定路由
+    // ==================================================================================
+
+    #[test]
+    fn test_pinned_account_ignores_protection_when_disabled() {
+        let tokens = vec![create_mock_token(
+            "account-vip",
+            "vip@example.com",
+            vec!["claude-sonnet-4-5"],
+        )];
+
+        let route = resolve_pinned_route(
+            &tokens,
+            "account-vip",
+            "claude-sonnet-4-5",
+            false,
+            ProtectedModelsMode::Exclude,
+        );
+
+        assert!(
+            matches!(route, PinnedRoute::Use(0)),
+            "配额保护关闭时固定账号应直接命中"
+        );
+    }
+}
@@ -0,0 +1,76 @@
+// ==================================================================================
+// 加权调度 (SchedulingMode::Weighted) 测试
+// 验证 pick_weighted_index 的比例分布、weight = 0 排除与空候选处理
+// ==================================================================================
+
+#[cfg(test)]
+mod tests {
+    use crate::proxy::token_manager::pick_weighted_index;
+
+    // ==================================================================================
+    // 测试 1: 均匀 roll 序列下，选中次数严格与权重成比例
+    // ==================================================================================
+
+    #[test]
+    fn test_weighted_distribution_matches_weights() {
+        let weights = vec![1u32, 2, 3];
+        let total: u64 = weights.iter().map(|w| *w as u64).sum();
+
+        let mut counts = vec![0u64; weights.len()];
+        // roll 覆盖 0..total 的整数倍时，roll % total 均匀分布，
+        // 因此每个下标的命中次数应精确等于 weight * 轮数
+        let rounds = 1000u64;
+        for roll in 0..(total * rounds) {
+            let idx = pick_weighted_index(&weights, roll).expect("总权重 > 0 时必须有结果");
+            counts[idx] += 1;
+        }
+
+        assert_eq!(counts[0], 1 * rounds);
+        assert_eq!(counts[1], 2 * rounds);
+        assert_eq!(counts[2], 3 * rounds);
+    }
+
+    // ==================================================================================
+    // 测试 2: 随机 roll 下分布大致与权重成比例 (容忍 ±20%)
+    // ==================================================================================
+
+    #[test]
+    fn test_weighted_distribution_with_random_rolls() {
+        use rand::Rng;
+
+        let weights = vec![1u32, 4];
+        let iterations = 50_000u64;
+        let mut counts = vec![0u64; weights.len()];
+        let mut rng = rand::thread_rng();
+
+        for _ in 0..iterations {
+            let roll: u64 = rng.gen();
+            let idx = pick_weighted_index(&weights, roll).unwrap();
+            counts[idx] += 1;
+        }
+
+        // 期望比例 1:4 → 账号 1 约占 80%
+        let ratio = counts[1] as f64 / iterations as f64;
+        assert!(
+            (0.64..=0.96).contains(&ratio),
+            "权重 4/5 的账号应拿到约 80% 的选择 (实际 {:.2}%)",
+            ratio * 100.0
+        );
+    }
+
+    // ==================================================================================
+    // 测试 3: weight = 0 的候选不被选中；总权重为 0 时返回 None
+    // ==================================================================================
+
+    #[test]
+    fn test_zero_weight_is_never_selected() {
+        let weights = vec![0u32, 5, 0];
+        for roll in 0..100u64 {
+            assert_eq!(pick_weighted_index(&weights, roll), Some(1));
+        }
+
+        let all_zero = vec![0u32, 0];
+        assert_eq!(pick_weighted_index(&all_zero, 42), None);
+        assert_eq!(pick_weighted_index(&[], 42), None);
+    }
+}
@@ -1,13 +1,25 @@
 // 移除冗余的顶层导入，因为这些在代码中已由 full path 或局部导入处理
 use dashmap::DashMap;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, LazyLock};
 
 use crate::proxy::rate_limit::RateLimitTracker;
 use crate::proxy::sticky_config::StickySessionConfig;
 
+/// 账号自定义上游 Headers 注册表 (account_id -> headers)
+/// UpstreamClient 转发请求时按账号查询并附加 (Authorization 除外)
+static CUSTOM_HEADERS_REGISTRY: LazyLock<DashMap<String, HashMap<String, String>>> =
+    LazyLock::new(DashMap::new);
+
+/// 查询账号的自定义上游 Headers (空则返回 None)
+pub fn custom_headers_for(account_id: &str) -> Option<HashMap<String, String>> {
+    CUSTOM_HEADERS_REGISTRY
+        .get(account_id)
+        .map(|entry| entry.clone())
+}
+
 #[derive(Debug, Clone)]
 pub struct ProxyToken {
     pub account_id: String,
@@ -22,6 +34,107 @@ pub struct ProxyToken {
     pub remaining_quota: Option<i32>,      // [FIX #563] Remaining quota for priority sorting
     pub protected_models: HashSet<String>, // [NEW #621]
     pub health_score: f32,                 // [NEW] 健康分数 (0.0 - 1.0)
+    pub group: Option<String>,             // [NEW] 账号分组 (调度可按组约束)
+    pub weight: u32,                       // [NEW] 调度权重 (0 = 仅手动选择)
+    pub order_index: i32,                  // [NEW] 显式调度顺序 (Priority 模式按此升序尝试)
+}
+
+/// [NEW] 按权重随机选择：roll 为外部传入的随机数 (便于测试)，
+/// 返回被选中的下标；总权重为 0 时返回 None
+pub(crate) fn pick_weighted_index(weights: &[u32], roll: u64) -> Option<usize> {
+    let total: u64 = weights.iter().map(|w| *w as u64).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut remaining = roll % total;
+    for (idx, w) in weights.iter().enumerate() {
+        let w = *w as u64;
+        if remaining < w {
+            return Some(idx);
+        }
+        remaining -= w;
+    }
+    None
+}
+
+/// [NEW] 配额保护拦截判定 (纯函数便于测试)。Exclude 模式下 protected_models
+/// 是排除名单：名单内的模型不由该账号服务；Reserve 模式下为独占名单：
+/// 模型一旦被池中任一账号列入，仅列入它的账号可服务，未被列入的模型不受影响
+pub(crate) fn quota_protection_blocks(
+    mode: crate::models::ProtectedModelsMode,
+    pool: &[ProxyToken],
+    token_protected: &HashSet<String>,
+    normalized_model: &str,
+) -> bool {
+    use crate::models::ProtectedModelsMode;
+    match mode {
+        ProtectedModelsMode::Exclude => token_protected.contains(normalized_model),
+        ProtectedModelsMode::Reserve => {
+            !token_protected.contains(normalized_model)
+                && pool
+                    .iter()
+                    .any(|t| t.protected_models.contains(normalized_model))
+        }
+    }
+}
+
+/// [NEW] 用户令牌固定路由判定结果
+pub(crate) enum PinnedRoute {
+    /// 固定账号可用，直接路由 (池快照下标)
+    Use(usize),
+    /// 在池中但被配额保护，回退正常调度 (池快照下标，用于日志)
+    QuotaProtected(usize),
+    /// 不在池中 (已禁用或删除)，回退正常调度
+    NotInPool,
+}
+
+/// [NEW] 用户令牌固定路由判定 (纯函数便于测试)：固定账号在池中且未被
+/// 配额保护时直接路由；限流检查需异步查询，由调用方在 Use 分支补充
+pub(crate) fn resolve_pinned_route(
+    pool: &[ProxyToken],
+    pin_id: &str,
+    normalized_model: &str,
+    quota_protection_enabled: bool,
+    mode: crate::models::ProtectedModelsMode,
+) -> PinnedRoute {
+    match pool.iter().position(|t| t.account_id == pin_id) {
+        None => PinnedRoute::NotInPool,
+        Some(idx) => {
+            let blocked = quota_protection_enabled
+                && quota_protection_blocks(mode, pool, &pool[idx].protected_models, normalized_model);
+            if blocked {
+                PinnedRoute::QuotaProtected(idx)
+            } else {
+                PinnedRoute::Use(idx)
+            }
+        }
+    }
+}
+
+/// [NEW] 计算账号限速需补齐的等待毫秒数 (纯函数便于测试)。
+/// min_interval_ms = 0 表示关闭；距上次转发已满最小间隔时返回 0，
+/// 否则返回差值 + jitter_ms (调用方负责生成随机抖动)
+pub(crate) fn pacing_delay_ms(
+    last_dispatch_ms: i64,
+    now_ms: i64,
+    min_interval_ms: u64,
+    jitter_ms: u64,
+) -> u64 {
+    if min_interval_ms == 0 || last_dispatch_ms <= 0 {
+        return 0;
+    }
+    let elapsed = now_ms.saturating_sub(last_dispatch_ms).max(0) as u64;
+    if elapsed >= min_interval_ms {
+        return 0;
+    }
+    min_interval_ms - elapsed + jitter_ms
+}
+
+/// [NEW] 各账号上次转发时间 (毫秒)，供抗模式检测限速使用；跨 TokenManager 实例共享
+static PACING_LAST_DISPATCH: std::sync::OnceLock<DashMap<String, i64>> = std::sync::OnceLock::new();
+
+fn pacing_last_dispatch() -> &'static DashMap<String, i64> {
+    PACING_LAST_DISPATCH.get_or_init(DashMap::new)
 }
 
 pub struct TokenManager {
@@ -33,7 +146,8 @@ pub struct TokenManager {
     sticky_config: Arc<tokio::sync::RwLock<StickySessionConfig>>, // 新增：调度配置
     session_accounts: Arc<DashMap<String, String>>, // 新增：会话与账号映射 (SessionID -> AccountID)
     preferred_account_id: Arc<tokio::sync::RwLock<Option<String>>>, // [FIX #820] 优先使用的账号ID（固定账号模式）
-    health_scores: Arc<DashMap<String, f32>>,                       // account_id -> health_score
+    group_filter: Arc<tokio::sync::RwLock<Option<String>>>, // [NEW] 分组约束：只在指定分组的账号中调度
+    health_scores: Arc<DashMap<String, f32>>,               // account_id -> health_score
     circuit_breaker_config: Arc<tokio::sync::RwLock<crate::models::CircuitBreakerConfig>>, // [NEW] 熔断配置缓存
 }
 
@@ -49,6 +163,7 @@ impl TokenManager {
             sticky_config: Arc::new(tokio::sync::RwLock::new(StickySessionConfig::default())),
             session_accounts: Arc::new(DashMap::new()),
             preferred_account_id: Arc::new(tokio::sync::RwLock::new(None)), // [FIX #820]
+            group_filter: Arc::new(tokio::sync::RwLock::new(None)),         // [NEW]
             health_scores: Arc::new(DashMap::new()),
             circuit_breaker_config: Arc::new(tokio::sync::RwLock::new(
                 crate::models::CircuitBreakerConfig::default(),
@@ -85,6 +200,7 @@ impl TokenManager {
 
         // Reload should reflect current on-disk state (accounts can be added/removed/disabled).
         self.tokens.clear();
+        CUSTOM_HEADERS_REGISTRY.clear();
         self.current_index.store(0, Ordering::SeqCst);
         {
             let mut last_used = self.last_used_account.lock().await;
@@ -176,6 +292,43 @@ impl TokenManager {
             return Ok(None);
         }
 
+        // [NEW] 上游限流冷却：仅对 reason == "upstream_rate_limited" 生效，
+        // 窗口内跳过加载，过期自动清除标记 (403 验证阻止的语义保持不变)
+        let blocked_reason = account
+            .get("validation_blocked_reason")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string();
+        if blocked_reason == "upstream_rate_limited" {
+            let blocked_until = account
+                .get("validation_blocked_until")
+                .and_then(|v| v.as_i64())
+                .unwrap_or(0);
+            let now = chrono::Utc::now().timestamp();
+            if blocked_until > now {
+                tracing::debug!(
+                    "Account skipped due to upstream rate-limit cooldown ({}s left): {:?}",
+                    blocked_until - now,
+                    path
+                );
+                return Ok(None);
+            }
+            // 冷却已过：清除标记并写回，账号自动恢复
+            account["validation_blocked"] = serde_json::Value::Bool(false);
+            account["validation_blocked_until"] = serde_json::Value::Null;
+            account["validation_blocked_reason"] = serde_json::Value::Null;
+            if let Ok(serialized) = serde_json::to_string_pretty(&account) {
+                let _ = std::fs::write(path, serialized);
+            }
+            if let Some(id) = account.get("id").and_then(|v| v.as_str()) {
+                crate::modules::account_events::record_event(
+                    id,
+                    "validation_unblocked",
+                    Some("upstream_rate_limited_expired"),
+                );
+            }
+        }
+
         // [修复 #1344] 先检查账号是否被手动禁用(非配额保护原因)
         let is_proxy_disabled = account
             .get("proxy_disabled")
@@ -296,6 +449,42 @@ impl TokenManager {
             .map(|v| *v)
             .unwrap_or(1.0);
 
+        // [NEW] 同步自定义上游 Headers 到注册表 (供 UpstreamClient 查询)
+        let custom_headers: HashMap<String, String> = account
+            .get("custom_headers")
+            .and_then(|v| v.as_object())
+            .map(|obj| {
+                obj.iter()
+                    .filter_map(|(k, v)| v.as_str().map(|s| (k.clone(), s.to_string())))
+                    .collect()
+            })
+            .unwrap_or_default();
+        if custom_headers.is_empty() {
+            CUSTOM_HEADERS_REGISTRY.remove(&account_id);
+        } else {
+            CUSTOM_HEADERS_REGISTRY.insert(account_id.clone(), custom_headers);
+        }
+
+        // [NEW] 账号分组 (空字符串视为未分组)
+        let group = account
+            .get("group")
+            .and_then(|v| v.as_str())
+            .filter(|s| !s.trim().is_empty())
+            .map(|s| s.to_string());
+
+        // [NEW] 调度权重 (缺省 1；0 = 仅手动选择)
+        let weight = account
+            .get("weight")
+            .and_then(|v| v.as_u64())
+            .unwrap_or(1)
+            .min(u32::MAX as u64) as u32;
+
+        // [NEW] 显式调度顺序 (缺省 0)
+        let order_index = account
+            .get("order_index")
+            .and_then(|v| v.as_i64())
+            .unwrap_or(0) as i32;
+
         Ok(Some(ProxyToken {
             account_id,
             access_token,
@@ -309,6 +498,9 @@ impl TokenManager {
             remaining_quota,
             protected_models,
             health_score,
+            group,
+            weight,
+            order_index,
         }))
     }
 
@@ -454,6 +646,24 @@ impl TokenManager {
         }
     }
 
+    /// [TEST] 从账号 JSON 文件读取指定模型的剩余配额百分比 (测试辅助)
+    #[cfg(test)]
+    pub(crate) fn get_model_quota_from_json_for_test(
+        account_path: &std::path::Path,
+        model_name: &str,
+    ) -> Option<i32> {
+        let content = std::fs::read_to_string(account_path).ok()?;
+        let json: serde_json::Value = serde_json::from_str(&content).ok()?;
+        json.get("quota")?
+            .get("models")?
+            .as_array()?
+            .iter()
+            .find(|m| m.get("name").and_then(|n| n.as_str()) == Some(model_name))
+            .and_then(|m| m.get("percentage"))
+            .and_then(|p| p.as_i64())
+            .map(|p| p as i32)
+    }
+
     /// 触发配额保护，限制特定模型 (Issue #621)
     /// 返回 true 如果发生了改变
     async fn trigger_quota_protection(
@@ -586,33 +796,132 @@ impl TokenManager {
         Ok(false)
     }
 
+    /// [NEW] 直接使用指定账号 (固定账号模式与用户令牌固定路由共用)：
+    /// 刷新临期 token、补齐 project_id 后返回凭据
+    async fn use_account_directly(
+        &self,
+        mut token: ProxyToken,
+    ) -> (String, String, String, String, u64) {
+        // 检查 token 是否过期（提前5分钟刷新）
+        let now = chrono::Utc::now().timestamp();
+        if now >= token.timestamp - 300 {
+            tracing::debug!("账号 {} 的 token 即将过期，正在刷新...", token.email);
+            match crate::modules::oauth::refresh_access_token(&token.refresh_token, None).await {
+                Ok(token_response) => {
+                    token.access_token = token_response.access_token.clone();
+                    token.expires_in = token_response.expires_in;
+                    token.timestamp = now + token_response.expires_in;
+                    if let Some(new_refresh_token) = &token_response.refresh_token {
+                        token.refresh_token = new_refresh_token.clone();
+                    }
+
+                    if let Some(mut entry) = self.tokens.get_mut(&token.account_id) {
+                        entry.access_token = token.access_token.clone();
+                        entry.refresh_token = token.refresh_token.clone();
+                        entry.expires_in = token.expires_in;
+                        entry.timestamp = token.timestamp;
+                    }
+                    let _ = self
+                        .save_refreshed_token(&token.account_id, &token_response)
+                        .await;
+                }
+                Err(e) => {
+                    tracing::warn!("Preferred account token refresh failed: {}", e);
+                    // 继续使用旧 token，让后续逻辑处理失败
+                }
+            }
+        }
+
+        // 确保有 project_id
+        let project_id = if let Some(pid) = &token.project_id {
+            pid.clone()
+        } else {
+            match crate::proxy::project_resolver::fetch_project_id(&token.access_token).await {
+                Ok(pid) => {
+                    if let Some(mut entry) = self.tokens.get_mut(&token.account_id) {
+                        entry.project_id = Some(pid.clone());
+                    }
+                    let _ = self.save_project_id(&token.account_id, &pid).await;
+                    pid
+                }
+                Err(_) => "bamboo-precept-lgxtn".to_string(), // fallback
+            }
+        };
+
+        (
+            token.access_token,
+            project_id,
+            token.email,
+            token.account_id,
+            0,
+        )
+    }
+
     /// 获取当前可用的 Token（支持粘性会话与智能调度）
     /// 参数 `quota_group` 用于区分 "claude" vs "gemini" 组
     /// 参数 `force_rotate` 为 true 时将忽略锁定，强制切换账号
     /// 参数 `session_id` 用于跨请求维持会话粘性
     /// 参数 `target_model` 用于检查配额保护 (Issue #621)
+    /// 参数 `pinned_account_id` 为用户令牌固定路由的账号 (不可用时回退正常调度) [NEW]
     pub async fn get_token(
         &self,
         quota_group: &str,
         force_rotate: bool,
         session_id: Option<&str>,
         target_model: &str,
+        pinned_account_id: Option<&str>,
     ) -> Result<(String, String, String, String, u64), String> {
         // 【优化 Issue #284】添加 5 秒超时，防止死锁
         let timeout_duration = std::time::Duration::from_secs(5);
         match tokio::time::timeout(
             timeout_duration,
-            self.get_token_internal(quota_group, force_rotate, session_id, target_model),
+            self.get_token_internal(
+                quota_group,
+                force_rotate,
+                session_id,
+                target_model,
+                pinned_account_id,
+            ),
         )
         .await
         {
-            Ok(result) => result,
+            Ok(result) => {
+                // [NEW] 抗模式检测限速：同账号两次转发间隔不足时补齐差值 + 抖动。
+                // 放在超时块之外，等待不占用 5 秒获取预算
+                if let Ok((_, _, _, account_id, _)) = &result {
+                    self.pace_account(account_id).await;
+                }
+                result
+            }
             Err(_) => Err(
                 "Token acquisition timeout (5s) - system too busy or deadlock detected".to_string(),
             ),
         }
     }
 
+    /// [NEW] 账号限速：per_account_min_interval_ms > 0 时，保证同一账号两次
+    /// 转发之间至少间隔该毫秒数，并叠加最多 10% 的随机抖动
+    async fn pace_account(&self, account_id: &str) {
+        let min_interval = crate::proxy::config::get_per_account_min_interval_ms();
+        if min_interval == 0 {
+            return;
+        }
+
+        let map = pacing_last_dispatch();
+        let now_ms = chrono::Utc::now().timestamp_millis();
+        let last_ms = map.get(account_id).map(|v| *v).unwrap_or(0);
+        let jitter: u64 = rand::Rng::gen_range(&mut rand::thread_rng(), 0..=min_interval / 10);
+        let delay = pacing_delay_ms(last_ms, now_ms, min_interval, jitter);
+        if delay > 0 {
+            tracing::debug!("[Pacing] 账号 {} 限速等待 {} ms", account_id, delay);
+            tokio::time::sleep(std::time::Duration::from_millis(delay)).await;
+        }
+        map.insert(
+            account_id.to_string(),
+            chrono::Utc::now().timestamp_millis(),
+        );
+    }
+
     /// 内部实现：获取 Token 的核心逻辑
     async fn get_token_internal(
         &self,
@@ -620,14 +929,24 @@ impl TokenManager {
         force_rotate: bool,
         session_id: Option<&str>,
         target_model: &str,
+        pinned_account_id: Option<&str>,
     ) -> Result<(String, String, String, String, u64), String> {
         let mut tokens_snapshot: Vec<ProxyToken> =
             self.tokens.iter().map(|e| e.value().clone()).collect();
-        let total = tokens_snapshot.len();
-        if total == 0 {
+        if tokens_snapshot.is_empty() {
             return Err("Token pool is empty".to_string());
         }
 
+        // [NEW] 分组约束：启用后只在该分组的账号中调度
+        let group_filter = self.group_filter.read().await.clone();
+        if let Some(ref group) = group_filter {
+            tokens_snapshot.retain(|t| t.group.as_deref() == Some(group.as_str()));
+            if tokens_snapshot.is_empty() {
+                return Err(format!("No available accounts in group '{}'", group));
+            }
+        }
+        let total = tokens_snapshot.len();
+
         // ===== 【优化】根据订阅等级和剩余配额排序 =====
         // [FIX #563] 优先级: ULTRA > PRO > FREE, 同tier内优先高配额账号
         // 理由: ULTRA/PRO 重置快，优先消耗；FREE 重置慢，用于兜底
@@ -678,9 +997,16 @@ impl TokenManager {
         use crate::proxy::sticky_config::SchedulingMode;
 
         // 【新增】检查配额保护是否启用（如果关闭，则忽略 protected_models 检查）
-        let quota_protection_enabled = crate::modules::config::load_app_config()
-            .map(|cfg| cfg.quota_protection.enabled)
-            .unwrap_or(false);
+        // [NEW] 同时读取 protected_models 的解释模式 (exclude / reserve)
+        let (quota_protection_enabled, protected_models_mode) =
+            crate::modules::config::load_app_config()
+                .map(|cfg| {
+                    (
+                        cfg.quota_protection.enabled,
+                        cfg.quota_protection.protected_models_mode,
+                    )
+                })
+                .unwrap_or((false, crate::models::ProtectedModelsMode::Exclude));
 
         // ===== [FIX #820] 固定账号模式：优先使用指定账号 =====
         let preferred_id = self.preferred_account_id.read().await.clone();
@@ -699,67 +1025,48 @@ impl TokenManager {
                 );
 
                 // 直接使用优先账号，跳过轮询逻辑
-                let mut token = preferred_token.clone();
-
-                // 检查 token 是否过期（提前5分钟刷新）
-                let now = chrono::Utc::now().timestamp();
-                if now >= token.timestamp - 300 {
-                    tracing::debug!("账号 {} 的 token 即将过期，正在刷新...", token.email);
-                    match crate::modules::oauth::refresh_access_token(&token.refresh_token, None)
-                        .await
-                    {
-                        Ok(token_response) => {
-                            token.access_token = token_response.access_token.clone();
-                            token.expires_in = token_response.expires_in;
-                            token.timestamp = now + token_response.expires_in;
-
-                            if let Some(mut entry) = self.tokens.get_mut(&token.account_id) {
-                                entry.access_token = token.access_token.clone();
-                                entry.expires_in = token.expires_in;
-                                entry.timestamp = token.timestamp;
-                            }
-                            let _ = self
-                                .save_refreshed_token(&token.account_id, &token_response)
-                                .await;
-                        }
-                        Err(e) => {
-                            tracing::warn!("Preferred account token refresh failed: {}", e);
-                            // 继续使用旧 token，让后续逻辑处理失败
-                        }
-                    }
-                }
-
-                // 确保有 project_id
-                let project_id = if let Some(pid) = &token.project_id {
-                    pid.clone()
-                } else {
-                    match crate::proxy::project_resolver::fetch_project_id(&token.access_token)
-                        .await
-                    {
-                        Ok(pid) => {
-                            if let Some(mut entry) = self.tokens.get_mut(&token.account_id) {
-                                entry.project_id = Some(pid.clone());
-                            }
-                            let _ = self.save_project_id(&token.account_id, &pid).await;
-                            pid
-                        }
-                        Err(_) => "bamboo-precept-lgxtn".to_string(), // fallback
-                    }
-                };
-
-                return Ok((
-                    token.access_token,
-                    project_id,
-                    token.email,
-                    token.account_id,
-                    0,
-                ));
+                return Ok(self.use_account_directly(preferred_token.clone()).await);
             } else {
                 tracing::warn!("🔒 [FIX #820] Preferred account {} not found in pool, falling back to round-robin", pref_id);
             }
         }
         // ===== [END FIX #820] =====
 
+        // ===== [NEW] 用户令牌固定账号：可用时直接路由，不可用时回退正常调度 =====
+        if let Some(pin_id) = pinned_account_id {
+            let normalized_pin_target =
+                crate::proxy::common::model_mapping::normalize_to_standard_id(target_model)
+                    .unwrap_or_else(|| target_model.to_string());
+            match resolve_pinned_route(
+                &tokens_snapshot,
+                pin_id,
+                &normalized_pin_target,
+                quota_protection_enabled,
+                protected_models_mode,
+            ) {
+                PinnedRoute::Use(idx) if !self.is_rate_limited(pin_id, None).await => {
+                    let pinned_token = &tokens_snapshot[idx];
+                    tracing::info!(
+                        "📌 Pinned account {} is usable, routing user token to it",
+                        pinned_token.email
+                    );
+                    return Ok(self.use_account_directly(pinned_token.clone()).await);
+                }
+                PinnedRoute::Use(idx) | PinnedRoute::QuotaProtected(idx) => {
+                    tracing::warn!(
+                        "📌 Pinned account {} unavailable (rate-limited or quota-protected), falling back to normal selection",
+                        tokens_snapshot[idx].email
+                    );
+                }
+                PinnedRoute::NotInPool => {
+                    tracing::warn!(
+                        "📌 Pinned account {} not in pool (disabled or removed), falling back to normal selection",
+                        pin_id
+                    );
+                }
+            }
+        }
+
         // 【优化 Issue #284】将锁操作移到循环外，避免重复获取锁
         // 预先获取 last_used_account 的快照，避免在循环中多次加锁
         let last_used_account_id = if quota_group != "image_gen" {
@@ -785,10 +1092,7 @@ impl TokenManager {
                     .unwrap_or_else(|| target_model.to_string());
 
             // 模式 A: 粘性会话处理 (CacheFirst 或 Balance 且有 session_id)
-            if !rotate
-                && session_id.is_some()
-                && scheduling.mode != SchedulingMode::PerformanceFirst
-            {
+            if !rotate && session_id.is_some() && scheduling.mode.is_sticky() {
                 let sid = session_id.unwrap();
 
                 // 1. 检查会话是否已绑定账号
@@ -813,13 +1117,23 @@ impl TokenManager {
                             self.session_accounts.remove(sid);
                         } else if !attempted.contains(&bound_id)
                             && !(quota_protection_enabled
-                                && bound_token.protected_models.contains(&normalized_target))
+                                && quota_protection_blocks(
+                                    protected_models_mode,
+                                    &tokens_snapshot,
+                                    &bound_token.protected_models,
+                                    &normalized_target,
+                                ))
                         {
                             // 3. 账号可用且未被标记为尝试失败，优先复用
                             tracing::debug!("Sticky Session: Successfully reusing bound account {} for session {}", bound_token.email, sid);
                             target_token = Some(bound_token.clone());
                         } else if quota_protection_enabled
-                            && bound_token.protected_models.contains(&normalized_target)
+                            && quota_protection_blocks(
+                                protected_models_mode,
+                                &tokens_snapshot,
+                                &bound_token.protected_models,
+                                &normalized_target,
+                            )
                         {
                             tracing::debug!("Sticky Session: Bound account {} is quota-protected for model {} [{}], unbinding and switching.", bound_token.email, normalized_target, target_model);
                             self.session_accounts.remove(sid);
@@ -840,7 +1154,7 @@ impl TokenManager {
             if target_token.is_none()
                 && !rotate
                 && quota_group != "image_gen"
-                && scheduling.mode != SchedulingMode::PerformanceFirst
+                && scheduling.mode.is_sticky()
             {
                 // 【优化】使用预先获取的快照，不再在循环内加锁
                 if let Some((account_id, last_time)) = &last_used_account_id {
@@ -854,7 +1168,12 @@ impl TokenManager {
                                 .is_rate_limited(&found.account_id, Some(&normalized_target))
                                 .await
                                 && !(quota_protection_enabled
-                                    && found.protected_models.contains(&normalized_target))
+                                    && quota_protection_blocks(
+                                        protected_models_mode,
+                                        &tokens_snapshot,
+                                        &found.protected_models,
+                                        &normalized_target,
+                                    ))
                             {
                                 tracing::debug!(
                                     "60s Window: Force reusing last account: {}",
@@ -888,9 +1207,23 @@ impl TokenManager {
                             continue;
                         }
 
+                        // [NEW] weight = 0 的账号不参与自动调度
+                        if candidate.weight == 0 {
+                            tracing::debug!(
+                                "Account {} has weight 0 (manual only), skipping",
+                                candidate.email
+                            );
+                            continue;
+                        }
+
                         // 【新增 #621】模型级限流检查
                         if quota_protection_enabled
-                            && candidate.protected_models.contains(&normalized_target)
+                            && quota_protection_blocks(
+                                protected_models_mode,
+                                &tokens_snapshot,
+                                &candidate.protected_models,
+                                &normalized_target,
+                            )
                         {
                             tracing::debug!(
                                 "Account {} is quota-protected for model {} [{}], skipping",
@@ -917,7 +1250,7 @@ impl TokenManager {
 
                         // 如果是会话首次分配且需要粘性，在此建立绑定
                         if let Some(sid) = session_id {
-                            if scheduling.mode != SchedulingMode::PerformanceFirst {
+                            if scheduling.mode.is_sticky() {
                                 self.session_accounts
                                     .insert(sid.to_string(), candidate.account_id.clone());
                                 tracing::debug!(
@@ -930,6 +1263,83 @@ impl TokenManager {
                         break;
                     }
                 }
+            } else if target_token.is_none() && scheduling.mode == SchedulingMode::Weighted {
+                // 模式 C2: [NEW] 加权选择，在可用候选中按 weight 比例随机
+                let mut eligible: Vec<&ProxyToken> = Vec::new();
+                for candidate in &tokens_snapshot {
+                    if attempted.contains(&candidate.account_id) {
+                        continue;
+                    }
+                    if candidate.weight == 0 {
+                        continue; // 仅手动选择
+                    }
+                    if quota_protection_enabled
+                        && quota_protection_blocks(
+                            protected_models_mode,
+                            &tokens_snapshot,
+                            &candidate.protected_models,
+                            &normalized_target,
+                        )
+                    {
+                        continue;
+                    }
+                    if self
+                        .is_rate_limited(&candidate.account_id, Some(&normalized_target))
+                        .await
+                    {
+                        continue;
+                    }
+                    eligible.push(candidate);
+                }
+
+                let weights: Vec<u32> = eligible.iter().map(|t| t.weight).collect();
+                let roll: u64 = rand::Rng::gen(&mut rand::thread_rng());
+                if let Some(idx) = pick_weighted_index(&weights, roll) {
+                    let candidate = eligible[idx];
+                    tracing::debug!(
+                        "⚖️ [Weighted] Selected {} (weight {} of total {})",
+                        candidate.email,
+                        candidate.weight,
+                        weights.iter().map(|w| *w as u64).sum::<u64>()
+                    );
+                    target_token = Some(candidate.clone());
+                }
+            } else if target_token.is_none() && scheduling.mode == SchedulingMode::Priority {
+                // 模式 C3: [NEW] 优先级模式，按 order_index 升序尝试第一个可用账号
+                // (稳定排序：order_index 相同的账号保持 tier/配额排序后的相对顺序)
+                let mut ordered: Vec<&ProxyToken> = tokens_snapshot.iter().collect();
+                ordered.sort_by_key(|t| t.order_index);
+                for candidate in ordered {
+                    if attempted.contains(&candidate.account_id) {
+                        continue;
+                    }
+                    if candidate.weight == 0 {
+                        continue; // 仅手动选择
+                    }
+                    if quota_protection_enabled
+                        && quota_protection_blocks(
+                            protected_models_mode,
+                            &tokens_snapshot,
+                            &candidate.protected_models,
+                            &normalized_target,
+                        )
+                    {
+                        continue;
+                    }
+                    if self
+                        .is_rate_limited(&candidate.account_id, Some(&normalized_target))
+                        .await
+                    {
+                        continue;
+                    }
+                    tracing::debug!(
+                        "🥇 [Priority] Selected {} (order_index {})",
+                        candidate.email,
+                        candidate.order_index
+                    );
+                    target_token = Some(candidate.clone());
+                    break;
+                }
             } else if target_token.is_none() {
                 // 模式 C: 纯轮询模式 (Round-robin) 或强制轮换
                 let start_idx = self.current_index.fetch_add(1, Ordering::SeqCst) % total;
@@ -951,9 +1361,20 @@ impl TokenManager {
                         continue;
                     }
 
+                    // [NEW] weight = 0 的账号不参与自动调度
+                    if candidate.weight == 0 {
+                        tracing::debug!("  ✋ {} - SKIP: weight 0 (manual only)", candidate.email);
+                        continue;
+                    }
+
                     // 【新增 #621】模型级限流检查
                     if quota_protection_enabled
-                        && candidate.protected_models.contains(&normalized_target)
+                        && quota_protection_blocks(
+                            protected_models_mode,
+                            &tokens_snapshot,
+                            &candidate.protected_models,
+                            &normalized_target,
+                        )
                     {
                         tracing::debug!(
                             "  ⛔ {} - SKIP: quota-protected for {} [{}]",
@@ -1070,10 +1491,14 @@ impl TokenManager {
                         token.access_token = token_response.access_token.clone();
                         token.expires_in = token_response.expires_in;
                         token.timestamp = now + token_response.expires_in;
+                        if let Some(new_refresh_token) = &token_response.refresh_token {
+                            token.refresh_token = new_refresh_token.clone();
+                        }
 
                         // 同步更新跨线程共享的 DashMap
                         if let Some(mut entry) = self.tokens.get_mut(&token.account_id) {
                             entry.access_token = token.access_token.clone();
+                            entry.refresh_token = token.refresh_token.clone();
                             entry.expires_in = token.expires_in;
                             entry.timestamp = token.timestamp;
                         }
@@ -1197,6 +1622,10 @@ impl TokenManager {
         content["disabled"] = serde_json::Value::Bool(true);
         content["disabled_at"] = serde_json::Value::Number(now.into());
         content["disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
+        // [NEW] 同步标记反代禁用，前端账号池视图保持一致
+        content["proxy_disabled"] = serde_json::Value::Bool(true);
+        content["proxy_disabled_at"] = serde_json::Value::Number(now.into());
+        content["proxy_disabled_reason"] = serde_json::Value::String(truncate_reason(reason, 800));
 
         std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
             .map_err(|e| format!("写入文件失败: {}", e))?;
@@ -1204,6 +1633,11 @@ impl TokenManager {
         // 【修复 Issue #3】从内存中移除禁用的账号，防止被60s锁定逻辑继续使用
         self.tokens.remove(account_id);
 
+        // [NEW] 失效账号主动通知（系统通知 + account://invalidated 事件）
+        if let Some(email) = content.get("email").and_then(|v| v.as_str()) {
+            crate::modules::integration::notify_account_invalidated(email, reason);
+        }
+
         tracing::warn!("Account disabled: {} ({:?})", account_id, path);
         Ok(())
     }
@@ -1236,6 +1670,43 @@ impl TokenManager {
         Ok(())
     }
 
+    /// [NEW] 记录账号最近一次失败 (非 2xx)，写入账号文件供前端池状态展示
+    ///
+    /// 仅覆盖 last_error / last_error_at 两个字段，失败时只记日志不中断请求流程。
+    pub fn record_account_error(&self, email: &str, status: u16, error_body: &str) {
+        let account_id = match self.email_to_account_id(email) {
+            Some(id) => id,
+            None => return, // 外部提供商等非池内账号，无对应文件
+        };
+
+        let path = if let Some(entry) = self.tokens.get(&account_id) {
+            entry.account_path.clone()
+        } else {
+            self.data_dir
+                .join("accounts")
+                .join(format!("{}.json", account_id))
+        };
+
+        let mut content: serde_json::Value = match std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|s| serde_json::from_str(&s).map_err(|e| e.to_string()))
+        {
+            Ok(v) => v,
+            Err(e) => {
+                tracing::warn!("记录账号 {} 最近错误失败 (读取): {}", account_id, e);
+                return;
+            }
+        };
+
+        let message = format!("HTTP {}: {}", status, error_body);
+        content["last_error"] = serde_json::Value::String(truncate_reason(&message, 800));
+        content["last_error_at"] = serde_json::Value::Number(chrono::Utc::now().timestamp().into());
+
+        if let Err(e) = std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap()) {
+            tracing::warn!("记录账号 {} 最近错误失败 (写入): {}", account_id, e);
+        }
+    }
+
     pub fn get_account_id_by_email(&self, email: &str) -> Option<String> {
         for entry in self.tokens.iter() {
             if entry.value().email == email {
@@ -1294,10 +1765,76 @@ impl TokenManager {
         std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
             .map_err(|e| format!("写入文件失败: {}", e))?;
 
+        // [NEW] 审计事件：保留验证阻止历史
+        crate::modules::account_events::record_event(
+            account_id,
+            "validation_blocked",
+            Some(&truncate_reason(reason, 200)),
+        );
+
         self.tokens.remove(account_id);
         Ok(())
     }
 
+    /// [NEW] 上游 429 后的持久化冷却：把 validation_blocked_until 写入账号文件，
+    /// 原因固定为 "upstream_rate_limited"。冷却时长优先取 Retry-After header，
+    /// 否则用 proxy.rate_limit_cooldown_secs 配置；过期后在账号加载时自动清除
+    /// (见 load_single_account)。账号保留在内存池中 —— 运行期的调度跳过由
+    /// rate_limit_tracker 负责，这里只保证冷却跨重启生效。
+    pub fn apply_rate_limit_cooldown(&self, account_id: &str, retry_after_header: Option<&str>) {
+        let default_secs = crate::modules::config::load_app_config()
+            .map(|c| c.proxy.rate_limit_cooldown_secs)
+            .unwrap_or(60);
+        if default_secs == 0 {
+            return; // 0 = 不持久化冷却
+        }
+
+        let cooldown_secs = retry_after_header
+            .and_then(|s| s.parse::<u64>().ok())
+            .filter(|s| *s > 0)
+            .unwrap_or(default_secs);
+        let until = chrono::Utc::now().timestamp() + cooldown_secs as i64;
+
+        let path = if let Some(entry) = self.tokens.get(account_id) {
+            entry.account_path.clone()
+        } else {
+            self.data_dir
+                .join("accounts")
+                .join(format!("{}.json", account_id))
+        };
+
+        let result = (|| -> Result<(), String> {
+            let mut content: serde_json::Value = serde_json::from_str(
+                &std::fs::read_to_string(&path).map_err(|e| format!("读取文件失败: {}", e))?,
+            )
+            .map_err(|e| format!("解析 JSON 失败: {}", e))?;
+
+            content["validation_blocked"] = serde_json::Value::Bool(true);
+            content["validation_blocked_until"] = serde_json::Value::Number(until.into());
+            content["validation_blocked_reason"] =
+                serde_json::Value::String("upstream_rate_limited".to_string());
+
+            std::fs::write(&path, serde_json::to_string_pretty(&content).unwrap())
+                .map_err(|e| format!("写入文件失败: {}", e))
+        })();
+
+        match result {
+            Ok(()) => {
+                crate::modules::account_events::record_event(
+                    account_id,
+                    "validation_blocked",
+                    Some("upstream_rate_limited"),
+                );
+                tracing::info!(
+                    "账号 {} 因上游 429 进入持久化冷却 {} 秒 (reason=upstream_rate_limited)",
+                    account_id,
+                    cooldown_secs
+                )
+            }
+            Err(e) => tracing::warn!("账号 {} 持久化限流冷却写入失败: {}", account_id, e),
+        }
+    }
+
     /// 保存刷新后的 token 到账号文件
     async fn save_refreshed_token(
         &self,
@@ -1322,6 +1859,13 @@ impl TokenManager {
         content["token"]["expiry_timestamp"] =
             serde_json::Value::Number((now + token_response.expires_in).into());
 
+        // [FIX] Google 可能在刷新时轮换 refresh_token，新值必须落盘，否则旧值失效后账号无法再刷新；
+        // 未返回时保留原 refresh_token
+        if let Some(new_refresh_token) = &token_response.refresh_token {
+            content["token"]["refresh_token"] =
+                serde_json::Value::String(new_refresh_token.clone());
+        }
+
         std::fs::write(path, serde_json::to_string_pretty(&content).unwrap())
             .map_err(|e| format!("写入文件失败: {}", e))?;
 
@@ -1333,6 +1877,23 @@ impl TokenManager {
         self.tokens.len()
     }
 
+    /// [NEW] 可用账号数 (已加载且未被限流)，供健康探针等使用
+    pub async fn usable_account_count(&self) -> usize {
+        let account_ids: Vec<String> = self
+            .tokens
+            .iter()
+            .map(|entry| entry.value().account_id.clone())
+            .collect();
+
+        let mut count = 0;
+        for account_id in account_ids {
+            if !self.is_rate_limited(&account_id, None).await {
+                count += 1;
+            }
+        }
+        count
+    }
+
     /// 通过 email 获取指定账号的 Token（用于预热等需要指定账号的场景）
     /// 此方法会自动刷新过期的 token
     pub async fn get_token_by_email(
@@ -1397,6 +1958,9 @@ impl TokenManager {
                 // 更新缓存
                 if let Some(mut entry) = self.tokens.get_mut(&account_id) {
                     entry.access_token = token_response.access_token.clone();
+                    if let Some(new_refresh_token) = &token_response.refresh_token {
+                        entry.refresh_token = new_refresh_token.clone();
+                    }
                     entry.expires_in = token_response.expires_in;
                     entry.timestamp = new_now;
                 }
@@ -1432,6 +1996,16 @@ impl TokenManager {
         retry_after_header: Option<&str>,
         error_body: &str,
     ) {
+        // [NEW] 无论熔断开关如何，先记录该账号的最近错误
+        self.record_account_error(email, status, error_body);
+
+        // [NEW] 429 持久化冷却，不依赖熔断开关 (跨重启生效)
+        if status == 429 {
+            if let Some(account_id) = self.email_to_account_id(email) {
+                self.apply_rate_limit_cooldown(&account_id, retry_after_header);
+            }
+        }
+
         // [NEW] 检查熔断是否启用 (使用内存缓存，极快)
         let config = self.circuit_breaker_config.read().await.clone();
         if !config.enabled {
@@ -1487,7 +2061,7 @@ impl TokenManager {
 
     /// 【替代方案】通过 email 查找对应的 account_id
     /// 用于将 handlers 传入的 email 转换为 tracker 使用的 account_id
-    fn email_to_account_id(&self, email: &str) -> Option<String> {
+    pub(crate) fn email_to_account_id(&self, email: &str) -> Option<String> {
         self.tokens
             .iter()
             .find(|entry| entry.value().email == email)
@@ -1534,9 +2108,20 @@ impl TokenManager {
     /// ```
     pub async fn has_available_account(&self, _quota_group: &str, target_model: &str) -> bool {
         // 检查配额保护是否启用
-        let quota_protection_enabled = crate::modules::config::load_app_config()
-            .map(|cfg| cfg.quota_protection.enabled)
-            .unwrap_or(false);
+        // [NEW] 同时读取 protected_models 的解释模式 (exclude / reserve)
+        let (quota_protection_enabled, protected_models_mode) =
+            crate::modules::config::load_app_config()
+                .map(|cfg| {
+                    (
+                        cfg.quota_protection.enabled,
+                        cfg.quota_protection.protected_models_mode,
+                    )
+                })
+                .unwrap_or((false, crate::models::ProtectedModelsMode::Exclude));
+
+        // [NEW] Reserve 模式需要全池信息判断目标模型是否被独占
+        let pool_snapshot: Vec<ProxyToken> =
+            self.tokens.iter().map(|e| e.value().clone()).collect();
 
         // 遍历所有账号,检查是否有可用的
         for entry in self.tokens.iter() {
@@ -1552,7 +2137,14 @@ impl TokenManager {
             }
 
             // 2. 检查是否被配额保护(如果启用)
-            if quota_protection_enabled && token.protected_models.contains(target_model) {
+            if quota_protection_enabled
+                && quota_protection_blocks(
+                    protected_models_mode,
+                    &pool_snapshot,
+                    &token.protected_models,
+                    target_model,
+                )
+            {
                 tracing::debug!(
                     "[Fallback Check] Account {} is quota-protected for model {}, skipping",
                     token.email,
@@ -1741,6 +2333,16 @@ impl TokenManager {
         error_body: &str,
         model: Option<&str>, // 🆕 新增模型参数
     ) {
+        // [NEW] 无论熔断开关如何，先记录该账号的最近错误
+        self.record_account_error(email, status, error_body);
+
+        // [NEW] 429 持久化冷却，不依赖熔断开关 (跨重启生效)
+        if status == 429 {
+            if let Some(account_id) = self.email_to_account_id(email) {
+                self.apply_rate_limit_cooldown(&account_id, retry_after_header);
+            }
+        }
+
         // [NEW] 检查熔断是否启用
         let config = self.circuit_breaker_config.read().await.clone();
         if !config.enabled {
@@ -1897,6 +2499,22 @@ impl TokenManager {
         self.preferred_account_id.read().await.clone()
     }
 
+    /// [NEW] 设置分组约束 (None 表示不限制分组)
+    pub async fn set_group_filter(&self, group: Option<String>) {
+        let group = group.filter(|g| !g.trim().is_empty());
+        if let Some(ref g) = group {
+            tracing::info!("🏷️ Group filter enabled: only accounts in group '{}'", g);
+        } else {
+            tracing::info!("🏷️ Group filter disabled (all accounts eligible)");
+        }
+        *self.group_filter.write().await = group;
+    }
+
+    /// [NEW] 获取当前分组约束
+    pub async fn get_group_filter(&self) -> Option<String> {
+        self.group_filter.read().await.clone()
+    }
+
     /// 使用 Authorization Code 交换 Refresh Token (Web OAuth)
     pub async fn exchange_code(&self, code: &str, redirect_uri: &str) -> Result<String, String> {
         crate::modules::oauth::exchange_code(code, redirect_uri)
@@ -1908,7 +2526,11 @@ impl TokenManager {
     }
 
     /// 获取 OAuth URL (支持自定义 Redirect URI)
-    pub fn get_oauth_url_with_redirect(&self, redirect_uri: &str, state: &str) -> String {
+    pub fn get_oauth_url_with_redirect(
+        &self,
+        redirect_uri: &str,
+        state: &str,
+    ) -> Result<String, String> {
         crate::modules::oauth::get_auth_url(redirect_uri, state)
     }
 
@@ -1939,7 +2561,11 @@ impl TokenManager {
 
         // 3. 委托给 modules::account::add_account 处理 (包含文件写入、索引更新、锁)
         let email_clone = email.to_string();
-        let refresh_token_clone = refresh_token.to_string();
+        // 刷新时 Google 可能轮换 refresh_token，优先保存新值
+        let refresh_token_clone = token_info
+            .refresh_token
+            .clone()
+            .unwrap_or_else(|| refresh_token.to_string());
 
         tokio::task::spawn_blocking(move || {
             let token_data = crate::models::TokenData::new(
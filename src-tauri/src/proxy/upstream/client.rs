@@ -45,13 +45,15 @@ impl UpstreamClient {
 
     /// Internal helper to build a client with optional upstream proxy config
     fn build_client_internal(proxy_config: Option<crate::proxy::config::UpstreamProxyConfig>) -> Result<Client, reqwest::Error> {
+        // [NEW] 连接超时从全局配置读取；不设置整体 timeout，
+        // 请求超时改为在 send() 处仅约束响应头返回 (流式响应不受限)
+        let (connect_ms, _) = crate::proxy::config::get_upstream_timeouts();
         let mut builder = Client::builder()
             // Connection settings (优化连接复用，减少建立开销)
-            .connect_timeout(Duration::from_secs(20))
+            .connect_timeout(Duration::from_millis(connect_ms))
             .pool_max_idle_per_host(16)                  // 每主机最多 16 个空闲连接
             .pool_idle_timeout(Duration::from_secs(90))  // 空闲连接保持 90 秒
             .tcp_keepalive(Duration::from_secs(60))      // TCP 保活探测 60 秒
-            .timeout(Duration::from_secs(600))
             .user_agent(crate::constants::USER_AGENT.as_str());
 
         if let Some(config) = proxy_config {
@@ -69,17 +71,22 @@ impl UpstreamClient {
     /// Build a client with a specific PoolProxyConfig (from ProxyPool)
     fn build_client_with_proxy(&self, proxy_config: crate::proxy::proxy_pool::PoolProxyConfig) -> Result<Client, reqwest::Error> {
         // Reuse base settings similar to default client but with specific proxy
+        let (connect_ms, _) = crate::proxy::config::get_upstream_timeouts();
         Client::builder()
-            .connect_timeout(Duration::from_secs(20))
+            .connect_timeout(Duration::from_millis(connect_ms))
             .pool_max_idle_per_host(16)
             .pool_idle_timeout(Duration::from_secs(90))
             .tcp_keepalive(Duration::from_secs(60))
-            .timeout(Duration::from_secs(600))
             .user_agent(crate::constants::USER_AGENT.as_str())
             .proxy(proxy_config.proxy) // Apply the specific proxy
             .build()
     }
 
+    /// [NEW] 判断错误消息是否来自上游超时 (handlers 据此向客户端返回 504)
+    pub fn is_timeout_error(err: &str) -> bool {
+        err.contains("connect timeout") || err.contains("read timeout")
+    }
+
     /// Set dynamic User-Agent override
     pub async fn set_user_agent_override(&self, ua: Option<String>) {
         let mut lock = self.user_agent_override.write().await;
@@ -88,9 +95,10 @@ impl UpstreamClient {
     }
 
     /// Get current User-Agent
+    /// Priority: per-instance override (from proxy config) > global runtime override > computed constant
     pub async fn get_user_agent(&self) -> String {
         let ua_override = self.user_agent_override.read().await;
-        ua_override.as_ref().cloned().unwrap_or_else(|| crate::constants::USER_AGENT.clone())
+        ua_override.as_ref().cloned().unwrap_or_else(crate::constants::effective_user_agent)
     }
 
     /// Get client for a specific account (or default if no proxy bound)
@@ -172,6 +180,10 @@ impl UpstreamClient {
         extra_headers: std::collections::HashMap<String, String>,
         account_id: Option<&str>, // [NEW] Account ID
     ) -> Result<Response, String> {
+        // [NEW] 上游并发限制：饱和时排队或立即拒绝 (handlers 将错误映射为 503 + Retry-After)；
+        // 许可持有至响应头返回，流式响应体阶段不占用
+        let _permit = crate::proxy::concurrency::acquire_upstream_permit().await?;
+
         // [NEW] Get client based on account (cached in proxy pool manager)
         let client = self.get_client(account_id).await;
 
@@ -206,19 +218,61 @@ impl UpstreamClient {
             }
         }
 
+        // [NEW] 注入账号级自定义 Headers (如组织 ID)
+        // Authorization 由 Token 注入设置，不允许被账号自定义覆盖
+        if let Some(acc_id) = account_id {
+            if let Some(custom) = crate::proxy::token_manager::custom_headers_for(acc_id) {
+                for (k, v) in custom {
+                    if k.eq_ignore_ascii_case("authorization") {
+                        tracing::warn!(
+                            "Ignoring custom Authorization header for account {}",
+                            acc_id
+                        );
+                        continue;
+                    }
+                    if let Ok(hk) = header::HeaderName::from_bytes(k.as_bytes()) {
+                        if let Ok(hv) = header::HeaderValue::from_str(&v) {
+                            headers.insert(hk, hv);
+                        }
+                    }
+                }
+            }
+        }
+
         let mut last_err: Option<String> = None;
 
+        // [NEW] 请求超时仅约束响应头返回 (流式响应体不受限)
+        let (_, request_ms) = crate::proxy::config::get_upstream_timeouts();
+
         // 遍历所有端点，失败时自动切换
         for (idx, base_url) in V1_INTERNAL_BASE_URL_FALLBACKS.iter().enumerate() {
             let url = Self::build_url(base_url, method, query_string);
             let has_next = idx + 1 < V1_INTERNAL_BASE_URL_FALLBACKS.len();
 
-            let response = client
+            let send_future = client
                 .post(&url)
                 .headers(headers.clone())
                 .json(&body)
-                .send()
-                .await;
+                .send();
+
+            let response = match tokio::time::timeout(Duration::from_millis(request_ms), send_future)
+                .await
+            {
+                Ok(res) => res,
+                Err(_) => {
+                    // [NEW] 首包超时 (read timeout)，区别于连接超时
+                    let msg = format!(
+                        "Upstream read timeout after {}ms waiting for response headers at {}",
+                        request_ms, base_url
+                    );
+                    tracing::warn!("{}", msg);
+                    last_err = Some(msg);
+                    if !has_next {
+                        break;
+                    }
+                    continue;
+                }
+            };
 
             match response {
                 Ok(resp) => {
@@ -253,7 +307,12 @@ impl UpstreamClient {
                     return Ok(resp);
                 }
                 Err(e) => {
-                    let msg = format!("HTTP request failed at {}: {}", base_url, e);
+                    // [NEW] 标注连接超时，便于 handlers 映射为 504
+                    let msg = if e.is_connect() && e.is_timeout() {
+                        format!("Upstream connect timeout at {}: {}", base_url, e)
+                    } else {
+                        format!("HTTP request failed at {}: {}", base_url, e)
+                    };
                     tracing::debug!("{}", msg);
                     last_err = Some(msg);
 
@@ -1,4 +1,5 @@
 use base64::Engine;
+use serde::Serialize;
 
 /// Protobuf Varint Encoding
 pub fn encode_varint(mut value: u64) -> Vec<u8> {
@@ -110,6 +111,58 @@ pub fn find_field(data: &[u8], target_field: u32) -> Result<Option<Vec<u8>>, Str
     Ok(None)
 }
 
+/// Summary of a single top-level Protobuf field (for debugging/preview)
+#[derive(Debug, Clone, Serialize)]
+pub struct FieldSummary {
+    pub field_number: u32,
+    pub wire_type: u8,
+    /// Payload length in bytes (length-delimited fields) or encoded size (others)
+    pub length: usize,
+    /// UTF-8 preview of the payload for length-delimited fields, if printable
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_preview: Option<String>,
+}
+
+/// Walk the top-level fields of a Protobuf message and summarize them.
+/// Length-delimited payloads that are valid UTF-8 get a truncated text preview.
+pub fn describe_fields(data: &[u8]) -> Result<Vec<FieldSummary>, String> {
+    let mut fields = Vec::new();
+    let mut offset = 0;
+
+    while offset < data.len() {
+        let (tag, new_offset) = read_varint(data, offset)?;
+        let wire_type = (tag & 7) as u8;
+        let field_number = (tag >> 3) as u32;
+
+        let next_offset = skip_field(data, new_offset, wire_type)?;
+        if next_offset > data.len() {
+            return Err("incomplete_data".to_string());
+        }
+
+        let (length, text_preview) = if wire_type == 2 {
+            let (len, content_offset) = read_varint(data, new_offset)?;
+            let payload = &data[content_offset..next_offset];
+            let preview = std::str::from_utf8(payload)
+                .ok()
+                .map(|s| s.chars().take(64).collect::<String>());
+            (len as usize, preview)
+        } else {
+            (next_offset - new_offset, None)
+        };
+
+        fields.push(FieldSummary {
+            field_number,
+            wire_type,
+            length,
+            text_preview,
+        });
+
+        offset = next_offset;
+    }
+
+    Ok(fields)
+}
+
 /// Create OAuthTokenInfo (Field 6)
 ///
 /// Structure: